@@ -0,0 +1,3 @@
+ALTER TABLE projects DROP COLUMN editors_can_edit_others_expenses;
+ALTER TABLE account_projects DROP COLUMN role;
+DROP TYPE project_role;
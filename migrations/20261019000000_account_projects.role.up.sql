@@ -0,0 +1,11 @@
+CREATE TYPE project_role AS ENUM ('owner', 'editor', 'viewer');
+
+-- Existing links were all allowed to edit, so they become editors
+ALTER TABLE account_projects ADD COLUMN role project_role NOT NULL DEFAULT 'editor';
+
+-- Every owned project gets an explicit owner membership
+INSERT INTO account_projects (account_id, project_id, role)
+SELECT owner_account_id, id, 'owner' FROM projects WHERE owner_account_id IS NOT NULL
+ON CONFLICT (account_id, project_id) DO UPDATE SET role = 'owner';
+
+ALTER TABLE projects ADD COLUMN editors_can_edit_others_expenses BOOLEAN NOT NULL DEFAULT TRUE;
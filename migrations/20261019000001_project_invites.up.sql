@@ -0,0 +1,14 @@
+CREATE TABLE project_invites (
+  id         UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  token_hash VARCHAR NOT NULL UNIQUE,
+  role       project_role NOT NULL,
+  expires_at TIMESTAMP NOT NULL,
+  max_uses   INTEGER,
+  use_count  INTEGER NOT NULL DEFAULT 0,
+  created_by UUID REFERENCES accounts(id) ON DELETE SET NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+  revoked_at TIMESTAMP
+);
+
+CREATE INDEX project_invites_project_id_idx ON project_invites (project_id);
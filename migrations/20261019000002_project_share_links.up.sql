@@ -0,0 +1,10 @@
+CREATE TABLE project_share_links (
+  id         UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  token_hash VARCHAR NOT NULL UNIQUE,
+  created_by UUID REFERENCES accounts(id) ON DELETE SET NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+  revoked_at TIMESTAMP
+);
+
+CREATE INDEX project_share_links_project_id_idx ON project_share_links (project_id);
@@ -0,0 +1,3 @@
+DROP TABLE account_tokens;
+DROP TYPE account_token_purpose;
+ALTER TABLE accounts DROP COLUMN email_verified_at;
@@ -0,0 +1,15 @@
+ALTER TABLE accounts ADD COLUMN email_verified_at TIMESTAMP;
+
+CREATE TYPE account_token_purpose AS ENUM ('email_verification', 'password_reset');
+
+CREATE TABLE account_tokens (
+  id         UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  purpose    account_token_purpose NOT NULL,
+  token_hash VARCHAR NOT NULL UNIQUE,
+  expires_at TIMESTAMP NOT NULL,
+  used_at    TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX account_tokens_account_id_idx ON account_tokens (account_id);
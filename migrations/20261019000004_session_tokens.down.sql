@@ -0,0 +1,11 @@
+-- Sessions created after the upgrade have no usable cookie once the token is gone
+DELETE FROM sessions WHERE token_hash <> encode(sha256(id::text::bytea), 'hex');
+
+DROP INDEX sessions_expires_at_idx;
+DROP INDEX sessions_account_id_idx;
+
+ALTER TABLE sessions
+  DROP COLUMN ip_address,
+  DROP COLUMN user_agent,
+  DROP COLUMN last_seen_at,
+  DROP COLUMN token_hash;
@@ -0,0 +1,16 @@
+-- Sessions were looked up by their id, which was also the cookie value. From now on the
+-- cookie carries a random token and only its SHA-256 is stored. Existing cookies keep
+-- working: their value is the session id, so its hash becomes the token hash.
+ALTER TABLE sessions
+  ADD COLUMN token_hash   VARCHAR,
+  ADD COLUMN last_seen_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+  ADD COLUMN user_agent   VARCHAR,
+  ADD COLUMN ip_address   VARCHAR;
+
+UPDATE sessions SET token_hash = encode(sha256(id::text::bytea), 'hex'), last_seen_at = created_at;
+
+ALTER TABLE sessions ALTER COLUMN token_hash SET NOT NULL;
+ALTER TABLE sessions ADD CONSTRAINT sessions_token_hash_key UNIQUE (token_hash);
+
+CREATE INDEX sessions_account_id_idx ON sessions (account_id);
+CREATE INDEX sessions_expires_at_idx ON sessions (expires_at);
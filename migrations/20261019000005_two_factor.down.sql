@@ -0,0 +1,8 @@
+DROP TABLE login_challenges;
+DROP TABLE account_recovery_codes;
+
+ALTER TABLE accounts
+  DROP COLUMN totp_last_used_step,
+  DROP COLUMN totp_enabled_at,
+  DROP COLUMN totp_pending_secret,
+  DROP COLUMN totp_secret;
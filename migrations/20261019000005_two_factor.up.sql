@@ -0,0 +1,28 @@
+-- TOTP secrets are base32. The pending secret is the one being enrolled, until the first
+-- code confirms it.
+ALTER TABLE accounts
+  ADD COLUMN totp_secret          VARCHAR,
+  ADD COLUMN totp_pending_secret  VARCHAR,
+  ADD COLUMN totp_enabled_at      TIMESTAMP,
+  ADD COLUMN totp_last_used_step  BIGINT;
+
+CREATE TABLE account_recovery_codes (
+  id         UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  code_hash  VARCHAR NOT NULL,
+  used_at    TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX account_recovery_codes_account_id_idx ON account_recovery_codes (account_id);
+
+-- Issued once the password is verified for an account with 2FA, traded for a session
+-- together with a valid code.
+CREATE TABLE login_challenges (
+  id         UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  token_hash VARCHAR NOT NULL UNIQUE,
+  attempts   INTEGER NOT NULL DEFAULT 0,
+  expires_at TIMESTAMP NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+);
@@ -0,0 +1,6 @@
+DROP TABLE oidc_login_states;
+DROP TABLE account_identities;
+
+-- Accounts without a password cannot survive the NOT NULL constraint
+DELETE FROM accounts WHERE password_hash IS NULL;
+ALTER TABLE accounts ALTER COLUMN password_hash SET NOT NULL;
@@ -0,0 +1,27 @@
+-- Accounts created through an identity provider have no password until they set one.
+ALTER TABLE accounts ALTER COLUMN password_hash DROP NOT NULL;
+
+-- External OpenID Connect subjects linked to an account.
+CREATE TABLE account_identities (
+  id         UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  issuer     VARCHAR NOT NULL,
+  subject    VARCHAR NOT NULL,
+  email      VARCHAR,
+  created_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+  UNIQUE (issuer, subject)
+);
+
+CREATE INDEX account_identities_account_id_idx ON account_identities (account_id);
+
+-- Pending authorization requests, keyed by the `state` parameter sent to the provider.
+-- `link_account_id` is set when a signed-in account links a new identity.
+CREATE TABLE oidc_login_states (
+  id              UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  state_hash      VARCHAR NOT NULL UNIQUE,
+  code_verifier   VARCHAR NOT NULL,
+  nonce           VARCHAR NOT NULL,
+  link_account_id UUID REFERENCES accounts(id) ON DELETE CASCADE,
+  expires_at      TIMESTAMP NOT NULL,
+  created_at      TIMESTAMP NOT NULL DEFAULT current_timestamp
+);
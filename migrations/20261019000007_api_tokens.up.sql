@@ -0,0 +1,19 @@
+CREATE TYPE api_token_scope AS ENUM ('read', 'write');
+
+-- Personal access tokens, sent as `Authorization: Bearer`. Only the SHA-256 of the token is
+-- stored; `token_prefix` identifies it in the account page.
+CREATE TABLE api_tokens (
+  id           UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+  account_id   UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  name         VARCHAR NOT NULL,
+  token_hash   VARCHAR NOT NULL UNIQUE,
+  token_prefix VARCHAR NOT NULL,
+  scope        api_token_scope NOT NULL,
+  -- NULL gives access to every project of the account
+  project_ids  UUID[],
+  expires_at   TIMESTAMP NOT NULL,
+  last_used_at TIMESTAMP,
+  created_at   TIMESTAMP NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX api_tokens_account_id_idx ON api_tokens (account_id);
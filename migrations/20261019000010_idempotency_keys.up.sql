@@ -0,0 +1,15 @@
+-- Responses to create requests sent with an `Idempotency-Key` header, replayed when the same
+-- request is sent again. `scope` identifies the caller and `request_hash` the method, path and
+-- body of the request; `status` stays NULL while the first request is being processed.
+CREATE TABLE idempotency_keys (
+  scope           VARCHAR NOT NULL,
+  idempotency_key VARCHAR NOT NULL,
+  request_hash    VARCHAR NOT NULL,
+  status          INTEGER,
+  response_body   BYTEA,
+  expires_at      TIMESTAMP NOT NULL,
+  created_at      TIMESTAMP NOT NULL DEFAULT current_timestamp,
+  PRIMARY KEY (scope, idempotency_key)
+);
+
+CREATE INDEX idempotency_keys_expires_at_idx ON idempotency_keys (expires_at);
@@ -0,0 +1,16 @@
+DROP TABLE api_tokens;
+DROP TABLE oidc_login_states;
+DROP TABLE account_identities;
+DROP TABLE login_challenges;
+DROP TABLE account_recovery_codes;
+DROP TABLE account_tokens;
+DROP TABLE project_share_links;
+DROP TABLE project_invites;
+DROP TABLE sessions;
+DROP TABLE account_projects;
+DROP TABLE payments;
+DROP TABLE expenses;
+DROP TABLE user_projects;
+DROP TABLE users;
+DROP TABLE projects;
+DROP TABLE accounts;
@@ -0,0 +1,183 @@
+-- The schema of the Postgres migrations up to 20261019000007, for SQLite. UUIDs are stored as
+-- 16-byte blobs and generated by the server; enums are text checked against their values;
+-- timestamps are UTC text, which sorts chronologically.
+
+CREATE TABLE accounts (
+  id                   BLOB PRIMARY KEY,
+  email                TEXT NOT NULL UNIQUE,
+  -- NULL for accounts created through an identity provider
+  password_hash        TEXT,
+  display_name         TEXT NOT NULL,
+  created_at           TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  failed_login_count   INTEGER NOT NULL DEFAULT 0,
+  locked_until         TIMESTAMP,
+  email_verified_at    TIMESTAMP,
+  totp_secret          TEXT,
+  totp_pending_secret  TEXT,
+  totp_enabled_at      TIMESTAMP,
+  totp_last_used_step  INTEGER
+);
+
+CREATE TABLE projects (
+  id                               BLOB PRIMARY KEY,
+  name                             TEXT NOT NULL,
+  created_at                       TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  currency                         TEXT NOT NULL,
+  description                      TEXT,
+  owner_account_id                 BLOB REFERENCES accounts(id) ON DELETE SET NULL,
+  status                           TEXT NOT NULL DEFAULT 'ongoing'
+                                     CHECK (status IN ('ongoing', 'closed', 'archived')),
+  editors_can_edit_others_expenses BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE TABLE users (
+  id         INTEGER PRIMARY KEY,
+  name       TEXT NOT NULL,
+  balance    REAL,
+  created_at TIMESTAMP DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  email_hash TEXT
+);
+
+CREATE TABLE user_projects (
+  project_id BLOB REFERENCES projects(id) ON DELETE CASCADE,
+  user_id    INTEGER REFERENCES users(id) ON DELETE CASCADE,
+  PRIMARY KEY (project_id, user_id)
+);
+
+CREATE TABLE expenses (
+  id           INTEGER PRIMARY KEY,
+  author_id    INTEGER NOT NULL REFERENCES users(id),
+  project_id   BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  created_at   TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  amount       REAL NOT NULL,
+  description  TEXT,
+  name         TEXT NOT NULL,
+  expense_type TEXT NOT NULL CHECK (expense_type IN ('expense', 'transfer', 'gain')),
+  date         DATE NOT NULL
+);
+
+CREATE TABLE payments (
+  id         INTEGER PRIMARY KEY,
+  expense_id INTEGER NOT NULL REFERENCES expenses(id) ON DELETE CASCADE,
+  user_id    INTEGER NOT NULL REFERENCES users(id),
+  is_debt    BOOLEAN NOT NULL,
+  amount     REAL NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE TABLE account_projects (
+  account_id BLOB NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  user_id    INTEGER,
+  role       TEXT NOT NULL DEFAULT 'editor' CHECK (role IN ('owner', 'editor', 'viewer')),
+  PRIMARY KEY (account_id, project_id)
+);
+
+CREATE TABLE sessions (
+  id           BLOB PRIMARY KEY,
+  account_id   BLOB NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  token_hash   TEXT NOT NULL UNIQUE,
+  created_at   TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  last_seen_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  expires_at   TIMESTAMP NOT NULL,
+  user_agent   TEXT,
+  ip_address   TEXT
+);
+
+CREATE INDEX sessions_account_id_idx ON sessions (account_id);
+CREATE INDEX sessions_expires_at_idx ON sessions (expires_at);
+
+CREATE TABLE project_invites (
+  id         BLOB PRIMARY KEY,
+  project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  token_hash TEXT NOT NULL UNIQUE,
+  role       TEXT NOT NULL CHECK (role IN ('owner', 'editor', 'viewer')),
+  expires_at TIMESTAMP NOT NULL,
+  max_uses   INTEGER,
+  use_count  INTEGER NOT NULL DEFAULT 0,
+  created_by BLOB REFERENCES accounts(id) ON DELETE SET NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  revoked_at TIMESTAMP
+);
+
+CREATE INDEX project_invites_project_id_idx ON project_invites (project_id);
+
+CREATE TABLE project_share_links (
+  id         BLOB PRIMARY KEY,
+  project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  token_hash TEXT NOT NULL UNIQUE,
+  created_by BLOB REFERENCES accounts(id) ON DELETE SET NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  revoked_at TIMESTAMP
+);
+
+CREATE INDEX project_share_links_project_id_idx ON project_share_links (project_id);
+
+CREATE TABLE account_tokens (
+  id         BLOB PRIMARY KEY,
+  account_id BLOB NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  purpose    TEXT NOT NULL CHECK (purpose IN ('email_verification', 'password_reset')),
+  token_hash TEXT NOT NULL UNIQUE,
+  expires_at TIMESTAMP NOT NULL,
+  used_at    TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE INDEX account_tokens_account_id_idx ON account_tokens (account_id);
+
+CREATE TABLE account_recovery_codes (
+  id         BLOB PRIMARY KEY,
+  account_id BLOB NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  code_hash  TEXT NOT NULL,
+  used_at    TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE INDEX account_recovery_codes_account_id_idx ON account_recovery_codes (account_id);
+
+CREATE TABLE login_challenges (
+  id         BLOB PRIMARY KEY,
+  account_id BLOB NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  token_hash TEXT NOT NULL UNIQUE,
+  attempts   INTEGER NOT NULL DEFAULT 0,
+  expires_at TIMESTAMP NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE TABLE account_identities (
+  id         BLOB PRIMARY KEY,
+  account_id BLOB NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  issuer     TEXT NOT NULL,
+  subject    TEXT NOT NULL,
+  email      TEXT,
+  created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  UNIQUE (issuer, subject)
+);
+
+CREATE INDEX account_identities_account_id_idx ON account_identities (account_id);
+
+CREATE TABLE oidc_login_states (
+  id              BLOB PRIMARY KEY,
+  state_hash      TEXT NOT NULL UNIQUE,
+  code_verifier   TEXT NOT NULL,
+  nonce           TEXT NOT NULL,
+  link_account_id BLOB REFERENCES accounts(id) ON DELETE CASCADE,
+  expires_at      TIMESTAMP NOT NULL,
+  created_at      TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE TABLE api_tokens (
+  id           BLOB PRIMARY KEY,
+  account_id   BLOB NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+  name         TEXT NOT NULL,
+  token_hash   TEXT NOT NULL UNIQUE,
+  token_prefix TEXT NOT NULL,
+  scope        TEXT NOT NULL CHECK (scope IN ('read', 'write')),
+  -- JSON array of project ids; NULL gives access to every project of the account
+  project_ids  TEXT,
+  expires_at   TIMESTAMP NOT NULL,
+  last_used_at TIMESTAMP,
+  created_at   TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE INDEX api_tokens_account_id_idx ON api_tokens (account_id);
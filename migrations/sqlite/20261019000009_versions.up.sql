@@ -0,0 +1,4 @@
+-- Incremented by every edit. An edit names the version it was based on and is refused when the
+-- row has moved on since.
+ALTER TABLE projects ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE expenses ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
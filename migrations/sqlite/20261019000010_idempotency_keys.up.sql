@@ -0,0 +1,15 @@
+-- Responses to create requests sent with an `Idempotency-Key` header, replayed when the same
+-- request is sent again. `scope` identifies the caller and `request_hash` the method, path and
+-- body of the request; `status` stays NULL while the first request is being processed.
+CREATE TABLE idempotency_keys (
+  scope           TEXT NOT NULL,
+  idempotency_key TEXT NOT NULL,
+  request_hash    TEXT NOT NULL,
+  status          INTEGER,
+  response_body   BLOB,
+  expires_at      TIMESTAMP NOT NULL,
+  created_at      TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  PRIMARY KEY (scope, idempotency_key)
+);
+
+CREATE INDEX idempotency_keys_expires_at_idx ON idempotency_keys (expires_at);
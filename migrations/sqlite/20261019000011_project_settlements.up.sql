@@ -0,0 +1,8 @@
+-- Balances and reimbursements of a project when it was last closed. `users` holds the
+-- participants and `summary` the balances and reimbursement suggestions, both as JSON.
+CREATE TABLE project_settlements (
+  project_id BLOB PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+  users      TEXT NOT NULL,
+  summary    TEXT NOT NULL,
+  closed_at  TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
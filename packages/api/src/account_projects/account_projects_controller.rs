@@ -1,29 +1,34 @@
 use dioxus::fullstack::Json;
 use dioxus::prelude::*;
-use shared::{AccountProject, UpsertAccountProject};
+use shared::{AccountProject, ProjectMember, UpdateProjectMember, UpsertAccountProject};
 use uuid::Uuid;
 
-#[cfg(feature = "server")]
-use crate::account_projects::account_projects_repository;
+use crate::error::ApiError;
+
 #[cfg(feature = "server")]
 use crate::db::get_db;
 #[cfg(feature = "server")]
-use crate::utils::get_current_account_id;
+use crate::projects::project_access::{authorize, resolve_project_access, ProjectAction};
+#[cfg(feature = "server")]
+use crate::utils::{ensure_account_write_allowed, get_api_token_grant, get_current_account_id};
 
 #[get("/api/v1/account/projects")]
-pub async fn get_account_projects() -> Result<Vec<AccountProject>, ServerFnError> {
+pub async fn get_account_projects() -> Result<Vec<AccountProject>, ApiError> {
     #[cfg(feature = "server")]
     {
         let Some(account_id) = get_current_account_id().await else {
-            return Err(ServerFnError::new("Forbidden"));
+            return Err(ApiError::forbidden("Forbidden"));
         };
 
-        let pool = get_db().await;
-        let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+        let pool = get_db();
+        let mut tx = pool.begin().await?;
 
-        let projects = account_projects_repository::get_account_projects(&mut *tx, account_id).await?;
+        let mut projects = tx.get_account_projects(account_id).await?;
+        if let Some(grant) = get_api_token_grant() {
+            projects.retain(|p| grant.allows_project(p.project_id));
+        }
 
-        tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+        tx.commit().await?;
 
         Ok(projects)
     }
@@ -34,25 +39,23 @@ pub async fn get_account_projects() -> Result<Vec<AccountProject>, ServerFnError
 #[post("/api/v1/account/projects")]
 pub async fn upsert_account_project(
     Json(payload): Json<UpsertAccountProject>,
-) -> Result<(), ServerFnError> {
+) -> Result<(), ApiError> {
     #[cfg(feature = "server")]
     {
+        ensure_account_write_allowed()?;
         let Some(account_id) = get_current_account_id().await else {
-            return Err(ServerFnError::new("Forbidden"));
+            return Err(ApiError::forbidden("Forbidden"));
         };
 
-        let pool = get_db().await;
-        let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+        let pool = get_db();
+        let mut tx = pool.begin().await?;
+
+        // Owned projects are joined through an invite link, not by knowing their id
+        resolve_project_access(&mut *tx, payload.project_id).await?;
 
-        account_projects_repository::upsert_account_project(
-            &mut *tx,
-            account_id,
-            payload.project_id,
-            payload.user_id,
-        )
-        .await?;
+        tx.upsert_account_project(account_id, payload.project_id, payload.user_id).await?;
 
-        tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+        tx.commit().await?;
     }
     Ok(())
 }
@@ -60,24 +63,20 @@ pub async fn upsert_account_project(
 #[post("/api/v1/account/projects/batch")]
 pub async fn batch_upsert_account_projects(
     Json(payload): Json<Vec<UpsertAccountProject>>,
-) -> Result<Vec<Uuid>, ServerFnError> {
+) -> Result<Vec<Uuid>, ApiError> {
     #[cfg(feature = "server")]
     {
+        ensure_account_write_allowed()?;
         let Some(account_id) = get_current_account_id().await else {
-            return Err(ServerFnError::new("Forbidden"));
+            return Err(ApiError::forbidden("Forbidden"));
         };
 
-        let pool = get_db().await;
-        let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+        let pool = get_db();
+        let mut tx = pool.begin().await?;
 
-        let accepted = account_projects_repository::batch_upsert_account_projects(
-            &mut *tx,
-            account_id,
-            payload,
-        )
-        .await?;
+        let accepted = tx.batch_upsert_account_projects(account_id, payload).await?;
 
-        tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+        tx.commit().await?;
 
         return Ok(accepted);
     }
@@ -86,20 +85,75 @@ pub async fn batch_upsert_account_projects(
 }
 
 #[delete("/api/v1/account/projects/{project_id}")]
-pub async fn delete_account_project(project_id: Uuid) -> Result<(), ServerFnError> {
+pub async fn delete_account_project(project_id: Uuid) -> Result<(), ApiError> {
     #[cfg(feature = "server")]
     {
+        ensure_account_write_allowed()?;
         let Some(account_id) = get_current_account_id().await else {
-            return Err(ServerFnError::new("Forbidden"));
+            return Err(ApiError::forbidden("Forbidden"));
         };
 
-        let pool = get_db().await;
-        let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+        let pool = get_db();
+        let mut tx = pool.begin().await?;
+
+        tx.delete_account_project(account_id, project_id).await?;
+
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+#[get("/api/v1/projects/{project_id}/members")]
+pub async fn get_project_members(project_id: Uuid) -> Result<Vec<ProjectMember>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+
+    let members = tx.get_project_members(project_id).await?;
+
+    tx.commit().await?;
 
-        account_projects_repository::delete_account_project(&mut *tx, account_id, project_id)
-            .await?;
+    Ok(members)
+}
+
+#[put("/api/v1/projects/{project_id}/members/{account_id}")]
+pub async fn update_project_member(
+    project_id: Uuid,
+    account_id: Uuid,
+    Json(payload): Json<UpdateProjectMember>,
+) -> Result<(), ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let access = authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+    if access.project.owner_account_id == Some(account_id) {
+        return Err(ApiError::validation("The project owner's role cannot be changed"));
+    }
 
-        tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let updated = tx.update_account_project_role(account_id, project_id, payload.role).await?;
+    if !updated {
+        return Err(ApiError::not_found("Member not found"));
     }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[delete("/api/v1/projects/{project_id}/members/{account_id}")]
+pub async fn remove_project_member(project_id: Uuid, account_id: Uuid) -> Result<(), ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let access = authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+    if access.project.owner_account_id == Some(account_id) {
+        return Err(ApiError::validation("The project owner cannot be removed"));
+    }
+
+    tx.delete_account_project(account_id, project_id).await?;
+
+    tx.commit().await?;
+
     Ok(())
 }
@@ -1,105 +1,276 @@
 use dioxus::prelude::*;
-use shared::{AccountProject, UpsertAccountProject};
+use shared::{AccountProject, ProjectMember, ProjectRole, UpsertAccountProject};
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
-use sqlx::{PgConnection, Postgres, QueryBuilder};
+use crate::error::{db_error, ApiError};
 
 #[cfg(feature = "server")]
-pub async fn get_account_projects(
-    executor: &mut PgConnection,
-    account_id: Uuid,
-) -> Result<Vec<AccountProject>, ServerFnError> {
-    let rows = sqlx::query_as!(
-        AccountProject,
-        "SELECT project_id, user_id FROM account_projects WHERE account_id = $1",
-        account_id
-    )
-    .fetch_all(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to get account projects: {}", e)))?;
-
-    Ok(rows)
-}
+use futures::future::BoxFuture;
+#[cfg(feature = "server")]
+use sqlx::{PgConnection, Postgres, QueryBuilder};
 
 #[cfg(feature = "server")]
-pub async fn upsert_account_project(
-    executor: &mut PgConnection,
-    account_id: Uuid,
-    project_id: Uuid,
-    user_id: Option<i32>,
-) -> Result<(), ServerFnError> {
-    sqlx::query!(
-        "INSERT INTO account_projects (account_id, project_id, user_id)
-         VALUES ($1, $2, $3)
-         ON CONFLICT (account_id, project_id) DO UPDATE SET user_id = EXCLUDED.user_id",
-        account_id,
-        project_id,
-        user_id
-    )
-    .execute(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to upsert account project: {}", e)))?;
-
-    Ok(())
+pub trait AccountProjectsRepository {
+    fn get_account_projects(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<AccountProject>, ApiError>>;
+
+    fn get_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<AccountProject>, ApiError>>;
+
+    fn upsert_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        user_id: Option<i32>,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Batch-upserts account_projects entries. Silently skips any project_id that does not exist
+    /// (avoids FK violations from stale localStorage entries) or that is owned by someone the
+    /// account is not a member of. Returns the accepted project IDs.
+    fn batch_upsert_account_projects(
+        &mut self,
+        account_id: Uuid,
+        entries: Vec<UpsertAccountProject>,
+    ) -> BoxFuture<'_, Result<Vec<Uuid>, ApiError>>;
+
+    fn delete_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Grants `role` to the account on the project, creating the membership if needed.
+    fn upsert_account_project_role(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        role: ProjectRole,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn get_project_members(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<ProjectMember>, ApiError>>;
+
+    /// Returns false when the account is not a member of the project.
+    fn update_account_project_role(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        role: ProjectRole,
+    ) -> BoxFuture<'_, Result<bool, ApiError>>;
 }
 
-/// Batch-upserts account_projects entries. Silently skips any project_id that does not exist
-/// (avoids FK violations from stale localStorage entries). Returns the accepted project IDs.
 #[cfg(feature = "server")]
-pub async fn batch_upsert_account_projects(
-    executor: &mut PgConnection,
-    account_id: Uuid,
-    entries: Vec<UpsertAccountProject>,
-) -> Result<Vec<Uuid>, ServerFnError> {
-    if entries.is_empty() {
-        return Ok(vec![]);
+impl AccountProjectsRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn get_account_projects(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<AccountProject>, ApiError>> {
+        Box::pin(async move {
+            let rows = sqlx::query_as!(
+                AccountProject,
+                r#"SELECT project_id, user_id, role as "role: ProjectRole" FROM account_projects WHERE account_id = $1"#,
+                account_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get account projects"))?;
+
+            Ok(rows)
+        })
     }
 
-    let requested_ids: Vec<Uuid> = entries.iter().map(|e| e.project_id).collect();
-    let valid_ids: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM projects WHERE id = ANY($1)")
-        .bind(&requested_ids)
-        .fetch_all(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to validate project IDs: {}", e)))?;
+    #[tracing::instrument(skip_all)]
+    fn get_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<AccountProject>, ApiError>> {
+        Box::pin(async move {
+            let row = sqlx::query_as!(
+                AccountProject,
+                r#"SELECT project_id, user_id, role as "role: ProjectRole" FROM account_projects WHERE account_id = $1 AND project_id = $2"#,
+                account_id,
+                project_id
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get account project"))?;
 
-    let valid_set: std::collections::HashSet<Uuid> = valid_ids.iter().copied().collect();
-    let valid_entries: Vec<&UpsertAccountProject> =
-        entries.iter().filter(|e| valid_set.contains(&e.project_id)).collect();
+            Ok(row)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn upsert_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        user_id: Option<i32>,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "INSERT INTO account_projects (account_id, project_id, user_id)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (account_id, project_id) DO UPDATE SET user_id = EXCLUDED.user_id",
+                account_id,
+                project_id,
+                user_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to upsert account project"))?;
 
-    if valid_entries.is_empty() {
-        return Ok(vec![]);
+            Ok(())
+        })
     }
 
-    let mut qb: QueryBuilder<Postgres> =
-        QueryBuilder::new("INSERT INTO account_projects (account_id, project_id, user_id) ");
-    qb.push_values(valid_entries.iter(), |mut b, e| {
-        b.push_bind(account_id).push_bind(e.project_id).push_bind(e.user_id);
-    });
-    qb.push(" ON CONFLICT (account_id, project_id) DO UPDATE SET user_id = EXCLUDED.user_id");
+    #[tracing::instrument(skip_all)]
+    fn batch_upsert_account_projects(
+        &mut self,
+        account_id: Uuid,
+        entries: Vec<UpsertAccountProject>,
+    ) -> BoxFuture<'_, Result<Vec<Uuid>, ApiError>> {
+        Box::pin(async move {
+            if entries.is_empty() {
+                return Ok(vec![]);
+            }
 
-    qb.build()
-        .execute(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to batch upsert account projects: {}", e)))?;
+            let requested_ids: Vec<Uuid> = entries.iter().map(|e| e.project_id).collect();
+            let valid_ids: Vec<Uuid> = sqlx::query_scalar(
+                "SELECT p.id FROM projects p
+                 WHERE p.id = ANY($1)
+                   AND (p.owner_account_id IS NULL OR EXISTS (
+                     SELECT 1 FROM account_projects ap WHERE ap.project_id = p.id AND ap.account_id = $2
+                   ))",
+            )
+            .bind(&requested_ids)
+            .bind(account_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to validate project IDs"))?;
 
-    Ok(valid_ids)
-}
+            let valid_set: std::collections::HashSet<Uuid> = valid_ids.iter().copied().collect();
+            let valid_entries: Vec<&UpsertAccountProject> =
+                entries.iter().filter(|e| valid_set.contains(&e.project_id)).collect();
 
-#[cfg(feature = "server")]
-pub async fn delete_account_project(
-    executor: &mut PgConnection,
-    account_id: Uuid,
-    project_id: Uuid,
-) -> Result<(), ServerFnError> {
-    sqlx::query!(
-        "DELETE FROM account_projects WHERE account_id = $1 AND project_id = $2",
-        account_id,
-        project_id
-    )
-    .execute(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to delete account project: {}", e)))?;
-
-    Ok(())
+            if valid_entries.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+                "INSERT INTO account_projects (account_id, project_id, user_id) ",
+            );
+            qb.push_values(valid_entries.iter(), |mut b, e| {
+                b.push_bind(account_id).push_bind(e.project_id).push_bind(e.user_id);
+            });
+            qb.push(
+                " ON CONFLICT (account_id, project_id) DO UPDATE SET user_id = EXCLUDED.user_id",
+            );
+
+            qb.build()
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to batch upsert account projects"))?;
+
+            Ok(valid_ids)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "DELETE FROM account_projects WHERE account_id = $1 AND project_id = $2",
+                account_id,
+                project_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to delete account project"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn upsert_account_project_role(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        role: ProjectRole,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "INSERT INTO account_projects (account_id, project_id, role)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (account_id, project_id) DO UPDATE SET role = EXCLUDED.role",
+                account_id,
+                project_id,
+                role as ProjectRole
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to set account project role"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_project_members(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<ProjectMember>, ApiError>> {
+        Box::pin(async move {
+            let members = sqlx::query_as!(
+                ProjectMember,
+                r#"SELECT a.id as account_id, a.display_name, a.email, ap.user_id, ap.role as "role: ProjectRole"
+                   FROM account_projects ap
+                   JOIN accounts a ON a.id = ap.account_id
+                   WHERE ap.project_id = $1
+                   ORDER BY a.display_name"#,
+                project_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get project members"))?;
+
+            Ok(members)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn update_account_project_role(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        role: ProjectRole,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!(
+                "UPDATE account_projects SET role = $1 WHERE account_id = $2 AND project_id = $3",
+                role as ProjectRole,
+                account_id,
+                project_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to update account project role"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
 }
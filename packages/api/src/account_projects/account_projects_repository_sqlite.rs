@@ -0,0 +1,213 @@
+use futures::future::BoxFuture;
+use shared::{AccountProject, ProjectMember, ProjectRole, UpsertAccountProject};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use uuid::Uuid;
+
+use super::account_projects_repository::AccountProjectsRepository;
+use crate::error::{db_error, ApiError};
+
+impl AccountProjectsRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn get_account_projects(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<AccountProject>, ApiError>> {
+        Box::pin(async move {
+            let rows = sqlx::query_as(
+                "SELECT project_id, user_id, role FROM account_projects WHERE account_id = $1",
+            )
+            .bind(account_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get account projects"))?;
+
+            Ok(rows)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<AccountProject>, ApiError>> {
+        Box::pin(async move {
+            let row = sqlx::query_as(
+                "SELECT project_id, user_id, role FROM account_projects
+                 WHERE account_id = $1 AND project_id = $2",
+            )
+            .bind(account_id)
+            .bind(project_id)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get account project"))?;
+
+            Ok(row)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn upsert_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        user_id: Option<i32>,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO account_projects (account_id, project_id, user_id)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (account_id, project_id) DO UPDATE SET user_id = excluded.user_id",
+            )
+            .bind(account_id)
+            .bind(project_id)
+            .bind(user_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to upsert account project"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn batch_upsert_account_projects(
+        &mut self,
+        account_id: Uuid,
+        entries: Vec<UpsertAccountProject>,
+    ) -> BoxFuture<'_, Result<Vec<Uuid>, ApiError>> {
+        Box::pin(async move {
+            if entries.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "SELECT p.id FROM projects p
+                 WHERE (p.owner_account_id IS NULL OR EXISTS (
+                     SELECT 1 FROM account_projects ap WHERE ap.project_id = p.id AND ap.account_id = ",
+            );
+            query_builder.push_bind(account_id).push(")) AND p.id IN (");
+            let mut separated = query_builder.separated(", ");
+            for entry in &entries {
+                separated.push_bind(entry.project_id);
+            }
+            separated.push_unseparated(")");
+
+            let valid_ids: Vec<Uuid> = query_builder
+                .build_query_scalar()
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to validate project IDs"))?;
+
+            let valid_entries: Vec<&UpsertAccountProject> =
+                entries.iter().filter(|e| valid_ids.contains(&e.project_id)).collect();
+
+            if valid_entries.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO account_projects (account_id, project_id, user_id) ",
+            );
+            qb.push_values(valid_entries.iter(), |mut b, e| {
+                b.push_bind(account_id).push_bind(e.project_id).push_bind(e.user_id);
+            });
+            qb.push(
+                " ON CONFLICT (account_id, project_id) DO UPDATE SET user_id = excluded.user_id",
+            );
+
+            qb.build()
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to batch upsert account projects"))?;
+
+            Ok(valid_ids)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_account_project(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM account_projects WHERE account_id = $1 AND project_id = $2")
+                .bind(account_id)
+                .bind(project_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete account project"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn upsert_account_project_role(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        role: ProjectRole,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO account_projects (account_id, project_id, role)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (account_id, project_id) DO UPDATE SET role = excluded.role",
+            )
+            .bind(account_id)
+            .bind(project_id)
+            .bind(role)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to set account project role"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_project_members(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<ProjectMember>, ApiError>> {
+        Box::pin(async move {
+            let members = sqlx::query_as(
+                "SELECT a.id AS account_id, a.display_name, a.email, ap.user_id, ap.role
+                 FROM account_projects ap
+                 JOIN accounts a ON a.id = ap.account_id
+                 WHERE ap.project_id = $1
+                 ORDER BY a.display_name",
+            )
+            .bind(project_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get project members"))?;
+
+            Ok(members)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn update_account_project_role(
+        &mut self,
+        account_id: Uuid,
+        project_id: Uuid,
+        role: ProjectRole,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "UPDATE account_projects SET role = $1 WHERE account_id = $2 AND project_id = $3",
+            )
+            .bind(role)
+            .bind(account_id)
+            .bind(project_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to update account project role"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+}
@@ -1,2 +1,4 @@
 pub mod account_projects_controller;
 pub mod account_projects_repository;
+#[cfg(feature = "server")]
+mod account_projects_repository_sqlite;
@@ -0,0 +1,203 @@
+//! Personal API tokens, for scripts and integrations.
+//!
+//! A token is sent as `Authorization: Bearer cnt_…` and stands for its account wherever the
+//! current account is resolved, within its scope: read tokens act as viewers, and tokens limited
+//! to some projects cannot reach the others nor act outside of a project. Managing the account
+//! itself (password, sessions, 2FA, tokens) always needs a browser session.
+use shared::{ApiTokenScope, ProjectRole};
+use uuid::Uuid;
+
+/// Prefix of every token, so that secret scanners and humans can recognise one.
+pub const TOKEN_PREFIX: &str = "cnt_";
+/// Characters of the token kept in clear to identify it, prefix included.
+pub const DISPLAYED_PREFIX_LENGTH: usize = 12;
+pub const MAX_TOKEN_LIFETIME_DAYS: i64 = 365;
+const MAX_NAME_LENGTH: usize = 100;
+#[cfg(feature = "server")]
+const MAX_PROJECTS_PER_TOKEN: usize = 100;
+
+/// What the token of the current request may do, stored in the request extensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiTokenGrant {
+    pub token_id: Uuid,
+    pub account_id: Uuid,
+    pub scope: ApiTokenScope,
+    /// `None` for every project of the account
+    pub project_ids: Option<Vec<Uuid>>,
+}
+
+impl ApiTokenGrant {
+    pub fn allows_project(&self, project_id: Uuid) -> bool {
+        self.project_ids.as_ref().is_none_or(|ids| ids.contains(&project_id))
+    }
+
+    /// Role on a project the token can reach: a read token never does more than a viewer.
+    pub fn restrict_role(&self, role: ProjectRole) -> ProjectRole {
+        match self.scope {
+            ApiTokenScope::Read => ProjectRole::Viewer,
+            ApiTokenScope::Write => role,
+        }
+    }
+
+    /// Whether the token may act outside of a project, e.g. create one.
+    pub fn allows_account_writes(&self) -> bool {
+        self.scope == ApiTokenScope::Write && self.project_ids.is_none()
+    }
+}
+
+/// Token of an `Authorization: Bearer` header value.
+pub fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+pub fn validate_new_token(name: &str, expires_in_days: i64) -> Result<(), &'static str> {
+    if name.trim().is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err("The token name must be 1 to 100 characters long");
+    }
+    if !(1..=MAX_TOKEN_LIFETIME_DAYS).contains(&expires_in_days) {
+        return Err("The token must expire within 1 to 365 days");
+    }
+    Ok(())
+}
+
+/// A new token and the part of it kept in clear.
+#[cfg(feature = "server")]
+pub fn generate_api_token() -> (String, String) {
+    let token = format!("{}{}", TOKEN_PREFIX, crate::utils::generate_token());
+    let prefix = token.chars().take(DISPLAYED_PREFIX_LENGTH).collect();
+    (token, prefix)
+}
+
+#[cfg(feature = "server")]
+pub fn validate_project_count(project_ids: &Option<Vec<Uuid>>) -> Result<(), &'static str> {
+    match project_ids {
+        Some(ids) if ids.is_empty() => Err("Pick at least one project, or none to allow them all"),
+        Some(ids) if ids.len() > MAX_PROJECTS_PER_TOKEN => {
+            Err("A token can be limited to 100 projects at most")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "server")]
+pub use layer::api_token_auth;
+
+#[cfg(feature = "server")]
+mod layer {
+    use axum::extract::{Request, State};
+    use axum::http::header::AUTHORIZATION;
+    use axum::middleware::Next;
+    use axum::response::{IntoResponse, Response};
+
+    use super::{bearer_token, ApiTokenGrant, TOKEN_PREFIX};
+    use crate::auth::sessions::needs_touch;
+    use crate::error::ApiError;
+    use crate::state::AppState;
+    use crate::utils::sha256_hex;
+
+    /// Axum middleware: resolve an `Authorization: Bearer` API token into an [`ApiTokenGrant`].
+    ///
+    /// Requests without the header pass through untouched. An unknown or expired token is
+    /// refused outright rather than treated as anonymous, so scripts get a clear error.
+    pub async fn api_token_auth(
+        State(state): State<AppState>,
+        mut request: Request,
+        next: Next,
+    ) -> Response {
+        let Some(header) = request.headers().get(AUTHORIZATION).and_then(|v| v.to_str().ok())
+        else {
+            return next.run(request).await;
+        };
+        let Some(token) = bearer_token(header).filter(|t| t.starts_with(TOKEN_PREFIX)) else {
+            return unauthorized("Invalid Authorization header");
+        };
+        let token_hash = sha256_hex(token);
+
+        let mut conn = match state.db.acquire().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                return ApiError::internal(format!("Failed to check API token: {}", e))
+                    .into_response();
+            }
+        };
+        let active = match conn.find_active_api_token(&token_hash).await {
+            Ok(Some(active)) => active,
+            Ok(None) => return unauthorized("Invalid or expired API token"),
+            Err(e) => return e.into_response(),
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        if active.last_used_at.is_none_or(|last| needs_touch(last, now)) {
+            // A failure is logged by the repository and does not refuse the request
+            let _ = conn.touch_api_token(active.id).await;
+        }
+        drop(conn);
+
+        request.extensions_mut().insert(ApiTokenGrant {
+            token_id: active.id,
+            account_id: active.account_id,
+            scope: active.scope,
+            project_ids: active.project_ids,
+        });
+
+        next.run(request).await
+    }
+
+    fn unauthorized(message: &str) -> Response {
+        ApiError::unauthorized(message).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(scope: ApiTokenScope, project_ids: Option<Vec<Uuid>>) -> ApiTokenGrant {
+        ApiTokenGrant { token_id: Uuid::nil(), account_id: Uuid::nil(), scope, project_ids }
+    }
+
+    #[test]
+    fn bearer_token_is_parsed() {
+        assert_eq!(bearer_token("Bearer cnt_abc"), Some("cnt_abc"));
+        assert_eq!(bearer_token("bearer  cnt_abc "), Some("cnt_abc"));
+        assert_eq!(bearer_token("Basic dXNlcjpwYXNz"), None);
+        assert_eq!(bearer_token("Bearer "), None);
+        assert_eq!(bearer_token("Bearer"), None);
+    }
+
+    #[test]
+    fn read_tokens_act_as_viewers() {
+        let read = grant(ApiTokenScope::Read, None);
+        assert_eq!(read.restrict_role(ProjectRole::Owner), ProjectRole::Viewer);
+        assert_eq!(read.restrict_role(ProjectRole::Editor), ProjectRole::Viewer);
+
+        let write = grant(ApiTokenScope::Write, None);
+        assert_eq!(write.restrict_role(ProjectRole::Owner), ProjectRole::Owner);
+        assert_eq!(write.restrict_role(ProjectRole::Viewer), ProjectRole::Viewer);
+    }
+
+    #[test]
+    fn project_limits_apply() {
+        let allowed = Uuid::from_u128(1);
+        let limited = grant(ApiTokenScope::Write, Some(vec![allowed]));
+        assert!(limited.allows_project(allowed));
+        assert!(!limited.allows_project(Uuid::from_u128(2)));
+        assert!(!limited.allows_account_writes());
+
+        let unlimited = grant(ApiTokenScope::Write, None);
+        assert!(unlimited.allows_project(Uuid::from_u128(2)));
+        assert!(unlimited.allows_account_writes());
+        assert!(!grant(ApiTokenScope::Read, None).allows_account_writes());
+    }
+
+    #[test]
+    fn new_token_is_validated() {
+        assert!(validate_new_token("backup script", 30).is_ok());
+        assert!(validate_new_token("  ", 30).is_err());
+        assert!(validate_new_token(&"x".repeat(101), 30).is_err());
+        assert!(validate_new_token("ci", 0).is_err());
+        assert!(validate_new_token("ci", MAX_TOKEN_LIFETIME_DAYS + 1).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{ApiTokenInfo, CreateApiTokenPayload, CreatedApiToken};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+#[cfg(feature = "server")]
+use crate::auth::api_tokens::{generate_api_token, validate_new_token, validate_project_count};
+#[cfg(feature = "server")]
+use crate::auth::auth_repository::NewApiToken;
+#[cfg(feature = "server")]
+use crate::db::get_db;
+#[cfg(feature = "server")]
+use crate::projects::project_access::resolve_project_access;
+#[cfg(feature = "server")]
+use crate::utils::{get_session_account_id, sha256_hex};
+#[cfg(feature = "server")]
+use chrono::{Duration, Utc};
+
+#[get("/api/v1/auth/api-tokens")]
+pub async fn list_api_tokens() -> Result<Vec<ApiTokenInfo>, ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut conn = pool.acquire().await?;
+
+    conn.list_api_tokens(account_id).await
+}
+
+/// Create a personal API token. The returned secret is not stored and cannot be shown again.
+#[post("/api/v1/auth/api-tokens")]
+pub async fn create_api_token(
+    Json(payload): Json<CreateApiTokenPayload>,
+) -> Result<CreatedApiToken, ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    validate_new_token(&payload.name, payload.expires_in_days).map_err(ApiError::validation)?;
+    let mut project_ids = payload.project_ids;
+    if let Some(ids) = project_ids.as_mut() {
+        ids.sort();
+        ids.dedup();
+    }
+    validate_project_count(&project_ids).map_err(ApiError::validation)?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    // A token never reaches a project its account cannot
+    for project_id in project_ids.iter().flatten() {
+        resolve_project_access(&mut *tx, *project_id).await?;
+    }
+
+    let (token, token_prefix) = generate_api_token();
+    let expires_at = (Utc::now() + Duration::days(payload.expires_in_days)).naive_utc();
+    let info = tx
+        .create_api_token(
+            account_id,
+            NewApiToken {
+                name: payload.name.trim(),
+                token_hash: &sha256_hex(&token),
+                token_prefix: &token_prefix,
+                scope: payload.scope,
+                project_ids: project_ids.as_deref(),
+                expires_at,
+            },
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(CreatedApiToken { token, info })
+}
+
+#[delete("/api/v1/auth/api-tokens/{token_id}")]
+pub async fn revoke_api_token(token_id: Uuid) -> Result<(), ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut conn = pool.acquire().await?;
+
+    if !conn.delete_api_token(account_id, token_id).await? {
+        return Err(ApiError::not_found("API token not found"));
+    }
+
+    Ok(())
+}
@@ -1,238 +1,542 @@
 use chrono::NaiveDateTime;
 use dioxus::fullstack::Json;
 use dioxus::prelude::*;
-use shared::{Account, LoginPayload, RegisterPayload};
+use shared::{
+    Account, LoginPayload, LoginResponse, PasswordResetPayload, PasswordResetRequestPayload,
+    RegisterPayload, SessionInfo, TwoFactorLoginPayload, VerifyEmailPayload,
+};
+use uuid::Uuid;
+
+use crate::error::ApiError;
 
 #[cfg(feature = "server")]
-use crate::account_projects::account_projects_repository;
+use crate::auth::auth_repository::AccountTokenPurpose;
+#[cfg(feature = "server")]
+use crate::auth::{sessions, two_factor_controller};
+#[cfg(feature = "server")]
+use crate::config::get_config;
+#[cfg(feature = "server")]
+use crate::db::{get_db, Store};
 #[cfg(feature = "server")]
-use crate::auth::auth_repository;
+use crate::mailer::{
+    self, templates::password_reset_email, templates::verification_email, templates::Locale, Email,
+};
 #[cfg(feature = "server")]
-use crate::db::get_db;
+use crate::metrics;
 #[cfg(feature = "server")]
-use crate::utils::{get_current_account_id, sha256_hex};
+use crate::utils::{
+    generate_token, get_client_ip, get_current_account_id, get_request_header,
+    get_session_account_id, sha256_hex,
+};
 #[cfg(feature = "server")]
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
 #[cfg(feature = "server")]
-use axum::http::header::{HeaderValue, SET_COOKIE};
-#[cfg(feature = "server")]
 use chrono::{Duration, Utc};
+
 #[cfg(feature = "server")]
-use dioxus_fullstack::FullstackContext;
+const EMAIL_VERIFICATION_TTL_HOURS: i64 = 24;
 #[cfg(feature = "server")]
-use sqlx::PgConnection;
+const PASSWORD_RESET_TTL_MINUTES: i64 = 60;
 #[cfg(feature = "server")]
-use uuid::Uuid;
+const MAX_USER_AGENT_LENGTH: usize = 512;
+#[cfg(feature = "server")]
+const LOGIN_CHALLENGE_TTL_MINUTES: i64 = 5;
+#[cfg(feature = "server")]
+const MAX_LOGIN_CHALLENGE_ATTEMPTS: i32 = 5;
 
 fn is_account_locked(locked_until: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
     locked_until.map_or(false, |l| l > now)
 }
 
+/// Same 128-byte ceiling as login, so a password set here can always be used to sign in.
+fn is_valid_new_password(password: &str) -> bool {
+    password.chars().count() >= 8 && password.len() <= 128
+}
+
 #[post("/api/v1/auth/register")]
-pub async fn register(Json(payload): Json<RegisterPayload>) -> Result<Account, ServerFnError> {
-    if payload.email.len() > 254 || payload.password.len() > 128 || payload.display_name.len() > 100 {
-        return Err(ServerFnError::new("Invalid input"));
+pub async fn register(Json(payload): Json<RegisterPayload>) -> Result<Account, ApiError> {
+    if payload.email.len() > 254 || payload.password.len() > 128 || payload.display_name.len() > 100
+    {
+        return Err(ApiError::validation("Invalid input"));
     }
 
     let email = payload.email.to_lowercase();
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    if auth_repository::find_account_by_email(&mut *tx, &email).await?.is_some() {
-        return Err(ServerFnError::new("Registration failed"));
+    if tx.find_account_by_email(&email).await?.is_some() {
+        return Err(ApiError::validation("Registration failed"));
     }
 
-    let password_bytes = payload.password.into_bytes();
-    let password_hash = tokio::task::spawn_blocking(move || {
-        let salt = SaltString::generate(&mut OsRng);
-        Argon2::default()
-            .hash_password(&password_bytes, &salt)
-            .map(|h| h.to_string())
-    })
-    .await
-    .map_err(|e| ServerFnError::new(e.to_string()))?
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let password_hash = hash_password(payload.password).await?;
 
-    let account_id =
-        auth_repository::create_account(&mut *tx, &email, &password_hash, &payload.display_name)
-            .await?;
+    let account_id = tx.create_account(&email, &password_hash, &payload.display_name).await?;
 
     create_session_and_set_cookie(&mut *tx, account_id).await?;
 
-    let account = auth_repository::get_account_by_id(&mut *tx, account_id)
+    let account = tx
+        .get_account_by_id(account_id)
         .await?
-        .ok_or_else(|| ServerFnError::new("Account not found after creation"))?;
+        .ok_or_else(|| ApiError::internal("Account not found after creation"))?;
+
+    // Invitations sent to this address only resolve once the address is verified
+    let verification = issue_verification_email(&mut *tx, &account).await?;
 
-    resolve_pending_invitations(&mut *tx, account_id, &email, &account.display_name).await?;
+    tx.commit().await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    mailer::send_in_background(verification);
 
     Ok(account)
 }
 
 #[post("/api/v1/auth/login")]
-pub async fn login(Json(payload): Json<LoginPayload>) -> Result<Account, ServerFnError> {
+pub async fn login(Json(payload): Json<LoginPayload>) -> Result<LoginResponse, ApiError> {
     if payload.email.len() > 254 || payload.password.len() > 128 {
-        return Err(ServerFnError::new("Invalid email or password"));
+        return Err(ApiError::unauthorized("Invalid email or password"));
     }
 
     let email = payload.email.to_lowercase();
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    let account_with_hash = auth_repository::find_account_by_email(&mut *tx, &email)
-        .await?
-        .ok_or_else(|| ServerFnError::new("Invalid email or password"))?;
+    let Some(account_with_hash) = tx.find_account_by_email(&email).await? else {
+        metrics::record_login_failure("password");
+        return Err(ApiError::unauthorized("Invalid email or password"));
+    };
 
     if is_account_locked(account_with_hash.locked_until, Utc::now().naive_utc()) {
-        return Err(ServerFnError::new("Account temporarily locked. Try again later."));
+        metrics::record_login_failure("locked");
+        return Err(ApiError::locked("Account temporarily locked. Try again later."));
     }
 
-    let hash_str = account_with_hash.password_hash.clone();
+    // Accounts created through an identity provider have no password until they reset one
+    let Some(hash_str) = account_with_hash.password_hash.clone() else {
+        metrics::record_login_failure("password");
+        return Err(ApiError::unauthorized("Invalid email or password"));
+    };
     let password_bytes = payload.password.into_bytes();
     let verified = tokio::task::spawn_blocking(move || {
-        PasswordHash::new(&hash_str)
-            .map_err(|e| e.to_string())
-            .and_then(|parsed| {
-                Argon2::default()
-                    .verify_password(&password_bytes, &parsed)
-                    .map_err(|e| e.to_string())
-            })
+        PasswordHash::new(&hash_str).map_err(|e| e.to_string()).and_then(|parsed| {
+            Argon2::default().verify_password(&password_bytes, &parsed).map_err(|e| e.to_string())
+        })
     })
     .await
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
+    .map_err(ApiError::internal)?;
 
     if verified.is_err() {
-        auth_repository::increment_failed_login(&mut *tx, account_with_hash.id)
-            .await
-            .map_err(|e| {
-                eprintln!("ERROR: Failed to increment failed_login_count: {}", e);
-                e
-            })?;
-        tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-        return Err(ServerFnError::new("Invalid email or password"));
+        record_failed_login(&mut *tx, account_with_hash.id, "password").await?;
+        tx.commit().await?;
+        return Err(ApiError::unauthorized("Invalid email or password"));
     }
 
-    auth_repository::reset_failed_login(&mut *tx, account_with_hash.id)
-        .await
-        .map_err(|e| {
-            eprintln!("ERROR: Failed to reset failed_login_count: {}", e);
-            e
-        })?;
-    create_session_and_set_cookie(&mut *tx, account_with_hash.id).await?;
-    resolve_pending_invitations(
+    let account = Account {
+        id: account_with_hash.id,
+        email: account_with_hash.email,
+        display_name: account_with_hash.display_name,
+        created_at: account_with_hash.created_at,
+        email_verified_at: account_with_hash.email_verified_at,
+        two_factor_enabled: account_with_hash.two_factor_enabled,
+    };
+    let response = begin_sign_in(&mut *tx, account).await?;
+
+    tx.commit().await?;
+
+    Ok(response)
+}
+
+/// Second step of the login for accounts with 2FA: trade the challenge and a TOTP or recovery
+/// code for a session.
+#[post("/api/v1/auth/login/two-factor")]
+pub async fn login_two_factor(
+    Json(payload): Json<TwoFactorLoginPayload>,
+) -> Result<Account, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let expired = || ApiError::unauthorized("This sign-in has expired. Sign in again.");
+    let challenge = tx
+        .find_login_challenge_for_update(&sha256_hex(&payload.challenge))
+        .await?
+        .ok_or_else(expired)?;
+
+    if challenge.attempts >= MAX_LOGIN_CHALLENGE_ATTEMPTS {
+        tx.delete_login_challenge(challenge.id).await?;
+        tx.commit().await?;
+        return Err(expired());
+    }
+
+    let now = Utc::now();
+    let verified = two_factor_controller::verify_second_factor(
         &mut *tx,
-        account_with_hash.id,
-        &account_with_hash.email,
-        &account_with_hash.display_name,
+        challenge.account_id,
+        &payload.code,
+        now.timestamp(),
     )
     .await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    if !verified {
+        tx.increment_login_challenge_attempts(challenge.id).await?;
+        record_failed_login(&mut *tx, challenge.account_id, "two_factor").await?;
+        tx.commit().await?;
+        return Err(ApiError::validation("Invalid code"));
+    }
 
-    Ok(Account {
-        id: account_with_hash.id,
-        email: account_with_hash.email,
-        display_name: account_with_hash.display_name,
-        created_at: account_with_hash.created_at,
-    })
+    tx.delete_login_challenge(challenge.id).await?;
+    let account = tx
+        .get_account_by_id(challenge.account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+    complete_login(&mut *tx, &account).await?;
+
+    tx.commit().await?;
+
+    Ok(account)
 }
 
 #[post("/api/v1/auth/logout")]
-pub async fn logout() -> Result<(), ServerFnError> {
-    let ctx = FullstackContext::current()
-        .ok_or_else(|| ServerFnError::new("No server context"))?;
-
-    let session_id_opt = {
-        let parts = ctx.parts_mut();
-        parts
-            .headers
-            .get("cookie")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| {
-                s.split(';')
-                    .map(|p| p.trim())
-                    .find(|p| p.starts_with("session_id="))
-                    .and_then(|p| p.strip_prefix("session_id="))
-                    .map(|p| p.to_owned())
-            })
-            .and_then(|id| id.parse::<Uuid>().ok())
-    };
-
-    if let Some(session_id) = session_id_opt {
-        let pool = get_db().await;
-        let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-        let _ = auth_repository::delete_session(&mut *tx, session_id).await;
+pub async fn logout() -> Result<(), ApiError> {
+    if let Some(token) = sessions::request_session_token() {
+        let pool = get_db();
+        let mut tx = pool.begin().await?;
+        let _ = tx.delete_session(&sha256_hex(&token)).await;
         let _ = tx.commit().await;
     }
 
-    let secure_attr =
-        if std::env::var("COOKIE_SECURE").as_deref() == Ok("false") { "" } else { "; Secure" };
-    let clear_cookie_value = format!(
-        "session_id=; HttpOnly; SameSite=Lax; Path=/; Max-Age=0{}",
-        secure_attr
-    );
-    let clear_cookie = HeaderValue::from_str(&clear_cookie_value)
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-    ctx.add_response_header(SET_COOKIE, clear_cookie);
+    sessions::set_response_cookie(&sessions::cleared_session_cookie())
+}
+
+/// Sign the account out of every device, this one included.
+#[post("/api/v1/auth/logout-all")]
+pub async fn logout_everywhere() -> Result<(), ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    tx.delete_sessions_for_account(account_id).await?;
+
+    tx.commit().await?;
+
+    sessions::set_response_cookie(&sessions::cleared_session_cookie())
+}
+
+#[get("/api/v1/auth/sessions")]
+pub async fn list_sessions() -> Result<Vec<SessionInfo>, ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+    let current_token_hash = sessions::request_session_token().map(|t| sha256_hex(&t));
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let sessions = tx.list_sessions(account_id, current_token_hash.as_deref()).await?;
+
+    tx.commit().await?;
+
+    Ok(sessions)
+}
+
+#[delete("/api/v1/auth/sessions/{session_id}")]
+pub async fn revoke_session(session_id: Uuid) -> Result<(), ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    if !tx.delete_account_session(account_id, session_id).await? {
+        return Err(ApiError::not_found("Session not found"));
+    }
+
+    tx.commit().await?;
 
     Ok(())
 }
 
 #[get("/api/v1/auth/me")]
-pub async fn me() -> Result<Option<Account>, ServerFnError> {
+pub async fn me() -> Result<Option<Account>, ApiError> {
     let Some(account_id) = get_current_account_id().await else {
         return Ok(None);
     };
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let account = tx.get_account_by_id(account_id).await?;
+
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+/// Send a fresh verification link to the current account. Earlier links stop working.
+#[post("/api/v1/auth/verify-email/request")]
+pub async fn request_email_verification() -> Result<(), ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let account = tx
+        .get_account_by_id(account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+
+    let verification = match account.email_verified_at {
+        None => Some(issue_verification_email(&mut *tx, &account).await?),
+        Some(_) => None,
+    };
+
+    tx.commit().await?;
+
+    if let Some(verification) = verification {
+        mailer::send_in_background(verification);
+    }
+
+    Ok(())
+}
+
+/// Confirm an email address from the emailed link, then link the invitations sent to it.
+#[post("/api/v1/auth/verify-email")]
+pub async fn verify_email(Json(payload): Json<VerifyEmailPayload>) -> Result<Account, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let account_id = tx
+        .consume_account_token(&sha256_hex(&payload.token), AccountTokenPurpose::EmailVerification)
+        .await?
+        .ok_or_else(|| ApiError::validation("This link is invalid or has expired"))?;
+
+    tx.mark_email_verified(account_id).await?;
+
+    let account = tx
+        .get_account_by_id(account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
 
-    let account = auth_repository::get_account_by_id(&mut *tx, account_id).await?;
+    resolve_pending_invitations(&mut *tx, account.id, &account.email, &account.display_name)
+        .await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(account)
 }
 
+/// Email a password reset link. Always succeeds, so the response never reveals whether an
+/// account exists for the address.
+#[post("/api/v1/auth/password-reset/request")]
+pub async fn request_password_reset(
+    Json(payload): Json<PasswordResetRequestPayload>,
+) -> Result<(), ApiError> {
+    if payload.email.len() > 254 {
+        return Ok(());
+    }
+
+    let email = payload.email.to_lowercase();
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let Some(account) = tx.find_account_by_email(&email).await? else {
+        return Ok(());
+    };
+
+    let token = issue_account_token(
+        &mut *tx,
+        account.id,
+        AccountTokenPurpose::PasswordReset,
+        Duration::minutes(PASSWORD_RESET_TTL_MINUTES),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    let locale = Locale::from_accept_language(get_request_header("accept-language").as_deref());
+    let link = format!("{}/reset-password/{}", get_config().app_base_url, token);
+    mailer::send_in_background(password_reset_email(
+        locale,
+        &account.email,
+        &account.display_name,
+        &link,
+    ));
+
+    Ok(())
+}
+
+/// Set a new password from the emailed link, which also verifies the address and so links the
+/// invitations sent to it. Every existing session is signed out.
+#[post("/api/v1/auth/password-reset")]
+pub async fn reset_password(Json(payload): Json<PasswordResetPayload>) -> Result<(), ApiError> {
+    if !is_valid_new_password(&payload.password) {
+        return Err(ApiError::validation("Password must be at least 8 characters long"));
+    }
+
+    let password_hash = hash_password(payload.password).await?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let account_id = tx
+        .consume_account_token(&sha256_hex(&payload.token), AccountTokenPurpose::PasswordReset)
+        .await?
+        .ok_or_else(|| ApiError::validation("This link is invalid or has expired"))?;
+
+    tx.update_password_hash(account_id, &password_hash).await?;
+    // Receiving the link proves the address belongs to the account holder
+    tx.mark_email_verified(account_id).await?;
+    tx.delete_sessions_for_account(account_id).await?;
+
+    let account = tx
+        .get_account_by_id(account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+    resolve_pending_invitations(&mut *tx, account.id, &account.email, &account.display_name)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+async fn hash_password(password: String) -> Result<String, ApiError> {
+    let password_bytes = password.into_bytes();
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default().hash_password(&password_bytes, &salt).map(|h| h.to_string())
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map_err(ApiError::internal)
+}
+
+/// Store a new single-use token for this purpose, replacing the unused ones, and return it.
+#[cfg(feature = "server")]
+async fn issue_account_token(
+    executor: &mut dyn Store,
+    account_id: Uuid,
+    purpose: AccountTokenPurpose,
+    ttl: Duration,
+) -> Result<String, ApiError> {
+    executor.delete_unused_account_tokens(account_id, purpose).await?;
+
+    let token = generate_token();
+    let expires_at = (Utc::now() + ttl).naive_utc();
+    executor.create_account_token(account_id, purpose, &sha256_hex(&token), expires_at).await?;
+
+    Ok(token)
+}
+
+/// Store a new verification token and return the email carrying its link, to send once the
+/// transaction commits.
+#[cfg(feature = "server")]
+async fn issue_verification_email(
+    executor: &mut dyn Store,
+    account: &Account,
+) -> Result<Email, ApiError> {
+    let token = issue_account_token(
+        executor,
+        account.id,
+        AccountTokenPurpose::EmailVerification,
+        Duration::hours(EMAIL_VERIFICATION_TTL_HOURS),
+    )
+    .await?;
+
+    let locale = Locale::from_accept_language(get_request_header("accept-language").as_deref());
+    let link = format!("{}/verify-email/{}", get_config().app_base_url, token);
+    Ok(verification_email(locale, &account.email, &account.display_name, &link))
+}
+
 /// Resolve any pending invitations for this email: update user names and create account_projects entries.
 #[cfg(feature = "server")]
-async fn resolve_pending_invitations(
-    executor: &mut PgConnection,
+pub(crate) async fn resolve_pending_invitations(
+    executor: &mut dyn Store,
     account_id: Uuid,
     email: &str,
     display_name: &str,
-) -> Result<(), ServerFnError> {
+) -> Result<(), ApiError> {
     let email_hash = sha256_hex(email); // caller must pass already-lowercased email
 
-    let rows: Vec<(i32, Uuid)> = sqlx::query_as(
-        "SELECT u.id, up.project_id FROM users u \
-         JOIN user_projects up ON up.user_id = u.id \
-         WHERE u.email_hash = $1",
-    )
-    .bind(&email_hash)
-    .fetch_all(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to resolve pending invitations: {}", e)))?;
+    let participants = executor.find_invited_participants(&email_hash).await?;
 
-    for (user_id, project_id) in rows {
-        sqlx::query("UPDATE users SET name = $1 WHERE id = $2")
-            .bind(display_name)
-            .bind(user_id)
-            .execute(&mut *executor)
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to update invited user name: {}", e)))?;
+    for participant in participants {
+        executor.rename_user(participant.user_id, display_name).await?;
 
-        account_projects_repository::upsert_account_project(
-            &mut *executor,
+        executor
+            .upsert_account_project(account_id, participant.project_id, Some(participant.user_id))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// The first factor (password or identity provider) is verified: sign in, or with 2FA issue the
+/// challenge for the second step.
+///
+/// With 2FA the first factor only buys a short-lived challenge; failures keep counting towards
+/// the lockout until the second factor is verified too.
+#[cfg(feature = "server")]
+pub(crate) async fn begin_sign_in(
+    executor: &mut dyn Store,
+    account: Account,
+) -> Result<LoginResponse, ApiError> {
+    if account.two_factor_enabled {
+        let challenge = generate_token();
+        let expires_at = (Utc::now() + Duration::minutes(LOGIN_CHALLENGE_TTL_MINUTES)).naive_utc();
+        executor.create_login_challenge(account.id, &sha256_hex(&challenge), expires_at).await?;
+        return Ok(LoginResponse::TwoFactorRequired { two_factor_challenge: challenge });
+    }
+
+    complete_login(&mut *executor, &account).await?;
+
+    Ok(LoginResponse::SignedIn(account))
+}
+
+/// Count a failed sign-in; enough of them in a row lock the account.
+#[cfg(feature = "server")]
+async fn record_failed_login(
+    executor: &mut dyn Store,
+    account_id: Uuid,
+    step: &str,
+) -> Result<(), ApiError> {
+    metrics::record_login_failure(step);
+    let config = get_config();
+    let locked = executor
+        .increment_failed_login(
             account_id,
-            project_id,
-            Some(user_id),
+            config.auth.lockout_threshold,
+            config.auth.lockout_duration,
+        )
+        .await?;
+    if locked {
+        metrics::record_lockout();
+    }
+    Ok(())
+}
+
+/// Every factor is verified: clear the lockout counter, open the session and link the invitations
+/// of a verified address.
+#[cfg(feature = "server")]
+async fn complete_login(executor: &mut dyn Store, account: &Account) -> Result<(), ApiError> {
+    executor.reset_failed_login(account.id).await?;
+    create_session_and_set_cookie(&mut *executor, account.id).await?;
+    if account.email_verified_at.is_some() {
+        resolve_pending_invitations(
+            &mut *executor,
+            account.id,
+            &account.email,
+            &account.display_name,
         )
         .await?;
     }
@@ -241,28 +545,34 @@ async fn resolve_pending_invitations(
 }
 
 /// Create a session in the DB and set the HttpOnly session cookie on the response.
+///
+/// A session already carried by the request is deleted first, so signing in always issues a
+/// fresh token.
 #[cfg(feature = "server")]
 async fn create_session_and_set_cookie(
-    executor: &mut PgConnection,
+    executor: &mut dyn Store,
     account_id: Uuid,
-) -> Result<Uuid, ServerFnError> {
-    let expires_at = (Utc::now() + Duration::days(30)).naive_utc();
-    let session_id = auth_repository::create_session(executor, account_id, expires_at).await?;
-
-    let secure_attr =
-        if std::env::var("COOKIE_SECURE").as_deref() == Ok("false") { "" } else { "; Secure" };
-
-    let cookie_value = format!(
-        "session_id={}; HttpOnly; SameSite=Lax; Path=/; Max-Age=2592000{}",
-        session_id, secure_attr
-    );
+) -> Result<Uuid, ApiError> {
+    if let Some(previous) = sessions::request_session_token() {
+        executor.delete_session(&sha256_hex(&previous)).await?;
+    }
 
-    let header_value =
-        HeaderValue::from_str(&cookie_value).map_err(|e| ServerFnError::new(e.to_string()))?;
+    let token = generate_token();
+    let now = Utc::now().naive_utc();
+    let expires_at = sessions::session_expiry(&get_config().auth, now, now);
+    let user_agent = get_request_header("user-agent")
+        .map(|ua| ua.chars().take(MAX_USER_AGENT_LENGTH).collect::<String>());
+    let session_id = executor
+        .create_session(
+            account_id,
+            &sha256_hex(&token),
+            expires_at,
+            user_agent.as_deref(),
+            get_client_ip().as_deref(),
+        )
+        .await?;
 
-    let ctx = FullstackContext::current()
-        .ok_or_else(|| ServerFnError::new("No server context"))?;
-    ctx.add_response_header(SET_COOKIE, header_value);
+    sessions::set_response_cookie(&sessions::session_cookie(&token, expires_at, now))?;
 
     Ok(session_id)
 }
@@ -293,6 +603,18 @@ mod tests {
         assert!(is_account_locked(Some(future), now()));
     }
 
+    #[test]
+    fn new_password_length_is_bounded() {
+        assert!(!is_valid_new_password(""));
+        assert!(!is_valid_new_password("1234567"));
+        assert!(is_valid_new_password("12345678"));
+        assert!(is_valid_new_password(&"a".repeat(128)));
+        assert!(!is_valid_new_password(&"a".repeat(129)));
+        // Minimum counted in characters, maximum in bytes
+        assert!(is_valid_new_password("éééééééé"));
+        assert!(!is_valid_new_password(&"é".repeat(65)));
+    }
+
     #[test]
     fn not_locked_exactly_at_expiry() {
         // locked_until == now: not strictly greater, so not locked
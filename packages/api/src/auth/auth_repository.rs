@@ -2,9 +2,14 @@ use chrono::NaiveDateTime;
 use dioxus::prelude::*;
 use uuid::Uuid;
 
+#[cfg(feature = "server")]
+use crate::error::{db_error, ApiError};
+
+#[cfg(feature = "server")]
+use futures::future::BoxFuture;
+use shared::{Account, ApiTokenInfo, ApiTokenScope, LinkedIdentity, SessionInfo};
 #[cfg(feature = "server")]
 use sqlx::PgConnection;
-use shared::Account;
 
 #[cfg(feature = "server")]
 #[derive(sqlx::FromRow)]
@@ -13,147 +18,1230 @@ pub struct AccountWithHash {
     pub email: String,
     pub display_name: String,
     pub created_at: NaiveDateTime,
-    pub password_hash: String,
+    /// `None` for accounts created through an identity provider.
+    pub password_hash: Option<String>,
     pub failed_login_count: i32,
     pub locked_until: Option<NaiveDateTime>,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub two_factor_enabled: bool,
 }
 
 #[cfg(feature = "server")]
-pub async fn create_account(
-    executor: &mut PgConnection,
-    email: &str,
-    password_hash: &str,
-    display_name: &str,
-) -> Result<Uuid, ServerFnError> {
-    let id: Uuid = sqlx::query_scalar!(
-        "INSERT INTO accounts (email, password_hash, display_name) VALUES ($1, $2, $3) RETURNING id",
-        email,
-        password_hash,
-        display_name
-    )
-    .fetch_one(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to create account: {}", e)))?;
-
-    Ok(id)
+#[derive(sqlx::FromRow)]
+pub struct ActiveSession {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
 }
 
 #[cfg(feature = "server")]
-pub async fn find_account_by_email(
-    executor: &mut PgConnection,
-    email: &str,
-) -> Result<Option<AccountWithHash>, ServerFnError> {
-    let account = sqlx::query_as!(
-        AccountWithHash,
-        "SELECT id, email, display_name, created_at, password_hash, failed_login_count, locked_until FROM accounts WHERE email = $1",
-        email
-    )
-    .fetch_optional(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to find account by email: {}", e)))?;
-
-    Ok(account)
+#[derive(Debug, Clone, Copy, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "account_token_purpose", rename_all = "snake_case")]
+pub enum AccountTokenPurpose {
+    EmailVerification,
+    PasswordReset,
 }
 
 #[cfg(feature = "server")]
-pub async fn get_account_by_id(
-    executor: &mut PgConnection,
-    id: Uuid,
-) -> Result<Option<Account>, ServerFnError> {
-    let account = sqlx::query_as!(
-        Account,
-        "SELECT id, email, display_name, created_at FROM accounts WHERE id = $1",
-        id
-    )
-    .fetch_optional(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to get account by id: {}", e)))?;
-
-    Ok(account)
+#[derive(sqlx::FromRow)]
+pub struct TotpState {
+    pub totp_secret: Option<String>,
+    pub totp_pending_secret: Option<String>,
+    pub totp_last_used_step: Option<i64>,
 }
 
 #[cfg(feature = "server")]
-pub async fn increment_failed_login(
-    executor: &mut PgConnection,
-    id: Uuid,
-) -> Result<(), ServerFnError> {
-    sqlx::query!(
-        "UPDATE accounts
-         SET
-           failed_login_count = failed_login_count + 1,
-           locked_until = CASE
-             WHEN failed_login_count + 1 >= 5 THEN NOW() + INTERVAL '15 minutes'
-             ELSE locked_until
-           END
-         WHERE id = $1",
-        id
-    )
-    .execute(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to increment failed login count: {}", e)))?;
-
-    Ok(())
+#[derive(sqlx::FromRow)]
+pub struct LoginChallenge {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub attempts: i32,
 }
 
 #[cfg(feature = "server")]
-pub async fn reset_failed_login(
-    executor: &mut PgConnection,
-    id: Uuid,
-) -> Result<(), ServerFnError> {
-    sqlx::query!(
-        "UPDATE accounts SET failed_login_count = 0, locked_until = NULL WHERE id = $1",
-        id
-    )
-    .execute(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to reset failed login count: {}", e)))?;
-
-    Ok(())
+#[derive(sqlx::FromRow)]
+pub struct OidcLoginState {
+    pub code_verifier: String,
+    pub nonce: String,
+    pub link_account_id: Option<Uuid>,
 }
 
 #[cfg(feature = "server")]
-pub async fn create_session(
-    executor: &mut PgConnection,
-    account_id: Uuid,
-    expires_at: NaiveDateTime,
-) -> Result<Uuid, ServerFnError> {
-    let session_id: Uuid = sqlx::query_scalar!(
-        "INSERT INTO sessions (account_id, expires_at) VALUES ($1, $2) RETURNING id",
-        account_id,
-        expires_at
-    )
-    .fetch_one(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to create session: {}", e)))?;
-
-    Ok(session_id)
+pub struct NewApiToken<'a> {
+    pub name: &'a str,
+    pub token_hash: &'a str,
+    pub token_prefix: &'a str,
+    pub scope: ApiTokenScope,
+    pub project_ids: Option<&'a [Uuid]>,
+    pub expires_at: NaiveDateTime,
 }
 
 #[cfg(feature = "server")]
-pub async fn get_session_account_id(
-    executor: &mut PgConnection,
-    session_id: Uuid,
-) -> Result<Option<Uuid>, ServerFnError> {
-    let account_id: Option<Uuid> = sqlx::query_scalar!(
-        "SELECT account_id FROM sessions WHERE id = $1 AND expires_at > NOW()",
-        session_id
-    )
-    .fetch_optional(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to get session: {}", e)))?;
-
-    Ok(account_id)
+pub struct ActiveApiToken {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub scope: ApiTokenScope,
+    pub project_ids: Option<Vec<Uuid>>,
+    pub last_used_at: Option<NaiveDateTime>,
 }
 
 #[cfg(feature = "server")]
-pub async fn delete_session(
-    executor: &mut PgConnection,
-    session_id: Uuid,
-) -> Result<(), ServerFnError> {
-    sqlx::query!("DELETE FROM sessions WHERE id = $1", session_id)
-        .execute(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to delete session: {}", e)))?;
-
-    Ok(())
+pub trait AuthRepository {
+    fn create_account<'a>(
+        &'a mut self,
+        email: &'a str,
+        password_hash: &'a str,
+        display_name: &'a str,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>>;
+
+    /// Account signed up through an identity provider that vouched for the address.
+    fn create_account_without_password<'a>(
+        &'a mut self,
+        email: &'a str,
+        display_name: &'a str,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>>;
+
+    fn find_account_by_email<'a>(
+        &'a mut self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<AccountWithHash>, ApiError>>;
+
+    fn get_account_by_id(&mut self, id: Uuid) -> BoxFuture<'_, Result<Option<Account>, ApiError>>;
+
+    /// Count a failed sign-in and lock the account once `threshold` failures follow each other.
+    /// Returns whether the account is now locked.
+    fn increment_failed_login(
+        &mut self,
+        id: Uuid,
+        threshold: i32,
+        lockout: chrono::Duration,
+    ) -> BoxFuture<'_, Result<bool, ApiError>>;
+
+    fn reset_failed_login(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn create_session<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+        user_agent: Option<&'a str>,
+        ip_address: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>>;
+
+    fn find_active_session<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ActiveSession>, ApiError>>;
+
+    fn touch_session(
+        &mut self,
+        session_id: Uuid,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn list_sessions<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        current_token_hash: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<SessionInfo>, ApiError>>;
+
+    fn delete_session<'a>(&'a mut self, token_hash: &'a str)
+        -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// Delete one session of this account. Returns false if it does not exist or belongs to
+    /// another account.
+    fn delete_account_session(
+        &mut self,
+        account_id: Uuid,
+        session_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>>;
+
+    fn purge_expired_sessions(&mut self) -> BoxFuture<'_, Result<u64, ApiError>>;
+
+    fn delete_sessions_for_account(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn mark_email_verified(&mut self, account_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Replace the password hash and lift any login lockout.
+    fn update_password_hash<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        password_hash: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    // -------- ACCOUNT TOKENS ---------
+
+    fn create_account_token<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        purpose: AccountTokenPurpose,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// Drop the unused tokens of this purpose, so only the most recent email's link works.
+    fn delete_unused_account_tokens(
+        &mut self,
+        account_id: Uuid,
+        purpose: AccountTokenPurpose,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Mark a token as used and return its account, in a single statement so that two concurrent
+    /// requests cannot both consume it. Returns `None` for unknown, expired or already used tokens.
+    fn consume_account_token<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+        purpose: AccountTokenPurpose,
+    ) -> BoxFuture<'a, Result<Option<Uuid>, ApiError>>;
+
+    // -------- TWO-FACTOR AUTHENTICATION ---------
+
+    /// Lock the account row while a code is checked, so a code cannot be accepted twice.
+    fn get_totp_state_for_update(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<TotpState>, ApiError>>;
+
+    fn set_pending_totp_secret<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        secret: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// Promote the pending secret once its first code has been verified at `step`.
+    fn enable_totp(&mut self, account_id: Uuid, step: i64) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn record_totp_step(
+        &mut self,
+        account_id: Uuid,
+        step: i64,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn disable_totp(&mut self, account_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn replace_recovery_codes<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        code_hashes: &'a [String],
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// Mark a recovery code as used. Returns false if it is unknown or already used.
+    fn consume_recovery_code<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        code_hash: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ApiError>>;
+
+    fn create_login_challenge<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    fn find_login_challenge_for_update<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<LoginChallenge>, ApiError>>;
+
+    fn increment_login_challenge_attempts(
+        &mut self,
+        id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn delete_login_challenge(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn purge_expired_login_challenges(&mut self) -> BoxFuture<'_, Result<u64, ApiError>>;
+
+    fn create_oidc_login_state<'a>(
+        &'a mut self,
+        state_hash: &'a str,
+        login_state: &'a OidcLoginState,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// Delete and return a pending authorization request, if it has not expired.
+    fn consume_oidc_login_state<'a>(
+        &'a mut self,
+        state_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<OidcLoginState>, ApiError>>;
+
+    fn purge_expired_oidc_login_states(&mut self) -> BoxFuture<'_, Result<u64, ApiError>>;
+
+    fn find_identity_account_id<'a>(
+        &'a mut self,
+        issuer: &'a str,
+        subject: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Uuid>, ApiError>>;
+
+    fn create_identity<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        issuer: &'a str,
+        subject: &'a str,
+        email: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    fn list_identities(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<LinkedIdentity>, ApiError>>;
+
+    /// Whether the account could still sign in after losing `identity_id`: it has a password or
+    /// another linked identity.
+    fn has_other_sign_in_method(
+        &mut self,
+        account_id: Uuid,
+        identity_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>>;
+
+    fn delete_identity(
+        &mut self,
+        account_id: Uuid,
+        identity_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>>;
+
+    fn create_api_token<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token: NewApiToken<'a>,
+    ) -> BoxFuture<'a, Result<ApiTokenInfo, ApiError>>;
+
+    fn list_api_tokens(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<ApiTokenInfo>, ApiError>>;
+
+    fn delete_api_token(
+        &mut self,
+        account_id: Uuid,
+        token_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>>;
+
+    fn find_active_api_token<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ActiveApiToken>, ApiError>>;
+
+    fn touch_api_token(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn purge_expired_api_tokens(&mut self) -> BoxFuture<'_, Result<u64, ApiError>>;
+}
+
+#[cfg(feature = "server")]
+impl AuthRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn create_account<'a>(
+        &'a mut self,
+        email: &'a str,
+        password_hash: &'a str,
+        display_name: &'a str,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let id: Uuid = sqlx::query_scalar!(
+                "INSERT INTO accounts (email, password_hash, display_name) VALUES ($1, $2, $3) RETURNING id",
+                email,
+                password_hash,
+                display_name
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create account"))?;
+
+            Ok(id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_account_without_password<'a>(
+        &'a mut self,
+        email: &'a str,
+        display_name: &'a str,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let id: Uuid = sqlx::query_scalar!(
+                "INSERT INTO accounts (email, display_name, email_verified_at) VALUES ($1, $2, NOW()) RETURNING id",
+                email,
+                display_name
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create account"))?;
+
+            Ok(id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_account_by_email<'a>(
+        &'a mut self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<AccountWithHash>, ApiError>> {
+        Box::pin(async move {
+            let account = sqlx::query_as!(
+                AccountWithHash,
+                r#"SELECT id, email, display_name, created_at, password_hash, failed_login_count, locked_until, email_verified_at,
+                          totp_enabled_at IS NOT NULL AS "two_factor_enabled!"
+                   FROM accounts WHERE email = $1"#,
+                email
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find account by email"))?;
+
+            Ok(account)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_account_by_id(&mut self, id: Uuid) -> BoxFuture<'_, Result<Option<Account>, ApiError>> {
+        Box::pin(async move {
+            let account = sqlx::query_as!(
+                Account,
+                r#"SELECT id, email, display_name, created_at, email_verified_at,
+                          totp_enabled_at IS NOT NULL AS "two_factor_enabled!"
+                   FROM accounts WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get account by id"))?;
+
+            Ok(account)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn increment_failed_login(
+        &mut self,
+        id: Uuid,
+        threshold: i32,
+        lockout: chrono::Duration,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let locked = sqlx::query_scalar!(
+                r#"UPDATE accounts
+                 SET
+                   failed_login_count = failed_login_count + 1,
+                   locked_until = CASE
+                     WHEN failed_login_count + 1 >= $2 THEN NOW() + $3::bigint * INTERVAL '1 minute'
+                     ELSE locked_until
+                   END
+                 WHERE id = $1
+                 RETURNING failed_login_count >= $2 AS "locked!""#,
+                id,
+                threshold,
+                lockout.num_minutes()
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to increment failed login count"))?;
+
+            Ok(locked)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn reset_failed_login(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE accounts SET failed_login_count = 0, locked_until = NULL WHERE id = $1",
+                id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to reset failed login count"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_session<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+        user_agent: Option<&'a str>,
+        ip_address: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let session_id: Uuid = sqlx::query_scalar!(
+                "INSERT INTO sessions (account_id, token_hash, expires_at, user_agent, ip_address)
+                 VALUES ($1, $2, $3, $4, $5)
+                 RETURNING id",
+                account_id,
+                token_hash,
+                expires_at,
+                user_agent,
+                ip_address
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create session"))?;
+
+            Ok(session_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_active_session<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ActiveSession>, ApiError>> {
+        Box::pin(async move {
+            let session = sqlx::query_as!(
+                ActiveSession,
+                "SELECT id, account_id, created_at, last_seen_at FROM sessions
+                 WHERE token_hash = $1 AND expires_at > NOW()",
+                token_hash
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get session"))?;
+
+            Ok(session)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn touch_session(
+        &mut self,
+        session_id: Uuid,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE sessions SET last_seen_at = NOW(), expires_at = $1 WHERE id = $2",
+                expires_at,
+                session_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to refresh session"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn list_sessions<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        current_token_hash: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<SessionInfo>, ApiError>> {
+        Box::pin(async move {
+            let sessions = sqlx::query_as!(
+                SessionInfo,
+                r#"SELECT id, created_at, last_seen_at, expires_at, user_agent, ip_address,
+                          token_hash IS NOT DISTINCT FROM $2 AS "current!"
+                   FROM sessions
+                   WHERE account_id = $1 AND expires_at > NOW()
+                   ORDER BY last_seen_at DESC"#,
+                account_id,
+                current_token_hash
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to list sessions"))?;
+
+            Ok(sessions)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_session<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM sessions WHERE token_hash = $1", token_hash)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete session"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_account_session(
+        &mut self,
+        account_id: Uuid,
+        session_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!(
+                "DELETE FROM sessions WHERE id = $1 AND account_id = $2",
+                session_id,
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to delete session"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_sessions(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!("DELETE FROM sessions WHERE expires_at <= NOW()")
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge expired sessions"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_sessions_for_account(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM sessions WHERE account_id = $1", account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete sessions"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn mark_email_verified(&mut self, account_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE accounts SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE id = $1",
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to mark email as verified"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn update_password_hash<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        password_hash: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE accounts
+                 SET password_hash = $1, failed_login_count = 0, locked_until = NULL
+                 WHERE id = $2",
+                password_hash,
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to update password"))?;
+
+            Ok(())
+        })
+    }
+
+    // -------- ACCOUNT TOKENS ---------
+
+    #[tracing::instrument(skip_all)]
+    fn create_account_token<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        purpose: AccountTokenPurpose,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "INSERT INTO account_tokens (account_id, purpose, token_hash, expires_at)
+                 VALUES ($1, $2, $3, $4)",
+                account_id,
+                purpose as AccountTokenPurpose,
+                token_hash,
+                expires_at
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create account token"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_unused_account_tokens(
+        &mut self,
+        account_id: Uuid,
+        purpose: AccountTokenPurpose,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "DELETE FROM account_tokens WHERE account_id = $1 AND purpose = $2 AND used_at IS NULL",
+                account_id,
+                purpose as AccountTokenPurpose
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to delete account tokens"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn consume_account_token<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+        purpose: AccountTokenPurpose,
+    ) -> BoxFuture<'a, Result<Option<Uuid>, ApiError>> {
+        Box::pin(async move {
+            let account_id: Option<Uuid> = sqlx::query_scalar!(
+                "UPDATE account_tokens
+                 SET used_at = NOW()
+                 WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+                 RETURNING account_id",
+                token_hash,
+                purpose as AccountTokenPurpose
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to consume account token"))?;
+
+            Ok(account_id)
+        })
+    }
+
+    // -------- TWO-FACTOR AUTHENTICATION ---------
+
+    #[tracing::instrument(skip_all)]
+    fn get_totp_state_for_update(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<TotpState>, ApiError>> {
+        Box::pin(async move {
+            let state = sqlx::query_as!(
+                TotpState,
+                "SELECT totp_secret, totp_pending_secret, totp_last_used_step FROM accounts
+                 WHERE id = $1 FOR UPDATE",
+                account_id
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get two-factor state"))?;
+
+            Ok(state)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn set_pending_totp_secret<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        secret: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE accounts SET totp_pending_secret = $1 WHERE id = $2",
+                secret,
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to store two-factor secret"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn enable_totp(&mut self, account_id: Uuid, step: i64) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE accounts
+                 SET totp_secret = totp_pending_secret,
+                     totp_pending_secret = NULL,
+                     totp_enabled_at = NOW(),
+                     totp_last_used_step = $1
+                 WHERE id = $2 AND totp_pending_secret IS NOT NULL",
+                step,
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to enable two-factor authentication"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn record_totp_step(
+        &mut self,
+        account_id: Uuid,
+        step: i64,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE accounts SET totp_last_used_step = $1 WHERE id = $2",
+                step,
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to record two-factor code"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn disable_totp(&mut self, account_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE accounts
+                 SET totp_secret = NULL, totp_pending_secret = NULL, totp_enabled_at = NULL,
+                     totp_last_used_step = NULL
+                 WHERE id = $1",
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to disable two-factor authentication"))?;
+
+            sqlx::query!("DELETE FROM account_recovery_codes WHERE account_id = $1", account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete recovery codes"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn replace_recovery_codes<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        code_hashes: &'a [String],
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM account_recovery_codes WHERE account_id = $1", account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete recovery codes"))?;
+
+            sqlx::query!(
+                "INSERT INTO account_recovery_codes (account_id, code_hash)
+                 SELECT $1, UNNEST($2::VARCHAR[])",
+                account_id,
+                code_hashes
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to store recovery codes"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn consume_recovery_code<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        code_hash: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!(
+                "UPDATE account_recovery_codes SET used_at = NOW()
+                 WHERE id = (
+                   SELECT id FROM account_recovery_codes
+                   WHERE account_id = $1 AND code_hash = $2 AND used_at IS NULL
+                   LIMIT 1
+                 )",
+                account_id,
+                code_hash
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to use recovery code"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_login_challenge<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "INSERT INTO login_challenges (account_id, token_hash, expires_at) VALUES ($1, $2, $3)",
+                account_id,
+                token_hash,
+                expires_at
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create login challenge"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_login_challenge_for_update<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<LoginChallenge>, ApiError>> {
+        Box::pin(async move {
+            let challenge = sqlx::query_as!(
+                LoginChallenge,
+                "SELECT id, account_id, attempts FROM login_challenges
+                 WHERE token_hash = $1 AND expires_at > NOW()
+                 FOR UPDATE",
+                token_hash
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get login challenge"))?;
+
+            Ok(challenge)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn increment_login_challenge_attempts(
+        &mut self,
+        id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("UPDATE login_challenges SET attempts = attempts + 1 WHERE id = $1", id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to update login challenge"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_login_challenge(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM login_challenges WHERE id = $1", id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete login challenge"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_login_challenges(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!("DELETE FROM login_challenges WHERE expires_at <= NOW()")
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge login challenges"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_oidc_login_state<'a>(
+        &'a mut self,
+        state_hash: &'a str,
+        login_state: &'a OidcLoginState,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "INSERT INTO oidc_login_states (state_hash, code_verifier, nonce, link_account_id, expires_at)
+                 VALUES ($1, $2, $3, $4, $5)",
+                state_hash,
+                login_state.code_verifier,
+                login_state.nonce,
+                login_state.link_account_id,
+                expires_at
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create OIDC login state"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn consume_oidc_login_state<'a>(
+        &'a mut self,
+        state_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<OidcLoginState>, ApiError>> {
+        Box::pin(async move {
+            let login_state = sqlx::query_as!(
+                OidcLoginState,
+                "DELETE FROM oidc_login_states
+                 WHERE state_hash = $1 AND expires_at > NOW()
+                 RETURNING code_verifier, nonce, link_account_id",
+                state_hash
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to consume OIDC login state"))?;
+
+            Ok(login_state)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_oidc_login_states(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!("DELETE FROM oidc_login_states WHERE expires_at <= NOW()")
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge OIDC login states"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_identity_account_id<'a>(
+        &'a mut self,
+        issuer: &'a str,
+        subject: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Uuid>, ApiError>> {
+        Box::pin(async move {
+            let account_id = sqlx::query_scalar!(
+                "SELECT account_id FROM account_identities WHERE issuer = $1 AND subject = $2",
+                issuer,
+                subject
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find identity"))?;
+
+            Ok(account_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_identity<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        issuer: &'a str,
+        subject: &'a str,
+        email: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "INSERT INTO account_identities (account_id, issuer, subject, email) VALUES ($1, $2, $3, $4)",
+                account_id,
+                issuer,
+                subject,
+                email
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to link identity"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn list_identities(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<LinkedIdentity>, ApiError>> {
+        Box::pin(async move {
+            let identities = sqlx::query_as!(
+                LinkedIdentity,
+                "SELECT id, issuer, email, created_at FROM account_identities
+                 WHERE account_id = $1
+                 ORDER BY created_at",
+                account_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to list identities"))?;
+
+            Ok(identities)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn has_other_sign_in_method(
+        &mut self,
+        account_id: Uuid,
+        identity_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query_scalar!(
+                r#"SELECT (a.password_hash IS NOT NULL
+                           OR EXISTS (SELECT 1 FROM account_identities i WHERE i.account_id = a.id AND i.id <> $2))
+                          AS "result!"
+                   FROM accounts a WHERE a.id = $1
+                   FOR UPDATE"#,
+                account_id,
+                identity_id
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to check sign-in methods"))?;
+
+            Ok(result)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_identity(
+        &mut self,
+        account_id: Uuid,
+        identity_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!(
+                "DELETE FROM account_identities WHERE id = $1 AND account_id = $2",
+                identity_id,
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to unlink identity"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_api_token<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token: NewApiToken<'a>,
+    ) -> BoxFuture<'a, Result<ApiTokenInfo, ApiError>> {
+        Box::pin(async move {
+            let info = sqlx::query_as!(
+                ApiTokenInfo,
+                r#"INSERT INTO api_tokens (account_id, name, token_hash, token_prefix, scope, project_ids, expires_at)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7)
+                   RETURNING id, name, token_prefix, scope AS "scope: ApiTokenScope", project_ids,
+                             expires_at, last_used_at, created_at"#,
+                account_id,
+                token.name,
+                token.token_hash,
+                token.token_prefix,
+                token.scope as ApiTokenScope,
+                token.project_ids,
+                token.expires_at
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create API token"))?;
+
+            Ok(info)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn list_api_tokens(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<ApiTokenInfo>, ApiError>> {
+        Box::pin(async move {
+            let tokens = sqlx::query_as!(
+                ApiTokenInfo,
+                r#"SELECT id, name, token_prefix, scope AS "scope: ApiTokenScope", project_ids,
+                          expires_at, last_used_at, created_at
+                   FROM api_tokens
+                   WHERE account_id = $1
+                   ORDER BY created_at DESC"#,
+                account_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to list API tokens"))?;
+
+            Ok(tokens)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_api_token(
+        &mut self,
+        account_id: Uuid,
+        token_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!(
+                "DELETE FROM api_tokens WHERE id = $1 AND account_id = $2",
+                token_id,
+                account_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to revoke API token"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_active_api_token<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ActiveApiToken>, ApiError>> {
+        Box::pin(async move {
+            let token = sqlx::query_as!(
+                ActiveApiToken,
+                r#"SELECT id, account_id, scope AS "scope: ApiTokenScope", project_ids, last_used_at
+                   FROM api_tokens
+                   WHERE token_hash = $1 AND expires_at > NOW()"#,
+                token_hash
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find API token"))?;
+
+            Ok(token)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn touch_api_token(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("UPDATE api_tokens SET last_used_at = NOW() WHERE id = $1", id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to update API token"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_api_tokens(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!("DELETE FROM api_tokens WHERE expires_at <= NOW()")
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge API tokens"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
 }
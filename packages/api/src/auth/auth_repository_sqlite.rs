@@ -0,0 +1,1002 @@
+use chrono::{NaiveDateTime, Utc};
+use futures::future::BoxFuture;
+use shared::{Account, ApiTokenInfo, ApiTokenScope, LinkedIdentity, SessionInfo};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+use super::auth_repository::{
+    AccountTokenPurpose, AccountWithHash, ActiveApiToken, ActiveSession, AuthRepository,
+    LoginChallenge, NewApiToken, OidcLoginState, TotpState,
+};
+use crate::error::{db_error, ApiError};
+
+const ACCOUNT_COLUMNS: &str = "id, email, display_name, created_at, email_verified_at,
+     totp_enabled_at IS NOT NULL AS two_factor_enabled";
+
+const API_TOKEN_COLUMNS: &str =
+    "id, name, token_prefix, scope, project_ids, expires_at, last_used_at, created_at";
+
+/// SQLite has no arrays: `api_tokens.project_ids` holds a JSON array of UUIDs.
+#[derive(sqlx::FromRow)]
+struct ApiTokenRow {
+    id: Uuid,
+    name: String,
+    token_prefix: String,
+    scope: ApiTokenScope,
+    project_ids: Option<String>,
+    expires_at: NaiveDateTime,
+    last_used_at: Option<NaiveDateTime>,
+    created_at: NaiveDateTime,
+}
+
+impl TryFrom<ApiTokenRow> for ApiTokenInfo {
+    type Error = ApiError;
+
+    fn try_from(row: ApiTokenRow) -> Result<Self, ApiError> {
+        Ok(ApiTokenInfo {
+            id: row.id,
+            name: row.name,
+            token_prefix: row.token_prefix,
+            scope: row.scope,
+            project_ids: decode_project_ids(row.project_ids)?,
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ActiveApiTokenRow {
+    id: Uuid,
+    account_id: Uuid,
+    scope: ApiTokenScope,
+    project_ids: Option<String>,
+    last_used_at: Option<NaiveDateTime>,
+}
+
+fn decode_project_ids(project_ids: Option<String>) -> Result<Option<Vec<Uuid>>, ApiError> {
+    project_ids
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| ApiError::internal(format!("Invalid API token project ids: {e}")))
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+impl AuthRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn create_account<'a>(
+        &'a mut self,
+        email: &'a str,
+        password_hash: &'a str,
+        display_name: &'a str,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO accounts (id, email, password_hash, display_name) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(id)
+            .bind(email)
+            .bind(password_hash)
+            .bind(display_name)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create account"))?;
+
+            Ok(id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_account_without_password<'a>(
+        &'a mut self,
+        email: &'a str,
+        display_name: &'a str,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO accounts (id, email, display_name, email_verified_at) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(id)
+            .bind(email)
+            .bind(display_name)
+            .bind(now())
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create account"))?;
+
+            Ok(id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_account_by_email<'a>(
+        &'a mut self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<AccountWithHash>, ApiError>> {
+        Box::pin(async move {
+            let account = sqlx::query_as(
+                "SELECT id, email, display_name, created_at, password_hash, failed_login_count, locked_until,
+                        email_verified_at, totp_enabled_at IS NOT NULL AS two_factor_enabled
+                 FROM accounts WHERE email = $1",
+            )
+            .bind(email)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find account by email"))?;
+
+            Ok(account)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_account_by_id(&mut self, id: Uuid) -> BoxFuture<'_, Result<Option<Account>, ApiError>> {
+        Box::pin(async move {
+            let account =
+                sqlx::query_as(&format!("SELECT {} FROM accounts WHERE id = $1", ACCOUNT_COLUMNS))
+                    .bind(id)
+                    .fetch_optional(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to get account by id"))?;
+
+            Ok(account)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn increment_failed_login(
+        &mut self,
+        id: Uuid,
+        threshold: i32,
+        lockout: chrono::Duration,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let locked = sqlx::query_scalar(
+                "UPDATE accounts
+                 SET
+                   failed_login_count = failed_login_count + 1,
+                   locked_until = CASE
+                     WHEN failed_login_count + 1 >= $2 THEN $3
+                     ELSE locked_until
+                   END
+                 WHERE id = $1
+                 RETURNING failed_login_count >= $2",
+            )
+            .bind(id)
+            .bind(threshold)
+            .bind(now() + lockout)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to increment failed login count"))?;
+
+            Ok(locked)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn reset_failed_login(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE accounts SET failed_login_count = 0, locked_until = NULL WHERE id = $1",
+            )
+            .bind(id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to reset failed login count"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_session<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+        user_agent: Option<&'a str>,
+        ip_address: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let session_id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO sessions (id, account_id, token_hash, expires_at, user_agent, ip_address)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(session_id)
+            .bind(account_id)
+            .bind(token_hash)
+            .bind(expires_at)
+            .bind(user_agent)
+            .bind(ip_address)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create session"))?;
+
+            Ok(session_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_active_session<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ActiveSession>, ApiError>> {
+        Box::pin(async move {
+            let session = sqlx::query_as(
+                "SELECT id, account_id, created_at, last_seen_at FROM sessions
+                 WHERE token_hash = $1 AND expires_at > $2",
+            )
+            .bind(token_hash)
+            .bind(now())
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get session"))?;
+
+            Ok(session)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn touch_session(
+        &mut self,
+        session_id: Uuid,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE sessions SET last_seen_at = $1, expires_at = $2 WHERE id = $3")
+                .bind(now())
+                .bind(expires_at)
+                .bind(session_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to refresh session"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn list_sessions<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        current_token_hash: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<SessionInfo>, ApiError>> {
+        Box::pin(async move {
+            let sessions = sqlx::query_as(
+                "SELECT id, created_at, last_seen_at, expires_at, user_agent, ip_address,
+                        token_hash IS $2 AS current
+                 FROM sessions
+                 WHERE account_id = $1 AND expires_at > $3
+                 ORDER BY last_seen_at DESC",
+            )
+            .bind(account_id)
+            .bind(current_token_hash)
+            .bind(now())
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to list sessions"))?;
+
+            Ok(sessions)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_session<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+                .bind(token_hash)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete session"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_account_session(
+        &mut self,
+        account_id: Uuid,
+        session_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM sessions WHERE id = $1 AND account_id = $2")
+                .bind(session_id)
+                .bind(account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete session"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_sessions(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM sessions WHERE expires_at <= $1")
+                .bind(now())
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge expired sessions"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_sessions_for_account(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM sessions WHERE account_id = $1")
+                .bind(account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete sessions"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn mark_email_verified(&mut self, account_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE accounts SET email_verified_at = COALESCE(email_verified_at, $1) WHERE id = $2",
+            )
+            .bind(now())
+            .bind(account_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to mark email as verified"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn update_password_hash<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        password_hash: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE accounts
+                 SET password_hash = $1, failed_login_count = 0, locked_until = NULL
+                 WHERE id = $2",
+            )
+            .bind(password_hash)
+            .bind(account_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to update password"))?;
+
+            Ok(())
+        })
+    }
+
+    // -------- ACCOUNT TOKENS ---------
+
+    #[tracing::instrument(skip_all)]
+    fn create_account_token<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        purpose: AccountTokenPurpose,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO account_tokens (id, account_id, purpose, token_hash, expires_at)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(account_id)
+            .bind(purpose)
+            .bind(token_hash)
+            .bind(expires_at)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create account token"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_unused_account_tokens(
+        &mut self,
+        account_id: Uuid,
+        purpose: AccountTokenPurpose,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "DELETE FROM account_tokens WHERE account_id = $1 AND purpose = $2 AND used_at IS NULL",
+            )
+            .bind(account_id)
+            .bind(purpose)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to delete account tokens"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn consume_account_token<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+        purpose: AccountTokenPurpose,
+    ) -> BoxFuture<'a, Result<Option<Uuid>, ApiError>> {
+        Box::pin(async move {
+            let account_id = sqlx::query_scalar(
+                "UPDATE account_tokens
+                 SET used_at = $3
+                 WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > $3
+                 RETURNING account_id",
+            )
+            .bind(token_hash)
+            .bind(purpose)
+            .bind(now())
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to consume account token"))?;
+
+            Ok(account_id)
+        })
+    }
+
+    // -------- TWO-FACTOR AUTHENTICATION ---------
+
+    /// No row lock in SQLite: the transaction already holds the database write lock.
+    #[tracing::instrument(skip_all)]
+    fn get_totp_state_for_update(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<TotpState>, ApiError>> {
+        Box::pin(async move {
+            let state = sqlx::query_as(
+                "SELECT totp_secret, totp_pending_secret, totp_last_used_step FROM accounts
+                 WHERE id = $1",
+            )
+            .bind(account_id)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get two-factor state"))?;
+
+            Ok(state)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn set_pending_totp_secret<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        secret: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE accounts SET totp_pending_secret = $1 WHERE id = $2")
+                .bind(secret)
+                .bind(account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to store two-factor secret"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn enable_totp(&mut self, account_id: Uuid, step: i64) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE accounts
+                 SET totp_secret = totp_pending_secret,
+                     totp_pending_secret = NULL,
+                     totp_enabled_at = $1,
+                     totp_last_used_step = $2
+                 WHERE id = $3 AND totp_pending_secret IS NOT NULL",
+            )
+            .bind(now())
+            .bind(step)
+            .bind(account_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to enable two-factor authentication"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn record_totp_step(
+        &mut self,
+        account_id: Uuid,
+        step: i64,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE accounts SET totp_last_used_step = $1 WHERE id = $2")
+                .bind(step)
+                .bind(account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to record two-factor code"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn disable_totp(&mut self, account_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE accounts
+                 SET totp_secret = NULL, totp_pending_secret = NULL, totp_enabled_at = NULL,
+                     totp_last_used_step = NULL
+                 WHERE id = $1",
+            )
+            .bind(account_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to disable two-factor authentication"))?;
+
+            sqlx::query("DELETE FROM account_recovery_codes WHERE account_id = $1")
+                .bind(account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete recovery codes"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn replace_recovery_codes<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        code_hashes: &'a [String],
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM account_recovery_codes WHERE account_id = $1")
+                .bind(account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete recovery codes"))?;
+
+            for code_hash in code_hashes {
+                sqlx::query(
+                    "INSERT INTO account_recovery_codes (id, account_id, code_hash) VALUES ($1, $2, $3)",
+                )
+                .bind(Uuid::new_v4())
+                .bind(account_id)
+                .bind(code_hash)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to store recovery codes"))?;
+            }
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn consume_recovery_code<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        code_hash: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "UPDATE account_recovery_codes SET used_at = $3
+                 WHERE id = (
+                   SELECT id FROM account_recovery_codes
+                   WHERE account_id = $1 AND code_hash = $2 AND used_at IS NULL
+                   LIMIT 1
+                 )",
+            )
+            .bind(account_id)
+            .bind(code_hash)
+            .bind(now())
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to use recovery code"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_login_challenge<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token_hash: &'a str,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO login_challenges (id, account_id, token_hash, expires_at) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(account_id)
+            .bind(token_hash)
+            .bind(expires_at)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create login challenge"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_login_challenge_for_update<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<LoginChallenge>, ApiError>> {
+        Box::pin(async move {
+            let challenge = sqlx::query_as(
+                "SELECT id, account_id, attempts FROM login_challenges
+                 WHERE token_hash = $1 AND expires_at > $2",
+            )
+            .bind(token_hash)
+            .bind(now())
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get login challenge"))?;
+
+            Ok(challenge)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn increment_login_challenge_attempts(
+        &mut self,
+        id: Uuid,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE login_challenges SET attempts = attempts + 1 WHERE id = $1")
+                .bind(id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to update login challenge"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_login_challenge(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM login_challenges WHERE id = $1")
+                .bind(id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete login challenge"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_login_challenges(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM login_challenges WHERE expires_at <= $1")
+                .bind(now())
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge login challenges"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_oidc_login_state<'a>(
+        &'a mut self,
+        state_hash: &'a str,
+        login_state: &'a OidcLoginState,
+        expires_at: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO oidc_login_states (id, state_hash, code_verifier, nonce, link_account_id, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(state_hash)
+            .bind(&login_state.code_verifier)
+            .bind(&login_state.nonce)
+            .bind(login_state.link_account_id)
+            .bind(expires_at)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to create OIDC login state"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn consume_oidc_login_state<'a>(
+        &'a mut self,
+        state_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<OidcLoginState>, ApiError>> {
+        Box::pin(async move {
+            let login_state = sqlx::query_as(
+                "DELETE FROM oidc_login_states
+                 WHERE state_hash = $1 AND expires_at > $2
+                 RETURNING code_verifier, nonce, link_account_id",
+            )
+            .bind(state_hash)
+            .bind(now())
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to consume OIDC login state"))?;
+
+            Ok(login_state)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_oidc_login_states(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM oidc_login_states WHERE expires_at <= $1")
+                .bind(now())
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge OIDC login states"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_identity_account_id<'a>(
+        &'a mut self,
+        issuer: &'a str,
+        subject: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Uuid>, ApiError>> {
+        Box::pin(async move {
+            let account_id = sqlx::query_scalar(
+                "SELECT account_id FROM account_identities WHERE issuer = $1 AND subject = $2",
+            )
+            .bind(issuer)
+            .bind(subject)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find identity"))?;
+
+            Ok(account_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_identity<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        issuer: &'a str,
+        subject: &'a str,
+        email: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO account_identities (id, account_id, issuer, subject, email)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(account_id)
+            .bind(issuer)
+            .bind(subject)
+            .bind(email)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to link identity"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn list_identities(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<LinkedIdentity>, ApiError>> {
+        Box::pin(async move {
+            let identities = sqlx::query_as(
+                "SELECT id, issuer, email, created_at FROM account_identities
+                 WHERE account_id = $1
+                 ORDER BY created_at",
+            )
+            .bind(account_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to list identities"))?;
+
+            Ok(identities)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn has_other_sign_in_method(
+        &mut self,
+        account_id: Uuid,
+        identity_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query_scalar(
+                "SELECT (a.password_hash IS NOT NULL
+                         OR EXISTS (SELECT 1 FROM account_identities i WHERE i.account_id = a.id AND i.id <> $2))
+                 FROM accounts a WHERE a.id = $1",
+            )
+            .bind(account_id)
+            .bind(identity_id)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to check sign-in methods"))?;
+
+            Ok(result)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_identity(
+        &mut self,
+        account_id: Uuid,
+        identity_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result =
+                sqlx::query("DELETE FROM account_identities WHERE id = $1 AND account_id = $2")
+                    .bind(identity_id)
+                    .bind(account_id)
+                    .execute(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to unlink identity"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn create_api_token<'a>(
+        &'a mut self,
+        account_id: Uuid,
+        token: NewApiToken<'a>,
+    ) -> BoxFuture<'a, Result<ApiTokenInfo, ApiError>> {
+        Box::pin(async move {
+            let project_ids =
+                token.project_ids.map(serde_json::to_string).transpose().map_err(|e| {
+                    ApiError::internal(format!("Failed to encode project ids: {e}"))
+                })?;
+
+            let row: ApiTokenRow = sqlx::query_as(&format!(
+                "INSERT INTO api_tokens (id, account_id, name, token_hash, token_prefix, scope, project_ids, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 RETURNING {}",
+                API_TOKEN_COLUMNS
+            ))
+            .bind(Uuid::new_v4())
+            .bind(account_id)
+            .bind(token.name)
+            .bind(token.token_hash)
+            .bind(token.token_prefix)
+            .bind(token.scope)
+            .bind(project_ids)
+            .bind(token.expires_at)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create API token"))?;
+
+            row.try_into()
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn list_api_tokens(
+        &mut self,
+        account_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<ApiTokenInfo>, ApiError>> {
+        Box::pin(async move {
+            let rows: Vec<ApiTokenRow> = sqlx::query_as(&format!(
+                "SELECT {} FROM api_tokens
+                 WHERE account_id = $1
+                 ORDER BY created_at DESC",
+                API_TOKEN_COLUMNS
+            ))
+            .bind(account_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to list API tokens"))?;
+
+            rows.into_iter().map(ApiTokenInfo::try_from).collect()
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_api_token(
+        &mut self,
+        account_id: Uuid,
+        token_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND account_id = $2")
+                .bind(token_id)
+                .bind(account_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to revoke API token"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_active_api_token<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ActiveApiToken>, ApiError>> {
+        Box::pin(async move {
+            let row: Option<ActiveApiTokenRow> = sqlx::query_as(
+                "SELECT id, account_id, scope, project_ids, last_used_at
+                 FROM api_tokens
+                 WHERE token_hash = $1 AND expires_at > $2",
+            )
+            .bind(token_hash)
+            .bind(now())
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find API token"))?;
+
+            row.map(|row| {
+                Ok(ActiveApiToken {
+                    id: row.id,
+                    account_id: row.account_id,
+                    scope: row.scope,
+                    project_ids: decode_project_ids(row.project_ids)?,
+                    last_used_at: row.last_used_at,
+                })
+            })
+            .transpose()
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn touch_api_token(&mut self, id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE api_tokens SET last_used_at = $1 WHERE id = $2")
+                .bind(now())
+                .bind(id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to update API token"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_api_tokens(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM api_tokens WHERE expires_at <= $1")
+                .bind(now())
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge API tokens"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+}
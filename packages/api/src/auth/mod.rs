@@ -1,2 +1,81 @@
+pub mod api_tokens;
+pub mod api_tokens_controller;
 pub mod auth_controller;
 pub mod auth_repository;
+#[cfg(feature = "server")]
+mod auth_repository_sqlite;
+#[cfg(feature = "server")]
+pub mod oidc;
+pub mod oidc_controller;
+pub mod sessions;
+#[cfg(feature = "server")]
+pub mod totp;
+pub mod two_factor_controller;
+
+use chrono::Duration;
+
+#[cfg(feature = "server")]
+use crate::config::Source;
+
+/// Session and sign-in policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthConfig {
+    /// Mark the session cookie `Secure`. Only turned off for local development over plain HTTP.
+    pub cookie_secure: bool,
+    /// A session expires after this long without activity
+    pub session_idle_timeout: Duration,
+    /// However active, a session never outlives this; the user signs in again
+    pub session_max_lifetime: Duration,
+    /// Consecutive failed sign-ins that lock an account
+    pub lockout_threshold: i32,
+    pub lockout_duration: Duration,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            cookie_secure: true,
+            session_idle_timeout: Duration::days(30),
+            session_max_lifetime: Duration::days(365),
+            lockout_threshold: 5,
+            lockout_duration: Duration::minutes(15),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl AuthConfig {
+    /// `COOKIE_SECURE`, `SESSION_IDLE_TIMEOUT_DAYS`, `SESSION_MAX_LIFETIME_DAYS`,
+    /// `LOGIN_LOCKOUT_THRESHOLD` and `LOGIN_LOCKOUT_MINUTES`.
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let default = Self::default();
+        let idle_days =
+            source.parse("SESSION_IDLE_TIMEOUT_DAYS", default.session_idle_timeout.num_days())?;
+        let max_days =
+            source.parse("SESSION_MAX_LIFETIME_DAYS", default.session_max_lifetime.num_days())?;
+        let lockout_threshold =
+            source.parse("LOGIN_LOCKOUT_THRESHOLD", default.lockout_threshold)?;
+        let lockout_minutes =
+            source.parse("LOGIN_LOCKOUT_MINUTES", default.lockout_duration.num_minutes())?;
+
+        if idle_days < 1 || max_days < 1 {
+            anyhow::bail!(
+                "SESSION_IDLE_TIMEOUT_DAYS and SESSION_MAX_LIFETIME_DAYS must be at least 1"
+            );
+        }
+        if idle_days > max_days {
+            anyhow::bail!("SESSION_IDLE_TIMEOUT_DAYS cannot exceed SESSION_MAX_LIFETIME_DAYS");
+        }
+        if lockout_threshold < 1 || lockout_minutes < 1 {
+            anyhow::bail!("LOGIN_LOCKOUT_THRESHOLD and LOGIN_LOCKOUT_MINUTES must be at least 1");
+        }
+
+        Ok(Self {
+            cookie_secure: source.parse("COOKIE_SECURE", default.cookie_secure)?,
+            session_idle_timeout: Duration::days(idle_days),
+            session_max_lifetime: Duration::days(max_days),
+            lockout_threshold,
+            lockout_duration: Duration::minutes(lockout_minutes),
+        })
+    }
+}
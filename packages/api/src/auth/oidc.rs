@@ -0,0 +1,514 @@
+//! OpenID Connect relying party: authorization code flow with PKCE against one configured issuer.
+//!
+//! The provider is configured with `OIDC_ISSUER`, `OIDC_CLIENT_ID` and optionally
+//! `OIDC_CLIENT_SECRET`, `OIDC_REDIRECT_URL`, `OIDC_SCOPES` and `OIDC_PROVIDER_NAME`. Without
+//! `OIDC_ISSUER` the feature is off. Endpoints come from the issuer's discovery document; ID
+//! tokens are checked against its JWKS (signature, issuer, audience, expiry, nonce).
+use anyhow::{bail, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand::Rng;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::{get_config, Source};
+
+const DEFAULT_SCOPES: &str = "openid email profile";
+const DEFAULT_PROVIDER_NAME: &str = "SSO";
+const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Signature algorithms accepted for ID tokens. Symmetric ones would let anyone holding the
+/// client secret mint tokens, and `none` is never acceptable.
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: String,
+    /// Shown on the sign-in button, e.g. "Google" or "Keycloak".
+    pub provider_name: String,
+}
+
+impl OidcConfig {
+    /// `None` when `OIDC_ISSUER` is not set.
+    pub fn from_source(source: &Source, app_base_url: &str) -> anyhow::Result<Option<Self>> {
+        let var = |name: &str| source.var(name);
+
+        let Some(issuer) = var("OIDC_ISSUER") else {
+            return Ok(None);
+        };
+        let client_id =
+            var("OIDC_CLIENT_ID").context("OIDC_CLIENT_ID must be set with OIDC_ISSUER")?;
+
+        Ok(Some(Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: var("OIDC_CLIENT_SECRET"),
+            redirect_url: var("OIDC_REDIRECT_URL")
+                .unwrap_or_else(|| format!("{}/auth/oidc/callback", app_base_url)),
+            scopes: var("OIDC_SCOPES").unwrap_or_else(|| DEFAULT_SCOPES.to_string()),
+            provider_name: var("OIDC_PROVIDER_NAME")
+                .unwrap_or_else(|| DEFAULT_PROVIDER_NAME.to_string()),
+        }))
+    }
+}
+
+/// The configured provider, if any.
+pub fn config() -> Option<OidcConfig> {
+    get_config().oidc.clone()
+}
+
+/// Provider client built from the discovery document. Discovery is retried on the next call
+/// when it fails, so a provider that is down at boot does not disable sign-in until a restart.
+pub async fn client() -> anyhow::Result<&'static OidcClient> {
+    static CLIENT: tokio::sync::OnceCell<OidcClient> = tokio::sync::OnceCell::const_new();
+
+    let config = config().context("OpenID Connect is not configured")?;
+    CLIENT.get_or_try_init(|| OidcClient::discover(config)).await
+}
+
+/// The parts of the discovery document we use.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// Verified claims of an ID token.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: Option<bool>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcClient {
+    config: OidcConfig,
+    metadata: ProviderMetadata,
+    http: reqwest::Client,
+}
+
+impl OidcClient {
+    pub async fn discover(config: OidcConfig) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?;
+        let url = format!("{}/.well-known/openid-configuration", config.issuer);
+        let metadata: ProviderMetadata = http
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to fetch {}", url))?
+            .json()
+            .await
+            .context("Invalid discovery document")?;
+
+        // OpenID Connect Discovery 1.0, section 4.3
+        if metadata.issuer.trim_end_matches('/') != config.issuer {
+            bail!(
+                "Discovery document is for issuer \"{}\", expected \"{}\"",
+                metadata.issuer,
+                config.issuer
+            );
+        }
+
+        Ok(Self { config, metadata, http })
+    }
+
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
+    /// URL of the provider's sign-in page for a new authorization request.
+    pub fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> anyhow::Result<String> {
+        let mut url = reqwest::Url::parse(&self.metadata.authorization_endpoint)
+            .context("Invalid authorization_endpoint")?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes)
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", &pkce_challenge(code_verifier))
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.into())
+    }
+
+    /// Trade an authorization code for the ID token and verify it.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> anyhow::Result<IdTokenClaims> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let response = self
+            .http
+            .post(&self.metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .context("Token request failed")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("Token endpoint returned {}: {}", status, body);
+        }
+        let tokens: TokenResponse = response.json().await.context("Invalid token response")?;
+        let id_token = tokens.id_token.context("Token response has no id_token")?;
+
+        let jwks: JwkSet = self
+            .http
+            .get(&self.metadata.jwks_uri)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .context("Failed to fetch the JWKS")?
+            .json()
+            .await
+            .context("Invalid JWKS")?;
+
+        verify_id_token(&id_token, &jwks, &self.metadata.issuer, &self.config.client_id, nonce)
+    }
+}
+
+/// Check the signature and the claims of an ID token.
+pub fn verify_id_token(
+    id_token: &str,
+    jwks: &JwkSet,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+) -> anyhow::Result<IdTokenClaims> {
+    let header = jsonwebtoken::decode_header(id_token).context("Malformed ID token")?;
+    if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+        bail!("ID token algorithm {:?} is not accepted", header.alg);
+    }
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid).with_context(|| format!("No key \"{}\" in the JWKS", kid))?,
+        None if jwks.keys.len() == 1 => &jwks.keys[0],
+        None => bail!("ID token has no key id and the JWKS has several keys"),
+    };
+    let key = DecodingKey::from_jwk(jwk).context("Unusable key in the JWKS")?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[issuer]);
+    validation.set_audience(&[client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    let claims = jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+        .context("Invalid ID token")?
+        .claims;
+
+    if claims.nonce.as_deref() != Some(nonce) {
+        bail!("ID token nonce does not match the authorization request");
+    }
+
+    Ok(claims)
+}
+
+/// Random URL-safe value for `state`, `nonce` and the PKCE verifier.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// RFC 7636 `S256` code challenge for a verifier.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::oidc_controller::create_account_from_claims;
+    use axum::extract::{Form, State};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use rsa::pkcs8::DecodePrivateKey;
+    use rsa::traits::PublicKeyParts;
+    use shared::{CreatableProject, CreatableUser};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    const CLIENT_ID: &str = "counted-test";
+    const KID: &str = "test-key";
+    const CODE: &str = "auth-code";
+
+    // Throwaway key for the mock issuer
+    const TEST_KEY_PEM: &str = include_str!("testdata/oidc_test_key.pem");
+
+    #[derive(Default)]
+    struct MockState {
+        issuer: String,
+        /// Code challenge and nonce of the pending authorization request
+        pending: Option<(String, String)>,
+        claims_override: HashMap<&'static str, serde_json::Value>,
+    }
+
+    type Shared = Arc<Mutex<MockState>>;
+
+    fn jwks() -> serde_json::Value {
+        let key = rsa::RsaPrivateKey::from_pkcs8_pem(TEST_KEY_PEM).unwrap();
+        serde_json::json!({ "keys": [{
+            "kty": "RSA",
+            "kid": KID,
+            "alg": "RS256",
+            "use": "sig",
+            "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+            "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+        }]})
+    }
+
+    async fn discovery(State(state): State<Shared>) -> Json<serde_json::Value> {
+        let issuer = state.lock().unwrap().issuer.clone();
+        Json(serde_json::json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+            "jwks_uri": format!("{}/jwks", issuer),
+        }))
+    }
+
+    async fn token(
+        State(state): State<Shared>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
+        let state = state.lock().unwrap();
+        let (challenge, nonce) =
+            state.pending.clone().ok_or(axum::http::StatusCode::BAD_REQUEST)?;
+        let verifier = form.get("code_verifier").ok_or(axum::http::StatusCode::BAD_REQUEST)?;
+        if form.get("code").map(String::as_str) != Some(CODE)
+            || pkce_challenge(verifier) != challenge
+        {
+            return Err(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut claims = serde_json::json!({
+            "iss": state.issuer,
+            "aud": CLIENT_ID,
+            "sub": "user-42",
+            "exp": now + 300,
+            "iat": now,
+            "nonce": nonce,
+            "email": "Alice@Example.com",
+            "email_verified": true,
+            "name": "Alice",
+        });
+        for (claim, value) in &state.claims_override {
+            claims[*claim] = value.clone();
+        }
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(KID.to_string());
+        let key = EncodingKey::from_rsa_pem(TEST_KEY_PEM.as_bytes()).unwrap();
+        let id_token = encode(&header, &claims, &key).unwrap();
+
+        Ok(Json(
+            serde_json::json!({ "access_token": "at", "token_type": "Bearer", "id_token": id_token }),
+        ))
+    }
+
+    /// Local issuer serving discovery, JWKS and a token endpoint that checks PKCE.
+    async fn mock_issuer() -> (String, Shared) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let state: Shared =
+            Arc::new(Mutex::new(MockState { issuer: issuer.clone(), ..Default::default() }));
+
+        let jwks = jwks();
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(move || async move { Json(jwks) }))
+            .route("/token", post(token))
+            .with_state(state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (issuer, state)
+    }
+
+    fn config(issuer: &str) -> OidcConfig {
+        OidcConfig {
+            issuer: issuer.to_string(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: None,
+            redirect_url: "http://localhost:8080/auth/oidc/callback".to_string(),
+            scopes: DEFAULT_SCOPES.to_string(),
+            provider_name: "Mock".to_string(),
+        }
+    }
+
+    /// Start an authorization request the way the sign-in endpoint does.
+    fn authorize(client: &OidcClient, state: &Shared) -> (String, String, String) {
+        let (request_state, nonce, verifier) = (random_token(), random_token(), random_token());
+        let url = reqwest::Url::parse(
+            &client.authorization_url(&request_state, &nonce, &verifier).unwrap(),
+        )
+        .unwrap();
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        state.lock().unwrap().pending =
+            Some((params["code_challenge"].clone(), params["nonce"].clone()));
+        (url.to_string(), nonce, verifier)
+    }
+
+    #[test]
+    fn pkce_challenge_is_unpadded_base64url_sha256() {
+        // base64url(sha256(verifier)) computed independently
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mJ92K9NgH3nsdKQJ3uYD9SyjhcvgLk"),
+            "QO3hlkDpVe-5tb1gqOVFLuv5lQ3gp4VbovvN0Bh7KBA"
+        );
+    }
+
+    #[tokio::test]
+    async fn code_flow_against_mock_issuer() {
+        let (issuer, state) = mock_issuer().await;
+        let client = OidcClient::discover(config(&issuer)).await.unwrap();
+
+        let (url, nonce, verifier) = authorize(&client, &state);
+        assert!(url.starts_with(&format!(
+            "{}/authorize?response_type=code&client_id={}",
+            issuer, CLIENT_ID
+        )));
+        assert!(url.contains("code_challenge_method=S256"));
+        assert!(url.contains("scope=openid+email+profile"));
+
+        let claims = client.exchange_code(CODE, &verifier, &nonce).await.unwrap();
+        assert_eq!(claims.iss, issuer);
+        assert_eq!(claims.sub, "user-42");
+        assert_eq!(claims.email.as_deref(), Some("Alice@Example.com"));
+        assert_eq!(claims.email_verified, Some(true));
+
+        // The new account joins the projects its address was invited to
+        let db = crate::db::connect(&crate::db::DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+            acquire_timeout: std::time::Duration::from_secs(5),
+            statement_cache_capacity: 100,
+        })
+        .await
+        .unwrap();
+        crate::migrations::migrate(&db).await.unwrap();
+        let mut tx = db.begin().await.unwrap();
+        let project =
+            CreatableProject { name: "Vacances".to_string(), description: None, currency: None };
+        let project_id = tx.add_project(project, None).await.unwrap();
+        let invited = CreatableUser {
+            name: "alice".to_string(),
+            project_id,
+            invited_email: Some("alice@example.com".to_string()),
+        };
+        let user_id = tx.insert_users(vec![invited]).await.unwrap()[0].id;
+
+        let account_id = create_account_from_claims(&mut *tx, &claims, "Mock").await.unwrap();
+        let membership = tx.get_account_project(account_id, project_id).await.unwrap().unwrap();
+        assert_eq!(membership.user_id, Some(user_id));
+        assert_eq!(tx.get_users_by_project_id(project_id).await.unwrap()[0].name, "Alice");
+    }
+
+    #[tokio::test]
+    async fn wrong_pkce_verifier_is_refused_by_issuer() {
+        let (issuer, state) = mock_issuer().await;
+        let client = OidcClient::discover(config(&issuer)).await.unwrap();
+
+        let (_, nonce, _) = authorize(&client, &state);
+        assert!(client.exchange_code(CODE, &random_token(), &nonce).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn nonce_mismatch_is_rejected() {
+        let (issuer, state) = mock_issuer().await;
+        let client = OidcClient::discover(config(&issuer)).await.unwrap();
+
+        let (_, _, verifier) = authorize(&client, &state);
+        let err = client.exchange_code(CODE, &verifier, "another-nonce").await.unwrap_err();
+        assert!(err.to_string().contains("nonce"));
+    }
+
+    #[tokio::test]
+    async fn token_claims_are_validated() {
+        let (issuer, state) = mock_issuer().await;
+        let client = OidcClient::discover(config(&issuer)).await.unwrap();
+
+        let cases: [(&str, serde_json::Value); 3] = [
+            ("aud", serde_json::json!("someone-else")),
+            ("iss", serde_json::json!("http://evil.example")),
+            ("exp", serde_json::json!(chrono::Utc::now().timestamp() - 3600)),
+        ];
+        for (claim, value) in cases {
+            {
+                let mut state = state.lock().unwrap();
+                state.claims_override.clear();
+                state.claims_override.insert(claim, value);
+            }
+            let (_, nonce, verifier) = authorize(&client, &state);
+            assert!(
+                client.exchange_code(CODE, &verifier, &nonce).await.is_err(),
+                "bad {} accepted",
+                claim
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn discovery_for_another_issuer_is_rejected() {
+        let (issuer, state) = mock_issuer().await;
+        state.lock().unwrap().issuer = "http://evil.example".to_string();
+        assert!(OidcClient::discover(config(&issuer)).await.is_err());
+    }
+
+    #[test]
+    fn unsigned_tokens_are_rejected() {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(r#"{"sub":"x"}"#);
+        let jwks: JwkSet = serde_json::from_value(jwks()).unwrap();
+        assert!(verify_id_token(&format!("{}.{}.", header, payload), &jwks, "i", CLIENT_ID, "n")
+            .is_err());
+    }
+}
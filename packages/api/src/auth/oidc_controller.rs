@@ -0,0 +1,262 @@
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{
+    LinkedIdentity, LoginResponse, OidcAuthorization, OidcCallbackPayload, OidcProvider,
+    OidcStartPayload,
+};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+#[cfg(feature = "server")]
+use crate::auth::auth_controller::{begin_sign_in, resolve_pending_invitations};
+#[cfg(feature = "server")]
+use crate::auth::auth_repository::OidcLoginState;
+#[cfg(feature = "server")]
+use crate::auth::oidc::{self, IdTokenClaims};
+#[cfg(feature = "server")]
+use crate::db::{get_db, Store};
+#[cfg(feature = "server")]
+use crate::utils::{get_session_account_id, sha256_hex};
+#[cfg(feature = "server")]
+use chrono::{Duration, Utc};
+
+/// Time the user has to sign in at the provider.
+#[cfg(feature = "server")]
+const LOGIN_STATE_TTL_MINUTES: i64 = 10;
+
+/// Display name for an account created from ID token claims.
+pub fn display_name_from_claims(
+    name: Option<&str>,
+    preferred_username: Option<&str>,
+    email: &str,
+) -> String {
+    let name = [name, preferred_username]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|n| !n.is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email));
+
+    name.chars().take(100).collect()
+}
+
+/// The configured identity provider, or `None` when OpenID Connect is off.
+#[get("/api/v1/auth/oidc/provider")]
+pub async fn get_oidc_provider() -> Result<Option<OidcProvider>, ApiError> {
+    Ok(oidc::config().map(|config| OidcProvider { name: config.provider_name }))
+}
+
+/// Start an authorization request and return the provider URL to send the browser to.
+#[post("/api/v1/auth/oidc/start")]
+pub async fn start_oidc_login(
+    Json(payload): Json<OidcStartPayload>,
+) -> Result<OidcAuthorization, ApiError> {
+    let link_account_id = if payload.link {
+        Some(
+            get_session_account_id()
+                .await
+                .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?,
+        )
+    } else {
+        None
+    };
+
+    let client = oidc::client().await.map_err(|e| {
+        tracing::error!("OpenID Connect provider unavailable: {:#}", e);
+        ApiError::not_found("Sign-in with this provider is unavailable")
+    })?;
+
+    let state = oidc::random_token();
+    let login_state = OidcLoginState {
+        code_verifier: oidc::random_token(),
+        nonce: oidc::random_token(),
+        link_account_id,
+    };
+    let authorization_url = client
+        .authorization_url(&state, &login_state.nonce, &login_state.code_verifier)
+        .map_err(ApiError::internal)?;
+
+    let pool = get_db();
+    let mut conn = pool.acquire().await?;
+    let expires_at = (Utc::now() + Duration::minutes(LOGIN_STATE_TTL_MINUTES)).naive_utc();
+    conn.create_oidc_login_state(&sha256_hex(&state), &login_state, expires_at).await?;
+
+    Ok(OidcAuthorization { authorization_url })
+}
+
+/// Finish an authorization request with the code the provider redirected back with.
+///
+/// Signs in the account linked to the external subject, creating one for a new subject, or
+/// links the subject to the signed-in account when the request was started with `link`.
+#[post("/api/v1/auth/oidc/callback")]
+pub async fn complete_oidc_login(
+    Json(payload): Json<OidcCallbackPayload>,
+) -> Result<LoginResponse, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let login_state = tx
+        .consume_oidc_login_state(&sha256_hex(&payload.state))
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("This sign-in has expired. Sign in again."))?;
+    // The state is spent whatever happens next
+    tx.commit().await?;
+
+    let client = oidc::client().await.map_err(|e| {
+        tracing::error!("OpenID Connect provider unavailable: {:#}", e);
+        ApiError::not_found("Sign-in with this provider is unavailable")
+    })?;
+    let claims = client
+        .exchange_code(&payload.code, &login_state.code_verifier, &login_state.nonce)
+        .await
+        .map_err(|e| {
+            tracing::warn!("OpenID Connect sign-in failed: {:#}", e);
+            ApiError::unauthorized(format!("Sign-in with {} failed", client.config().provider_name))
+        })?;
+
+    let mut tx = pool.begin().await?;
+
+    let existing = tx.find_identity_account_id(&claims.iss, &claims.sub).await?;
+
+    let account_id = match login_state.link_account_id {
+        Some(link_account_id) => {
+            // The session must still be the one that started the request
+            if get_session_account_id().await != Some(link_account_id) {
+                return Err(ApiError::unauthorized("Not authenticated"));
+            }
+            match existing {
+                Some(id) if id != link_account_id => {
+                    return Err(ApiError::conflict(
+                        "This identity is already linked to another account",
+                    ));
+                }
+                Some(_) => {}
+                None => link_identity(&mut *tx, link_account_id, &claims).await?,
+            }
+            let account = tx
+                .get_account_by_id(link_account_id)
+                .await?
+                .ok_or_else(|| ApiError::not_found("Account not found"))?;
+            tx.commit().await?;
+            return Ok(LoginResponse::SignedIn(account));
+        }
+        None => match existing {
+            Some(id) => id,
+            None => {
+                create_account_from_claims(&mut *tx, &claims, &client.config().provider_name)
+                    .await?
+            }
+        },
+    };
+
+    let account = tx
+        .get_account_by_id(account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+    let response = begin_sign_in(&mut *tx, account).await?;
+
+    tx.commit().await?;
+
+    Ok(response)
+}
+
+#[get("/api/v1/auth/oidc/identities")]
+pub async fn list_linked_identities() -> Result<Vec<LinkedIdentity>, ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut conn = pool.acquire().await?;
+
+    conn.list_identities(account_id).await
+}
+
+/// Unlink an identity, unless it is the account's only way to sign in.
+#[delete("/api/v1/auth/oidc/identities/{identity_id}")]
+pub async fn unlink_identity(identity_id: Uuid) -> Result<(), ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    if !tx.has_other_sign_in_method(account_id, identity_id).await? {
+        return Err(ApiError::conflict("Set a password before unlinking your only sign-in method"));
+    }
+    if !tx.delete_identity(account_id, identity_id).await? {
+        return Err(ApiError::not_found("Identity not found"));
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+async fn link_identity(
+    executor: &mut dyn Store,
+    account_id: Uuid,
+    claims: &IdTokenClaims,
+) -> Result<(), ApiError> {
+    let email = claims.email.as_deref().map(str::to_lowercase);
+    executor.create_identity(account_id, &claims.iss, &claims.sub, email.as_deref()).await
+}
+
+/// New account for a subject seen for the first time, linked to the invitations sent to its
+/// address.
+///
+/// The provider must vouch for the email address. An existing account with that address is
+/// never taken over: its owner signs in with the password and links the provider explicitly.
+#[cfg(feature = "server")]
+pub(crate) async fn create_account_from_claims(
+    executor: &mut dyn Store,
+    claims: &IdTokenClaims,
+    provider_name: &str,
+) -> Result<Uuid, ApiError> {
+    let email = match (&claims.email, claims.email_verified) {
+        (Some(email), Some(true)) if email.len() <= 254 => email.to_lowercase(),
+        _ => {
+            return Err(ApiError::validation(format!(
+                "{} did not share a verified email address",
+                provider_name
+            )))
+        }
+    };
+
+    if executor.find_account_by_email(&email).await?.is_some() {
+        return Err(ApiError::conflict(format!(
+            "An account already uses this email. Sign in with your password, then link {} from your account page.",
+            provider_name
+        )));
+    }
+
+    let display_name = display_name_from_claims(
+        claims.name.as_deref(),
+        claims.preferred_username.as_deref(),
+        &email,
+    );
+    let account_id = executor.create_account_without_password(&email, &display_name).await?;
+    link_identity(&mut *executor, account_id, claims).await?;
+    resolve_pending_invitations(&mut *executor, account_id, &email, &display_name).await?;
+
+    Ok(account_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_name_prefers_name_then_username_then_email() {
+        assert_eq!(
+            display_name_from_claims(Some("Alice Martin"), Some("alice"), "a@b.c"),
+            "Alice Martin"
+        );
+        assert_eq!(display_name_from_claims(Some("  "), Some("alice"), "a@b.c"), "alice");
+        assert_eq!(display_name_from_claims(None, None, "bob@example.com"), "bob");
+        assert_eq!(display_name_from_claims(Some(&"x".repeat(150)), None, "a@b.c").len(), 100);
+    }
+}
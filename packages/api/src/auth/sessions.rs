@@ -0,0 +1,199 @@
+//! Session lifetime policy, the session cookie and the expired-session purge job.
+//!
+//! The cookie carries a random token; the `sessions` table only stores its SHA-256. A session
+//! slides its expiry forward while it is used, up to a hard maximum lifetime.
+use chrono::{Duration, NaiveDateTime};
+
+use crate::auth::AuthConfig;
+
+#[cfg(feature = "server")]
+use crate::auth::auth_repository::ActiveSession;
+#[cfg(feature = "server")]
+use crate::config::get_config;
+#[cfg(feature = "server")]
+use crate::db::{get_db, Db};
+#[cfg(feature = "server")]
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::utils::{get_request_header, sha256_hex};
+
+/// Activity is recorded at most this often, so reads do not write on every request.
+const TOUCH_INTERVAL_MINUTES: i64 = 5;
+#[cfg(feature = "server")]
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+pub const COOKIE_NAME: &str = "session_id";
+
+/// Expiry of a session used at `now`: the idle timeout, capped by the maximum lifetime.
+pub fn session_expiry(
+    config: &AuthConfig,
+    created_at: NaiveDateTime,
+    now: NaiveDateTime,
+) -> NaiveDateTime {
+    (now + config.session_idle_timeout).min(created_at + config.session_max_lifetime)
+}
+
+pub fn needs_touch(last_seen_at: NaiveDateTime, now: NaiveDateTime) -> bool {
+    now - last_seen_at >= Duration::minutes(TOUCH_INTERVAL_MINUTES)
+}
+
+/// Value of the session cookie in a `Cookie` header, if present.
+pub fn token_from_cookie_header(header: &str) -> Option<&str> {
+    header
+        .split(';')
+        .map(|s| s.trim())
+        .find_map(|s| s.strip_prefix(COOKIE_NAME)?.strip_prefix('='))
+        .filter(|token| !token.is_empty())
+}
+
+#[cfg(feature = "server")]
+fn secure_attr() -> &'static str {
+    if get_config().auth.cookie_secure {
+        "; Secure"
+    } else {
+        ""
+    }
+}
+
+/// `Set-Cookie` value for a session token that the browser should keep until `expires_at`.
+#[cfg(feature = "server")]
+pub fn session_cookie(token: &str, expires_at: NaiveDateTime, now: NaiveDateTime) -> String {
+    let max_age = (expires_at - now).num_seconds().max(0);
+    format!(
+        "{}={}; HttpOnly; SameSite=Lax; Path=/; Max-Age={}{}",
+        COOKIE_NAME,
+        token,
+        max_age,
+        secure_attr()
+    )
+}
+
+#[cfg(feature = "server")]
+pub fn cleared_session_cookie() -> String {
+    format!("{}=; HttpOnly; SameSite=Lax; Path=/; Max-Age=0{}", COOKIE_NAME, secure_attr())
+}
+
+/// Session token sent with the current request, if any.
+#[cfg(feature = "server")]
+pub fn request_session_token() -> Option<String> {
+    let cookie = get_request_header("cookie")?;
+    token_from_cookie_header(&cookie).map(|t| t.to_owned())
+}
+
+#[cfg(feature = "server")]
+pub fn set_response_cookie(value: &str) -> Result<(), ApiError> {
+    use axum::http::header::{HeaderValue, SET_COOKIE};
+    use dioxus_fullstack::FullstackContext;
+
+    let header_value = HeaderValue::from_str(value).map_err(ApiError::internal)?;
+    let ctx = FullstackContext::current().ok_or_else(|| ApiError::internal("No server context"))?;
+    ctx.add_response_header(SET_COOKIE, header_value);
+
+    Ok(())
+}
+
+/// Resolve the session of the current request and record the activity.
+///
+/// When the session is touched, its expiry slides forward and the cookie is re-issued with
+/// the matching `Max-Age`. Returns `None` on any failure (missing, unknown or expired).
+#[cfg(feature = "server")]
+pub async fn current_session() -> Option<ActiveSession> {
+    let token = request_session_token()?;
+
+    let pool = get_db();
+    let mut conn = pool.acquire().await.ok()?;
+    let session = conn.find_active_session(&sha256_hex(&token)).await.ok()??;
+
+    let now = chrono::Utc::now().naive_utc();
+    if needs_touch(session.last_seen_at, now) {
+        let expires_at = session_expiry(&get_config().auth, session.created_at, now);
+        // A failure is logged by the repository; the session stays valid until its expiry
+        if conn.touch_session(session.id, expires_at).await.is_ok() {
+            let _ = set_response_cookie(&session_cookie(&token, expires_at, now));
+        }
+    }
+
+    Some(session)
+}
+
+/// Start the background task deleting expired sessions, 2FA login challenges, OIDC
+/// authorization requests, API tokens and idempotency keys. Only the first call has an effect.
+#[cfg(feature = "server")]
+pub fn spawn_purge_job(db: Db) {
+    static STARTED: std::sync::Once = std::sync::Once::new();
+
+    STARTED.call_once(|| {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+
+                let mut conn = match db.acquire().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::error!("Failed to purge expired sessions: {}", e);
+                        continue;
+                    }
+                };
+                // Failures are logged by the repository and retried on the next tick
+                if let Ok(count @ 1..) = conn.purge_expired_sessions().await {
+                    tracing::info!("Purged {} expired session(s)", count);
+                }
+                let _ = conn.purge_expired_login_challenges().await;
+                let _ = conn.purge_expired_oidc_login_states().await;
+                let _ = conn.purge_expired_api_tokens().await;
+                let _ = conn.purge_expired_idempotency_keys().await;
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 1, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn expiry_slides_with_activity() {
+        let config = AuthConfig::default();
+        let created = at(1, 10, 0);
+        assert_eq!(session_expiry(&config, created, at(1, 10, 0)), created + Duration::days(30));
+        assert_eq!(
+            session_expiry(&config, created, at(20, 8, 0)),
+            at(20, 8, 0) + Duration::days(30)
+        );
+    }
+
+    #[test]
+    fn expiry_is_capped_by_max_lifetime() {
+        let config = AuthConfig::default();
+        let created = at(1, 10, 0);
+        let late = created + Duration::days(364);
+        assert_eq!(session_expiry(&config, created, late), created + Duration::days(365));
+    }
+
+    #[test]
+    fn touch_is_throttled() {
+        let last_seen = at(1, 10, 0);
+        assert!(!needs_touch(last_seen, at(1, 10, 4)));
+        assert!(needs_touch(last_seen, at(1, 10, 5)));
+        assert!(needs_touch(last_seen, at(2, 9, 0)));
+    }
+
+    #[test]
+    fn token_is_read_from_cookie_header() {
+        assert_eq!(token_from_cookie_header("session_id=abc"), Some("abc"));
+        assert_eq!(
+            token_from_cookie_header("theme=dark; session_id=abc-_1; lang=fr"),
+            Some("abc-_1")
+        );
+        assert_eq!(token_from_cookie_header("session_id="), None);
+        assert_eq!(token_from_cookie_header("other_session_id=abc"), None);
+        assert_eq!(token_from_cookie_header("session_idx=abc"), None);
+        assert_eq!(token_from_cookie_header(""), None);
+    }
+}
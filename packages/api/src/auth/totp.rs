@@ -0,0 +1,231 @@
+//! RFC 6238 time-based one-time passwords (HMAC-SHA1, 6 digits, 30-second steps), the
+//! parameters every authenticator app supports, and the recovery codes that back them up.
+//!
+//! Functions take the current Unix time as a parameter so they can be tested with fixed clocks.
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+
+pub const ISSUER: &str = "Counted";
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Codes from one step before or after are accepted, for clock drift and slow typing.
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const SECRET_BYTES: usize = 20;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+/// No 0/o, 1/l/i: recovery codes get copied by hand.
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// New random secret, base32-encoded as authenticator apps expect it.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// RFC 4226 HOTP value for a counter.
+pub fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary =
+        u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]])
+            & 0x7fff_ffff;
+    binary % 10u32.pow(DIGITS)
+}
+
+pub fn time_step(unix_time: i64) -> i64 {
+    unix_time.div_euclid(STEP_SECONDS)
+}
+
+/// Check a code typed by the user and return the time step it matched.
+///
+/// Steps up to `last_used_step` are refused, so a code cannot be replayed once accepted.
+pub fn verify_code(
+    secret_b32: &str,
+    code: &str,
+    unix_time: i64,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let secret = BASE32_NOPAD.decode(secret_b32.as_bytes()).ok()?;
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+
+    let current = time_step(unix_time);
+    (current - ALLOWED_DRIFT_STEPS..=current + ALLOWED_DRIFT_STEPS)
+        .filter(|step| *step >= 0 && last_used_step.is_none_or(|last| *step > last))
+        .find(|step| hotp(&secret, *step as u64) == code)
+}
+
+/// `otpauth://` URI encoded in the enrollment QR code.
+pub fn otpauth_uri(account_email: &str, secret_b32: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{label}?secret={secret_b32}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECONDS}",
+        issuer = percent_encode(ISSUER),
+        label = percent_encode(account_email),
+    )
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// SVG rendering of the enrollment QR code.
+pub fn qr_code_svg(uri: &str) -> anyhow::Result<String> {
+    use qrcode::{render::svg, QrCode};
+
+    let code = QrCode::new(uri.as_bytes())?;
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+/// A fresh set of recovery codes, formatted `xxxxx-xxxxx`.
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..10)
+                .map(|_| {
+                    RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char
+                })
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// Canonical form of a recovery code, the one that is hashed: case, spaces and dashes ignored.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// Whether the input looks like a TOTP code rather than a recovery code.
+pub fn is_totp_format(code: &str) -> bool {
+    let digits: Vec<char> = code.chars().filter(|c| !c.is_whitespace()).collect();
+    digits.len() == DIGITS as usize && digits.iter().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Secret of the RFC 4226 / RFC 6238 SHA-1 test vectors
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    fn rfc_secret_b32() -> String {
+        BASE32_NOPAD.encode(RFC_SECRET)
+    }
+
+    #[test]
+    fn hotp_matches_rfc4226_vectors() {
+        let expected =
+            [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(RFC_SECRET, counter as u64), *code, "counter {}", counter);
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc6238_vectors() {
+        // The RFC lists 8-digit values; 6-digit codes are their last six digits
+        let expected = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+            (20000000000, "353130"),
+        ];
+        for (time, code) in expected {
+            assert_eq!(
+                verify_code(&rfc_secret_b32(), code, time, None),
+                Some(time_step(time)),
+                "time {}",
+                time
+            );
+        }
+    }
+
+    #[test]
+    fn adjacent_steps_are_accepted() {
+        let secret = rfc_secret_b32();
+        // "081804" belongs to step 37037036 (t = 1111111109)
+        assert!(verify_code(&secret, "081804", 1111111109 + 30, None).is_some());
+        assert!(verify_code(&secret, "081804", 1111111109 - 30, None).is_some());
+        assert!(verify_code(&secret, "081804", 1111111109 + 60, None).is_none());
+    }
+
+    #[test]
+    fn used_steps_cannot_be_replayed() {
+        let secret = rfc_secret_b32();
+        let step = verify_code(&secret, "081804", 1111111109, None).unwrap();
+        assert_eq!(verify_code(&secret, "081804", 1111111109, Some(step)), None);
+        assert_eq!(verify_code(&secret, "081804", 1111111109, Some(step - 1)), Some(step));
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        let secret = rfc_secret_b32();
+        assert!(verify_code(&secret, "", 59, None).is_none());
+        assert!(verify_code(&secret, "28708", 59, None).is_none());
+        assert!(verify_code(&secret, "2870821", 59, None).is_none());
+        assert!(verify_code(&secret, "28708a", 59, None).is_none());
+        assert_eq!(verify_code(&secret, "287 082", 59, None), Some(1));
+        assert!(verify_code("not base32!", "287082", 59, None).is_none());
+    }
+
+    #[test]
+    fn generated_secret_round_trips() {
+        let secret = generate_secret();
+        assert_eq!(BASE32_NOPAD.decode(secret.as_bytes()).unwrap().len(), SECRET_BYTES);
+    }
+
+    #[test]
+    fn otpauth_uri_is_encoded() {
+        let uri = otpauth_uri("alice+test@example.com", "ABC");
+        assert_eq!(
+            uri,
+            "otpauth://totp/Counted:alice%2Btest%40example.com?secret=ABC&issuer=Counted&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn qr_code_is_svg() {
+        let svg = qr_code_svg(&otpauth_uri("a@b.c", "ABC")).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+
+    #[test]
+    fn recovery_codes_are_unique_and_normalized() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), 11);
+            assert_eq!(normalize_recovery_code(code).len(), 10);
+        }
+        assert_eq!(normalize_recovery_code(" ABCDE-fghjk "), "abcdefghjk");
+    }
+
+    #[test]
+    fn totp_format_detection() {
+        assert!(is_totp_format("123456"));
+        assert!(is_totp_format("123 456"));
+        assert!(!is_totp_format("abcde-fghjk"));
+        assert!(!is_totp_format("12345"));
+    }
+}
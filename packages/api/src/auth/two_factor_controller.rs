@@ -0,0 +1,171 @@
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{RecoveryCodes, TwoFactorCodePayload, TwoFactorSetup};
+
+use crate::error::ApiError;
+
+#[cfg(feature = "server")]
+use crate::auth::totp;
+#[cfg(feature = "server")]
+use crate::db::{get_db, Store};
+#[cfg(feature = "server")]
+use crate::utils::{get_session_account_id, sha256_hex};
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+/// Generate a new secret for the current account. It only takes effect once confirmed.
+#[post("/api/v1/auth/two-factor/setup")]
+pub async fn start_two_factor_setup() -> Result<TwoFactorSetup, ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let account = tx
+        .get_account_by_id(account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+    if account.two_factor_enabled {
+        return Err(ApiError::conflict("Two-factor authentication is already enabled"));
+    }
+
+    let secret = totp::generate_secret();
+    tx.set_pending_totp_secret(account_id, &secret).await?;
+
+    let otpauth_uri = totp::otpauth_uri(&account.email, &secret);
+    let qr_code_svg = totp::qr_code_svg(&otpauth_uri)
+        .map_err(|e| ApiError::internal(format!("Failed to render QR code: {}", e)))?;
+
+    tx.commit().await?;
+
+    Ok(TwoFactorSetup { secret, otpauth_uri, qr_code_svg })
+}
+
+/// Enable 2FA with a first code from the authenticator app, and issue recovery codes.
+#[post("/api/v1/auth/two-factor/confirm")]
+pub async fn confirm_two_factor_setup(
+    Json(payload): Json<TwoFactorCodePayload>,
+) -> Result<RecoveryCodes, ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let state = tx
+        .get_totp_state_for_update(account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+    if state.totp_secret.is_some() {
+        return Err(ApiError::conflict("Two-factor authentication is already enabled"));
+    }
+    let pending = state
+        .totp_pending_secret
+        .ok_or_else(|| ApiError::validation("Start the two-factor setup first"))?;
+
+    let step = totp::verify_code(&pending, &payload.code, chrono::Utc::now().timestamp(), None)
+        .ok_or_else(|| ApiError::validation("Invalid code"))?;
+
+    tx.enable_totp(account_id, step).await?;
+    let codes = issue_recovery_codes(&mut *tx, account_id).await?;
+
+    tx.commit().await?;
+
+    Ok(RecoveryCodes { codes })
+}
+
+/// Turn 2FA off. Takes a current code or a recovery code, not just the session.
+#[post("/api/v1/auth/two-factor/disable")]
+pub async fn disable_two_factor(Json(payload): Json<TwoFactorCodePayload>) -> Result<(), ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    if !verify_second_factor(&mut *tx, account_id, &payload.code, chrono::Utc::now().timestamp())
+        .await?
+    {
+        return Err(ApiError::validation("Invalid code"));
+    }
+
+    tx.disable_totp(account_id).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Replace every recovery code with a fresh set.
+#[post("/api/v1/auth/two-factor/recovery-codes")]
+pub async fn regenerate_recovery_codes(
+    Json(payload): Json<TwoFactorCodePayload>,
+) -> Result<RecoveryCodes, ApiError> {
+    let account_id = get_session_account_id()
+        .await
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    if !verify_second_factor(&mut *tx, account_id, &payload.code, chrono::Utc::now().timestamp())
+        .await?
+    {
+        return Err(ApiError::validation("Invalid code"));
+    }
+
+    let codes = issue_recovery_codes(&mut *tx, account_id).await?;
+
+    tx.commit().await?;
+
+    Ok(RecoveryCodes { codes })
+}
+
+/// Check a TOTP code or a recovery code for an account with 2FA enabled, consuming it.
+///
+/// Accepted TOTP steps are recorded so the same code cannot be used twice.
+#[cfg(feature = "server")]
+pub(crate) async fn verify_second_factor(
+    executor: &mut dyn Store,
+    account_id: Uuid,
+    code: &str,
+    unix_time: i64,
+) -> Result<bool, ApiError> {
+    let state = executor
+        .get_totp_state_for_update(account_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+    let Some(secret) = state.totp_secret else {
+        return Ok(false);
+    };
+
+    if totp::is_totp_format(code) {
+        match totp::verify_code(&secret, code, unix_time, state.totp_last_used_step) {
+            Some(step) => {
+                executor.record_totp_step(account_id, step).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    } else {
+        let code_hash = sha256_hex(&totp::normalize_recovery_code(code));
+        executor.consume_recovery_code(account_id, &code_hash).await
+    }
+}
+
+#[cfg(feature = "server")]
+async fn issue_recovery_codes(
+    executor: &mut dyn Store,
+    account_id: Uuid,
+) -> Result<Vec<String>, ApiError> {
+    let codes = totp::generate_recovery_codes();
+    let hashes: Vec<String> =
+        codes.iter().map(|code| sha256_hex(&totp::normalize_recovery_code(code))).collect();
+    executor.replace_recovery_codes(account_id, &hashes).await?;
+
+    Ok(codes)
+}
@@ -0,0 +1,233 @@
+//! Server configuration, loaded and validated once at startup and shared through [`AppState`].
+//!
+//! Settings come from environment variables (a `.env` file included) and, optionally, a TOML
+//! file: `CONFIG_FILE`, or `counted.toml` in the working directory when it exists. Each
+//! subsystem reads its own settings; every invalid one is reported before the server exits.
+mod source;
+
+pub use source::Source;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::auth::oidc::OidcConfig;
+use crate::auth::AuthConfig;
+use crate::db::DbConfig;
+use crate::idempotency::IdempotencyConfig;
+use crate::mailer::MailConfig;
+use crate::rate_limit::RateLimitConfig;
+use crate::state::AppState;
+use crate::telemetry::LogConfig;
+use crate::tricount::TricountConfig;
+
+const DEFAULT_CONFIG_FILE: &str = "counted.toml";
+const DEFAULT_APP_BASE_URL: &str = "http://localhost:8080";
+
+#[derive(Clone)]
+pub struct Config {
+    /// Public URL of the app, without a trailing `/`, used to build links in emails
+    pub app_base_url: String,
+    pub database: DbConfig,
+    pub log: LogConfig,
+    pub rate_limit: RateLimitConfig,
+    pub auth: AuthConfig,
+    pub mail: MailConfig,
+    /// `None` when OpenID Connect sign-in is off
+    pub oidc: Option<OidcConfig>,
+    pub tricount: TricountConfig,
+    pub idempotency: IdempotencyConfig,
+}
+
+impl Config {
+    /// Configuration of the process: environment variables and the configuration file.
+    pub fn load() -> anyhow::Result<Self> {
+        let _ = dotenvy::dotenv();
+        let file = config_file(std::env::var_os("CONFIG_FILE").map(PathBuf::from));
+        Self::from_source(&Source::load(file.as_deref())?)
+    }
+
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let mut errors = Vec::new();
+
+        let app_base_url = check(&mut errors, app_base_url(source));
+        let database = check(&mut errors, DbConfig::from_source(source));
+        let log = check(&mut errors, LogConfig::from_source(source));
+        let rate_limit = check(&mut errors, RateLimitConfig::from_source(source));
+        let auth = check(&mut errors, AuthConfig::from_source(source));
+        let mail = check(
+            &mut errors,
+            MailConfig::from_source(
+                source,
+                app_base_url.as_deref().unwrap_or(DEFAULT_APP_BASE_URL),
+            ),
+        );
+        let oidc = check(
+            &mut errors,
+            OidcConfig::from_source(
+                source,
+                app_base_url.as_deref().unwrap_or(DEFAULT_APP_BASE_URL),
+            ),
+        );
+        let tricount = check(&mut errors, TricountConfig::from_source(source));
+        let idempotency = check(&mut errors, IdempotencyConfig::from_source(source));
+        // A subsystem stops reading at its first error, so unread settings mean something only
+        // once everything else is valid
+        if errors.is_empty() {
+            errors.extend(source.unused_file_settings());
+        }
+
+        match (app_base_url, database, log, rate_limit, auth, mail, oidc, tricount, idempotency) {
+            (
+                Some(app_base_url),
+                Some(database),
+                Some(log),
+                Some(rate_limit),
+                Some(auth),
+                Some(mail),
+                Some(oidc),
+                Some(tricount),
+                Some(idempotency),
+            ) if errors.is_empty() => Ok(Self {
+                app_base_url,
+                database,
+                log,
+                rate_limit,
+                auth,
+                mail,
+                oidc,
+                tricount,
+                idempotency,
+            }),
+            _ => anyhow::bail!("Invalid configuration:\n- {}", errors.join("\n- ")),
+        }
+    }
+}
+
+/// The configuration of the running server, for the current request.
+pub fn get_config() -> Arc<Config> {
+    AppState::current().expect("The app state is not available outside of a request").config
+}
+
+/// The file given with `CONFIG_FILE`, which must exist, or the default one if present.
+fn config_file(explicit: Option<PathBuf>) -> Option<PathBuf> {
+    explicit.or_else(|| Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()))
+}
+
+fn check<T>(errors: &mut Vec<String>, result: anyhow::Result<T>) -> Option<T> {
+    result.map_err(|e| errors.push(format!("{:#}", e))).ok()
+}
+
+fn app_base_url(source: &Source) -> anyhow::Result<String> {
+    let url = source.var("APP_BASE_URL").unwrap_or_else(|| DEFAULT_APP_BASE_URL.to_string());
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        anyhow::bail!("Invalid APP_BASE_URL \"{}\": expected an http:// or https:// URL", url);
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(env: &[(&str, &str)], file: &str) -> anyhow::Result<Config> {
+        Config::from_source(&Source::from_pairs(env, file)?)
+    }
+
+    const DATABASE_URL: (&str, &str) = ("DATABASE_URL", "postgres://localhost/counted");
+
+    #[test]
+    fn defaults_only_need_a_database() {
+        let config = config(&[DATABASE_URL], "").unwrap();
+        assert_eq!(config.app_base_url, "http://localhost:8080");
+        assert_eq!(config.auth, AuthConfig::default());
+        assert!(config.oidc.is_none());
+        assert_eq!(config.tricount.api_url, "https://api.tricount.bunq.com");
+        assert_eq!(config.idempotency, IdempotencyConfig::default());
+    }
+
+    #[test]
+    fn file_and_environment_are_combined() {
+        let config = config(
+            &[
+                DATABASE_URL,
+                ("APP_BASE_URL", "https://counted.example.com/"),
+                ("MAIL_TRANSPORT", "file"),
+            ],
+            "app_base_url = \"https://ignored.example.com\"\n[login_lockout]\nthreshold = 3",
+        )
+        .unwrap();
+        assert_eq!(config.app_base_url, "https://counted.example.com");
+        assert_eq!(config.auth.lockout_threshold, 3);
+    }
+
+    #[test]
+    fn every_error_is_reported() {
+        let error = config(
+            &[("DB_MAX_CONNECTIONS", "0"), ("SESSION_IDLE_TIMEOUT_DAYS", "400")],
+            "app_base_url = \"counted.example.com\"",
+        )
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(error.contains("- DATABASE_URL must be set"), "{}", error);
+        assert!(error.contains("- Invalid APP_BASE_URL"), "{}", error);
+        assert!(error.contains("- SESSION_IDLE_TIMEOUT_DAYS cannot exceed"), "{}", error);
+    }
+
+    #[test]
+    fn misspelled_file_settings_are_refused() {
+        let error = config(&[DATABASE_URL], "mail_transprot = \"smtp\"").err().unwrap().to_string();
+        assert!(
+            error.contains("- MAIL_TRANSPROT in the configuration file is not used"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn https_deployments_need_a_mail_transport() {
+        for transport in [None, Some("log")] {
+            let mut env = vec![DATABASE_URL, ("APP_BASE_URL", "https://counted.example.com")];
+            env.extend(transport.map(|transport| ("MAIL_TRANSPORT", transport)));
+            let error = config(&env, "").err().unwrap().to_string();
+            assert!(error.contains("- MAIL_TRANSPORT must be smtp or file"), "{}", error);
+        }
+        assert!(config(&[DATABASE_URL, ("MAIL_TRANSPORT", "log")], "").is_ok());
+    }
+
+    #[test]
+    fn idempotency_keys_are_reclaimed_within_their_retention() {
+        let idempotency = config(&[DATABASE_URL, ("IDEMPOTENCY_RECLAIM_MINUTES", "120")], "")
+            .unwrap()
+            .idempotency;
+        assert_eq!(idempotency.reclaim_after, chrono::Duration::minutes(120));
+
+        for minutes in ["1", "1500"] {
+            let env = [DATABASE_URL, ("IDEMPOTENCY_RECLAIM_MINUTES", minutes)];
+            let error = config(&env, "").err().unwrap().to_string();
+            assert!(
+                error.contains("- IDEMPOTENCY_RECLAIM_MINUTES must be at least 5"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn oidc_redirect_defaults_to_the_app_url() {
+        let config = config(
+            &[
+                DATABASE_URL,
+                ("APP_BASE_URL", "https://counted.example.com"),
+                ("MAIL_TRANSPORT", "file"),
+                ("OIDC_ISSUER", "https://id.example.com/"),
+                ("OIDC_CLIENT_ID", "counted"),
+            ],
+            "",
+        )
+        .unwrap();
+        let oidc = config.oidc.unwrap();
+        assert_eq!(oidc.issuer, "https://id.example.com");
+        assert_eq!(oidc.redirect_url, "https://counted.example.com/auth/oidc/callback");
+    }
+}
@@ -0,0 +1,177 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::Context;
+
+/// Where settings are read from: environment variables, then the configuration file.
+///
+/// Settings are named like environment variables. In the TOML file a key is the variable name
+/// in lower case, and a table name is a prefix joined with `_`: `[db] max_connections = 20` is
+/// `DB_MAX_CONNECTIONS`. Environment variables win over the file.
+#[derive(Debug, Default)]
+pub struct Source {
+    env: HashMap<String, String>,
+    file: BTreeMap<String, String>,
+    file_path: Option<PathBuf>,
+    /// Names looked up so far, to report file settings nothing reads
+    used: RefCell<BTreeSet<String>>,
+}
+
+impl Source {
+    /// The environment of the process and, if given, a TOML file.
+    pub fn load(file_path: Option<&Path>) -> anyhow::Result<Self> {
+        let file = match file_path {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("Unable to read {}", path.display()))?;
+                parse_file(&text).with_context(|| format!("Invalid {}", path.display()))?
+            }
+            None => BTreeMap::new(),
+        };
+        Ok(Self {
+            env: std::env::vars().collect(),
+            file,
+            file_path: file_path.map(Path::to_path_buf),
+            used: RefCell::default(),
+        })
+    }
+
+    /// Settings given directly, for tests.
+    pub fn from_pairs(env: &[(&str, &str)], file: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            env: env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            file: parse_file(file)?,
+            ..Self::default()
+        })
+    }
+
+    /// Value of a setting; blank values count as unset.
+    pub fn var(&self, name: &str) -> Option<String> {
+        self.used.borrow_mut().insert(name.to_string());
+        [self.env.get(name), self.file.get(name)]
+            .into_iter()
+            .flatten()
+            .map(|value| value.trim())
+            .find(|value| !value.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Parsed value of a setting, or `default` when it is unset.
+    pub fn parse<T>(&self, name: &str, default: T) -> anyhow::Result<T>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match self.var(name) {
+            Some(value) => {
+                value.parse().map_err(|e| anyhow::anyhow!("Invalid {} \"{}\": {}", name, value, e))
+            }
+            None => Ok(default),
+        }
+    }
+
+    /// Settings of the file that nothing has read: typos, or options for a disabled feature.
+    pub fn unused_file_settings(&self) -> Vec<String> {
+        let used = self.used.borrow();
+        let location = self
+            .file_path
+            .as_ref()
+            .map_or_else(|| "the configuration file".to_string(), |p| p.display().to_string());
+        self.file
+            .keys()
+            .filter(|name| !used.contains(*name))
+            .map(|name| format!("{} in {} is not used", name, location))
+            .collect()
+    }
+}
+
+fn parse_file(text: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let table: toml::Table = text.parse()?;
+    let mut settings = BTreeMap::new();
+    flatten("", table, &mut settings)?;
+    Ok(settings)
+}
+
+fn flatten(
+    prefix: &str,
+    table: toml::Table,
+    settings: &mut BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    for (key, value) in table {
+        let name = format!("{}{}", prefix, key.to_uppercase());
+        let value = match value {
+            toml::Value::Table(table) => {
+                flatten(&format!("{}_", name), table, settings)?;
+                continue;
+            }
+            toml::Value::String(value) => value,
+            toml::Value::Integer(value) => value.to_string(),
+            toml::Value::Float(value) => value.to_string(),
+            toml::Value::Boolean(value) => value.to_string(),
+            toml::Value::Datetime(value) => value.to_string(),
+            toml::Value::Array(_) => anyhow::bail!("{} must be a single value", name),
+        };
+        settings.insert(name, value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_tables_prefix_their_keys() {
+        let source = Source::from_pairs(
+            &[],
+            r#"
+            app_base_url = "https://counted.example.com"
+            [db]
+            max_connections = 20
+            [rate_limit]
+            login = "10/60"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(source.var("APP_BASE_URL").as_deref(), Some("https://counted.example.com"));
+        assert_eq!(source.var("DB_MAX_CONNECTIONS").as_deref(), Some("20"));
+        assert_eq!(source.var("RATE_LIMIT_LOGIN").as_deref(), Some("10/60"));
+    }
+
+    #[test]
+    fn environment_wins_over_the_file() {
+        let source = Source::from_pairs(
+            &[("COOKIE_SECURE", "false"), ("LOG_FORMAT", " ")],
+            "cookie_secure = true\nlog_format = \"json\"",
+        )
+        .unwrap();
+        assert!(!source.parse("COOKIE_SECURE", true).unwrap());
+        // A blank variable is unset, not an override
+        assert_eq!(source.var("LOG_FORMAT").as_deref(), Some("json"));
+    }
+
+    #[test]
+    fn invalid_values_name_the_setting() {
+        let source = Source::from_pairs(&[("DB_MAX_CONNECTIONS", "many")], "").unwrap();
+        let error = source.parse::<u32>("DB_MAX_CONNECTIONS", 10).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid DB_MAX_CONNECTIONS \"many\""), "{}", error);
+    }
+
+    #[test]
+    fn unread_file_settings_are_reported() {
+        let source =
+            Source::from_pairs(&[], "[db]\nmax_conections = 20\nmax_connections = 20").unwrap();
+        source.var("DB_MAX_CONNECTIONS");
+        assert_eq!(
+            source.unused_file_settings(),
+            vec!["DB_MAX_CONECTIONS in the configuration file is not used".to_string()]
+        );
+    }
+
+    #[test]
+    fn arrays_are_refused() {
+        assert!(Source::from_pairs(&[], "trusted_proxies = [\"10.0.0.0/8\"]").is_err());
+    }
+}
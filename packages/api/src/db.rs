@@ -1,13 +1,389 @@
-#[cfg(feature = "sqlx")]
-use sqlx::{PgPool, Pool, Postgres};
+//! The database, opened once at startup and shared through [`AppState`].
+//!
+//! Postgres is the default backend; a `sqlite:` `DATABASE_URL` selects SQLite instead, for
+//! self-hosting on a single machine or running next to the desktop app. Each backend has its
+//! own migrations and implements every repository trait, gathered in [`Store`]: controllers
+//! work on a `&mut dyn Store` and never see which database is behind it.
+//!
+//! Configured with `DATABASE_URL` and optionally `DB_MAX_CONNECTIONS` (default 10),
+//! `DB_ACQUIRE_TIMEOUT_SECS` (default 5) and `DB_STATEMENT_CACHE_CAPACITY` (prepared statements
+//! kept per connection, default 100, `0` to disable).
+#[cfg(feature = "server")]
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "server")]
+use std::time::Duration;
+
+#[cfg(feature = "server")]
+use anyhow::Context;
+#[cfg(feature = "server")]
+use sqlx::migrate::Migrator;
+#[cfg(feature = "server")]
+use sqlx::pool::PoolConnection;
+#[cfg(feature = "server")]
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+#[cfg(feature = "server")]
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+#[cfg(feature = "server")]
+use sqlx::{PgPool, Postgres, Sqlite, SqlitePool, Transaction};
+
+#[cfg(feature = "server")]
+use crate::account_projects::account_projects_repository::AccountProjectsRepository;
+#[cfg(feature = "server")]
+use crate::auth::auth_repository::AuthRepository;
+#[cfg(feature = "server")]
+use crate::config::Source;
+#[cfg(feature = "server")]
+use crate::expenses::expenses_repository::ExpensesRepository;
+#[cfg(feature = "server")]
+use crate::idempotency::idempotency_repository::IdempotencyRepository;
+#[cfg(feature = "server")]
+use crate::invites::invites_repository::InvitesRepository;
+#[cfg(feature = "server")]
+use crate::payments::payments_repository::PaymentsRepository;
+#[cfg(feature = "server")]
+use crate::projects::projects_repository::ProjectsRepository;
+#[cfg(feature = "server")]
+use crate::share_links::share_links_repository::ShareLinksRepository;
+#[cfg(feature = "server")]
+use crate::state::AppState;
+#[cfg(feature = "server")]
+use crate::users::users_repository::UsersRepository;
+
+#[cfg(all(test, feature = "server"))]
+mod store_tests;
+
+/// The Postgres migrations in `migrations/`, embedded in the binary.
+#[cfg(feature = "server")]
+pub static MIGRATOR: Migrator = sqlx::migrate!("../../migrations");
+
+/// The SQLite migrations in `migrations/sqlite/`, embedded in the binary.
+#[cfg(feature = "server")]
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("../../migrations/sqlite");
+
+/// Every repository, as implemented by a connection of each backend.
+#[cfg(feature = "server")]
+pub trait Store:
+    AccountProjectsRepository
+    + AuthRepository
+    + ExpensesRepository
+    + IdempotencyRepository
+    + InvitesRepository
+    + PaymentsRepository
+    + ProjectsRepository
+    + ShareLinksRepository
+    + UsersRepository
+    + Send
+{
+}
+
+#[cfg(feature = "server")]
+impl<T> Store for T where
+    T: AccountProjectsRepository
+        + AuthRepository
+        + ExpensesRepository
+        + IdempotencyRepository
+        + InvitesRepository
+        + PaymentsRepository
+        + ProjectsRepository
+        + ShareLinksRepository
+        + UsersRepository
+        + Send
+{
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Postgres,
+    Sqlite,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbConfig {
+    pub url: String,
+    pub max_connections: u32,
+    /// How long a request waits for a free connection before failing
+    pub acquire_timeout: Duration,
+    pub statement_cache_capacity: usize,
+}
+
+#[cfg(feature = "server")]
+impl DbConfig {
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let url = source.var("DATABASE_URL").context("DATABASE_URL must be set")?;
+        if backend(&url).is_none() {
+            anyhow::bail!(
+                "Invalid DATABASE_URL: expected a postgres:// or sqlite: URL, got \"{}\"",
+                url.split(':').next().unwrap_or_default()
+            );
+        }
+        let max_connections = source.parse("DB_MAX_CONNECTIONS", 10)?;
+        if max_connections == 0 {
+            anyhow::bail!("DB_MAX_CONNECTIONS must be at least 1");
+        }
+
+        Ok(Self {
+            url,
+            max_connections,
+            acquire_timeout: Duration::from_secs(source.parse("DB_ACQUIRE_TIMEOUT_SECS", 5)?),
+            statement_cache_capacity: source.parse("DB_STATEMENT_CACHE_CAPACITY", 100)?,
+        })
+    }
+
+    pub fn backend(&self) -> Backend {
+        backend(&self.url).unwrap_or(Backend::Postgres)
+    }
+}
+
+#[cfg(feature = "server")]
+fn backend(url: &str) -> Option<Backend> {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        Some(Backend::Postgres)
+    } else if url.starts_with("sqlite:") {
+        Some(Backend::Sqlite)
+    } else {
+        None
+    }
+}
+
+/// Open the pool and check that the database answers, so a bad configuration stops the server
+/// at boot rather than failing its first requests.
+#[cfg(feature = "server")]
+pub async fn connect(config: &DbConfig) -> anyhow::Result<Db> {
+    match config.backend() {
+        Backend::Postgres => {
+            let options = config
+                .url
+                .parse::<PgConnectOptions>()
+                .context("Invalid DATABASE_URL")?
+                .statement_cache_capacity(config.statement_cache_capacity);
+
+            let pool = PgPoolOptions::new()
+                .max_connections(config.max_connections)
+                .acquire_timeout(config.acquire_timeout)
+                .connect_with(options)
+                .await
+                .context("Unable to connect to the database")?;
+            Ok(Db::Postgres(pool))
+        }
+        Backend::Sqlite => {
+            // Readers do not block the writer in WAL mode, and a writer waits for the previous
+            // one for as long as a request waits for a connection
+            let options = config
+                .url
+                .parse::<SqliteConnectOptions>()
+                .context("Invalid DATABASE_URL")?
+                .create_if_missing(true)
+                .journal_mode(SqliteJournalMode::Wal)
+                .foreign_keys(true)
+                .busy_timeout(config.acquire_timeout)
+                .statement_cache_capacity(config.statement_cache_capacity);
+
+            let pool = SqlitePoolOptions::new()
+                .max_connections(config.max_connections)
+                .acquire_timeout(config.acquire_timeout)
+                .connect_with(options)
+                .await
+                .context("Unable to open the database")?;
+            Ok(Db::Sqlite(pool))
+        }
+    }
+}
+
+/// A connection pool to either backend.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub enum Db {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+#[cfg(feature = "server")]
+impl Db {
+    pub fn backend(&self) -> Backend {
+        match self {
+            Db::Postgres(_) => Backend::Postgres,
+            Db::Sqlite(_) => Backend::Sqlite,
+        }
+    }
+
+    pub async fn begin(&self) -> Result<Tx, sqlx::Error> {
+        match self {
+            Db::Postgres(pool) => Ok(Tx::Postgres(pool.begin().await?)),
+            // Take the write lock upfront: a transaction that reads before writing would
+            // otherwise fail, instead of waiting, when another one wrote in between
+            Db::Sqlite(pool) => Ok(Tx::Sqlite(pool.begin_with("BEGIN IMMEDIATE").await?)),
+        }
+    }
+
+    pub async fn acquire(&self) -> Result<Conn, sqlx::Error> {
+        match self {
+            Db::Postgres(pool) => Ok(Conn::Postgres(pool.acquire().await?)),
+            Db::Sqlite(pool) => Ok(Conn::Sqlite(pool.acquire().await?)),
+        }
+    }
+
+    /// Open connections, in use or idle.
+    pub fn size(&self) -> u32 {
+        match self {
+            Db::Postgres(pool) => pool.size(),
+            Db::Sqlite(pool) => pool.size(),
+        }
+    }
+
+    pub fn num_idle(&self) -> usize {
+        match self {
+            Db::Postgres(pool) => pool.num_idle(),
+            Db::Sqlite(pool) => pool.num_idle(),
+        }
+    }
+
+    pub fn max_connections(&self) -> u32 {
+        match self {
+            Db::Postgres(pool) => pool.options().get_max_connections(),
+            Db::Sqlite(pool) => pool.options().get_max_connections(),
+        }
+    }
+}
+
+/// A transaction on either backend, rolled back when dropped without [`Tx::commit`].
+#[cfg(feature = "server")]
+pub enum Tx {
+    Postgres(Transaction<'static, Postgres>),
+    Sqlite(Transaction<'static, Sqlite>),
+}
 
 #[cfg(feature = "server")]
-pub async fn get_db() -> Pool<Postgres> {
-    // Load .env file if it exists (for local development), but don't panic if it doesn't (for Docker)
-    let _ = dotenvy::dotenv();
-    let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL doit être défini");
+impl Tx {
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        match self {
+            Tx::Postgres(tx) => tx.commit().await,
+            Tx::Sqlite(tx) => tx.commit().await,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl Deref for Tx {
+    type Target = dyn Store;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Tx::Postgres(tx) => &**tx,
+            Tx::Sqlite(tx) => &**tx,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl DerefMut for Tx {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Tx::Postgres(tx) => &mut **tx,
+            Tx::Sqlite(tx) => &mut **tx,
+        }
+    }
+}
+
+/// A connection of the pool of either backend, outside of any transaction.
+#[cfg(feature = "server")]
+pub enum Conn {
+    Postgres(PoolConnection<Postgres>),
+    Sqlite(PoolConnection<Sqlite>),
+}
+
+#[cfg(feature = "server")]
+impl Conn {
+    /// Close the connection instead of returning it to the pool.
+    pub fn detach(self) {
+        match self {
+            Conn::Postgres(conn) => drop(conn.detach()),
+            Conn::Sqlite(conn) => drop(conn.detach()),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl Deref for Conn {
+    type Target = dyn Store;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Conn::Postgres(conn) => &**conn,
+            Conn::Sqlite(conn) => &**conn,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl DerefMut for Conn {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Conn::Postgres(conn) => &mut **conn,
+            Conn::Sqlite(conn) => &mut **conn,
+        }
+    }
+}
+
+/// The database of the running server, for the current request.
+#[cfg(feature = "server")]
+pub fn get_db() -> Db {
+    AppState::current().expect("The app state is not available outside of a request").db
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> anyhow::Result<DbConfig> {
+        DbConfig::from_source(&Source::from_pairs(vars, "")?)
+    }
+
+    #[test]
+    fn defaults_apply() {
+        let config = config(&[("DATABASE_URL", "postgres://localhost/counted")]).unwrap();
+        assert_eq!(
+            config,
+            DbConfig {
+                url: "postgres://localhost/counted".to_string(),
+                max_connections: 10,
+                acquire_timeout: Duration::from_secs(5),
+                statement_cache_capacity: 100,
+            }
+        );
+        assert_eq!(config.backend(), Backend::Postgres);
+    }
+
+    #[test]
+    fn settings_are_read() {
+        let config = config(&[
+            ("DATABASE_URL", "postgres://localhost/counted"),
+            ("DB_MAX_CONNECTIONS", "32"),
+            ("DB_ACQUIRE_TIMEOUT_SECS", "2"),
+            ("DB_STATEMENT_CACHE_CAPACITY", "0"),
+        ])
+        .unwrap();
+        assert_eq!(config.max_connections, 32);
+        assert_eq!(config.acquire_timeout, Duration::from_secs(2));
+        assert_eq!(config.statement_cache_capacity, 0);
+    }
+
+    #[test]
+    fn the_url_selects_the_backend() {
+        let sqlite = config(&[("DATABASE_URL", "sqlite:///var/lib/counted/counted.db")]).unwrap();
+        assert_eq!(sqlite.backend(), Backend::Sqlite);
 
-    let pool = PgPool::connect(&db_url).await.expect("Unable to connect to the database");
+        let error = config(&[("DATABASE_URL", "mysql://localhost/counted")]).unwrap_err();
+        assert!(error.to_string().contains("expected a postgres:// or sqlite: URL"), "{}", error);
+    }
 
-    pool
+    #[test]
+    fn invalid_settings_are_refused() {
+        assert!(config(&[]).is_err());
+        let url = ("DATABASE_URL", "postgres://localhost/counted");
+        assert!(config(&[url, ("DB_MAX_CONNECTIONS", "0")]).is_err());
+        assert!(config(&[url, ("DB_MAX_CONNECTIONS", "many")]).is_err());
+        assert!(config(&[url, ("DB_ACQUIRE_TIMEOUT_SECS", "-1")]).is_err());
+    }
 }
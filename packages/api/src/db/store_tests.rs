@@ -0,0 +1,601 @@
+//! The repository suite, run against every backend: always on an in-memory SQLite database, and
+//! on Postgres too when `TEST_DATABASE_URL` points to a database the tests may migrate. Each
+//! scenario runs in a transaction that is rolled back, so the database is left as it was.
+use chrono::{Duration, NaiveDate, Utc};
+use futures::future::BoxFuture;
+use shared::{
+    ApiTokenScope, BatchProject, CreatableExpense, CreatableProject, CreatableUser,
+    EditableExpense, ErrorCode, ExpenseType, NewPayment, ProjectRole, ProjectStatus,
+    UpsertAccountProject, UserAmount,
+};
+use uuid::Uuid;
+
+use super::*;
+use crate::auth::auth_repository::{AccountTokenPurpose, NewApiToken, OidcLoginState};
+use crate::migrations;
+use crate::payments::payments_repository::get_summary_by_project_id;
+use crate::projects::project_lifecycle::settle;
+use crate::utils::sha256_hex;
+
+type Scenario = for<'a> fn(&'a mut dyn Store) -> BoxFuture<'a, ()>;
+
+async fn open(url: &str) -> Db {
+    let config = DbConfig {
+        url: url.to_string(),
+        // Every connection to `sqlite::memory:` opens a database of its own
+        max_connections: 1,
+        acquire_timeout: std::time::Duration::from_secs(5),
+        statement_cache_capacity: 100,
+    };
+    let db = connect(&config).await.unwrap();
+    migrations::migrate(&db).await.unwrap();
+    db
+}
+
+async fn run(scenario: Scenario) {
+    let mut backends = vec![open("sqlite::memory:").await];
+    if let Ok(url) = std::env::var("TEST_DATABASE_URL") {
+        backends.push(open(&url).await);
+    }
+
+    for db in backends {
+        let mut tx = db.begin().await.unwrap();
+        scenario(&mut *tx).await;
+    }
+}
+
+async fn new_account(store: &mut dyn Store, email: &str) -> Uuid {
+    store.create_account(email, "hash", "Alice").await.unwrap()
+}
+
+async fn new_project(store: &mut dyn Store, owner: Option<Uuid>) -> Uuid {
+    let project = CreatableProject {
+        name: "Vacances".to_string(),
+        description: Some("Ski".to_string()),
+        currency: Some("EUR".to_string()),
+    };
+    store.add_project(project, owner).await.unwrap()
+}
+
+fn participant(name: &str, project_id: Uuid) -> CreatableUser {
+    CreatableUser { name: name.to_string(), project_id, invited_email: None }
+}
+
+fn expense(project_id: Uuid, author_id: i32, debtor_id: i32, amount: f64) -> CreatableExpense {
+    CreatableExpense {
+        name: "Courses".to_string(),
+        amount,
+        expense_type: ExpenseType::Expense,
+        project_id,
+        payers: vec![UserAmount { user_id: author_id, amount }],
+        debtors: vec![UserAmount { user_id: debtor_id, amount }],
+        author_id,
+        description: None,
+        date: NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+    }
+}
+
+fn projects_and_memberships(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let owner = new_account(store, "owner@example.com").await;
+        let project_id = new_project(store, Some(owner)).await;
+        let other_project_id = new_project(store, None).await;
+
+        let project = store.get_project(project_id).await.unwrap();
+        assert_eq!(project.name, "Vacances");
+        assert_eq!(project.currency, "EUR");
+        assert_eq!(project.status, ProjectStatus::Ongoing);
+        assert_eq!(project.owner_account_id, Some(owner));
+        assert!(project.editors_can_edit_others_expenses);
+
+        let stale = shared::ProjectDto { name: "Week-end".to_string(), ..project.clone() };
+        let updated = store
+            .update_project(shared::ProjectDto {
+                name: "Week-end".to_string(),
+                status: ProjectStatus::Closed,
+                ..project
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.name, "Week-end");
+        assert_eq!(updated.version, 2);
+        assert!(store.update_project(stale).await.unwrap().is_none());
+        assert_eq!(store.get_project(project_id).await.unwrap().status, ProjectStatus::Closed);
+
+        let batch = BatchProject { ids: vec![project_id, other_project_id, Uuid::new_v4()] };
+        assert_eq!(store.get_projects_by_ids(batch).await.unwrap().len(), 2);
+        assert!(store.get_projects_by_ids(BatchProject { ids: vec![] }).await.unwrap().is_empty());
+
+        store.upsert_account_project_role(owner, project_id, ProjectRole::Owner).await.unwrap();
+        let mine = store.get_projects(Some(owner)).await.unwrap();
+        assert_eq!(mine.iter().map(|p| p.id).collect::<Vec<_>>(), vec![project_id]);
+
+        let member = new_account(store, "member@example.com").await;
+        let accepted = store
+            .batch_upsert_account_projects(
+                member,
+                vec![
+                    UpsertAccountProject { project_id: other_project_id, user_id: None },
+                    UpsertAccountProject { project_id: Uuid::new_v4(), user_id: None },
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(accepted, vec![other_project_id]);
+
+        store.upsert_account_project(member, project_id, Some(7)).await.unwrap();
+        let membership = store.get_account_project(member, project_id).await.unwrap().unwrap();
+        assert_eq!(membership.role, ProjectRole::Editor);
+        assert_eq!(membership.user_id, Some(7));
+
+        assert!(store
+            .update_account_project_role(member, project_id, ProjectRole::Viewer)
+            .await
+            .unwrap());
+        let members = store.get_project_members(project_id).await.unwrap();
+        assert_eq!(members.len(), 2);
+        assert!(members.iter().any(|m| m.account_id == member && m.role == ProjectRole::Viewer));
+
+        store.delete_account_project(member, project_id).await.unwrap();
+        assert!(store.get_account_project(member, project_id).await.unwrap().is_none());
+        assert!(!store
+            .update_account_project_role(member, project_id, ProjectRole::Owner)
+            .await
+            .unwrap());
+
+        store.delete_project_by_id(project_id).await.unwrap();
+        let error = store.get_project(project_id).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::NotFound);
+    })
+}
+
+fn participants_expenses_and_payments(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let project_id = new_project(store, None).await;
+        let mut invited = participant("Bob", project_id);
+        invited.invited_email = Some("Bob@Example.com".to_string());
+        let users =
+            store.insert_users(vec![participant("Alice", project_id), invited]).await.unwrap();
+        let (alice, bob) = (users[0].id, users[1].id);
+        let carol = store.add_user(participant("Carol", project_id)).await.unwrap();
+        assert_eq!(store.get_users_by_project_id(project_id).await.unwrap().len(), 3);
+        assert_eq!(store.get_project_ids_by_user_id(carol).await.unwrap(), vec![project_id]);
+
+        let pending =
+            store.find_invited_participants(&sha256_hex("bob@example.com")).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!((pending[0].project_id, pending[0].user_id), (project_id, bob));
+        store.rename_user(bob, "Robert").await.unwrap();
+
+        let expense_id = store.add_expense(expense(project_id, alice, bob, 30.0)).await.unwrap();
+        store
+            .add_payments(vec![
+                NewPayment { expense_id, user_id: alice, is_debt: false, amount: 30.0 },
+                NewPayment { expense_id, user_id: bob, is_debt: true, amount: 30.0 },
+            ])
+            .await
+            .unwrap();
+        store.add_payments(vec![]).await.unwrap();
+
+        let stored = store.get_expense_by_id(expense_id).await.unwrap();
+        assert_eq!(stored.amount, 30.0);
+        assert_eq!(stored.expense_type, ExpenseType::Expense);
+        assert_eq!(stored.date, NaiveDate::from_ymd_opt(2026, 10, 1).unwrap());
+
+        let summary = get_summary_by_project_id(store, project_id).await.unwrap();
+        assert_eq!(summary.summary[&alice], 30.0);
+        assert_eq!(summary.summary[&bob], -30.0);
+        assert_eq!(summary.reimbursement_suggestions.len(), 1);
+
+        let creatable = expense(project_id, alice, bob, 42.5);
+        let version = store
+            .edit_expense(EditableExpense {
+                id: expense_id,
+                name: "Restaurant".to_string(),
+                amount: creatable.amount,
+                expense_type: ExpenseType::Transfer,
+                project_id,
+                payers: creatable.payers,
+                debtors: creatable.debtors,
+                author_id: alice,
+                description: Some("Midi".to_string()),
+                date: creatable.date,
+                version: stored.version,
+            })
+            .await
+            .unwrap();
+        assert_eq!(version, Some(stored.version + 1));
+        let stale = EditableExpense::from_expense(&stored, &[]);
+        assert_eq!(store.edit_expense(stale).await.unwrap(), None);
+        let edited = store.get_expenses_by_project_id(project_id).await.unwrap();
+        assert_eq!(edited.len(), 1);
+        assert_eq!(edited[0].name, "Restaurant");
+        assert_eq!(edited[0].expense_type, ExpenseType::Transfer);
+
+        assert_eq!(store.get_payments_by_expense_id(expense_id).await.unwrap().len(), 2);
+        assert_eq!(store.get_payments_by_user_id(bob).await.unwrap().len(), 1);
+        assert_eq!(store.get_payments_by_expense_ids(vec![expense_id]).await.unwrap().len(), 2);
+        assert!(store.get_payments_by_expense_ids(vec![]).await.unwrap().is_empty());
+
+        store.delete_payments_by_expense_id(expense_id).await.unwrap();
+        store.delete_expense(expense_id).await.unwrap();
+        assert_eq!(
+            store.get_expense_by_id(expense_id).await.unwrap_err().code,
+            ErrorCode::NotFound
+        );
+
+        store.delete_users(vec![bob, carol]).await.unwrap();
+        store.delete_users(vec![]).await.unwrap();
+        store.delete_user(alice).await.unwrap();
+        assert!(store.get_users_by_project_id(project_id).await.unwrap().is_empty());
+    })
+}
+
+fn invites_and_share_links(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let owner = new_account(store, "owner@example.com").await;
+        let project_id = new_project(store, Some(owner)).await;
+        let now = Utc::now().naive_utc();
+
+        let invite = store
+            .create_invite(
+                project_id,
+                "invite-hash",
+                ProjectRole::Viewer,
+                now + Duration::hours(1),
+                Some(1),
+                Some(owner),
+            )
+            .await
+            .unwrap();
+        assert_eq!(invite.role, ProjectRole::Viewer);
+        assert_eq!(invite.use_count, 0);
+        assert_eq!(store.get_active_invites(project_id, now).await.unwrap(), vec![invite.clone()]);
+
+        let found = store.find_invite_by_token_hash_for_update("invite-hash").await.unwrap();
+        assert_eq!(found.map(|i| i.id), Some(invite.id));
+        store.increment_invite_use(invite.id).await.unwrap();
+        // Used up
+        assert!(store.get_active_invites(project_id, now).await.unwrap().is_empty());
+        assert!(store.revoke_invite(project_id, invite.id).await.unwrap());
+        assert!(!store.revoke_invite(project_id, invite.id).await.unwrap());
+
+        let link = store.create_share_link(project_id, "link-hash", Some(owner)).await.unwrap();
+        assert_eq!(store.get_active_share_links(project_id).await.unwrap(), vec![link.clone()]);
+        assert_eq!(store.find_shared_project_id("link-hash").await.unwrap(), Some(project_id));
+        assert!(store.revoke_share_link(project_id, link.id).await.unwrap());
+        assert_eq!(store.find_shared_project_id("link-hash").await.unwrap(), None);
+
+        // Last: a failed statement aborts the rest of a Postgres transaction
+        let error = store.create_share_link(project_id, "link-hash", None).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::Conflict);
+    })
+}
+
+fn accounts_and_sessions(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let account_id = new_account(store, "alice@example.com").await;
+
+        let account = store.find_account_by_email("alice@example.com").await.unwrap().unwrap();
+        assert_eq!(account.id, account_id);
+        assert_eq!(account.password_hash.as_deref(), Some("hash"));
+        assert!(!account.two_factor_enabled);
+        assert_eq!(
+            store.find_account_by_email("bob@example.com").await.unwrap().map(|a| a.id),
+            None
+        );
+
+        assert!(!store.increment_failed_login(account_id, 2, Duration::minutes(15)).await.unwrap());
+        assert!(store.increment_failed_login(account_id, 2, Duration::minutes(15)).await.unwrap());
+        let locked = store.find_account_by_email("alice@example.com").await.unwrap().unwrap();
+        assert_eq!(locked.failed_login_count, 2);
+        assert!(locked.locked_until.unwrap() > Utc::now().naive_utc());
+        store.reset_failed_login(account_id).await.unwrap();
+        store.update_password_hash(account_id, "new-hash").await.unwrap();
+
+        let now = Utc::now().naive_utc();
+        let session_id = store
+            .create_session(account_id, "current", now + Duration::days(1), Some("Firefox"), None)
+            .await
+            .unwrap();
+        store
+            .create_session(account_id, "other", now + Duration::days(1), None, None)
+            .await
+            .unwrap();
+        store
+            .create_session(account_id, "expired", now - Duration::days(1), None, None)
+            .await
+            .unwrap();
+
+        let session = store.find_active_session("current").await.unwrap().unwrap();
+        assert_eq!((session.id, session.account_id), (session_id, account_id));
+        assert!(store.find_active_session("expired").await.unwrap().is_none());
+        store.touch_session(session_id, now + Duration::days(2)).await.unwrap();
+
+        let sessions = store.list_sessions(account_id, Some("current")).await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(
+            sessions.iter().filter(|s| s.current).map(|s| s.id).collect::<Vec<_>>(),
+            vec![session_id]
+        );
+        assert!(store.list_sessions(account_id, None).await.unwrap().iter().all(|s| !s.current));
+
+        assert!(store.purge_expired_sessions().await.unwrap() >= 1);
+        store.delete_session("other").await.unwrap();
+        assert!(!store.delete_account_session(Uuid::new_v4(), session_id).await.unwrap());
+        assert!(store.delete_account_session(account_id, session_id).await.unwrap());
+        store.delete_sessions_for_account(account_id).await.unwrap();
+
+        let purpose = AccountTokenPurpose::EmailVerification;
+        store
+            .create_account_token(account_id, purpose, "verify", now + Duration::hours(1))
+            .await
+            .unwrap();
+        let wrong_purpose =
+            store.consume_account_token("verify", AccountTokenPurpose::PasswordReset).await;
+        assert_eq!(wrong_purpose.unwrap(), None);
+        assert_eq!(store.consume_account_token("verify", purpose).await.unwrap(), Some(account_id));
+        assert_eq!(store.consume_account_token("verify", purpose).await.unwrap(), None);
+        store.delete_unused_account_tokens(account_id, purpose).await.unwrap();
+
+        // Invitations only link to an address its account has verified
+        assert_eq!(store.find_invited_account("alice@example.com").await.unwrap(), None);
+        store.mark_email_verified(account_id).await.unwrap();
+        let verified = store.get_account_by_id(account_id).await.unwrap().unwrap();
+        assert!(verified.email_verified_at.is_some());
+        assert_eq!(
+            store.find_invited_account("alice@example.com").await.unwrap(),
+            Some((account_id, "Alice".to_string()))
+        );
+
+        let sso = store.create_account_without_password("sso@example.com", "Sso").await.unwrap();
+        let sso_account = store.find_account_by_email("sso@example.com").await.unwrap().unwrap();
+        assert_eq!(sso_account.password_hash, None);
+        assert!(sso_account.email_verified_at.is_some());
+        assert!(store.get_account_by_id(sso).await.unwrap().is_some());
+
+        // Last: a failed statement aborts the rest of a Postgres transaction
+        let error = store.create_account("alice@example.com", "hash", "Twin").await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::Conflict);
+    })
+}
+
+fn two_factor_and_identities(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let account_id = new_account(store, "alice@example.com").await;
+
+        store.set_pending_totp_secret(account_id, "SECRET").await.unwrap();
+        store.enable_totp(account_id, 100).await.unwrap();
+        store.record_totp_step(account_id, 101).await.unwrap();
+        let state = store.get_totp_state_for_update(account_id).await.unwrap().unwrap();
+        assert_eq!(state.totp_secret.as_deref(), Some("SECRET"));
+        assert_eq!(state.totp_pending_secret, None);
+        assert_eq!(state.totp_last_used_step, Some(101));
+        assert!(store.get_account_by_id(account_id).await.unwrap().unwrap().two_factor_enabled);
+
+        store
+            .replace_recovery_codes(account_id, &["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert!(store.consume_recovery_code(account_id, "a").await.unwrap());
+        assert!(!store.consume_recovery_code(account_id, "a").await.unwrap());
+        store.disable_totp(account_id).await.unwrap();
+        assert!(!store.consume_recovery_code(account_id, "b").await.unwrap());
+
+        let now = Utc::now().naive_utc();
+        store
+            .create_login_challenge(account_id, "challenge", now + Duration::minutes(5))
+            .await
+            .unwrap();
+        let challenge = store.find_login_challenge_for_update("challenge").await.unwrap().unwrap();
+        store.increment_login_challenge_attempts(challenge.id).await.unwrap();
+        let challenge = store.find_login_challenge_for_update("challenge").await.unwrap().unwrap();
+        assert_eq!(challenge.attempts, 1);
+        store.delete_login_challenge(challenge.id).await.unwrap();
+        store.purge_expired_login_challenges().await.unwrap();
+
+        let login_state = OidcLoginState {
+            code_verifier: "verifier".to_string(),
+            nonce: "nonce".to_string(),
+            link_account_id: Some(account_id),
+        };
+        store
+            .create_oidc_login_state("state", &login_state, now + Duration::minutes(5))
+            .await
+            .unwrap();
+        let consumed = store.consume_oidc_login_state("state").await.unwrap().unwrap();
+        assert_eq!(consumed.link_account_id, Some(account_id));
+        assert!(store.consume_oidc_login_state("state").await.unwrap().is_none());
+        store.purge_expired_oidc_login_states().await.unwrap();
+
+        store.create_identity(account_id, "https://idp", "sub", Some("a@idp")).await.unwrap();
+        assert_eq!(
+            store.find_identity_account_id("https://idp", "sub").await.unwrap(),
+            Some(account_id)
+        );
+        let identity = store.list_identities(account_id).await.unwrap().remove(0);
+        assert!(store.has_other_sign_in_method(account_id, identity.id).await.unwrap());
+        assert!(store.delete_identity(account_id, identity.id).await.unwrap());
+    })
+}
+
+fn api_tokens(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let account_id = new_account(store, "alice@example.com").await;
+        let project_id = new_project(store, Some(account_id)).await;
+        let expires_at = Utc::now().naive_utc() + Duration::days(30);
+
+        let project_ids = [project_id];
+        let scoped = store
+            .create_api_token(
+                account_id,
+                NewApiToken {
+                    name: "CI",
+                    token_hash: "scoped",
+                    token_prefix: "cnt_abc",
+                    scope: ApiTokenScope::Read,
+                    project_ids: Some(&project_ids),
+                    expires_at,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(scoped.project_ids, Some(vec![project_id]));
+        assert_eq!(scoped.scope, ApiTokenScope::Read);
+
+        let token = NewApiToken {
+            name: "Script",
+            token_hash: "global",
+            token_prefix: "cnt_def",
+            scope: ApiTokenScope::Write,
+            project_ids: None,
+            expires_at,
+        };
+        store.create_api_token(account_id, token).await.unwrap();
+        assert_eq!(store.list_api_tokens(account_id).await.unwrap().len(), 2);
+
+        let active = store.find_active_api_token("scoped").await.unwrap().unwrap();
+        assert_eq!((active.id, active.account_id), (scoped.id, account_id));
+        assert_eq!(active.project_ids, Some(vec![project_id]));
+        assert_eq!(active.last_used_at, None);
+        store.touch_api_token(active.id).await.unwrap();
+        let global = store.find_active_api_token("global").await.unwrap().unwrap();
+        assert_eq!(global.project_ids, None);
+
+        assert!(store.delete_api_token(account_id, scoped.id).await.unwrap());
+        assert!(store.find_active_api_token("scoped").await.unwrap().is_none());
+        store.purge_expired_api_tokens().await.unwrap();
+    })
+}
+
+fn idempotency_keys(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let now = Utc::now().naive_utc();
+        let expires_at = now + Duration::hours(24);
+        let abandoned_before = now - Duration::minutes(1);
+
+        let claim =
+            store.claim_idempotency_key("anonymous", "key", "hash", expires_at, abandoned_before);
+        assert_eq!(claim.await.unwrap(), None);
+        let in_progress = store
+            .claim_idempotency_key("anonymous", "key", "hash", expires_at, abandoned_before)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((in_progress.request_hash.as_str(), in_progress.status), ("hash", None));
+        // Keys of another caller are separate
+        let other =
+            store.claim_idempotency_key("token:1", "key", "hash", expires_at, abandoned_before);
+        assert_eq!(other.await.unwrap(), None);
+
+        store.complete_idempotency_key("anonymous", "key", 200, b"42").await.unwrap();
+        let completed = store
+            .claim_idempotency_key("anonymous", "key", "other", expires_at, abandoned_before)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(completed.request_hash, "hash");
+        assert_eq!((completed.status, completed.response_body), (Some(200), Some(b"42".to_vec())));
+
+        store.release_idempotency_key("token:1", "key").await.unwrap();
+        let reclaimed =
+            store.claim_idempotency_key("token:1", "key", "hash", expires_at, abandoned_before);
+        assert_eq!(reclaimed.await.unwrap(), None);
+        // A reservation older than `abandoned_before` is taken over
+        let taken_over = store.claim_idempotency_key(
+            "token:1",
+            "key",
+            "hash",
+            expires_at,
+            now + Duration::minutes(1),
+        );
+        assert_eq!(taken_over.await.unwrap(), None);
+
+        let expired = store.claim_idempotency_key(
+            "anonymous",
+            "old",
+            "hash",
+            abandoned_before,
+            abandoned_before,
+        );
+        assert_eq!(expired.await.unwrap(), None);
+        assert_eq!(store.purge_expired_idempotency_keys().await.unwrap(), 1);
+    })
+}
+
+fn project_settlements(store: &mut dyn Store) -> BoxFuture<'_, ()> {
+    Box::pin(async move {
+        let project_id = new_project(store, None).await;
+        assert_eq!(store.get_project_settlement(project_id).await.unwrap(), None);
+
+        let users = store
+            .insert_users(vec![participant("Alice", project_id), participant("Bob", project_id)])
+            .await
+            .unwrap();
+        let (alice, bob) = (users[0].id, users[1].id);
+        let expense_id = store.add_expense(expense(project_id, alice, bob, 30.0)).await.unwrap();
+        store
+            .add_payments(vec![
+                NewPayment { expense_id, user_id: alice, is_debt: false, amount: 30.0 },
+                NewPayment { expense_id, user_id: bob, is_debt: true, amount: 30.0 },
+            ])
+            .await
+            .unwrap();
+
+        let settled = settle(store, project_id).await.unwrap();
+        let stored = store.get_project_settlement(project_id).await.unwrap().unwrap();
+        assert_eq!(stored.users.len(), 2);
+        assert_eq!(stored.summary, settled.summary);
+        assert_eq!(stored.summary.summary[&bob], -30.0);
+
+        // Closing again replaces the settlement
+        store.delete_payments_by_expense_id(expense_id).await.unwrap();
+        settle(store, project_id).await.unwrap();
+        let stored = store.get_project_settlement(project_id).await.unwrap().unwrap();
+        assert!(stored.summary.reimbursement_suggestions.is_empty());
+
+        store.delete_project_by_id(project_id).await.unwrap();
+        assert_eq!(store.get_project_settlement(project_id).await.unwrap(), None);
+    })
+}
+
+#[tokio::test]
+async fn projects_and_memberships_are_stored() {
+    run(projects_and_memberships).await;
+}
+
+#[tokio::test]
+async fn participants_expenses_and_payments_are_stored() {
+    run(participants_expenses_and_payments).await;
+}
+
+#[tokio::test]
+async fn invites_and_share_links_are_stored() {
+    run(invites_and_share_links).await;
+}
+
+#[tokio::test]
+async fn accounts_and_sessions_are_stored() {
+    run(accounts_and_sessions).await;
+}
+
+#[tokio::test]
+async fn two_factor_and_identities_are_stored() {
+    run(two_factor_and_identities).await;
+}
+
+#[tokio::test]
+async fn api_tokens_are_stored() {
+    run(api_tokens).await;
+}
+
+#[tokio::test]
+async fn idempotency_keys_are_stored() {
+    run(idempotency_keys).await;
+}
+
+#[tokio::test]
+async fn project_settlements_are_stored() {
+    run(project_settlements).await;
+}
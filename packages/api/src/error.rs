@@ -0,0 +1,233 @@
+//! Errors returned by the server functions.
+//!
+//! Every failure carries an [`ErrorCode`] mapped to its HTTP status. The response body keeps the
+//! shape of Dioxus server function errors, with the error itself under `data`:
+//! `{"message": "Project not found", "code": 404, "data": {"code": "not_found", "message": "Project not found"}}`.
+//!
+//! A `conflict` caused by an edit based on an outdated version also carries the resource as the
+//! server has it, under `data.current`, so the client can reload or merge without another call.
+//! An error of a batch names the operation that caused it, by its index, under `data.operation`.
+//!
+//! Internal errors are logged where they happen and reach clients as a generic message, so
+//! database and upstream details never leave the server.
+use std::fmt;
+
+use dioxus::fullstack::{AsStatusCode, StatusCode};
+use dioxus::prelude::ServerFnError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+pub use shared::ErrorCode;
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// The resource as the server has it, for a conflicting edit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<serde_json::Value>,
+    /// Index of the operation that failed, for a batch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<usize>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), current: None, operation: None }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unauthorized, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Forbidden, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    /// An edit based on an outdated version of `current`.
+    pub fn stale(message: impl Into<String>, current: &impl Serialize) -> Self {
+        Self { current: serde_json::to_value(current).ok(), ..Self::conflict(message) }
+    }
+
+    pub fn locked(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Locked, message)
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unavailable, message)
+    }
+
+    /// Log `cause` and hide it from the client.
+    pub fn internal(cause: impl fmt::Display) -> Self {
+        #[cfg(feature = "server")]
+        tracing::error!("{}", cause);
+        #[cfg(not(feature = "server"))]
+        let _ = cause;
+        Self::new(ErrorCode::Internal, "Internal server error")
+    }
+
+    /// The error as caused by the operation at `index` of a batch.
+    pub fn at_operation(self, index: usize) -> Self {
+        Self {
+            message: format!("operations[{}]: {}", index, self.message),
+            operation: Some(index),
+            ..self
+        }
+    }
+
+    /// The resource sent with a conflict, if it has the expected type.
+    pub fn current<T: DeserializeOwned>(&self) -> Option<T> {
+        self.current.clone().and_then(|current| serde_json::from_value(current).ok())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.code.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl AsStatusCode for ApiError {
+    fn as_status_code(&self) -> StatusCode {
+        self.status()
+    }
+}
+
+/// On the server, a `ServerFnError` comes from code not using `ApiError` yet, or from Dioxus
+/// failing to decode the arguments. In the browser, it is a failed request or a response from
+/// something other than a server function.
+impl From<ServerFnError> for ApiError {
+    fn from(error: ServerFnError) -> Self {
+        match error {
+            ServerFnError::Args(message)
+            | ServerFnError::MissingArg(message)
+            | ServerFnError::Deserialization(message)
+                if cfg!(feature = "server") =>
+            {
+                Self::validation(message)
+            }
+            ServerFnError::ServerError { message, .. } if cfg!(feature = "server") => {
+                Self::internal(message)
+            }
+            ServerFnError::ServerError { message, code, .. } => {
+                Self::new(ErrorCode::from_status(code), message)
+            }
+            ServerFnError::Request(error) => Self::unavailable(error.to_string()),
+            other => Self::internal(other),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        db_error("Database error")(error)
+    }
+}
+
+/// Convert a database error: a missing row is `not_found`, a unique violation `conflict`, and
+/// anything else is internal, logged with `context`.
+#[cfg(feature = "server")]
+pub fn db_error(context: &'static str) -> impl FnOnce(sqlx::Error) -> ApiError {
+    move |error| match &error {
+        sqlx::Error::RowNotFound => ApiError::not_found("Not found"),
+        sqlx::Error::Database(e) if e.is_unique_violation() => ApiError::conflict("Already exists"),
+        _ => ApiError::internal(format!("{}: {}", context, error)),
+    }
+}
+
+#[cfg(feature = "server")]
+impl axum::response::IntoResponse for ApiError {
+    fn into_response(self) -> axum::response::Response {
+        let body = serde_json::json!({
+            "message": self.message,
+            "code": self.code.status(),
+            "data": self,
+        });
+        (self.status(), axum::Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_code_and_message() {
+        let error = ApiError::not_found("Project not found");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "code": "not_found", "message": "Project not found" })
+        );
+        assert_eq!(error.as_status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn stale_edits_carry_the_current_resource() {
+        let error = ApiError::stale("Expense was modified", &serde_json::json!({ "version": 3 }));
+        assert_eq!(error.code, ErrorCode::Conflict);
+        assert_eq!(
+            serde_json::to_value(&error).unwrap()["current"],
+            serde_json::json!({ "version": 3 })
+        );
+        assert_eq!(error.current::<serde_json::Value>().unwrap()["version"], 3);
+        assert_eq!(error.current::<String>(), None);
+    }
+
+    #[test]
+    fn batch_errors_name_the_operation() {
+        let error = ApiError::validation("name cannot be empty").at_operation(2);
+        assert_eq!(error.message, "operations[2]: name cannot be empty");
+        assert_eq!(serde_json::to_value(&error).unwrap()["operation"], 2);
+        assert_eq!(error.code, ErrorCode::Validation);
+    }
+
+    #[test]
+    fn internal_errors_are_sanitized() {
+        let error = ApiError::internal("Failed to get project: connection refused");
+        assert_eq!(error.code, ErrorCode::Internal);
+        assert_eq!(error.message, "Internal server error");
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn untyped_server_errors_become_internal() {
+        let error = ApiError::from(ServerFnError::new("relation \"account\" does not exist"));
+        assert_eq!(error, ApiError::internal(""));
+
+        let error = ApiError::from(ServerFnError::Args("missing field `name`".to_string()));
+        assert_eq!(error.code, ErrorCode::Validation);
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn database_errors_are_classified() {
+        assert_eq!(
+            db_error("Failed to get project")(sqlx::Error::RowNotFound).code,
+            ErrorCode::NotFound
+        );
+        assert_eq!(
+            db_error("Failed to get project")(sqlx::Error::PoolTimedOut).code,
+            ErrorCode::Internal
+        );
+    }
+}
@@ -2,22 +2,156 @@ use chrono::Local;
 use dioxus::{fullstack::Json, prelude::*};
 use uuid::Uuid;
 
+use crate::error::ApiError;
+
 #[cfg(feature = "server")]
-use crate::db::get_db;
-#[cfg(feature = "server")]
-use crate::expenses::expenses_repository;
+use crate::db::{get_db, Store};
 #[cfg(feature = "server")]
 use crate::payments::payments_repository;
-use shared::{CreatableExpense, EditableExpense, Expense, NewPayment, UserAmount};
+#[cfg(feature = "server")]
+use crate::projects::project_access::{authorize, ProjectAccess, ProjectAction};
+#[cfg(feature = "server")]
+use crate::projects::project_lifecycle::check_settlement;
+#[cfg(feature = "server")]
+use crate::sse::publish;
+#[cfg(feature = "server")]
+use shared::sse::EventSSE;
+use shared::{
+    CreatableExpense, EditableExpense, Expense, ExpenseOperation, ExpenseOperationResult,
+    NewPayment, UserAmount,
+};
+#[cfg(feature = "server")]
+use shared::{ExpenseType, ProjectRole, ProjectStatus};
+
+/// Most operations a batch may hold.
+pub const MAX_BATCH_OPERATIONS: usize = 500;
 
 #[post("/api/v1/expenses")]
-pub async fn add_expense(Json(expense): Json<CreatableExpense>) -> Result<Expense, ServerFnError> {
+pub async fn add_expense(Json(expense): Json<CreatableExpense>) -> Result<Expense, ApiError> {
     validate_expense(&expense.name, &expense.payers, &expense.debtors)?;
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let created_expense = create(&mut *tx, expense).await?;
+
+    tx.commit().await?;
+    publish(created_expense.project_id, EventSSE::ExpenseCreated);
+
+    Ok(created_expense)
+}
+
+#[put("/api/v1/expenses")]
+pub async fn edit_expense(Json(expense): Json<EditableExpense>) -> Result<Expense, ApiError> {
+    validate_expense(&expense.name, &expense.payers, &expense.debtors)?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let updated_expense = update(&mut *tx, expense).await?;
+
+    tx.commit().await?;
+    publish(updated_expense.project_id, EventSSE::ExpenseModified);
+
+    Ok(updated_expense)
+}
+
+/// Apply creations, edits and deletions of expenses of one project, all or none. Every
+/// operation is validated first; the first one that fails cancels the batch, and the error
+/// gives its index under `data.operation`.
+#[post("/api/v1/projects/{project_id}/expenses/batch")]
+pub async fn batch_expenses(
+    project_id: Uuid,
+    Json(operations): Json<Vec<ExpenseOperation>>,
+) -> Result<Vec<ExpenseOperationResult>, ApiError> {
+    validate_batch(project_id, &operations)?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let mut results = Vec::with_capacity(operations.len());
+    for (index, operation) in operations.into_iter().enumerate() {
+        let result = apply(&mut *tx, project_id, operation).await;
+        results.push(result.map_err(|e| e.at_operation(index))?);
+    }
+
+    tx.commit().await?;
+    let mut events: Vec<EventSSE> = Vec::new();
+    for result in &results {
+        let event = match result {
+            ExpenseOperationResult::Created(_) => EventSSE::ExpenseCreated,
+            ExpenseOperationResult::Updated(_) => EventSSE::ExpenseModified,
+            ExpenseOperationResult::Deleted { .. } => EventSSE::ExpenseDeleted,
+        };
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+    for event in events {
+        publish(project_id, event);
+    }
+
+    Ok(results)
+}
+
+#[get("/api/v1/projects/{project_id}/expenses")]
+pub async fn get_expenses_by_project_id(project_id: Uuid) -> Result<Vec<Expense>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::Read).await?;
+
+    let expenses = tx.get_expenses_by_project_id(project_id).await?;
+
+    tx.commit().await?;
+
+    Ok(expenses)
+}
 
-    let created_expense_id = expenses_repository::add_expense(&mut *tx, expense.clone()).await?;
+#[get("/api/v1/expenses/{expense_id}")]
+pub async fn get_expense_by_id(expense_id: i32) -> Result<Expense, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let expense = tx.get_expense_by_id(expense_id).await?;
+
+    authorize(&mut *tx, expense.project_id, ProjectAction::Read).await?;
+
+    tx.commit().await?;
+
+    Ok(expense)
+}
+
+#[delete("/api/v1/expenses/{expense_id}")]
+pub async fn delete_expense(expense_id: i32) -> Result<(), ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let existing = tx.get_expense_by_id(expense_id).await?;
+    remove(&mut *tx, &existing).await?;
+
+    tx.commit().await?;
+    publish(existing.project_id, EventSSE::ExpenseDeleted);
+
+    Ok(())
+}
+
+/// Add an expense and its payments.
+#[cfg(feature = "server")]
+async fn create(tx: &mut dyn Store, expense: CreatableExpense) -> Result<Expense, ApiError> {
+    let action = match expense.expense_type {
+        ExpenseType::Transfer => ProjectAction::AddSettlement,
+        _ => ProjectAction::AddExpense,
+    };
+    let access = authorize(&mut *tx, expense.project_id, action).await?;
+    check_new_author(&access, expense.author_id)?;
+    if access.project.status == ProjectStatus::Closed {
+        let balances =
+            payments_repository::get_summary_by_project_id(&mut *tx, expense.project_id).await?;
+        check_settlement(&balances.summary, &expense.payers, &expense.debtors)?;
+    }
+
+    let created_expense_id = tx.add_expense(expense.clone()).await?;
 
     let creatable_payments: Vec<NewPayment> = forge_creatable_payments_from_expense(
         expense.payers.clone(),
@@ -25,11 +159,9 @@ pub async fn add_expense(Json(expense): Json<CreatableExpense>) -> Result<Expens
         created_expense_id,
     );
 
-    payments_repository::add_payments(&mut *tx, creatable_payments).await?;
-
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.add_payments(creatable_payments).await?;
 
-    let created_expense = Expense {
+    Ok(Expense {
         id: created_expense_id,
         name: expense.name,
         amount: expense.amount,
@@ -39,21 +171,33 @@ pub async fn add_expense(Json(expense): Json<CreatableExpense>) -> Result<Expens
         description: expense.description,
         created_at: Local::now().naive_local(),
         date: expense.date,
-    };
-
-    Ok(created_expense)
+        version: 1,
+    })
 }
 
-#[put("/api/v1/expenses")]
-pub async fn edit_expense(Json(expense): Json<EditableExpense>) -> Result<Expense, ServerFnError> {
-    validate_expense(&expense.name, &expense.payers, &expense.debtors)?;
-
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    expenses_repository::edit_expense(&mut *tx, expense.clone()).await?;
+/// Replace an expense and its payments, unless it was edited since `expense.version`.
+#[cfg(feature = "server")]
+async fn update(tx: &mut dyn Store, expense: EditableExpense) -> Result<Expense, ApiError> {
+    let existing = tx.get_expense_by_id(expense.id).await?;
+    if existing.project_id != expense.project_id {
+        return Err(ApiError::validation("An expense cannot be moved to another project"));
+    }
+    let access = authorize(
+        &mut *tx,
+        existing.project_id,
+        ProjectAction::EditExpense { author_id: existing.author_id },
+    )
+    .await?;
+    check_author_change(&access, existing.author_id, expense.author_id)?;
+
+    let Some(version) = tx.edit_expense(expense.clone()).await? else {
+        let payments = tx.get_payments_by_expense_id(expense.id).await?;
+        let current =
+            EditableExpense::from_expense(&tx.get_expense_by_id(expense.id).await?, &payments);
+        return Err(ApiError::stale("The expense was edited in the meantime", &current));
+    };
 
-    payments_repository::delete_payments_by_expense_id(&mut *tx, expense.id).await?;
+    tx.delete_payments_by_expense_id(expense.id).await?;
 
     let creatable_payments: Vec<NewPayment> = forge_creatable_payments_from_expense(
         expense.payers.clone(),
@@ -61,11 +205,9 @@ pub async fn edit_expense(Json(expense): Json<EditableExpense>) -> Result<Expens
         expense.id,
     );
 
-    payments_repository::add_payments(&mut *tx, creatable_payments).await?;
-
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.add_payments(creatable_payments).await?;
 
-    let updated_expense = Expense {
+    Ok(Expense {
         id: expense.id,
         name: expense.name,
         amount: expense.amount,
@@ -75,46 +217,100 @@ pub async fn edit_expense(Json(expense): Json<EditableExpense>) -> Result<Expens
         description: expense.description,
         created_at: Local::now().naive_local(), // TODO
         date: expense.date,
-    };
-
-    Ok(updated_expense)
+        version,
+    })
 }
 
-#[get("/api/v1/projects/{project_id}/expenses")]
-pub async fn get_expenses_by_project_id(project_id: Uuid) -> Result<Vec<Expense>, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    let expenses = expenses_repository::get_expenses_by_project_id(&mut *tx, project_id).await?;
-
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+/// Only owners add an expense in the name of another participant than the one they claimed.
+#[cfg(feature = "server")]
+fn check_new_author(access: &ProjectAccess, author_id: i32) -> Result<(), ApiError> {
+    if access.role != ProjectRole::Owner && access.user_id != Some(author_id) {
+        return Err(ApiError::forbidden("Expenses can only be added in your own name"));
+    }
+    Ok(())
+}
 
-    Ok(expenses)
+/// Only owners give an expense to another participant.
+#[cfg(feature = "server")]
+fn check_author_change(
+    access: &ProjectAccess,
+    existing: i32,
+    author_id: i32,
+) -> Result<(), ApiError> {
+    if access.role != ProjectRole::Owner && existing != author_id {
+        return Err(ApiError::forbidden("Only owners can change the author of an expense"));
+    }
+    Ok(())
 }
 
-#[get("/api/v1/expenses/{expense_id}")]
-pub async fn get_expense_by_id(expense_id: i32) -> Result<Expense, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+/// Delete an expense and its payments.
+#[cfg(feature = "server")]
+async fn remove(tx: &mut dyn Store, existing: &Expense) -> Result<(), ApiError> {
+    authorize(
+        &mut *tx,
+        existing.project_id,
+        ProjectAction::EditExpense { author_id: existing.author_id },
+    )
+    .await?;
 
-    let expense = expenses_repository::get_expense_by_id(&mut *tx, expense_id).await?;
+    tx.delete_payments_by_expense_id(existing.id).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.delete_expense(existing.id).await?;
 
-    Ok(expense)
+    Ok(())
 }
 
-#[delete("/api/v1/expenses/{expense_id}")]
-pub async fn delete_expense(expense_id: i32) -> Result<(), ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    payments_repository::delete_payments_by_expense_id(&mut *tx, expense_id).await?;
-
-    expenses_repository::delete_expense(&mut *tx, expense_id).await?;
+/// One operation of a batch on the expenses of `project_id`.
+#[cfg(feature = "server")]
+async fn apply(
+    tx: &mut dyn Store,
+    project_id: Uuid,
+    operation: ExpenseOperation,
+) -> Result<ExpenseOperationResult, ApiError> {
+    match operation {
+        ExpenseOperation::Create(expense) => {
+            create(tx, expense).await.map(ExpenseOperationResult::Created)
+        }
+        ExpenseOperation::Update(expense) => {
+            update(tx, expense).await.map(ExpenseOperationResult::Updated)
+        }
+        ExpenseOperation::Delete { id } => {
+            let existing = tx.get_expense_by_id(id).await?;
+            if existing.project_id != project_id {
+                return Err(ApiError::not_found("Expense not found"));
+            }
+            remove(tx, &existing).await?;
+            Ok(ExpenseOperationResult::Deleted { id })
+        }
+    }
+}
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+/// Check every operation of a batch before any is applied.
+#[cfg(feature = "server")]
+fn validate_batch(project_id: Uuid, operations: &[ExpenseOperation]) -> Result<(), ApiError> {
+    if operations.is_empty() {
+        return Err(ApiError::validation("operations cannot be empty"));
+    }
+    if operations.len() > MAX_BATCH_OPERATIONS {
+        return Err(ApiError::validation(format!(
+            "A batch holds at most {} operations",
+            MAX_BATCH_OPERATIONS
+        )));
+    }
 
+    for (index, operation) in operations.iter().enumerate() {
+        let (expense_project_id, name, payers, debtors) = match operation {
+            ExpenseOperation::Create(e) => (e.project_id, &e.name, &e.payers, &e.debtors),
+            ExpenseOperation::Update(e) => (e.project_id, &e.name, &e.payers, &e.debtors),
+            ExpenseOperation::Delete { .. } => continue,
+        };
+        let checked = if expense_project_id != project_id {
+            Err(ApiError::validation("The expense belongs to another project"))
+        } else {
+            validate_expense(name, payers, debtors)
+        };
+        checked.map_err(|e| e.at_operation(index))?;
+    }
     Ok(())
 }
 
@@ -122,15 +318,15 @@ fn validate_expense(
     name: &str,
     payers: &[UserAmount],
     debtors: &[UserAmount],
-) -> Result<(), ServerFnError> {
+) -> Result<(), ApiError> {
     if name.is_empty() {
-        return Err(ServerFnError::new("name cannot be empty"));
+        return Err(ApiError::validation("name cannot be empty"));
     }
     if payers.is_empty() {
-        return Err(ServerFnError::new("payers cannot be empty"));
+        return Err(ApiError::validation("payers cannot be empty"));
     }
     if debtors.is_empty() {
-        return Err(ServerFnError::new("debtors cannot be empty"));
+        return Err(ApiError::validation("debtors cannot be empty"));
     }
     Ok(())
 }
@@ -164,3 +360,93 @@ fn forge_creatable_payments_from_expense(
 
     [creatable_debtors, creatable_payers].concat()
 }
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use chrono::NaiveDate;
+    use shared::ProjectDto;
+
+    fn creatable(project_id: Uuid, name: &str) -> ExpenseOperation {
+        ExpenseOperation::Create(CreatableExpense {
+            name: name.to_string(),
+            amount: 10.0,
+            expense_type: ExpenseType::Expense,
+            project_id,
+            payers: vec![UserAmount { user_id: 1, amount: 10.0 }],
+            debtors: vec![UserAmount { user_id: 2, amount: 10.0 }],
+            author_id: 1,
+            description: None,
+            date: NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(),
+        })
+    }
+
+    #[test]
+    fn batches_are_validated_before_they_run() {
+        let project_id = Uuid::from_u128(1);
+        let batch = [creatable(project_id, "Courses"), ExpenseOperation::Delete { id: 3 }];
+        assert!(validate_batch(project_id, &batch).is_ok());
+
+        let error = validate_batch(project_id, &[]).unwrap_err();
+        assert_eq!(error.operation, None);
+
+        let batch = [ExpenseOperation::Delete { id: 3 }, creatable(project_id, "")];
+        let error = validate_batch(project_id, &batch).unwrap_err();
+        assert_eq!((error.code, error.operation), (ErrorCode::Validation, Some(1)));
+        assert_eq!(error.message, "operations[1]: name cannot be empty");
+
+        let batch = [creatable(Uuid::from_u128(2), "Courses")];
+        assert_eq!(validate_batch(project_id, &batch).unwrap_err().operation, Some(0));
+    }
+
+    fn access(role: ProjectRole, user_id: Option<i32>) -> ProjectAccess {
+        let project = ProjectDto {
+            id: Uuid::from_u128(1),
+            name: "Vacances".to_string(),
+            created_at: NaiveDate::from_ymd_opt(2026, 10, 1).unwrap().into(),
+            currency: "EUR".to_string(),
+            description: None,
+            status: ProjectStatus::Ongoing,
+            owner_account_id: Some(Uuid::from_u128(2)),
+            editors_can_edit_others_expenses: true,
+            version: 1,
+        };
+        ProjectAccess { project, account_id: Some(Uuid::from_u128(3)), role, user_id }
+    }
+
+    #[test]
+    fn editors_add_expenses_in_their_own_name_only() {
+        let editor = access(ProjectRole::Editor, Some(1));
+        assert!(check_new_author(&editor, 1).is_ok());
+        assert_eq!(check_new_author(&editor, 2).unwrap_err().code, ErrorCode::Forbidden);
+        let unclaimed = access(ProjectRole::Editor, None);
+        assert_eq!(check_new_author(&unclaimed, 1).unwrap_err().code, ErrorCode::Forbidden);
+
+        assert!(check_new_author(&access(ProjectRole::Owner, None), 2).is_ok());
+    }
+
+    #[test]
+    fn editors_cannot_change_the_author() {
+        // Even where editors may edit everybody's expenses
+        let editor = access(ProjectRole::Editor, Some(1));
+        assert!(check_author_change(&editor, 2, 2).is_ok());
+        assert_eq!(check_author_change(&editor, 2, 1).unwrap_err().code, ErrorCode::Forbidden);
+        assert_eq!(check_author_change(&editor, 1, 2).unwrap_err().code, ErrorCode::Forbidden);
+
+        assert!(check_author_change(&access(ProjectRole::Owner, Some(1)), 1, 2).is_ok());
+    }
+
+    #[test]
+    fn operations_are_tagged_by_kind() {
+        let operation: ExpenseOperation =
+            serde_json::from_value(serde_json::json!({ "delete": { "id": 3 } })).unwrap();
+        assert!(matches!(operation, ExpenseOperation::Delete { id: 3 }));
+
+        let result = ExpenseOperationResult::Deleted { id: 3 };
+        assert_eq!(
+            serde_json::to_value(result).unwrap(),
+            serde_json::json!({ "deleted": { "id": 3 } })
+        );
+    }
+}
@@ -1,131 +1,160 @@
 use dioxus::prelude::*;
 use uuid::Uuid;
 
+#[cfg(feature = "server")]
+use crate::error::{db_error, ApiError};
+
+#[cfg(feature = "server")]
+use futures::future::BoxFuture;
 #[cfg(feature = "server")]
 use shared::{CreatableExpense, EditableExpense, Expense, ExpenseType};
 #[cfg(feature = "server")]
 use sqlx::PgConnection;
 
 #[cfg(feature = "server")]
-pub async fn add_expense(
-    executor: &mut PgConnection,
-    expense: CreatableExpense,
-) -> Result<i32, ServerFnError> {
-    let created_expense_id: i32 = sqlx::query_scalar!(
-        r"
-            INSERT INTO expenses
-            (
-                name,
-                amount,
-                expense_type,
-                project_id,
-                author_id,
-                description,
-                date
-            ) VALUES (
-                $1,
-                $2,
-                $3,
-                $4,
-                $5,
-                $6,
-                $7
-            ) RETURNING id",
-        expense.clone().name,
-        expense.clone().amount,
-        expense.clone().expense_type as ExpenseType,
-        expense.clone().project_id,
-        expense.clone().author_id,
-        expense.clone().description,
-        expense.clone().date
-    )
-    .fetch_one(&mut *executor)
-    .await
-    .context("Failed to create expense")
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(created_expense_id)
-}
+pub trait ExpensesRepository {
+    /// Returns the id of the new expense.
+    fn add_expense(&mut self, expense: CreatableExpense) -> BoxFuture<'_, Result<i32, ApiError>>;
 
-#[cfg(feature = "server")]
-pub async fn edit_expense(
-    executor: &mut PgConnection,
-    expense: EditableExpense,
-) -> Result<(), ServerFnError> {
-    sqlx::query!(
-        r#"
-        UPDATE expenses
-        SET
-            name          = $1,
-            amount        = $2,
-            expense_type  = $3,
-            project_id    = $4,
-            author_id     = $5,
-            description   = $6,
-            date          = $7
-        WHERE id = $8
-        "#,
-        expense.name,
-        expense.amount,
-        expense.clone().expense_type as ExpenseType,
-        expense.project_id,
-        expense.author_id,
-        expense.description,
-        expense.date,
-        expense.id
-    )
-    .execute(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(())
-}
+    /// Returns the new version, or `None` when the expense is no longer at `expense.version`.
+    fn edit_expense(
+        &mut self,
+        expense: EditableExpense,
+    ) -> BoxFuture<'_, Result<Option<i32>, ApiError>>;
 
-#[cfg(feature = "server")]
-pub async fn get_expenses_by_project_id(
-    executor: &mut PgConnection,
-    project_id: Uuid,
-) -> Result<Vec<Expense>, ServerFnError> {
-    let expenses: Vec<Expense> = sqlx::query_as!(
-        Expense,
-        "SELECT id, author_id, project_id, created_at, date, amount, description, name, expense_type as \"expense_type: ExpenseType\" \
-        FROM expenses \
-        WHERE project_id = $1",
-        project_id)
-        .fetch_all(&mut *executor)
-        .await
-        .context("Failed to get expenses")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(expenses)
-}
+    fn get_expenses_by_project_id(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<Expense>, ApiError>>;
 
-#[cfg(feature = "server")]
-pub async fn get_expense_by_id(
-    executor: &mut PgConnection,
-    expense_id: i32,
-) -> Result<Expense, ServerFnError> {
-    let expense: Expense = sqlx::query_as!(
-        Expense,
-        "SELECT id, author_id, project_id, created_at, date, amount, description, name, expense_type as \"expense_type: ExpenseType\" FROM expenses WHERE id = $1", expense_id)
-        .fetch_one(&mut *executor)
-        .await
-        .context("Failed to get expense")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(expense)
+    fn get_expense_by_id(&mut self, expense_id: i32) -> BoxFuture<'_, Result<Expense, ApiError>>;
+
+    fn delete_expense(&mut self, expense_id: i32) -> BoxFuture<'_, Result<(), ApiError>>;
 }
 
 #[cfg(feature = "server")]
-pub async fn delete_expense(
-    executor: &mut PgConnection,
-    expense_id: i32,
-) -> Result<(), ServerFnError> {
-    sqlx::query!("DELETE FROM expenses WHERE id = $1", expense_id)
-        .execute(&mut *executor)
-        .await
-        .context("Failed to delete expense")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(())
+impl ExpensesRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn add_expense(&mut self, expense: CreatableExpense) -> BoxFuture<'_, Result<i32, ApiError>> {
+        Box::pin(async move {
+            let created_expense_id: i32 = sqlx::query_scalar!(
+                r"
+                    INSERT INTO expenses
+                    (
+                        name,
+                        amount,
+                        expense_type,
+                        project_id,
+                        author_id,
+                        description,
+                        date
+                    ) VALUES (
+                        $1,
+                        $2,
+                        $3,
+                        $4,
+                        $5,
+                        $6,
+                        $7
+                    ) RETURNING id",
+                expense.clone().name,
+                expense.clone().amount,
+                expense.clone().expense_type as ExpenseType,
+                expense.clone().project_id,
+                expense.clone().author_id,
+                expense.clone().description,
+                expense.clone().date
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create expense"))?;
+
+            Ok(created_expense_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn edit_expense(
+        &mut self,
+        expense: EditableExpense,
+    ) -> BoxFuture<'_, Result<Option<i32>, ApiError>> {
+        Box::pin(async move {
+            let version = sqlx::query_scalar!(
+                r#"
+                UPDATE expenses
+                SET
+                    name          = $1,
+                    amount        = $2,
+                    expense_type  = $3,
+                    project_id    = $4,
+                    author_id     = $5,
+                    description   = $6,
+                    date          = $7,
+                    version       = version + 1
+                WHERE id = $8 AND version = $9
+                RETURNING version
+                "#,
+                expense.name,
+                expense.amount,
+                expense.clone().expense_type as ExpenseType,
+                expense.project_id,
+                expense.author_id,
+                expense.description,
+                expense.date,
+                expense.id,
+                expense.version
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to edit expense"))?;
+
+            Ok(version)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_expenses_by_project_id(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<Expense>, ApiError>> {
+        Box::pin(async move {
+            let expenses: Vec<Expense> = sqlx::query_as!(
+                Expense,
+                "SELECT id, author_id, project_id, created_at, date, amount, description, name, expense_type as \"expense_type: ExpenseType\", version \
+                FROM expenses \
+                WHERE project_id = $1",
+                project_id)
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to get expenses"))?;
+
+            Ok(expenses)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_expense_by_id(&mut self, expense_id: i32) -> BoxFuture<'_, Result<Expense, ApiError>> {
+        Box::pin(async move {
+            let expense: Expense = sqlx::query_as!(
+                Expense,
+                "SELECT id, author_id, project_id, created_at, date, amount, description, name, expense_type as \"expense_type: ExpenseType\", version FROM expenses WHERE id = $1", expense_id)
+                .fetch_one(&mut *self)
+                .await
+                .map_err(db_error("Failed to get expense"))?;
+
+            Ok(expense)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_expense(&mut self, expense_id: i32) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM expenses WHERE id = $1", expense_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete expense"))?;
+
+            Ok(())
+        })
+    }
 }
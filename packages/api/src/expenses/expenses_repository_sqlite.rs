@@ -0,0 +1,111 @@
+use futures::future::BoxFuture;
+use shared::{CreatableExpense, EditableExpense, Expense};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+use super::expenses_repository::ExpensesRepository;
+use crate::error::{db_error, ApiError};
+
+const EXPENSE_COLUMNS: &str =
+    "id, author_id, project_id, created_at, date, amount, description, name, expense_type, version";
+
+impl ExpensesRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn add_expense(&mut self, expense: CreatableExpense) -> BoxFuture<'_, Result<i32, ApiError>> {
+        Box::pin(async move {
+            let created_expense_id: i32 = sqlx::query_scalar(
+                "INSERT INTO expenses (name, amount, expense_type, project_id, author_id, description, date)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 RETURNING id",
+            )
+            .bind(expense.name)
+            .bind(expense.amount)
+            .bind(expense.expense_type)
+            .bind(expense.project_id)
+            .bind(expense.author_id)
+            .bind(expense.description)
+            .bind(expense.date)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create expense"))?;
+
+            Ok(created_expense_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn edit_expense(
+        &mut self,
+        expense: EditableExpense,
+    ) -> BoxFuture<'_, Result<Option<i32>, ApiError>> {
+        Box::pin(async move {
+            let version = sqlx::query_scalar(
+                "UPDATE expenses
+                 SET name = $1, amount = $2, expense_type = $3, project_id = $4, author_id = $5,
+                     description = $6, date = $7, version = version + 1
+                 WHERE id = $8 AND version = $9
+                 RETURNING version",
+            )
+            .bind(expense.name)
+            .bind(expense.amount)
+            .bind(expense.expense_type)
+            .bind(expense.project_id)
+            .bind(expense.author_id)
+            .bind(expense.description)
+            .bind(expense.date)
+            .bind(expense.id)
+            .bind(expense.version)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to edit expense"))?;
+
+            Ok(version)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_expenses_by_project_id(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<Expense>, ApiError>> {
+        Box::pin(async move {
+            let expenses = sqlx::query_as(&format!(
+                "SELECT {} FROM expenses WHERE project_id = $1",
+                EXPENSE_COLUMNS
+            ))
+            .bind(project_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get expenses"))?;
+
+            Ok(expenses)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_expense_by_id(&mut self, expense_id: i32) -> BoxFuture<'_, Result<Expense, ApiError>> {
+        Box::pin(async move {
+            let expense =
+                sqlx::query_as(&format!("SELECT {} FROM expenses WHERE id = $1", EXPENSE_COLUMNS))
+                    .bind(expense_id)
+                    .fetch_one(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to get expense"))?;
+
+            Ok(expense)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_expense(&mut self, expense_id: i32) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM expenses WHERE id = $1")
+                .bind(expense_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete expense"))?;
+
+            Ok(())
+        })
+    }
+}
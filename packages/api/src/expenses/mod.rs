@@ -1,2 +1,4 @@
 pub mod expenses_controller;
 pub mod expenses_repository;
+#[cfg(feature = "server")]
+mod expenses_repository_sqlite;
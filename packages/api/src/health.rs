@@ -0,0 +1,41 @@
+//! Probes for orchestrators and load balancers.
+//!
+//! `GET /healthz` answers as long as the process serves requests. `GET /readyz` also checks that
+//! the database answers and that every migration shipped with the binary has been applied.
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use crate::db::Db;
+use crate::migrations;
+use crate::state::AppState;
+
+/// `GET /healthz`
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// `GET /readyz`: `200 ok`, or `503` with the reason.
+pub async fn readyz(State(state): State<AppState>) -> Response {
+    match check_ready(&state.db).await {
+        Ok(()) => "ok".into_response(),
+        Err(reason) => (StatusCode::SERVICE_UNAVAILABLE, reason).into_response(),
+    }
+}
+
+async fn check_ready(db: &Db) -> Result<(), String> {
+    let mut conn = db.acquire().await.map_err(|e| {
+        tracing::error!("Readiness check failed to reach the database: {}", e);
+        "Database unavailable".to_string()
+    })?;
+
+    let status = migrations::check(&mut conn).await.map_err(|e| {
+        tracing::error!("Readiness check failed: {:#}", e);
+        "Migrations not applied".to_string()
+    })?;
+
+    match status.pending.len() {
+        0 => Ok(()),
+        pending => Err(format!("{} migrations not applied", pending)),
+    }
+}
@@ -0,0 +1,158 @@
+use chrono::NaiveDateTime;
+use futures::future::BoxFuture;
+use sqlx::PgConnection;
+
+use crate::error::{db_error, ApiError};
+
+/// A request already sent with the same key.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct StoredRequest {
+    pub request_hash: String,
+    /// `None` while the first request is still being processed
+    pub status: Option<i32>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+pub trait IdempotencyRepository {
+    /// Reserve `key` for a request of the caller `scope`. Returns `None` once reserved, or the
+    /// request that already holds the key. Expired keys, and reservations made before
+    /// `abandoned_before` that never got a response, are taken over.
+    fn claim_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+        request_hash: &'a str,
+        expires_at: NaiveDateTime,
+        abandoned_before: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<Option<StoredRequest>, ApiError>>;
+
+    /// Store the response to replay for a reserved key.
+    fn complete_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+        status: i32,
+        response_body: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// Drop a reservation, so the request can be sent again with the same key.
+    fn release_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    fn purge_expired_idempotency_keys(&mut self) -> BoxFuture<'_, Result<u64, ApiError>>;
+}
+
+impl IdempotencyRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn claim_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+        request_hash: &'a str,
+        expires_at: NaiveDateTime,
+        abandoned_before: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<Option<StoredRequest>, ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "DELETE FROM idempotency_keys
+                 WHERE scope = $1 AND idempotency_key = $2
+                   AND (expires_at <= NOW() OR (status IS NULL AND created_at <= $3))",
+                scope,
+                key,
+                abandoned_before
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to claim idempotency key"))?;
+
+            let claimed = sqlx::query!(
+                "INSERT INTO idempotency_keys (scope, idempotency_key, request_hash, expires_at)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT DO NOTHING",
+                scope,
+                key,
+                request_hash,
+                expires_at
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to claim idempotency key"))?;
+            if claimed.rows_affected() == 1 {
+                return Ok(None);
+            }
+
+            let stored = sqlx::query_as!(
+                StoredRequest,
+                "SELECT request_hash, status, response_body FROM idempotency_keys
+                 WHERE scope = $1 AND idempotency_key = $2",
+                scope,
+                key
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to get idempotency key"))?;
+
+            Ok(Some(stored))
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn complete_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+        status: i32,
+        response_body: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE idempotency_keys SET status = $3, response_body = $4
+                 WHERE scope = $1 AND idempotency_key = $2",
+                scope,
+                key,
+                status,
+                response_body
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to store idempotent response"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn release_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "DELETE FROM idempotency_keys WHERE scope = $1 AND idempotency_key = $2",
+                scope,
+                key
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to release idempotency key"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_idempotency_keys(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!("DELETE FROM idempotency_keys WHERE expires_at <= NOW()")
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge idempotency keys"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::{NaiveDateTime, Utc};
+use futures::future::BoxFuture;
+use sqlx::SqliteConnection;
+
+use super::idempotency_repository::{IdempotencyRepository, StoredRequest};
+use crate::error::{db_error, ApiError};
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+impl IdempotencyRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn claim_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+        request_hash: &'a str,
+        expires_at: NaiveDateTime,
+        abandoned_before: NaiveDateTime,
+    ) -> BoxFuture<'a, Result<Option<StoredRequest>, ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "DELETE FROM idempotency_keys
+                 WHERE scope = $1 AND idempotency_key = $2
+                   AND (expires_at <= $3 OR (status IS NULL AND created_at <= $4))",
+            )
+            .bind(scope)
+            .bind(key)
+            .bind(now())
+            .bind(abandoned_before)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to claim idempotency key"))?;
+
+            let claimed = sqlx::query(
+                "INSERT INTO idempotency_keys (scope, idempotency_key, request_hash, expires_at)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(scope)
+            .bind(key)
+            .bind(request_hash)
+            .bind(expires_at)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to claim idempotency key"))?;
+            if claimed.rows_affected() == 1 {
+                return Ok(None);
+            }
+
+            let stored = sqlx::query_as(
+                "SELECT request_hash, status, response_body FROM idempotency_keys
+                 WHERE scope = $1 AND idempotency_key = $2",
+            )
+            .bind(scope)
+            .bind(key)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to get idempotency key"))?;
+
+            Ok(Some(stored))
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn complete_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+        status: i32,
+        response_body: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query(
+                "UPDATE idempotency_keys SET status = $3, response_body = $4
+                 WHERE scope = $1 AND idempotency_key = $2",
+            )
+            .bind(scope)
+            .bind(key)
+            .bind(status)
+            .bind(response_body)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to store idempotent response"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn release_idempotency_key<'a>(
+        &'a mut self,
+        scope: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM idempotency_keys WHERE scope = $1 AND idempotency_key = $2")
+                .bind(scope)
+                .bind(key)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to release idempotency key"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn purge_expired_idempotency_keys(&mut self) -> BoxFuture<'_, Result<u64, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= $1")
+                .bind(now())
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to purge idempotency keys"))?;
+
+            Ok(result.rows_affected())
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::header::{CONTENT_TYPE, COOKIE};
+use axum::http::{HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+
+use super::idempotency_repository::StoredRequest;
+use super::{accepts_key, is_valid_key, IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH, REPLAYED_HEADER};
+use crate::auth::api_tokens::ApiTokenGrant;
+use crate::auth::sessions::token_from_cookie_header;
+use crate::error::ApiError;
+use crate::rate_limit::ClientIp;
+use crate::state::AppState;
+use crate::utils::sha256_hex;
+
+/// Request and response bodies kept for a key, well above what the create endpoints use.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Axum middleware: replay the stored response of a create request sent again with the same
+/// `Idempotency-Key`, or process it and store its response.
+///
+/// Runs after `api_token_auth` and `rate_limit`, which tell the caller a key belongs to.
+pub async fn idempotency(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST || !accepts_key(request.uri().path()) {
+        return next.run(request).await;
+    }
+    let Some(header) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match header.to_str() {
+        Ok(key) if is_valid_key(key) => key.to_owned(),
+        _ => {
+            return ApiError::validation(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_KEY_LENGTH
+            ))
+            .into_response();
+        }
+    };
+    let scope = caller_scope(
+        request.extensions().get::<ApiTokenGrant>(),
+        request.headers().get(COOKIE).and_then(|v| v.to_str().ok()),
+        request.extensions().get::<ClientIp>(),
+    );
+    // An anonymous caller whose address is unknown cannot be told from the others
+    let Some(scope) = scope else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => return ApiError::validation("Request body too large").into_response(),
+    };
+    let request_hash = request_hash(&parts.method, parts.uri.path(), &body);
+
+    let now = chrono::Utc::now().naive_utc();
+    let expires_at = now + state.config.idempotency.retention;
+    // Until then, a key without a response may belong to a request still running: a slow
+    // import must not run a second time
+    let abandoned_before = now - state.config.idempotency.reclaim_after;
+    let claimed = match state.db.acquire().await {
+        Ok(mut conn) => {
+            conn.claim_idempotency_key(&scope, &key, &request_hash, expires_at, abandoned_before)
+                .await
+        }
+        Err(e) => Err(ApiError::internal(format!("Failed to check idempotency key: {}", e))),
+    };
+    match claimed {
+        Ok(None) => {}
+        Ok(Some(stored)) => return stored_response(stored, &request_hash),
+        Err(e) => return e.into_response(),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            release(&state, &scope, &key).await;
+            return ApiError::internal(format!("Failed to read response: {}", e)).into_response();
+        }
+    };
+    if parts.status.is_server_error() {
+        release(&state, &scope, &key).await;
+    } else {
+        let stored = match state.db.acquire().await {
+            Ok(mut conn) => {
+                conn.complete_idempotency_key(&scope, &key, parts.status.as_u16().into(), &body)
+                    .await
+            }
+            Err(e) => {
+                Err(ApiError::internal(format!("Failed to store idempotent response: {}", e)))
+            }
+        };
+        // The request went through: answer it. A retry finds the key abandoned and runs again
+        if stored.is_err() {
+            release(&state, &scope, &key).await;
+        }
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Who a key belongs to: the API token, the session, or the address of an anonymous caller, as
+/// resolved by the rate limiter. Tokens are hashed, never stored.
+fn caller_scope(
+    grant: Option<&ApiTokenGrant>,
+    cookie: Option<&str>,
+    client_ip: Option<&ClientIp>,
+) -> Option<String> {
+    if let Some(grant) = grant {
+        return Some(format!("token:{}", grant.token_id));
+    }
+    match cookie.and_then(token_from_cookie_header) {
+        Some(token) => Some(format!("session:{}", sha256_hex(token))),
+        None => client_ip.map(|ip| format!("anonymous:{}", ip.0)),
+    }
+}
+
+fn request_hash(method: &Method, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b" ");
+    hasher.update(path);
+    hasher.update(b"\n");
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+/// What to answer to a request whose key is already taken.
+fn stored_response(stored: StoredRequest, request_hash: &str) -> Response {
+    if stored.request_hash != request_hash {
+        return ApiError::validation(
+            "This Idempotency-Key was already used for a different request",
+        )
+        .into_response();
+    }
+    let Some(status) = stored.status.and_then(|s| StatusCode::from_u16(s as u16).ok()) else {
+        return ApiError::conflict("A request with this Idempotency-Key is still being processed")
+            .into_response();
+    };
+
+    let mut response = Response::new(Body::from(stored.response_body.unwrap_or_default()));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+async fn release(state: &AppState, scope: &str, key: &str) {
+    // A failure is logged by the repository; the key is taken over once abandoned
+    if let Ok(mut conn) = state.db.acquire().await {
+        let _ = conn.release_idempotency_key(scope, key).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::ApiTokenScope;
+    use uuid::Uuid;
+
+    #[test]
+    fn keys_belong_to_the_token_then_the_session_then_the_address() {
+        let grant = ApiTokenGrant {
+            token_id: Uuid::from_u128(1),
+            account_id: Uuid::nil(),
+            scope: ApiTokenScope::Write,
+            project_ids: None,
+        };
+        let ip = ClientIp("203.0.113.9".parse().unwrap());
+        assert_eq!(
+            caller_scope(Some(&grant), Some("session_id=abc"), Some(&ip)).unwrap(),
+            "token:00000000-0000-0000-0000-000000000001"
+        );
+        assert_eq!(
+            caller_scope(None, Some("theme=dark; session_id=abc"), Some(&ip)).unwrap(),
+            format!("session:{}", sha256_hex("abc"))
+        );
+        assert_eq!(
+            caller_scope(None, Some("theme=dark"), Some(&ip)).unwrap(),
+            "anonymous:203.0.113.9"
+        );
+        assert_eq!(
+            caller_scope(None, None, Some(&ClientIp("198.51.100.7".parse().unwrap()))).unwrap(),
+            "anonymous:198.51.100.7"
+        );
+        assert_eq!(caller_scope(None, None, None), None);
+    }
+
+    #[test]
+    fn requests_differ_by_path_and_body() {
+        let hash = request_hash(&Method::POST, "/api/v1/expenses", b"{\"amount\":10}");
+        assert_eq!(hash, request_hash(&Method::POST, "/api/v1/expenses", b"{\"amount\":10}"));
+        assert_ne!(hash, request_hash(&Method::POST, "/api/v1/expenses", b"{\"amount\":11}"));
+        assert_ne!(hash, request_hash(&Method::POST, "/api/v1/users", b"{\"amount\":10}"));
+    }
+
+    #[test]
+    fn stored_requests_are_replayed_or_refused() {
+        let stored = |status| StoredRequest {
+            request_hash: "abc".to_string(),
+            status,
+            response_body: status.map(|_| b"42".to_vec()),
+        };
+
+        let replayed = stored_response(stored(Some(200)), "abc");
+        assert_eq!(replayed.status(), StatusCode::OK);
+        assert_eq!(replayed.headers()[REPLAYED_HEADER], "true");
+
+        assert_eq!(
+            stored_response(stored(Some(200)), "def").status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(stored_response(stored(None), "abc").status(), StatusCode::CONFLICT);
+    }
+}
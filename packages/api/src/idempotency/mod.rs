@@ -0,0 +1,161 @@
+//! Idempotency keys for the create endpoints, so that a retried request does not create twice.
+//!
+//! A `POST` to one of [`ROUTES`] may carry an `Idempotency-Key` header, a value unique to the
+//! operation such as a UUID. The first request with a key is processed and its response stored
+//! for `IDEMPOTENCY_RETENTION_HOURS` (default 24); the same request sent again with the key gets
+//! the stored response, marked with `Idempotent-Replayed: true`. Keys belong to the caller: its
+//! API token, or its session, or else its address. An anonymous request whose address is
+//! unknown is processed without its key.
+//!
+//! The same key with a different request is refused with `validation`, and while the first
+//! request is still being processed, with `conflict`. Server errors are not stored: the
+//! request can be retried with the same key. A key that never got a response, because the
+//! server stopped while processing it, is taken over after `IDEMPOTENCY_RECLAIM_MINUTES`
+//! (default 60).
+#[cfg(feature = "server")]
+pub mod idempotency_repository;
+#[cfg(feature = "server")]
+mod idempotency_repository_sqlite;
+#[cfg(feature = "server")]
+mod layer;
+
+#[cfg(feature = "server")]
+pub use layer::idempotency;
+
+use std::future::Future;
+
+#[cfg(feature = "server")]
+use chrono::Duration;
+
+#[cfg(feature = "server")]
+use crate::config::Source;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// Endpoints accepting an `Idempotency-Key`, all `POST`. `{…}` stands for any path segment.
+pub const ROUTES: &[&str] = &[
+    "/api/v1/expenses",
+    "/api/v1/import/tricount",
+    "/api/v1/projects",
+    "/api/v1/projects/{project_id}/expenses/batch",
+    "/api/v1/users",
+];
+
+/// Whether `path` is one of [`ROUTES`].
+pub fn accepts_key(path: &str) -> bool {
+    ROUTES.iter().any(|route| {
+        let mut segments = path.split('/');
+        route.split('/').all(|expected| match segments.next() {
+            Some(segment) => {
+                segment == expected || (expected.starts_with('{') && !segment.is_empty())
+            }
+            None => false,
+        }) && segments.next().is_none()
+    })
+}
+
+/// A key is 1 to 255 visible ASCII characters.
+pub fn is_valid_key(key: &str) -> bool {
+    (1..=MAX_KEY_LENGTH).contains(&key.len()) && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyConfig {
+    /// How long a response is replayed for its key
+    pub retention: Duration,
+    /// How long a key without a response is refused with `conflict` before it is taken over.
+    /// Longer than any request may run, or a slow one could run twice.
+    pub reclaim_after: Duration,
+}
+
+#[cfg(feature = "server")]
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self { retention: Duration::hours(24), reclaim_after: Duration::minutes(60) }
+    }
+}
+
+#[cfg(feature = "server")]
+impl IdempotencyConfig {
+    /// `IDEMPOTENCY_RETENTION_HOURS`, from 1 hour to 30 days, and
+    /// `IDEMPOTENCY_RECLAIM_MINUTES`, from 5 minutes to the retention.
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let default = Self::default();
+        let hours = source.parse("IDEMPOTENCY_RETENTION_HOURS", default.retention.num_hours())?;
+        if !(1..=720).contains(&hours) {
+            anyhow::bail!("IDEMPOTENCY_RETENTION_HOURS must be between 1 and 720");
+        }
+        let minutes =
+            source.parse("IDEMPOTENCY_RECLAIM_MINUTES", default.reclaim_after.num_minutes())?;
+        if !(5..=hours * 60).contains(&minutes) {
+            anyhow::bail!(
+                "IDEMPOTENCY_RECLAIM_MINUTES must be at least 5 and at most IDEMPOTENCY_RETENTION_HOURS"
+            );
+        }
+        Ok(Self { retention: Duration::hours(hours), reclaim_after: Duration::minutes(minutes) })
+    }
+}
+
+/// Send a server function call with an `Idempotency-Key` header.
+///
+/// Server functions read the extra request headers when first polled, so they are set for
+/// that poll only and the call is then awaited as usual. Calls made on the server, which do
+/// not go through HTTP, ignore the key.
+pub async fn with_idempotency_key<F: Future>(key: &str, call: F) -> F::Output {
+    #[cfg(not(feature = "server"))]
+    {
+        use dioxus::fullstack::{get_request_headers, set_request_headers, HeaderValue};
+
+        let mut call = std::pin::pin!(call);
+        let mut first_poll = true;
+        std::future::poll_fn(|cx| {
+            if !std::mem::take(&mut first_poll) {
+                return call.as_mut().poll(cx);
+            }
+            let previous = get_request_headers();
+            let mut headers = previous.clone();
+            if let Ok(value) = HeaderValue::from_str(key) {
+                headers.insert(IDEMPOTENCY_KEY_HEADER, value);
+            }
+            set_request_headers(headers);
+            let poll = call.as_mut().poll(cx);
+            set_request_headers(previous);
+            poll
+        })
+        .await
+    }
+    #[cfg(feature = "server")]
+    {
+        let _ = key;
+        call.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_match_any_id() {
+        assert!(accepts_key("/api/v1/expenses"));
+        assert!(accepts_key(
+            "/api/v1/projects/9b2f6c1e-7a4d-4f38-9c1e-2d5b8a7f0e31/expenses/batch"
+        ));
+        assert!(!accepts_key("/api/v1/projects//expenses/batch"));
+        assert!(!accepts_key("/api/v1/projects/batch"));
+        assert!(!accepts_key("/api/v1/expenses/12"));
+    }
+
+    #[test]
+    fn keys_are_visible_ascii() {
+        assert!(is_valid_key("9b2f6c1e-7a4d-4f38-9c1e-2d5b8a7f0e31"));
+        assert!(is_valid_key(&"k".repeat(MAX_KEY_LENGTH)));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key(&"k".repeat(MAX_KEY_LENGTH + 1)));
+        assert!(!is_valid_key("two words"));
+        assert!(!is_valid_key("clé"));
+    }
+}
@@ -0,0 +1,235 @@
+use chrono::NaiveDateTime;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{
+    CreatableProjectInvite, CreatedProjectInvite, InviteRedemption, ProjectInvite, ProjectRole,
+};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+#[cfg(feature = "server")]
+use crate::db::get_db;
+#[cfg(feature = "server")]
+use crate::projects::project_access::{authorize, ProjectAction};
+#[cfg(feature = "server")]
+use crate::utils::{
+    ensure_account_write_allowed, generate_token, get_current_account_id, sha256_hex,
+};
+#[cfg(feature = "server")]
+use chrono::{Duration, Utc};
+
+const MAX_INVITE_LIFETIME_HOURS: i64 = 24 * 90;
+
+fn validate_invite(payload: &CreatableProjectInvite) -> Result<(), &'static str> {
+    if payload.role == ProjectRole::Owner {
+        return Err("Invites cannot grant the owner role");
+    }
+    if payload.expires_in_hours < 1 || payload.expires_in_hours > MAX_INVITE_LIFETIME_HOURS {
+        return Err("Invite lifetime must be between 1 hour and 90 days");
+    }
+    if payload.max_uses.is_some_and(|max| max < 1) {
+        return Err("Invite usage limit must be at least 1");
+    }
+    Ok(())
+}
+
+fn check_invite_redeemable(invite: &ProjectInvite, now: NaiveDateTime) -> Result<(), &'static str> {
+    if invite.revoked_at.is_some() {
+        return Err("This invite link has been revoked");
+    }
+    if invite.expires_at <= now {
+        return Err("This invite link has expired");
+    }
+    if invite.max_uses.is_some_and(|max| invite.use_count >= max) {
+        return Err("This invite link has reached its usage limit");
+    }
+    Ok(())
+}
+
+#[post("/api/v1/projects/{project_id}/invites")]
+pub async fn create_project_invite(
+    project_id: Uuid,
+    Json(payload): Json<CreatableProjectInvite>,
+) -> Result<CreatedProjectInvite, ApiError> {
+    validate_invite(&payload).map_err(ApiError::validation)?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let access = authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+
+    let token = generate_token();
+    let expires_at = (Utc::now() + Duration::hours(payload.expires_in_hours)).naive_utc();
+    let invite = tx
+        .create_invite(
+            project_id,
+            &sha256_hex(&token),
+            payload.role,
+            expires_at,
+            payload.max_uses,
+            access.account_id,
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(CreatedProjectInvite { invite, token })
+}
+
+#[get("/api/v1/projects/{project_id}/invites")]
+pub async fn get_project_invites(project_id: Uuid) -> Result<Vec<ProjectInvite>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+
+    let invites = tx.get_active_invites(project_id, Utc::now().naive_utc()).await?;
+
+    tx.commit().await?;
+
+    Ok(invites)
+}
+
+#[delete("/api/v1/projects/{project_id}/invites/{invite_id}")]
+pub async fn revoke_project_invite(project_id: Uuid, invite_id: Uuid) -> Result<(), ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+
+    if !tx.revoke_invite(project_id, invite_id).await? {
+        return Err(ApiError::not_found("Invite not found"));
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Join a project through an invite link.
+///
+/// Signed-in callers get an `account_projects` membership with the invite's role. Anonymous
+/// callers can only join projects without an owner; the client keeps the project in local storage.
+/// Callers that are already members keep their current role and do not consume a use.
+#[post("/api/v1/invites/{token}/redeem")]
+pub async fn redeem_invite(token: String) -> Result<InviteRedemption, ApiError> {
+    ensure_account_write_allowed()?;
+
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let invite = tx
+        .find_invite_by_token_hash_for_update(&sha256_hex(&token))
+        .await?
+        .ok_or_else(|| ApiError::not_found("Invalid invite link"))?;
+    check_invite_redeemable(&invite, Utc::now().naive_utc()).map_err(ApiError::validation)?;
+
+    let project = tx.get_project(invite.project_id).await?;
+
+    match get_current_account_id().await {
+        Some(account_id) => {
+            if let Some(existing) = tx.get_account_project(account_id, project.id).await? {
+                return Ok(InviteRedemption {
+                    project_id: project.id,
+                    role: existing.role,
+                    user_id: existing.user_id,
+                });
+            }
+            tx.upsert_account_project_role(account_id, project.id, invite.role).await?;
+        }
+        None if project.owner_account_id.is_some() => {
+            return Err(ApiError::unauthorized("Sign in to join this project"));
+        }
+        None => {}
+    }
+
+    tx.increment_invite_use(invite.id).await?;
+
+    tx.commit().await?;
+
+    Ok(InviteRedemption { project_id: project.id, role: invite.role, user_id: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn now() -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+
+    fn invite() -> ProjectInvite {
+        ProjectInvite {
+            id: Uuid::nil(),
+            project_id: Uuid::nil(),
+            role: ProjectRole::Editor,
+            expires_at: now() + Duration::days(1),
+            max_uses: None,
+            use_count: 0,
+            created_at: now(),
+            revoked_at: None,
+        }
+    }
+
+    fn creatable(
+        role: ProjectRole,
+        expires_in_hours: i64,
+        max_uses: Option<i32>,
+    ) -> CreatableProjectInvite {
+        CreatableProjectInvite { role, expires_in_hours, max_uses }
+    }
+
+    #[test]
+    fn valid_invite_is_redeemable() {
+        assert!(check_invite_redeemable(&invite(), now()).is_ok());
+    }
+
+    #[test]
+    fn revoked_invite_is_not_redeemable() {
+        let revoked = ProjectInvite { revoked_at: Some(now()), ..invite() };
+        assert!(check_invite_redeemable(&revoked, now()).is_err());
+    }
+
+    #[test]
+    fn expired_invite_is_not_redeemable() {
+        let t = now();
+        let expired = ProjectInvite { expires_at: t, ..invite() };
+        assert!(check_invite_redeemable(&expired, t).is_err());
+    }
+
+    #[test]
+    fn used_up_invite_is_not_redeemable() {
+        let used_up = ProjectInvite { max_uses: Some(2), use_count: 2, ..invite() };
+        assert!(check_invite_redeemable(&used_up, now()).is_err());
+
+        let one_left = ProjectInvite { max_uses: Some(2), use_count: 1, ..invite() };
+        assert!(check_invite_redeemable(&one_left, now()).is_ok());
+    }
+
+    #[test]
+    fn invite_cannot_grant_owner_role() {
+        assert!(validate_invite(&creatable(ProjectRole::Owner, 24, None)).is_err());
+        assert!(validate_invite(&creatable(ProjectRole::Viewer, 24, None)).is_ok());
+    }
+
+    #[test]
+    fn invite_lifetime_is_bounded() {
+        assert!(validate_invite(&creatable(ProjectRole::Editor, 0, None)).is_err());
+        assert!(validate_invite(&creatable(ProjectRole::Editor, MAX_INVITE_LIFETIME_HOURS, None))
+            .is_ok());
+        assert!(validate_invite(&creatable(
+            ProjectRole::Editor,
+            MAX_INVITE_LIFETIME_HOURS + 1,
+            None
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn invite_usage_limit_must_be_positive() {
+        assert!(validate_invite(&creatable(ProjectRole::Editor, 24, Some(0))).is_err());
+        assert!(validate_invite(&creatable(ProjectRole::Editor, 24, Some(1))).is_ok());
+    }
+}
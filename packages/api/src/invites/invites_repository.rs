@@ -0,0 +1,167 @@
+use chrono::NaiveDateTime;
+use dioxus::prelude::*;
+use shared::{ProjectInvite, ProjectRole};
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use crate::error::{db_error, ApiError};
+
+#[cfg(feature = "server")]
+use futures::future::BoxFuture;
+#[cfg(feature = "server")]
+use sqlx::PgConnection;
+
+#[cfg(feature = "server")]
+pub trait InvitesRepository {
+    fn create_invite<'a>(
+        &'a mut self,
+        project_id: Uuid,
+        token_hash: &'a str,
+        role: ProjectRole,
+        expires_at: NaiveDateTime,
+        max_uses: Option<i32>,
+        created_by: Option<Uuid>,
+    ) -> BoxFuture<'a, Result<ProjectInvite, ApiError>>;
+
+    /// Invites that can still be redeemed: not revoked, not expired and below their usage limit.
+    fn get_active_invites(
+        &mut self,
+        project_id: Uuid,
+        now: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<Vec<ProjectInvite>, ApiError>>;
+
+    /// Locks the invite row until the end of the transaction so concurrent redemptions cannot
+    /// exceed `max_uses`.
+    fn find_invite_by_token_hash_for_update<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ProjectInvite>, ApiError>>;
+
+    fn increment_invite_use(&mut self, invite_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Returns false when no active invite with this id exists on the project.
+    fn revoke_invite(
+        &mut self,
+        project_id: Uuid,
+        invite_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>>;
+}
+
+#[cfg(feature = "server")]
+impl InvitesRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn create_invite<'a>(
+        &'a mut self,
+        project_id: Uuid,
+        token_hash: &'a str,
+        role: ProjectRole,
+        expires_at: NaiveDateTime,
+        max_uses: Option<i32>,
+        created_by: Option<Uuid>,
+    ) -> BoxFuture<'a, Result<ProjectInvite, ApiError>> {
+        Box::pin(async move {
+            let invite = sqlx::query_as!(
+                ProjectInvite,
+                r#"INSERT INTO project_invites (project_id, token_hash, role, expires_at, max_uses, created_by)
+                   VALUES ($1, $2, $3, $4, $5, $6)
+                   RETURNING id, project_id, role as "role: ProjectRole", expires_at, max_uses, use_count, created_at, revoked_at"#,
+                project_id,
+                token_hash,
+                role as ProjectRole,
+                expires_at,
+                max_uses,
+                created_by
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create invite"))?;
+
+            Ok(invite)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_active_invites(
+        &mut self,
+        project_id: Uuid,
+        now: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<Vec<ProjectInvite>, ApiError>> {
+        Box::pin(async move {
+            let invites = sqlx::query_as!(
+                ProjectInvite,
+                r#"SELECT id, project_id, role as "role: ProjectRole", expires_at, max_uses, use_count, created_at, revoked_at
+                   FROM project_invites
+                   WHERE project_id = $1
+                     AND revoked_at IS NULL
+                     AND expires_at > $2
+                     AND (max_uses IS NULL OR use_count < max_uses)
+                   ORDER BY created_at DESC"#,
+                project_id,
+                now
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get invites"))?;
+
+            Ok(invites)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_invite_by_token_hash_for_update<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ProjectInvite>, ApiError>> {
+        Box::pin(async move {
+            let invite = sqlx::query_as!(
+                ProjectInvite,
+                r#"SELECT id, project_id, role as "role: ProjectRole", expires_at, max_uses, use_count, created_at, revoked_at
+                   FROM project_invites
+                   WHERE token_hash = $1
+                   FOR UPDATE"#,
+                token_hash
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find invite"))?;
+
+            Ok(invite)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn increment_invite_use(&mut self, invite_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!(
+                "UPDATE project_invites SET use_count = use_count + 1 WHERE id = $1",
+                invite_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to update invite"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn revoke_invite(
+        &mut self,
+        project_id: Uuid,
+        invite_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query!(
+                "UPDATE project_invites SET revoked_at = current_timestamp
+                 WHERE id = $1 AND project_id = $2 AND revoked_at IS NULL",
+                invite_id,
+                project_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to revoke invite"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+}
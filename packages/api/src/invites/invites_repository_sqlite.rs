@@ -0,0 +1,126 @@
+use chrono::{NaiveDateTime, Utc};
+use futures::future::BoxFuture;
+use shared::{ProjectInvite, ProjectRole};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+use super::invites_repository::InvitesRepository;
+use crate::error::{db_error, ApiError};
+
+const INVITE_COLUMNS: &str =
+    "id, project_id, role, expires_at, max_uses, use_count, created_at, revoked_at";
+
+impl InvitesRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn create_invite<'a>(
+        &'a mut self,
+        project_id: Uuid,
+        token_hash: &'a str,
+        role: ProjectRole,
+        expires_at: NaiveDateTime,
+        max_uses: Option<i32>,
+        created_by: Option<Uuid>,
+    ) -> BoxFuture<'a, Result<ProjectInvite, ApiError>> {
+        Box::pin(async move {
+            let invite = sqlx::query_as(&format!(
+                "INSERT INTO project_invites (id, project_id, token_hash, role, expires_at, max_uses, created_by)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 RETURNING {}",
+                INVITE_COLUMNS
+            ))
+            .bind(Uuid::new_v4())
+            .bind(project_id)
+            .bind(token_hash)
+            .bind(role)
+            .bind(expires_at)
+            .bind(max_uses)
+            .bind(created_by)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create invite"))?;
+
+            Ok(invite)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_active_invites(
+        &mut self,
+        project_id: Uuid,
+        now: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<Vec<ProjectInvite>, ApiError>> {
+        Box::pin(async move {
+            let invites = sqlx::query_as(&format!(
+                "SELECT {} FROM project_invites
+                 WHERE project_id = $1
+                   AND revoked_at IS NULL
+                   AND expires_at > $2
+                   AND (max_uses IS NULL OR use_count < max_uses)
+                 ORDER BY created_at DESC",
+                INVITE_COLUMNS
+            ))
+            .bind(project_id)
+            .bind(now)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get invites"))?;
+
+            Ok(invites)
+        })
+    }
+
+    /// The transaction already holds the database write lock: no row lock is needed.
+    #[tracing::instrument(skip_all)]
+    fn find_invite_by_token_hash_for_update<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ProjectInvite>, ApiError>> {
+        Box::pin(async move {
+            let invite = sqlx::query_as(&format!(
+                "SELECT {} FROM project_invites WHERE token_hash = $1",
+                INVITE_COLUMNS
+            ))
+            .bind(token_hash)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find invite"))?;
+
+            Ok(invite)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn increment_invite_use(&mut self, invite_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE project_invites SET use_count = use_count + 1 WHERE id = $1")
+                .bind(invite_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to update invite"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn revoke_invite(
+        &mut self,
+        project_id: Uuid,
+        invite_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "UPDATE project_invites SET revoked_at = $1
+                 WHERE id = $2 AND project_id = $3 AND revoked_at IS NULL",
+            )
+            .bind(Utc::now().naive_utc())
+            .bind(invite_id)
+            .bind(project_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to revoke invite"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+}
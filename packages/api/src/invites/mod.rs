@@ -0,0 +1,4 @@
+pub mod invites_controller;
+pub mod invites_repository;
+#[cfg(feature = "server")]
+mod invites_repository_sqlite;
@@ -1,11 +1,33 @@
 //! This crate contains all shared fullstack server functions.
 pub mod account_projects;
 pub mod auth;
-mod db;
+#[cfg(feature = "server")]
+pub mod config;
+pub mod db;
+pub mod error;
 pub mod expenses;
+#[cfg(feature = "server")]
+pub mod health;
+pub mod idempotency;
+pub mod invites;
+pub mod mailer;
+#[cfg(feature = "server")]
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod migrations;
+pub mod openapi;
 pub mod payments;
 pub mod projects;
+#[cfg(feature = "server")]
+pub mod rate_limit;
+#[cfg(feature = "server")]
+pub mod router;
+pub mod share_links;
 pub mod sse;
+#[cfg(feature = "server")]
+pub mod state;
+#[cfg(feature = "server")]
+pub mod telemetry;
 pub mod tricount;
 pub mod users;
 pub mod utils;
@@ -0,0 +1,106 @@
+//! Outbound email.
+//!
+//! The transport is chosen at startup from `MAIL_TRANSPORT`: `smtp` for production, `file` or
+//! `log` (the default on http) for local development and tests.
+pub mod templates;
+#[cfg(feature = "server")]
+mod transports;
+
+#[cfg(feature = "server")]
+pub use transports::{FileTransport, LogTransport, SmtpConfig, SmtpTls, SmtpTransport};
+
+#[cfg(feature = "server")]
+use futures::future::BoxFuture;
+#[cfg(feature = "server")]
+use std::path::PathBuf;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+
+#[cfg(feature = "server")]
+use crate::config::Source;
+#[cfg(feature = "server")]
+use crate::state::AppState;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub text_body: String,
+}
+
+#[cfg(feature = "server")]
+pub trait MailTransport: Send + Sync {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MailConfig {
+    /// Sender of every email, e.g. `Counted <no-reply@example.com>`
+    pub from: String,
+    pub transport: MailTransportConfig,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum MailTransportConfig {
+    Log,
+    File { dir: PathBuf },
+    Smtp(SmtpConfig),
+}
+
+#[cfg(feature = "server")]
+impl MailConfig {
+    /// `MAIL_FROM`, `MAIL_TRANSPORT`, then `MAIL_FILE_DIR` or the `SMTP_*` settings.
+    ///
+    /// `log` is for local development only: a server behind an https `app_base_url` must choose
+    /// a transport that delivers.
+    pub fn from_source(source: &Source, app_base_url: &str) -> anyhow::Result<Self> {
+        let from = source
+            .var("MAIL_FROM")
+            .unwrap_or_else(|| "Counted <no-reply@counted.local>".to_string());
+        from.parse::<lettre::message::Mailbox>()
+            .map_err(|e| anyhow::anyhow!("Invalid MAIL_FROM \"{}\": {}", from, e))?;
+
+        let transport = match source.var("MAIL_TRANSPORT").as_deref() {
+            Some("smtp") => MailTransportConfig::Smtp(SmtpConfig::from_source(source)?),
+            Some("file") => MailTransportConfig::File {
+                dir: source.var("MAIL_FILE_DIR").unwrap_or_else(|| "mail".to_string()).into(),
+            },
+            Some("log") | None if app_base_url.starts_with("https://") => {
+                anyhow::bail!("MAIL_TRANSPORT must be smtp or file when APP_BASE_URL is https")
+            }
+            Some("log") | None => MailTransportConfig::Log,
+            Some(other) => anyhow::bail!("Unknown MAIL_TRANSPORT \"{}\"", other),
+        };
+
+        Ok(Self { from, transport })
+    }
+}
+
+/// The transport described by the configuration.
+#[cfg(feature = "server")]
+pub fn transport(config: &MailConfig) -> anyhow::Result<Arc<dyn MailTransport>> {
+    Ok(match &config.transport {
+        MailTransportConfig::Log => Arc::new(LogTransport),
+        MailTransportConfig::File { dir } => Arc::new(FileTransport::new(dir, &config.from)?),
+        MailTransportConfig::Smtp(smtp) => Arc::new(SmtpTransport::new(smtp, &config.from)?),
+    })
+}
+
+/// The transport of the running server, for the current request.
+#[cfg(feature = "server")]
+pub fn mailer() -> Arc<dyn MailTransport> {
+    AppState::current().expect("The app state is not available outside of a request").mailer
+}
+
+/// Send without holding up the request: failures are logged, never returned to the caller.
+#[cfg(feature = "server")]
+pub fn send_in_background(email: Email) {
+    let mailer = mailer();
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(&email).await {
+            tracing::error!("Failed to send email \"{}\": {}", email.subject, e);
+        }
+    });
+}
@@ -0,0 +1,202 @@
+use super::Email;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Locale {
+    Fr,
+    En,
+}
+
+impl Locale {
+    /// Pick the first supported language of an `Accept-Language` header, French otherwise.
+    ///
+    /// Browsers list languages by preference, so q-values are not inspected.
+    pub fn from_accept_language(header: Option<&str>) -> Locale {
+        header
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|tag| tag.split(';').next())
+            .map(|tag| tag.trim().to_lowercase())
+            .find_map(|tag| {
+                if tag.starts_with("fr") {
+                    Some(Locale::Fr)
+                } else if tag.starts_with("en") {
+                    Some(Locale::En)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(Locale::Fr)
+    }
+}
+
+/// Tells someone they were added as a participant of a project.
+///
+/// `link` points to the project when the address already belongs to an account, to the
+/// registration page otherwise — the invitation resolves once they sign up with this address.
+pub fn invitation_email(
+    locale: Locale,
+    to: &str,
+    participant_name: &str,
+    project_name: &str,
+    inviter_name: Option<&str>,
+    link: &str,
+) -> Email {
+    let (subject, text_body) = match locale {
+        Locale::Fr => {
+            let inviter = inviter_name.unwrap_or("Quelqu'un");
+            (
+                format!("Vous avez été invité(e) sur « {} »", project_name),
+                format!(
+                    "Bonjour {participant_name},\n\n\
+                     {inviter} vous a ajouté(e) au projet « {project_name} » sur Counted.\n\n\
+                     Pour suivre les dépenses partagées, ouvrez ce lien avec cette adresse email :\n\
+                     {link}\n\n\
+                     Si vous ne vous attendiez pas à cette invitation, vous pouvez ignorer ce message.\n"
+                ),
+            )
+        }
+        Locale::En => {
+            let inviter = inviter_name.unwrap_or("Someone");
+            (
+                format!("You have been invited to \"{}\"", project_name),
+                format!(
+                    "Hi {participant_name},\n\n\
+                     {inviter} added you to the project \"{project_name}\" on Counted.\n\n\
+                     To follow the shared expenses, open this link using this email address:\n\
+                     {link}\n\n\
+                     If you were not expecting this invitation, you can ignore this message.\n"
+                ),
+            )
+        }
+    };
+
+    Email { to: to.to_string(), subject, text_body }
+}
+
+/// Asks a new account holder to confirm their address. The link expires after 24 hours.
+pub fn verification_email(locale: Locale, to: &str, display_name: &str, link: &str) -> Email {
+    let (subject, text_body) = match locale {
+        Locale::Fr => (
+            "Confirmez votre adresse email".to_string(),
+            format!(
+                "Bonjour {display_name},\n\n\
+                 Pour confirmer votre adresse email sur Counted, ouvrez ce lien :\n\
+                 {link}\n\n\
+                 Ce lien est valable 24 heures. Les projets auxquels vous avez été invité(e) \
+                 apparaîtront une fois l'adresse confirmée.\n\n\
+                 Si vous n'avez pas créé de compte, vous pouvez ignorer ce message.\n"
+            ),
+        ),
+        Locale::En => (
+            "Confirm your email address".to_string(),
+            format!(
+                "Hi {display_name},\n\n\
+                 To confirm your email address on Counted, open this link:\n\
+                 {link}\n\n\
+                 This link is valid for 24 hours. Projects you were invited to will show up \
+                 once the address is confirmed.\n\n\
+                 If you did not create an account, you can ignore this message.\n"
+            ),
+        ),
+    };
+
+    Email { to: to.to_string(), subject, text_body }
+}
+
+/// Sends a single-use password reset link. The link expires after one hour.
+pub fn password_reset_email(locale: Locale, to: &str, display_name: &str, link: &str) -> Email {
+    let (subject, text_body) = match locale {
+        Locale::Fr => (
+            "Réinitialisation de votre mot de passe".to_string(),
+            format!(
+                "Bonjour {display_name},\n\n\
+                 Une réinitialisation du mot de passe de votre compte Counted a été demandée. \
+                 Pour choisir un nouveau mot de passe, ouvrez ce lien :\n\
+                 {link}\n\n\
+                 Ce lien est valable une heure et ne fonctionne qu'une seule fois.\n\n\
+                 Si vous n'êtes pas à l'origine de cette demande, ignorez ce message : \
+                 votre mot de passe reste inchangé.\n"
+            ),
+        ),
+        Locale::En => (
+            "Reset your password".to_string(),
+            format!(
+                "Hi {display_name},\n\n\
+                 Someone asked to reset the password of your Counted account. \
+                 To choose a new password, open this link:\n\
+                 {link}\n\n\
+                 This link is valid for one hour and works only once.\n\n\
+                 If you did not ask for this, ignore this message: your password stays unchanged.\n"
+            ),
+        ),
+    };
+
+    Email { to: to.to_string(), subject, text_body }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_defaults_to_french() {
+        assert_eq!(Locale::from_accept_language(None), Locale::Fr);
+        assert_eq!(Locale::from_accept_language(Some("")), Locale::Fr);
+        assert_eq!(Locale::from_accept_language(Some("de-DE,de;q=0.9")), Locale::Fr);
+    }
+
+    #[test]
+    fn locale_uses_first_supported_language() {
+        assert_eq!(Locale::from_accept_language(Some("en-US,en;q=0.9,fr;q=0.8")), Locale::En);
+        assert_eq!(Locale::from_accept_language(Some("fr-FR,fr;q=0.9,en;q=0.8")), Locale::Fr);
+        assert_eq!(Locale::from_accept_language(Some("de-DE, EN-GB;q=0.8")), Locale::En);
+    }
+
+    #[test]
+    fn invitation_email_is_localized() {
+        let fr = invitation_email(
+            Locale::Fr,
+            "a@b.c",
+            "Alice",
+            "Coloc",
+            Some("Bob"),
+            "https://x/register",
+        );
+        assert!(fr.subject.contains("« Coloc »"));
+        assert!(fr.text_body.contains("Bob vous a ajouté(e)"));
+
+        let en = invitation_email(
+            Locale::En,
+            "a@b.c",
+            "Alice",
+            "Coloc",
+            Some("Bob"),
+            "https://x/register",
+        );
+        assert!(en.subject.contains("\"Coloc\""));
+        assert!(en.text_body.contains("Bob added you"));
+    }
+
+    #[test]
+    fn invitation_email_contains_link_and_recipient() {
+        let email =
+            invitation_email(Locale::En, "a@b.c", "Alice", "Coloc", None, "https://x/register");
+        assert_eq!(email.to, "a@b.c");
+        assert!(email.text_body.contains("https://x/register"));
+        assert!(email.text_body.starts_with("Hi Alice"));
+        assert!(email.text_body.contains("Someone added you"));
+    }
+
+    #[test]
+    fn account_emails_are_localized_and_contain_link() {
+        let fr = verification_email(Locale::Fr, "a@b.c", "Alice", "https://x/verify-email/t");
+        assert_eq!(fr.subject, "Confirmez votre adresse email");
+        assert!(fr.text_body.starts_with("Bonjour Alice"));
+        assert!(fr.text_body.contains("https://x/verify-email/t"));
+
+        let en = password_reset_email(Locale::En, "a@b.c", "Alice", "https://x/reset-password/t");
+        assert_eq!(en.to, "a@b.c");
+        assert_eq!(en.subject, "Reset your password");
+        assert!(en.text_body.contains("https://x/reset-password/t"));
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use futures::future::BoxFuture;
+use lettre::message::{header::ContentType, Mailbox, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+use super::{Email, MailTransport};
+use crate::config::Source;
+use crate::telemetry::{redact, redact_links};
+
+fn build_message(from: &Mailbox, email: &Email) -> anyhow::Result<Message> {
+    let to: Mailbox = email.to.parse().context("Invalid recipient address")?;
+
+    Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(email.subject.clone())
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.text_body.clone())
+        .context("Failed to build email")
+}
+
+/// Connection security of the SMTP relay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmtpTls {
+    /// Implicit TLS, port 465
+    Tls,
+    /// Upgraded plain connection, port 587
+    StartTls,
+    /// For local catch-all servers such as Mailpit
+    None,
+}
+
+impl std::str::FromStr for SmtpTls {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tls" => Ok(Self::Tls),
+            "starttls" => Ok(Self::StartTls),
+            "none" => Ok(Self::None),
+            _ => Err("expected tls, starttls or none"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmtpConfig {
+    pub host: String,
+    /// Defaults to the standard port of `tls`
+    pub port: Option<u16>,
+    pub tls: SmtpTls,
+    pub credentials: Option<(String, String)>,
+}
+
+impl SmtpConfig {
+    /// `SMTP_HOST`, `SMTP_PORT`, `SMTP_TLS` (default `starttls`), `SMTP_USERNAME` and
+    /// `SMTP_PASSWORD`.
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let host = source.var("SMTP_HOST").context("SMTP_HOST must be set")?;
+        let port = match source.var("SMTP_PORT") {
+            Some(port) => Some(port.parse().context("SMTP_PORT must be a port number")?),
+            None => None,
+        };
+        let credentials = match (source.var("SMTP_USERNAME"), source.var("SMTP_PASSWORD")) {
+            (Some(username), Some(password)) => Some((username, password)),
+            (None, None) => None,
+            _ => anyhow::bail!("SMTP_USERNAME and SMTP_PASSWORD must be set together"),
+        };
+
+        Ok(Self { host, port, tls: source.parse("SMTP_TLS", SmtpTls::StartTls)?, credentials })
+    }
+}
+
+/// Delivers through an SMTP relay.
+pub struct SmtpTransport {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(config: &SmtpConfig, from: &str) -> anyhow::Result<Self> {
+        let host = &config.host;
+        let mut builder = match config.tls {
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        };
+
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some((username, password)) = &config.credentials {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            from: from.parse().context("Invalid MAIL_FROM address")?,
+            transport: builder.build(),
+        })
+    }
+}
+
+impl MailTransport for SmtpTransport {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let message = build_message(&self.from, email)?;
+            self.transport.send(message).await.context("SMTP delivery failed")?;
+            Ok(())
+        })
+    }
+}
+
+/// Writes every email as an `.eml` file, for local development and tests.
+pub struct FileTransport {
+    from: Mailbox,
+    dir: PathBuf,
+}
+
+impl FileTransport {
+    pub fn new(dir: impl Into<PathBuf>, from: &str) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create mail directory {}", dir.display()))?;
+
+        Ok(Self { from: from.parse().context("Invalid MAIL_FROM address")?, dir })
+    }
+}
+
+impl MailTransport for FileTransport {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let message = build_message(&self.from, email)?;
+            let file_name = format!(
+                "{}-{:08x}.eml",
+                chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+                rand::random::<u32>()
+            );
+            std::fs::write(self.dir.join(file_name), message.formatted())
+                .context("Failed to write email file")?;
+            Ok(())
+        })
+    }
+}
+
+/// Logs emails instead of sending them, for local development. The recipient and the tokens of
+/// the links are hidden, so the log never holds a working account link.
+pub struct LogTransport;
+
+impl MailTransport for LogTransport {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            tracing::info!(
+                to = %redact(&email.to),
+                subject = %email.subject,
+                body = %redact_links(&email.text_body),
+                "Email not sent: MAIL_TRANSPORT is log"
+            );
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(to: &str) -> Email {
+        Email { to: to.to_string(), subject: "Hello".to_string(), text_body: "Body\n".to_string() }
+    }
+
+    #[test]
+    fn file_transport_writes_eml() {
+        let dir = std::env::temp_dir().join(format!("counted-mail-{:08x}", rand::random::<u32>()));
+        let transport = FileTransport::new(&dir, "Counted <no-reply@counted.local>").unwrap();
+
+        futures::executor::block_on(transport.send(&email("alice@example.com"))).unwrap();
+
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        let content = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(content.contains("To: alice@example.com"));
+        assert!(content.contains("Subject: Hello"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_recipient_is_rejected() {
+        let from: Mailbox = "Counted <no-reply@counted.local>".parse().unwrap();
+        assert!(build_message(&from, &email("not an address")).is_err());
+    }
+}
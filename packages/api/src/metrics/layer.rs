@@ -0,0 +1,26 @@
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Axum middleware: count the requests to each `/api` route and time them.
+///
+/// Routes are labelled by their pattern (`/api/v1/projects/{project_id}`), so the number of
+/// series stays bounded. Pages and assets are not recorded.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .filter(|path| path.starts_with("/api/"));
+    let Some(route) = route else {
+        return next.run(request).await;
+    };
+
+    let method = request.method().clone();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    super::record_request(method.as_str(), &route, response.status().as_u16(), start.elapsed());
+    response
+}
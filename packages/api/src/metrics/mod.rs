@@ -0,0 +1,114 @@
+//! Metrics in the Prometheus text format, served at `GET /metrics`.
+//!
+//! Counters live in process memory and restart from zero with the server. Pool and SSE figures
+//! are read when the endpoint is scraped.
+mod layer;
+mod registry;
+
+pub use layer::track_requests;
+pub use registry::{render_gauge, CounterVec, HistogramVec, DURATION_BUCKETS};
+
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{header, HeaderValue};
+use axum::response::{IntoResponse, Response};
+
+use crate::error::ApiError;
+use crate::state::AppState;
+
+static HTTP_REQUESTS: CounterVec = CounterVec::new(
+    "counted_http_requests_total",
+    "Requests to the API, by route and response status.",
+    &["method", "route", "status"],
+);
+
+static HTTP_REQUEST_DURATION: HistogramVec = HistogramVec::new(
+    "counted_http_request_duration_seconds",
+    "Time to answer API requests, by route.",
+    &["method", "route"],
+    DURATION_BUCKETS,
+);
+
+static LOGIN_FAILURES: CounterVec = CounterVec::new(
+    "counted_login_failures_total",
+    "Failed sign-ins, by the step that failed.",
+    &["step"],
+);
+
+static ACCOUNT_LOCKOUTS: CounterVec = CounterVec::new(
+    "counted_account_lockouts_total",
+    "Accounts locked after too many failed sign-ins.",
+    &[],
+);
+
+static TRICOUNT_IMPORTS: CounterVec = CounterVec::new(
+    "counted_tricount_imports_total",
+    "Tricount imports, by outcome: success or the error code.",
+    &["outcome"],
+);
+
+pub fn record_request(method: &str, route: &str, status: u16, elapsed: Duration) {
+    HTTP_REQUESTS.inc(&[method, route, &status.to_string()]);
+    HTTP_REQUEST_DURATION.observe(&[method, route], elapsed.as_secs_f64());
+}
+
+/// `step` is `password`, `two_factor`, or `locked` for an attempt on a locked account.
+pub fn record_login_failure(step: &str) {
+    LOGIN_FAILURES.inc(&[step]);
+}
+
+pub fn record_lockout() {
+    ACCOUNT_LOCKOUTS.inc(&[]);
+}
+
+pub fn record_tricount_import<T>(result: &Result<T, ApiError>) {
+    let outcome = match result {
+        Ok(_) => "success",
+        Err(e) => e.code.as_str(),
+    };
+    TRICOUNT_IMPORTS.inc(&[outcome]);
+}
+
+/// Every metric, in the Prometheus text format.
+pub async fn render(state: &AppState) -> String {
+    let mut out = String::new();
+    HTTP_REQUESTS.render(&mut out);
+    HTTP_REQUEST_DURATION.render(&mut out);
+    LOGIN_FAILURES.render(&mut out);
+    ACCOUNT_LOCKOUTS.render(&mut out);
+    TRICOUNT_IMPORTS.render(&mut out);
+
+    let size = state.db.size() as f64;
+    let idle = state.db.num_idle() as f64;
+    render_gauge(
+        &mut out,
+        "counted_db_pool_connections",
+        "Open database connections, in use or idle.",
+        &["state"],
+        &[(&["in_use"], size - idle), (&["idle"], idle)],
+    );
+    render_gauge(
+        &mut out,
+        "counted_db_pool_max_connections",
+        "Size limit of the database pool.",
+        &[],
+        &[(&[], state.db.max_connections() as f64)],
+    );
+
+    let sse_clients = crate::sse::BROADCASTER.client_count().await as f64;
+    render_gauge(
+        &mut out,
+        "counted_sse_clients",
+        "Connected server-sent event clients, by stream.",
+        &["stream"],
+        &[(&["events"], sse_clients), (&["project_events"], crate::sse::follower_count() as f64)],
+    );
+    out
+}
+
+/// `GET /metrics`
+pub async fn metrics_handler(State(state): State<AppState>) -> Response {
+    let content_type = HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8");
+    ([(header::CONTENT_TYPE, content_type)], render(&state).await).into_response()
+}
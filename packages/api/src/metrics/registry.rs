@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Latency buckets, in seconds.
+pub const DURATION_BUCKETS: &[f64] =
+    &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// A counter per combination of label values.
+pub struct CounterVec {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+impl CounterVec {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+    ) -> Self {
+        Self { name, help, labels, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn inc(&self, label_values: &[&str]) {
+        debug_assert_eq!(label_values.len(), self.labels.len());
+        let key = label_values.iter().map(|v| v.to_string()).collect();
+        *self.values.lock().unwrap().entry(key).or_default() += 1;
+    }
+
+    pub fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "counter");
+        let values = self.values.lock().unwrap();
+        if values.is_empty() && self.labels.is_empty() {
+            let _ = writeln!(out, "{} 0", self.name);
+        }
+        for (label_values, value) in values.iter() {
+            let _ =
+                writeln!(out, "{}{} {}", self.name, labels(self.labels, label_values, None), value);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// A histogram per combination of label values.
+pub struct HistogramVec {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    bounds: &'static [f64],
+    values: Mutex<BTreeMap<Vec<String>, Histogram>>,
+}
+
+impl HistogramVec {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+        bounds: &'static [f64],
+    ) -> Self {
+        Self { name, help, labels, bounds, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn observe(&self, label_values: &[&str], value: f64) {
+        debug_assert_eq!(label_values.len(), self.labels.len());
+        let key = label_values.iter().map(|v| v.to_string()).collect();
+        let mut values = self.values.lock().unwrap();
+        let histogram = values.entry(key).or_default();
+        histogram.buckets.resize(self.bounds.len(), 0);
+        if let Some(bucket) = self.bounds.iter().position(|bound| value <= *bound) {
+            histogram.buckets[bucket] += 1;
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    pub fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "histogram");
+        for (label_values, histogram) in self.values.lock().unwrap().iter() {
+            let mut cumulative = 0;
+            for (bound, count) in self.bounds.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                let le = bound.to_string();
+                let labels = labels(self.labels, label_values, Some(&le));
+                let _ = writeln!(out, "{}_bucket{} {}", self.name, labels, cumulative);
+            }
+            let labels_inf = labels(self.labels, label_values, Some("+Inf"));
+            let labels = labels(self.labels, label_values, None);
+            let _ = writeln!(out, "{}_bucket{} {}", self.name, labels_inf, histogram.count);
+            let _ = writeln!(out, "{}_sum{} {}", self.name, labels, histogram.sum);
+            let _ = writeln!(out, "{}_count{} {}", self.name, labels, histogram.count);
+        }
+    }
+}
+
+/// A value read when the metrics are scraped, one line per set of label values.
+pub fn render_gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label_names: &[&str],
+    samples: &[(&[&str], f64)],
+) {
+    header(out, name, help, "gauge");
+    for (label_values, value) in samples {
+        let label_values: Vec<String> = label_values.iter().map(|v| v.to_string()).collect();
+        let _ = writeln!(out, "{}{} {}", name, labels(label_names, &label_values, None), value);
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// `{name="value",...}`, with `le` last for histogram buckets, or nothing without labels.
+fn labels(names: &[&str], values: &[String], le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_render_per_label_set() {
+        let counter = CounterVec::new("logins_total", "Logins.", &["step"]);
+        counter.inc(&["password"]);
+        counter.inc(&["password"]);
+        counter.inc(&["two_factor"]);
+
+        let mut out = String::new();
+        counter.render(&mut out);
+        assert_eq!(
+            out,
+            "# HELP logins_total Logins.\n\
+             # TYPE logins_total counter\n\
+             logins_total{step=\"password\"} 2\n\
+             logins_total{step=\"two_factor\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn counters_without_labels_start_at_zero() {
+        let counter = CounterVec::new("lockouts_total", "Lockouts.", &[]);
+        let mut out = String::new();
+        counter.render(&mut out);
+        assert!(out.ends_with("lockouts_total 0\n"));
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let histogram = HistogramVec::new("duration_seconds", "Duration.", &["route"], &[0.1, 1.0]);
+        histogram.observe(&["/a"], 0.25);
+        histogram.observe(&["/a"], 0.5);
+        histogram.observe(&["/a"], 3.0);
+
+        let mut out = String::new();
+        histogram.render(&mut out);
+        assert!(out.contains("duration_seconds_bucket{route=\"/a\",le=\"0.1\"} 0\n"));
+        assert!(out.contains("duration_seconds_bucket{route=\"/a\",le=\"1\"} 2\n"));
+        assert!(out.contains("duration_seconds_bucket{route=\"/a\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("duration_seconds_sum{route=\"/a\"} 3.75\n"));
+        assert!(out.contains("duration_seconds_count{route=\"/a\"} 3\n"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(labels(&["path"], &["a\"b\\c".to_string()], None), "{path=\"a\\\"b\\\\c\"}");
+    }
+}
@@ -0,0 +1,327 @@
+//! Schema migrations embedded in the binary ([`MIGRATOR`] and [`SQLITE_MIGRATOR`]) and applied
+//! at startup.
+//!
+//! Replicas starting together take turns through a Postgres advisory lock: the first applies
+//! the pending migrations, the others find nothing left to do. A SQLite database belongs to a
+//! single server and needs no lock. A database holding migrations this binary does not ship was
+//! migrated by a newer version, and the server refuses it rather than run against a schema it
+//! does not know.
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::Context;
+use sqlx::migrate::Migrator;
+use sqlx::{PgConnection, PgPool, SqliteConnection, SqlitePool};
+
+use crate::db::{Conn, Db, MIGRATOR, SQLITE_MIGRATOR};
+
+/// Key of the advisory lock held while migrating ("counted" in ASCII).
+const MIGRATION_LOCK_KEY: i64 = 0x0063_6f75_6e74_6564;
+
+/// A row of `_sqlx_migrations`.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub checksum: Vec<u8>,
+    pub success: bool,
+}
+
+/// How the database compares with the migrations of the binary.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MigrationStatus {
+    pub applied: usize,
+    /// Shipped with the binary, not applied yet
+    pub pending: Vec<i64>,
+    /// Applied by a newer binary
+    pub unknown: Vec<i64>,
+    /// Applied, but the file has changed since
+    pub modified: Vec<i64>,
+    /// Started and never completed
+    pub failed: Vec<i64>,
+}
+
+impl MigrationStatus {
+    /// Why the server must not use this database, whatever is pending.
+    pub fn refusal(&self) -> Option<String> {
+        if !self.unknown.is_empty() {
+            Some(format!(
+                "The database schema is newer than this binary: migration(s) {} are not shipped with it. Deploy a newer version.",
+                versions(&self.unknown)
+            ))
+        } else if !self.failed.is_empty() {
+            Some(format!(
+                "Migration(s) {} failed part-way. Repair the database before starting the server.",
+                versions(&self.failed)
+            ))
+        } else if !self.modified.is_empty() {
+            Some(format!(
+                "Migration(s) {} were changed after being applied. Restore the original files.",
+                versions(&self.modified)
+            ))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending.is_empty() && self.refusal().is_none()
+    }
+}
+
+impl fmt::Display for MigrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(refusal) = self.refusal() {
+            return write!(f, "{}", refusal);
+        }
+        match self.pending.len() {
+            0 => write!(f, "{} migration(s) applied, the schema is up to date", self.applied),
+            _ => write!(
+                f,
+                "{} migration(s) applied, {} pending: {}",
+                self.applied,
+                self.pending.len(),
+                versions(&self.pending)
+            ),
+        }
+    }
+}
+
+/// Compare the migrations of `migrator` with those recorded in the database.
+pub fn status(migrator: &Migrator, applied: &[AppliedMigration]) -> MigrationStatus {
+    let shipped: HashMap<i64, &[u8]> = migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| (m.version, m.checksum.as_ref()))
+        .collect();
+    let recorded: HashMap<i64, &AppliedMigration> =
+        applied.iter().map(|m| (m.version, m)).collect();
+
+    let mut status = MigrationStatus {
+        applied: applied.iter().filter(|m| m.success).count(),
+        ..MigrationStatus::default()
+    };
+    for migration in applied {
+        match shipped.get(&migration.version) {
+            None => status.unknown.push(migration.version),
+            Some(_) if !migration.success => status.failed.push(migration.version),
+            Some(checksum) if *checksum != migration.checksum.as_slice() => {
+                status.modified.push(migration.version)
+            }
+            Some(_) => {}
+        }
+    }
+    status.pending = shipped.keys().filter(|v| !recorded.contains_key(v)).copied().collect();
+
+    for versions in
+        [&mut status.pending, &mut status.unknown, &mut status.modified, &mut status.failed]
+    {
+        versions.sort_unstable();
+    }
+    status
+}
+
+/// State of the schema of the database behind `conn`.
+pub async fn check(conn: &mut Conn) -> anyhow::Result<MigrationStatus> {
+    match conn {
+        Conn::Postgres(conn) => check_postgres(conn).await,
+        Conn::Sqlite(conn) => check_sqlite(conn).await,
+    }
+}
+
+async fn check_postgres(conn: &mut PgConnection) -> anyhow::Result<MigrationStatus> {
+    let has_table =
+        sqlx::query_scalar!(r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "exists!""#)
+            .fetch_one(&mut *conn)
+            .await
+            .context("Unable to read the applied migrations")?;
+
+    let applied = if has_table {
+        sqlx::query_as!(
+            AppliedMigration,
+            "SELECT version, checksum, success FROM _sqlx_migrations ORDER BY version"
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .context("Unable to read the applied migrations")?
+    } else {
+        Vec::new()
+    };
+
+    Ok(status(&MIGRATOR, &applied))
+}
+
+async fn check_sqlite(conn: &mut SqliteConnection) -> anyhow::Result<MigrationStatus> {
+    let has_table: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("Unable to read the applied migrations")?;
+
+    let applied = if has_table {
+        sqlx::query_as("SELECT version, checksum, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Unable to read the applied migrations")?
+    } else {
+        Vec::new()
+    };
+
+    Ok(status(&SQLITE_MIGRATOR, &applied))
+}
+
+/// Apply the pending migrations, unless the database must be refused. Returns how many ran.
+pub async fn migrate(db: &Db) -> anyhow::Result<usize> {
+    match db {
+        Db::Postgres(pool) => migrate_postgres(pool).await,
+        Db::Sqlite(pool) => migrate_sqlite(pool).await,
+    }
+}
+
+async fn migrate_postgres(pool: &PgPool) -> anyhow::Result<usize> {
+    let mut conn = pool.acquire().await.context("Unable to connect to the database")?;
+
+    sqlx::query!("SELECT pg_advisory_lock($1)", MIGRATION_LOCK_KEY)
+        .fetch_one(&mut *conn)
+        .await
+        .context("Unable to take the migration lock")?;
+
+    let result = migrate_locked(&mut conn).await;
+
+    // Closing the connection would release the lock too, but it goes back to the pool
+    if let Err(e) = sqlx::query!("SELECT pg_advisory_unlock($1)", MIGRATION_LOCK_KEY)
+        .fetch_one(&mut *conn)
+        .await
+    {
+        tracing::warn!("Failed to release the migration lock: {}", e);
+        conn.detach();
+    }
+
+    result
+}
+
+async fn migrate_locked(conn: &mut PgConnection) -> anyhow::Result<usize> {
+    let status = check_postgres(conn).await?;
+    if !should_apply(&status)? {
+        return Ok(0);
+    }
+
+    MIGRATOR.run_direct(conn).await.context("Migration failed")?;
+
+    Ok(status.pending.len())
+}
+
+async fn migrate_sqlite(pool: &SqlitePool) -> anyhow::Result<usize> {
+    let mut conn = pool.acquire().await.context("Unable to open the database")?;
+
+    let status = check_sqlite(&mut conn).await?;
+    if !should_apply(&status)? {
+        return Ok(0);
+    }
+
+    SQLITE_MIGRATOR.run_direct(&mut *conn).await.context("Migration failed")?;
+
+    Ok(status.pending.len())
+}
+
+/// Whether migrations are pending, or an error if the database must be refused.
+fn should_apply(status: &MigrationStatus) -> anyhow::Result<bool> {
+    if let Some(refusal) = status.refusal() {
+        anyhow::bail!(refusal);
+    }
+    if status.pending.is_empty() {
+        return Ok(false);
+    }
+
+    tracing::info!("Applying migration(s) {}", versions(&status.pending));
+    Ok(true)
+}
+
+fn versions(versions: &[i64]) -> String {
+    versions.iter().map(i64::to_string).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied_all() -> Vec<AppliedMigration> {
+        MIGRATOR
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .map(|m| AppliedMigration {
+                version: m.version,
+                checksum: m.checksum.to_vec(),
+                success: true,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn up_to_date_when_everything_is_applied() {
+        let applied = applied_all();
+        let status = status(&MIGRATOR, &applied);
+        assert!(status.is_up_to_date(), "{}", status);
+        assert_eq!(status.applied, applied.len());
+    }
+
+    #[test]
+    fn missing_migrations_are_pending() {
+        let mut applied = applied_all();
+        let latest = applied.pop().unwrap();
+
+        let status = status(&MIGRATOR, &applied);
+        assert_eq!(status.pending, vec![latest.version]);
+        assert_eq!(status.refusal(), None);
+
+        let fresh = super::status(&MIGRATOR, &[]);
+        assert_eq!(fresh.pending.len(), applied.len() + 1);
+        assert!(fresh.pending.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn newer_schema_is_refused() {
+        let mut applied = applied_all();
+        applied.push(AppliedMigration {
+            version: 99990101000000,
+            checksum: vec![0],
+            success: true,
+        });
+
+        let status = status(&MIGRATOR, &applied);
+        assert_eq!(status.unknown, vec![99990101000000]);
+        assert!(status.refusal().unwrap().contains("newer than this binary"));
+    }
+
+    #[test]
+    fn changed_and_failed_migrations_are_refused() {
+        let mut applied = applied_all();
+        applied[0].checksum = vec![0];
+        applied[1].success = false;
+
+        let status = status(&MIGRATOR, &applied);
+        assert_eq!(status.modified, vec![applied[0].version]);
+        assert_eq!(status.failed, vec![applied[1].version]);
+        assert!(!status.is_up_to_date());
+    }
+
+    #[tokio::test]
+    async fn sqlite_databases_are_migrated_without_a_lock() {
+        let config = crate::db::DbConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+            acquire_timeout: std::time::Duration::from_secs(5),
+            statement_cache_capacity: 100,
+        };
+        let db = crate::db::connect(&config).await.unwrap();
+
+        let shipped =
+            SQLITE_MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()).count();
+        assert_eq!(migrate(&db).await.unwrap(), shipped);
+        assert_eq!(migrate(&db).await.unwrap(), 0);
+
+        let status = check(&mut db.acquire().await.unwrap()).await.unwrap();
+        assert!(status.is_up_to_date(), "{}", status);
+        assert_eq!(status.applied, shipped);
+    }
+}
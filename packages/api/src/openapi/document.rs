@@ -0,0 +1,337 @@
+use std::sync::OnceLock;
+
+use utoipa::openapi::path::{OperationBuilder, Parameter, ParameterBuilder, ParameterIn, PathItem};
+use utoipa::openapi::request_body::RequestBodyBuilder;
+use utoipa::openapi::schema::{
+    ArrayBuilder, KnownFormat, ObjectBuilder, OneOfBuilder, Schema, SchemaFormat, Type,
+};
+use utoipa::openapi::security::{
+    ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme,
+};
+use utoipa::openapi::{
+    ComponentsBuilder, ContentBuilder, HttpMethod, InfoBuilder, OpenApi, OpenApiBuilder,
+    PathsBuilder, Ref, RefOr, Required, ResponseBuilder,
+};
+use utoipa::{PartialSchema, ToSchema};
+
+use super::routes::ROUTES;
+use crate::auth::sessions::COOKIE_NAME;
+use crate::idempotency;
+
+const DESCRIPTION: &str = "\
+Every endpoint is a server function. Path parameters go in the URL, the request body (if any) \
+is JSON, and the response is the JSON encoding of the returned value — `null` when there is none.
+
+Errors come back with a non-2xx status and an `Error` body, whose `data.code` tells the cause \
+apart: `unauthorized` (401), `forbidden` (403), `not_found` (404), `conflict` (409), `locked` (423), \
+`validation` (422), `rate_limited` (429) or `internal` (500); clients report a server they cannot \
+reach as `unavailable`. Authentication is the session cookie \
+set by `login`, or a personal API token sent as `Authorization: Bearer cnt_…`; an invalid token \
+gets `401`. Edits carry the `version` they are based on and get a `conflict` with the current \
+resource in `data.current` when it has been edited since. A batch is applied all or none; its \
+errors give the index of the failing operation in `data.operation`. Create endpoints accept an \
+`Idempotency-Key` header: the same request sent again with the key gets the first response. \
+Closed projects only take transfers and archived ones no change at all, both refused with a \
+`conflict`. Endpoints that accept anonymous callers only reach projects without an owner.";
+
+/// Component schemas gathered while describing the routes.
+pub type Schemas = Vec<(String, RefOr<Schema>)>;
+/// Schema of a body, registering the components it refers to.
+pub type SchemaFn = fn(&mut Schemas) -> RefOr<Schema>;
+
+/// Credentials a route looks at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Auth {
+    /// None, e.g. signing in or a public share link
+    Public,
+    /// Session or API token, or anonymous for projects without an owner
+    Optional,
+    /// Session or API token
+    Account,
+    /// Session only: managing the account itself
+    Session,
+}
+
+/// JSON body of a request or response.
+#[derive(Clone, Copy)]
+pub enum Body {
+    Empty,
+    /// Any JSON value
+    Any,
+    One(SchemaFn),
+    List(SchemaFn),
+    /// The value or `null`
+    Optional(SchemaFn),
+    /// Server-sent events, each carrying one value as JSON
+    Events(SchemaFn),
+}
+
+/// Reference to `T`, added to the components with the types it uses.
+pub fn component<T: ToSchema>(schemas: &mut Schemas) -> RefOr<Schema> {
+    schemas.push((T::name().into_owned(), T::schema()));
+    T::schemas(schemas);
+    RefOr::Ref(Ref::from_schema_name(T::name()))
+}
+
+/// A UUID, written in place.
+pub fn uuid(_: &mut Schemas) -> RefOr<Schema> {
+    ObjectBuilder::new()
+        .schema_type(Type::String)
+        .format(Some(SchemaFormat::KnownFormat(KnownFormat::Uuid)))
+        .into()
+}
+
+impl Body {
+    fn schema(self, schemas: &mut Schemas) -> Option<RefOr<Schema>> {
+        match self {
+            Body::Empty => None,
+            Body::Any => Some(ObjectBuilder::new().into()),
+            Body::One(schema) | Body::Events(schema) => Some(schema(schemas)),
+            Body::List(schema) => Some(ArrayBuilder::new().items(schema(schemas)).into()),
+            Body::Optional(schema) => Some(
+                OneOfBuilder::new()
+                    .item(schema(schemas))
+                    .item(ObjectBuilder::new().schema_type(Type::Null))
+                    .into(),
+            ),
+        }
+    }
+}
+
+impl Auth {
+    fn security(self) -> Option<Vec<SecurityRequirement>> {
+        let session = || SecurityRequirement::new("session", Vec::<String>::new());
+        let token = || SecurityRequirement::new("apiToken", Vec::<String>::new());
+        match self {
+            Auth::Public => None,
+            Auth::Optional => Some(vec![SecurityRequirement::default(), session(), token()]),
+            Auth::Account => Some(vec![session(), token()]),
+            Auth::Session => Some(vec![session()]),
+        }
+    }
+}
+
+/// Schema of the `{name}` path parameter. Ids are UUIDs, except the integer ids of
+/// participants and expenses; tokens are opaque strings.
+fn path_parameter_schema(name: &str) -> RefOr<Schema> {
+    match name {
+        "user_id" | "expense_id" => i32::schema(),
+        "token" => String::schema(),
+        _ => uuid(&mut Schemas::new()),
+    }
+}
+
+fn path_parameters(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+}
+
+/// The optional `Idempotency-Key` header of the create endpoints.
+fn idempotency_key_parameter() -> Parameter {
+    ParameterBuilder::new()
+        .name("Idempotency-Key")
+        .parameter_in(ParameterIn::Header)
+        .required(Required::False)
+        .description(Some(
+            "Unique to the operation: sent again with the same request, it gets the first response",
+        ))
+        .schema(Some(
+            ObjectBuilder::new()
+                .schema_type(Type::String)
+                .max_length(Some(idempotency::MAX_KEY_LENGTH)),
+        ))
+        .build()
+}
+
+fn json<S: Into<RefOr<Schema>>>(schema: S) -> utoipa::openapi::Content {
+    ContentBuilder::new().schema(Some(schema)).build()
+}
+
+/// Body of every error response.
+fn error_schema(schemas: &mut Schemas) -> Schema {
+    let data = ObjectBuilder::new()
+        .property("code", component::<shared::ErrorCode>(schemas))
+        .property("message", ObjectBuilder::new().schema_type(Type::String))
+        .property(
+            "current",
+            ObjectBuilder::new().description(Some(
+                "For an edit based on an outdated version: the resource as it is now",
+            )),
+        )
+        .property(
+            "operation",
+            ObjectBuilder::new()
+                .schema_type(Type::Integer)
+                .description(Some("For a batch: index of the operation that failed")),
+        )
+        .required("code")
+        .required("message");
+    ObjectBuilder::new()
+        .property("message", ObjectBuilder::new().schema_type(Type::String))
+        .property("code", ObjectBuilder::new().schema_type(Type::Integer))
+        .property("data", data)
+        .required("message")
+        .required("code")
+        .required("data")
+        .into()
+}
+
+/// Build the document from [`ROUTES`].
+pub fn build() -> OpenApi {
+    let mut schemas = Schemas::new();
+    let mut paths = PathsBuilder::new();
+
+    for route in ROUTES {
+        let mut parameters: Vec<_> = path_parameters(route.path)
+            .map(|name| {
+                ParameterBuilder::new()
+                    .name(name)
+                    .parameter_in(ParameterIn::Path)
+                    .required(Required::True)
+                    .schema(Some(path_parameter_schema(name)))
+                    .build()
+            })
+            .collect();
+        if route.method == HttpMethod::Post && idempotency::accepts_key(route.path) {
+            parameters.push(idempotency_key_parameter());
+        }
+
+        let request_body = route.request.schema(&mut schemas).map(|schema| {
+            RequestBodyBuilder::new()
+                .content("application/json", json(schema))
+                .required(Some(Required::True))
+                .build()
+        });
+
+        // Functions returning nothing answer `null`
+        let response_schema = route
+            .response
+            .schema(&mut schemas)
+            .unwrap_or_else(|| ObjectBuilder::new().schema_type(Type::Null).into());
+        let response_type = match route.response {
+            Body::Events(_) => "text/event-stream",
+            _ => "application/json",
+        };
+        let success = ResponseBuilder::new()
+            .description("Success")
+            .content(response_type, json(response_schema));
+        let error = ResponseBuilder::new()
+            .description("Error")
+            .content("application/json", json(Ref::from_schema_name("Error")));
+
+        let operation = OperationBuilder::new()
+            .operation_id(Some(route.operation_id))
+            .tag(route.tag)
+            .summary(Some(route.summary))
+            .parameters((!parameters.is_empty()).then_some(parameters))
+            .request_body(request_body)
+            .response("200", success)
+            .response("default", error)
+            .securities(route.auth.security());
+
+        paths = paths.path(route.path, PathItem::new(route.method.clone(), operation));
+    }
+
+    let error = error_schema(&mut schemas);
+    let components = ComponentsBuilder::new()
+        .schemas_from_iter(schemas)
+        .schema("Error", error)
+        .security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(COOKIE_NAME))),
+        )
+        .security_scheme(
+            "apiToken",
+            SecurityScheme::Http(
+                HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("cnt_…").build(),
+            ),
+        )
+        .build();
+
+    OpenApiBuilder::new()
+        .info(
+            InfoBuilder::new()
+                .title("Counted API")
+                .version(env!("CARGO_PKG_VERSION"))
+                .description(Some(DESCRIPTION)),
+        )
+        .paths(paths)
+        .components(Some(components))
+        .build()
+}
+
+/// The document as served, built once.
+pub fn document() -> &'static serde_json::Value {
+    static DOCUMENT: OnceLock<serde_json::Value> = OnceLock::new();
+    DOCUMENT.get_or_init(|| {
+        serde_json::to_value(build()).expect("The OpenAPI document is serializable")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+
+    fn committed_document_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../docs/openapi.json")
+    }
+
+    #[test]
+    fn path_parameters_are_extracted() {
+        let names: Vec<_> =
+            path_parameters("/api/v1/projects/{project_id}/members/{account_id}").collect();
+        assert_eq!(names, ["project_id", "account_id"]);
+        assert_eq!(path_parameters("/api/v1/projects").count(), 0);
+    }
+
+    #[test]
+    fn every_server_function_is_documented() {
+        // `#[server]` functions without a route get a generated path and are not part of the API
+        let registered: BTreeSet<(String, String)> = dioxus::server::ServerFunction::collect()
+            .into_iter()
+            .filter(|f| f.path().starts_with("/api/v1/"))
+            .map(|f| (f.method().to_string(), f.path().to_string()))
+            .collect();
+        let documented: BTreeSet<(String, String)> = ROUTES
+            .iter()
+            .map(|r| {
+                (
+                    serde_json::to_value(&r.method).unwrap().as_str().unwrap().to_uppercase(),
+                    r.path.to_string(),
+                )
+            })
+            .collect();
+
+        let missing: Vec<_> = registered.difference(&documented).collect();
+        let stale: Vec<_> = documented.difference(&registered).collect();
+        assert!(
+            missing.is_empty() && stale.is_empty(),
+            "Update packages/api/src/openapi/routes.rs.\nUndocumented: {:?}\nNo longer served: {:?}",
+            missing,
+            stale
+        );
+        assert_eq!(documented.len(), ROUTES.len(), "A route is documented twice");
+    }
+
+    /// Fails when a route or a DTO changes without `docs/openapi.json` being regenerated with
+    /// `UPDATE_OPENAPI=1 cargo test -p api --features server,dioxus/server,shared/server openapi`.
+    #[test]
+    fn committed_document_is_up_to_date() {
+        let generated = serde_json::to_string_pretty(document()).unwrap() + "\n";
+        let path = committed_document_path();
+
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(&path, &generated).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "docs/openapi.json is out of date. Regenerate it with \
+             `UPDATE_OPENAPI=1 cargo test -p api --features server,dioxus/server,shared/server openapi` \
+             and review the diff."
+        );
+    }
+}
@@ -0,0 +1,13 @@
+//! OpenAPI 3 description of the `/api/v1` server functions, served at `/api/v1/openapi.json`.
+//!
+//! Routes are listed by hand in [`routes`] with the `shared` types they take and return; the
+//! schemas come from those types. Tests fail when a server function is missing from the list or
+//! when `docs/openapi.json` no longer matches what the code generates.
+#[cfg(feature = "server")]
+mod document;
+pub mod openapi_controller;
+#[cfg(feature = "server")]
+mod routes;
+
+#[cfg(feature = "server")]
+pub use document::{build, document};
@@ -0,0 +1,8 @@
+use dioxus::prelude::*;
+
+use crate::error::ApiError;
+
+#[get("/api/v1/openapi.json")]
+pub async fn get_openapi_spec() -> Result<serde_json::Value, ApiError> {
+    Ok(super::document().clone())
+}
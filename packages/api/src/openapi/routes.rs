@@ -0,0 +1,185 @@
+//! Every route of the REST surface, with its request and response bodies.
+use shared::*;
+use utoipa::openapi::HttpMethod::{self, Delete, Get, Post, Put};
+
+use super::document::{
+    component, uuid, Auth,
+    Body::{self, Empty, Events, List, One},
+};
+use crate::tricount::tricount_controller::{TricountImportRequest, TricountImportResponse};
+use shared::sse::ProjectEvent;
+
+/// A server function as seen over HTTP.
+pub struct Route {
+    pub method: HttpMethod,
+    pub path: &'static str,
+    /// Name of the server function, used as the operation id
+    pub operation_id: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+    pub auth: Auth,
+    pub request: Body,
+    pub response: Body,
+}
+
+/// `Method "path" server_fn ["tag", Auth] request => response, "summary";`
+macro_rules! routes {
+    ($($method:ident $path:literal $operation_id:ident [$tag:literal, $auth:ident] $request:expr => $response:expr, $summary:literal;)*) => {
+        &[$(Route {
+            method: $method,
+            path: $path,
+            operation_id: stringify!($operation_id),
+            tag: $tag,
+            summary: $summary,
+            auth: Auth::$auth,
+            request: $request,
+            response: $response,
+        },)*]
+    };
+}
+
+pub const ROUTES: &[Route] = routes![
+    // Projects
+    Get "/api/v1/projects" get_projects ["projects", Optional] Empty => List(component::<ProjectDto>),
+        "Projects of the caller, or projects without owner for anonymous callers";
+    Post "/api/v1/projects" add_project ["projects", Optional] One(component::<CreatableProject>) => One(component::<ProjectDto>),
+        "Create a project, owned by the caller when signed in";
+    Put "/api/v1/projects" update_project_by_id ["projects", Optional] One(component::<EditableProject>) => One(component::<ProjectDto>),
+        "Update a project";
+    Post "/api/v1/projects/batch" get_projects_by_ids ["projects", Optional] One(component::<BatchProject>) => List(component::<ProjectDto>),
+        "Projects by id";
+    Get "/api/v1/projects/{project_id}" get_project ["projects", Optional] Empty => One(component::<ProjectDto>),
+        "Get a project";
+    Delete "/api/v1/projects/{project_id}" delete_project_by_id ["projects", Optional] Empty => Empty,
+        "Delete a project and everything in it";
+    Get "/api/v1/projects/{project_id}/settlement" get_project_settlement ["projects", Optional] Empty => One(component::<ProjectSettlement>),
+        "Balances and reimbursements of a project when it was last closed";
+
+    // Users (participants of a project)
+    Get "/api/v1/users" get_users ["users", Optional] Empty => List(component::<User>),
+        "Every participant";
+    Post "/api/v1/users" add_user ["users", Optional] One(component::<CreatableUserBatch>) => List(component::<User>),
+        "Add one or several participants";
+    Delete "/api/v1/users/{user_id}" delete_user ["users", Optional] Empty => Empty,
+        "Remove a participant";
+    Get "/api/v1/projects/{project_id}/users" get_users_by_project_id ["users", Optional] Empty => List(component::<User>),
+        "Participants of a project, with their balance";
+
+    // Expenses
+    Post "/api/v1/expenses" add_expense ["expenses", Optional] One(component::<CreatableExpense>) => One(component::<Expense>),
+        "Add an expense with its payers and debtors";
+    Put "/api/v1/expenses" edit_expense ["expenses", Optional] One(component::<EditableExpense>) => One(component::<Expense>),
+        "Replace an expense and its payments";
+    Get "/api/v1/expenses/{expense_id}" get_expense_by_id ["expenses", Optional] Empty => One(component::<Expense>),
+        "Get an expense";
+    Delete "/api/v1/expenses/{expense_id}" delete_expense ["expenses", Optional] Empty => Empty,
+        "Delete an expense";
+    Get "/api/v1/projects/{project_id}/expenses" get_expenses_by_project_id ["expenses", Optional] Empty => List(component::<Expense>),
+        "Expenses of a project";
+    Post "/api/v1/projects/{project_id}/expenses/batch" batch_expenses ["expenses", Optional] List(component::<ExpenseOperation>) => List(component::<ExpenseOperationResult>),
+        "Create, replace and delete expenses of a project in one transaction";
+
+    // Payments and balances
+    Get "/api/v1/expenses/{expense_id}/payments" get_payments_by_expense_id ["payments", Optional] Empty => List(component::<Payment>),
+        "Payments of an expense";
+    Get "/api/v1/projects/{project_id}/payments" get_payments_by_project_id ["payments", Optional] Empty => List(component::<Payment>),
+        "Payments of a project";
+    Get "/api/v1/projects/{project_id}/expenses/summary" get_summary_by_project_id ["payments", Optional] Empty => One(component::<UserSummary>),
+        "Balances and reimbursement suggestions";
+
+    // Live updates
+    Get "/api/v1/projects/{project_id}/events" get_project_events ["events", Optional] Empty => Events(component::<ProjectEvent>),
+        "Follow the changes to a project as server-sent events";
+
+    // Members, invites and share links
+    Get "/api/v1/projects/{project_id}/members" get_project_members ["members", Optional] Empty => List(component::<ProjectMember>),
+        "Accounts with a role on a project";
+    Put "/api/v1/projects/{project_id}/members/{account_id}" update_project_member ["members", Optional] One(component::<UpdateProjectMember>) => Empty,
+        "Change the role of a member";
+    Delete "/api/v1/projects/{project_id}/members/{account_id}" remove_project_member ["members", Optional] Empty => Empty,
+        "Remove a member";
+    Get "/api/v1/projects/{project_id}/invites" get_project_invites ["members", Optional] Empty => List(component::<ProjectInvite>),
+        "Invite links of a project";
+    Post "/api/v1/projects/{project_id}/invites" create_project_invite ["members", Optional] One(component::<CreatableProjectInvite>) => One(component::<CreatedProjectInvite>),
+        "Create an invite link; the token is returned once";
+    Delete "/api/v1/projects/{project_id}/invites/{invite_id}" revoke_project_invite ["members", Optional] Empty => Empty,
+        "Revoke an invite link";
+    Post "/api/v1/invites/{token}/redeem" redeem_invite ["members", Optional] Empty => One(component::<InviteRedemption>),
+        "Join a project through an invite link";
+    Get "/api/v1/projects/{project_id}/share-links" get_share_links ["share-links", Optional] Empty => List(component::<ShareLink>),
+        "Public share links of a project";
+    Post "/api/v1/projects/{project_id}/share-links" create_share_link ["share-links", Optional] Empty => One(component::<CreatedShareLink>),
+        "Create a public share link; the token is returned once";
+    Delete "/api/v1/projects/{project_id}/share-links/{link_id}" revoke_share_link ["share-links", Optional] Empty => Empty,
+        "Revoke a public share link";
+    Get "/api/v1/public/{token}" get_public_project ["share-links", Public] Empty => One(component::<PublicProjectView>),
+        "Read-only view of a shared project";
+
+    // Projects of the account
+    Get "/api/v1/account/projects" get_account_projects ["account", Account] Empty => List(component::<AccountProject>),
+        "Projects the account is a member of";
+    Post "/api/v1/account/projects" upsert_account_project ["account", Account] One(component::<UpsertAccountProject>) => Empty,
+        "Remember a project and the participant claimed in it";
+    Post "/api/v1/account/projects/batch" batch_upsert_account_projects ["account", Account] List(component::<UpsertAccountProject>) => List(uuid),
+        "Remember several projects; returns the accepted ids";
+    Delete "/api/v1/account/projects/{project_id}" delete_account_project ["account", Account] Empty => Empty,
+        "Forget a project";
+
+    // Authentication
+    Post "/api/v1/auth/register" register ["auth", Public] One(component::<RegisterPayload>) => One(component::<Account>),
+        "Create an account and sign in";
+    Post "/api/v1/auth/login" login ["auth", Public] One(component::<LoginPayload>) => One(component::<LoginResponse>),
+        "Sign in with a password; may ask for a second factor";
+    Post "/api/v1/auth/login/two-factor" login_two_factor ["auth", Public] One(component::<TwoFactorLoginPayload>) => One(component::<Account>),
+        "Finish signing in with a TOTP or recovery code";
+    Post "/api/v1/auth/logout" logout ["auth", Session] Empty => Empty,
+        "Revoke the current session";
+    Post "/api/v1/auth/logout-all" logout_everywhere ["auth", Session] Empty => Empty,
+        "Revoke every session of the account";
+    Get "/api/v1/auth/me" me ["auth", Optional] Empty => Body::Optional(component::<Account>),
+        "The signed-in account, or null";
+    Get "/api/v1/auth/sessions" list_sessions ["auth", Session] Empty => List(component::<SessionInfo>),
+        "Active sessions of the account";
+    Delete "/api/v1/auth/sessions/{session_id}" revoke_session ["auth", Session] Empty => Empty,
+        "Revoke a session";
+    Post "/api/v1/auth/verify-email/request" request_email_verification ["auth", Session] Empty => Empty,
+        "Email a new verification link";
+    Post "/api/v1/auth/verify-email" verify_email ["auth", Public] One(component::<VerifyEmailPayload>) => One(component::<Account>),
+        "Confirm the email address with an emailed token";
+    Post "/api/v1/auth/password-reset/request" request_password_reset ["auth", Public] One(component::<PasswordResetRequestPayload>) => Empty,
+        "Email a password reset link";
+    Post "/api/v1/auth/password-reset" reset_password ["auth", Public] One(component::<PasswordResetPayload>) => Empty,
+        "Set a new password with an emailed token";
+    Post "/api/v1/auth/two-factor/setup" start_two_factor_setup ["auth", Session] Empty => One(component::<TwoFactorSetup>),
+        "Start enrolling a TOTP authenticator";
+    Post "/api/v1/auth/two-factor/confirm" confirm_two_factor_setup ["auth", Session] One(component::<TwoFactorCodePayload>) => One(component::<RecoveryCodes>),
+        "Turn two-factor authentication on";
+    Post "/api/v1/auth/two-factor/disable" disable_two_factor ["auth", Session] One(component::<TwoFactorCodePayload>) => Empty,
+        "Turn two-factor authentication off";
+    Post "/api/v1/auth/two-factor/recovery-codes" regenerate_recovery_codes ["auth", Session] One(component::<TwoFactorCodePayload>) => One(component::<RecoveryCodes>),
+        "Replace the recovery codes";
+    Get "/api/v1/auth/oidc/provider" get_oidc_provider ["auth", Public] Empty => Body::Optional(component::<OidcProvider>),
+        "The OpenID Connect provider, or null when disabled";
+    Post "/api/v1/auth/oidc/start" start_oidc_login ["auth", Public] One(component::<OidcStartPayload>) => One(component::<OidcAuthorization>),
+        "Start signing in with the OpenID Connect provider; linking needs a session";
+    Post "/api/v1/auth/oidc/callback" complete_oidc_login ["auth", Public] One(component::<OidcCallbackPayload>) => One(component::<LoginResponse>),
+        "Finish signing in with the code from the provider";
+    Get "/api/v1/auth/oidc/identities" list_linked_identities ["auth", Session] Empty => List(component::<LinkedIdentity>),
+        "External identities linked to the account";
+    Delete "/api/v1/auth/oidc/identities/{identity_id}" unlink_identity ["auth", Session] Empty => Empty,
+        "Unlink an external identity";
+    Get "/api/v1/auth/api-tokens" list_api_tokens ["auth", Session] Empty => List(component::<ApiTokenInfo>),
+        "Personal API tokens of the account";
+    Post "/api/v1/auth/api-tokens" create_api_token ["auth", Session] One(component::<CreateApiTokenPayload>) => One(component::<CreatedApiToken>),
+        "Create a personal API token; the token is returned once";
+    Delete "/api/v1/auth/api-tokens/{token_id}" revoke_api_token ["auth", Session] Empty => Empty,
+        "Revoke a personal API token";
+
+    // Import
+    Post "/api/v1/import/tricount" import_tricount ["import", Optional] One(component::<TricountImportRequest>) => One(component::<TricountImportResponse>),
+        "Copy a Tricount into a new project";
+
+    // Meta
+    Get "/api/v1/openapi.json" get_openapi_spec ["meta", Public] Empty => Body::Any,
+        "This document";
+];
@@ -274,9 +274,7 @@ fn resolve_equally_opposed_balances(
             .iter()
             .map(|(id, b)| (*id, b.remaining_amount))
             .collect();
-        entries.sort_by(|(u1, a1), (u2, a2)| {
-            f64::total_cmp(&a2.abs(), &a1.abs()).then(u1.cmp(u2))
-        });
+        entries.sort_by(|(u1, a1), (u2, a2)| f64::total_cmp(&a2.abs(), &a1.abs()).then(u1.cmp(u2)));
         entries.into_iter().map(|(id, _)| id).collect()
     };
 
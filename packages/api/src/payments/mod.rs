@@ -1,3 +1,5 @@
 pub mod balances;
 pub mod payments_controller;
 pub mod payments_repository;
+#[cfg(feature = "server")]
+mod payments_repository_sqlite;
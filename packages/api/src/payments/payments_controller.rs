@@ -1,64 +1,77 @@
 use dioxus::prelude::*;
 use uuid::Uuid;
 
+use crate::error::ApiError;
+
 #[cfg(feature = "server")]
 use crate::db::get_db;
-use crate::expenses::expenses_repository;
 use crate::payments::payments_repository;
+#[cfg(feature = "server")]
+use crate::projects::project_access::{authorize, ProjectAction};
 use shared::{Payment, UserSummary};
 
 #[get("/api/v1/expenses/{expense_id}/payments")]
-pub async fn get_payments_by_expense_id(expense_id: i32) -> Result<Vec<Payment>, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn get_payments_by_expense_id(expense_id: i32) -> Result<Vec<Payment>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    let payments = payments_repository::get_payments_by_expense_id(&mut *tx, expense_id).await?;
+    let expense = tx.get_expense_by_id(expense_id).await?;
+    authorize(&mut *tx, expense.project_id, ProjectAction::Read).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let payments = tx.get_payments_by_expense_id(expense_id).await?;
+
+    tx.commit().await?;
 
     Ok(payments)
 }
 
 #[server()]
-pub async fn get_payments_by_user_id(user_id: i32) -> Result<Vec<Payment>, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn get_payments_by_user_id(user_id: i32) -> Result<Vec<Payment>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    let payments = payments_repository::get_payments_by_user_id(&mut *tx, user_id).await?;
+    for project_id in tx.get_project_ids_by_user_id(user_id).await? {
+        authorize(&mut *tx, project_id, ProjectAction::Read).await?;
+    }
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let payments = tx.get_payments_by_user_id(user_id).await?;
+
+    tx.commit().await?;
 
     Ok(payments)
 }
 
 #[get("/api/v1/projects/{project_id}/payments")]
-pub async fn get_payments_by_project_id(project_id: Uuid) -> Result<Vec<Payment>, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn get_payments_by_project_id(project_id: Uuid) -> Result<Vec<Payment>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::Read).await?;
 
-    let expenses = expenses_repository::get_expenses_by_project_id(&mut *tx, project_id).await?;
+    let expenses = tx.get_expenses_by_project_id(project_id).await?;
     let expense_ids: Vec<i32> = expenses.iter().map(|e| e.id).collect();
 
     if expense_ids.is_empty() {
         return Ok(vec![]);
     }
 
-    let payments =
-        payments_repository::get_payments_by_expense_ids(&mut *tx, expense_ids).await?;
+    let payments = tx.get_payments_by_expense_ids(expense_ids).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(payments)
 }
 
 #[get("/api/v1/projects/{project_id}/expenses/summary")]
-pub async fn get_summary_by_project_id(project_id: Uuid) -> Result<UserSummary, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn get_summary_by_project_id(project_id: Uuid) -> Result<UserSummary, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::Read).await?;
 
     let summary = payments_repository::get_summary_by_project_id(&mut *tx, project_id).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(summary)
 }
@@ -1,154 +1,181 @@
-use anyhow::Context;
 use dioxus::prelude::*;
 use std::collections::HashMap;
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
-use crate::{
-    expenses::expenses_repository::get_expenses_by_project_id,
-    payments::balances::get_reimbursement_suggestions,
-};
+use crate::db::Store;
+#[cfg(feature = "server")]
+use crate::error::{db_error, ApiError};
+
+#[cfg(feature = "server")]
+use crate::payments::balances::get_reimbursement_suggestions;
 use crate::utils::round_currency;
 use shared::{Expense, ExpenseType, NewPayment, Payment};
 
+#[cfg(feature = "server")]
+use futures::future::BoxFuture;
 #[cfg(feature = "server")]
 use sqlx::PgConnection;
 
 #[cfg(feature = "server")]
-pub async fn add_payments(
-    executor: &mut PgConnection,
-    creatable_payments: Vec<NewPayment>,
-) -> Result<(), ServerFnError> {
-    let expense_ids: Vec<i32> = creatable_payments.iter().map(|p| p.expense_id).collect();
-    let user_ids: Vec<i32> = creatable_payments.iter().map(|p| p.user_id).collect();
-    let is_debts: Vec<bool> = creatable_payments.iter().map(|p| p.is_debt).collect();
-    let amounts: Vec<f64> = creatable_payments.iter().map(|p| p.amount).collect();
-
-    sqlx::query_scalar::<_, i32>(
-        r"
-        INSERT INTO payments
-         (
-            expense_id,
-            user_id,
-            is_debt,
-            amount
-        ) SELECT * FROM UNNEST(
-            $1::INT4[],
-            $2::INT4[],
-            $3::BOOL[],
-            $4::FLOAT8[]
-        ) RETURNING id",
-    )
-    .bind(&expense_ids)
-    .bind(&user_ids)
-    .bind(&is_debts)
-    .bind(&amounts)
-    .fetch_all(&mut *executor)
-    .await
-    .context("Failed add payments")
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(())
-}
+pub trait PaymentsRepository {
+    fn add_payments(
+        &mut self,
+        creatable_payments: Vec<NewPayment>,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn get_payments_by_expense_id(
+        &mut self,
+        expense_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>>;
 
-#[cfg(feature = "server")]
-pub async fn get_payments_by_expense_id(
-    executor: &mut PgConnection,
-    expense_id: i32,
-) -> Result<Vec<Payment>, ServerFnError> {
-    let payments: Vec<Payment> = sqlx::query_as!(
-        Payment,
-        "SELECT id, expense_id, user_id, is_debt, amount, created_at \
-        FROM payments \
-        WHERE expense_id = $1",
-        expense_id
-    )
-    .fetch_all(&mut *executor)
-    .await
-    .context("Failed get payments")
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(payments)
-}
+    fn get_payments_by_user_id(
+        &mut self,
+        user_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>>;
 
-#[cfg(feature = "server")]
-pub async fn get_payments_by_user_id(
-    executor: &mut PgConnection,
-    user_id: i32,
-) -> Result<Vec<Payment>, ServerFnError> {
-    let payments: Vec<Payment> = sqlx::query_as!(
-        Payment,
-        "SELECT id, expense_id, user_id, is_debt, amount, created_at \
-        FROM payments \
-        WHERE user_id = $1",
-        user_id
-    )
-    .fetch_all(&mut *executor)
-    .await
-    .context("Failed get payments")
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(payments)
-}
+    fn get_payments_by_expense_ids(
+        &mut self,
+        expense_ids: Vec<i32>,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>>;
 
-#[cfg(feature = "server")]
-pub async fn get_payments_by_expense_ids(
-    executor: &mut PgConnection,
-    expense_ids: Vec<i32>,
-) -> Result<Vec<Payment>, ServerFnError> {
-    let payments: Vec<Payment> = sqlx::query_as!(
-        Payment,
-        "SELECT id, expense_id, user_id, is_debt, amount, created_at \
-        FROM payments \
-        WHERE expense_id = ANY($1)",
-        &expense_ids[..] // a bug of the parameter typechecking code requires all array parameters to be slices
-    )
-    .fetch_all(&mut *executor)
-    .await
-    .context("Failed get payments")
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(payments)
+    fn delete_payments_by_expense_id(
+        &mut self,
+        expense_id: i32,
+    ) -> BoxFuture<'_, Result<(), ApiError>>;
 }
 
 #[cfg(feature = "server")]
-pub async fn delete_payments_by_expense_id(
-    executor: &mut PgConnection,
-    expense_id: i32,
-) -> Result<(), ServerFnError> {
-    sqlx::query!("DELETE FROM payments WHERE expense_id = $1", expense_id)
-        .execute(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(())
+impl PaymentsRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn add_payments(
+        &mut self,
+        creatable_payments: Vec<NewPayment>,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            let expense_ids: Vec<i32> = creatable_payments.iter().map(|p| p.expense_id).collect();
+            let user_ids: Vec<i32> = creatable_payments.iter().map(|p| p.user_id).collect();
+            let is_debts: Vec<bool> = creatable_payments.iter().map(|p| p.is_debt).collect();
+            let amounts: Vec<f64> = creatable_payments.iter().map(|p| p.amount).collect();
+
+            sqlx::query_scalar::<_, i32>(
+                r"
+                INSERT INTO payments
+                 (
+                    expense_id,
+                    user_id,
+                    is_debt,
+                    amount
+                ) SELECT * FROM UNNEST(
+                    $1::INT4[],
+                    $2::INT4[],
+                    $3::BOOL[],
+                    $4::FLOAT8[]
+                ) RETURNING id",
+            )
+            .bind(&expense_ids)
+            .bind(&user_ids)
+            .bind(&is_debts)
+            .bind(&amounts)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed add payments"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_payments_by_expense_id(
+        &mut self,
+        expense_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>> {
+        Box::pin(async move {
+            let payments: Vec<Payment> = sqlx::query_as!(
+                Payment,
+                "SELECT id, expense_id, user_id, is_debt, amount, created_at \
+                FROM payments \
+                WHERE expense_id = $1",
+                expense_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed get payments"))?;
+
+            Ok(payments)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_payments_by_user_id(
+        &mut self,
+        user_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>> {
+        Box::pin(async move {
+            let payments: Vec<Payment> = sqlx::query_as!(
+                Payment,
+                "SELECT id, expense_id, user_id, is_debt, amount, created_at \
+                FROM payments \
+                WHERE user_id = $1",
+                user_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed get payments"))?;
+
+            Ok(payments)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_payments_by_expense_ids(
+        &mut self,
+        expense_ids: Vec<i32>,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>> {
+        Box::pin(async move {
+            let payments: Vec<Payment> = sqlx::query_as!(
+                Payment,
+                "SELECT id, expense_id, user_id, is_debt, amount, created_at \
+                FROM payments \
+                WHERE expense_id = ANY($1)",
+                &expense_ids[..] // a bug of the parameter typechecking code requires all array parameters to be slices
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed get payments"))?;
+
+            Ok(payments)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_payments_by_expense_id(
+        &mut self,
+        expense_id: i32,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM payments WHERE expense_id = $1", expense_id)
+                .execute(&mut *self)
+                .await?;
+
+            Ok(())
+        })
+    }
 }
 
 #[cfg(feature = "server")]
+#[tracing::instrument(skip_all)]
 pub async fn get_summary_by_project_id(
-    executor: &mut PgConnection,
+    executor: &mut dyn Store,
     project_id: Uuid,
-) -> Result<shared::UserSummary, ServerFnError> {
+) -> Result<shared::UserSummary, ApiError> {
     use shared::UserBalance;
 
-    let expenses: Vec<Expense> = get_expenses_by_project_id(&mut *executor, project_id)
-        .await
-        .context("Failed get expenses")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let expenses: Vec<Expense> = executor.get_expenses_by_project_id(project_id).await?;
 
     let expense_ids: Vec<i32> = expenses.iter().map(|expense| expense.id).collect();
 
-    let payments: Vec<Payment> = sqlx::query_as!(
-        Payment,
-        "SELECT id, expense_id, user_id, is_debt, amount, created_at \
-        FROM payments \
-        WHERE expense_id = ANY($1)",
-        &expense_ids[..] // a bug of the parameter typechecking code requires all array parameters to be slices
-    )
-    .fetch_all(&mut *executor)
-    .await
-    .context("Failed get payments")
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let payments: Vec<Payment> = executor.get_payments_by_expense_ids(expense_ids).await?;
 
     let balances = calculate_balances(&expenses, &payments);
 
@@ -218,6 +245,7 @@ mod tests {
             description: None,
             name: "Test Expense".to_string(),
             expense_type,
+            version: 1,
         }
     }
 
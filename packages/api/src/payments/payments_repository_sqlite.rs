@@ -0,0 +1,122 @@
+use futures::future::BoxFuture;
+use shared::{NewPayment, Payment};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use super::payments_repository::PaymentsRepository;
+use crate::error::{db_error, ApiError};
+
+const PAYMENT_COLUMNS: &str = "id, expense_id, user_id, is_debt, amount, created_at";
+
+impl PaymentsRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn add_payments(
+        &mut self,
+        creatable_payments: Vec<NewPayment>,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            if creatable_payments.is_empty() {
+                return Ok(());
+            }
+
+            let mut query_builder: QueryBuilder<Sqlite> =
+                QueryBuilder::new("INSERT INTO payments (expense_id, user_id, is_debt, amount) ");
+            query_builder.push_values(creatable_payments, |mut b, payment| {
+                b.push_bind(payment.expense_id)
+                    .push_bind(payment.user_id)
+                    .push_bind(payment.is_debt)
+                    .push_bind(payment.amount);
+            });
+
+            query_builder
+                .build()
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed add payments"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_payments_by_expense_id(
+        &mut self,
+        expense_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>> {
+        Box::pin(async move {
+            let payments = sqlx::query_as(&format!(
+                "SELECT {} FROM payments WHERE expense_id = $1",
+                PAYMENT_COLUMNS
+            ))
+            .bind(expense_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed get payments"))?;
+
+            Ok(payments)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_payments_by_user_id(
+        &mut self,
+        user_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>> {
+        Box::pin(async move {
+            let payments = sqlx::query_as(&format!(
+                "SELECT {} FROM payments WHERE user_id = $1",
+                PAYMENT_COLUMNS
+            ))
+            .bind(user_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed get payments"))?;
+
+            Ok(payments)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_payments_by_expense_ids(
+        &mut self,
+        expense_ids: Vec<i32>,
+    ) -> BoxFuture<'_, Result<Vec<Payment>, ApiError>> {
+        Box::pin(async move {
+            if expense_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+                "SELECT {} FROM payments WHERE expense_id IN (",
+                PAYMENT_COLUMNS
+            ));
+            let mut separated = query_builder.separated(", ");
+            for id in expense_ids {
+                separated.push_bind(id);
+            }
+            separated.push_unseparated(")");
+
+            let payments = query_builder
+                .build_query_as()
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed get payments"))?;
+
+            Ok(payments)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_payments_by_expense_id(
+        &mut self,
+        expense_id: i32,
+    ) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM payments WHERE expense_id = $1")
+                .bind(expense_id)
+                .execute(&mut *self)
+                .await?;
+
+            Ok(())
+        })
+    }
+}
@@ -1,2 +1,6 @@
+pub mod project_access;
+pub mod project_lifecycle;
 pub mod projects_controller;
 pub mod projects_repository;
+#[cfg(feature = "server")]
+mod projects_repository_sqlite;
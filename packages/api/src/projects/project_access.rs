@@ -0,0 +1,251 @@
+use dioxus::prelude::*;
+use shared::{ProjectDto, ProjectRole};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::projects::project_lifecycle::{status_allows, status_error};
+
+#[cfg(feature = "server")]
+use crate::db::Store;
+#[cfg(feature = "server")]
+use crate::utils::{get_api_token_grant, get_current_account_id};
+
+/// Something a caller wants to do on a project.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectAction {
+    Read,
+    AddExpense,
+    /// Add a transfer paying off a balance
+    AddSettlement,
+    /// Edit or delete an expense authored by the given participant
+    EditExpense {
+        author_id: i32,
+    },
+    ManageParticipants,
+    ManageProject,
+    /// Move the project through its lifecycle
+    ChangeStatus,
+    DeleteProject,
+    ManageMembers,
+}
+
+/// Authorization matrix.
+///
+/// Viewers can only read. Editors can also add expenses and settlements and edit their own
+/// expenses — or everybody's when the project allows it. Everything else is reserved to owners.
+/// On top of that, the status of the project may forbid the action, see [`status_allows`].
+pub fn role_allows(
+    role: ProjectRole,
+    action: ProjectAction,
+    member_user_id: Option<i32>,
+    editors_can_edit_others_expenses: bool,
+) -> bool {
+    match (role, action) {
+        (ProjectRole::Owner, _) => true,
+        (_, ProjectAction::Read) => true,
+        (ProjectRole::Editor, ProjectAction::AddExpense | ProjectAction::AddSettlement) => true,
+        (ProjectRole::Editor, ProjectAction::EditExpense { author_id }) => {
+            editors_can_edit_others_expenses || member_user_id == Some(author_id)
+        }
+        _ => false,
+    }
+}
+
+/// Resolved access of the current caller on a project.
+#[derive(Debug, Clone)]
+pub struct ProjectAccess {
+    pub project: ProjectDto,
+    pub account_id: Option<Uuid>,
+    pub role: ProjectRole,
+    /// Participant claimed by the caller in this project, if any
+    pub user_id: Option<i32>,
+}
+
+impl ProjectAccess {
+    pub fn can(&self, action: ProjectAction) -> bool {
+        role_allows(self.role, action, self.user_id, self.project.editors_can_edit_others_expenses)
+    }
+
+    /// Forbidden when the role does not allow `action`, a conflict when the status does not.
+    pub fn require(&self, action: ProjectAction) -> Result<(), ApiError> {
+        if !self.can(action) {
+            return Err(ApiError::forbidden("Forbidden"));
+        }
+        if !status_allows(&self.project.status, action) {
+            return Err(status_error(&self.project.status));
+        }
+        Ok(())
+    }
+}
+
+/// Resolve the role of the current caller on a project.
+///
+/// Projects without an owner keep the URL-sharing model: whoever knows the id acts as an owner.
+/// An API token narrows this down to its projects and scope.
+#[cfg(feature = "server")]
+pub async fn resolve_project_access(
+    executor: &mut dyn Store,
+    project_id: Uuid,
+) -> Result<ProjectAccess, ApiError> {
+    let grant = get_api_token_grant();
+    if grant.as_ref().is_some_and(|g| !g.allows_project(project_id)) {
+        return Err(ApiError::forbidden("Forbidden"));
+    }
+
+    let mut access = resolve_account_access(&mut *executor, project_id).await?;
+    if let Some(grant) = grant {
+        access.role = grant.restrict_role(access.role);
+    }
+    Ok(access)
+}
+
+#[cfg(feature = "server")]
+async fn resolve_account_access(
+    executor: &mut dyn Store,
+    project_id: Uuid,
+) -> Result<ProjectAccess, ApiError> {
+    let project = executor.get_project(project_id).await?;
+    let account_id = get_current_account_id().await;
+
+    let Some(owner_id) = project.owner_account_id else {
+        return Ok(ProjectAccess { project, account_id, role: ProjectRole::Owner, user_id: None });
+    };
+
+    let Some(current) = account_id else {
+        return Err(ApiError::forbidden("Forbidden"));
+    };
+
+    let membership = executor.get_account_project(current, project_id).await?;
+
+    if current == owner_id {
+        let user_id = membership.and_then(|m| m.user_id);
+        return Ok(ProjectAccess { project, account_id, role: ProjectRole::Owner, user_id });
+    }
+
+    match membership {
+        Some(m) => Ok(ProjectAccess { project, account_id, role: m.role, user_id: m.user_id }),
+        None => Err(ApiError::forbidden("Forbidden")),
+    }
+}
+
+/// Resolve the caller's access and check that it allows `action`.
+#[cfg(feature = "server")]
+pub async fn authorize(
+    executor: &mut dyn Store,
+    project_id: Uuid,
+    action: ProjectAction,
+) -> Result<ProjectAccess, ApiError> {
+    let access = resolve_project_access(&mut *executor, project_id).await?;
+    access.require(action)?;
+    Ok(access)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use shared::ProjectStatus;
+
+    const ALL_ACTIONS: [ProjectAction; 9] = [
+        ProjectAction::Read,
+        ProjectAction::AddExpense,
+        ProjectAction::AddSettlement,
+        ProjectAction::EditExpense { author_id: 1 },
+        ProjectAction::ManageParticipants,
+        ProjectAction::ManageProject,
+        ProjectAction::ChangeStatus,
+        ProjectAction::DeleteProject,
+        ProjectAction::ManageMembers,
+    ];
+
+    #[test]
+    fn owner_can_do_everything() {
+        for action in ALL_ACTIONS {
+            assert!(role_allows(ProjectRole::Owner, action, None, false));
+        }
+    }
+
+    #[test]
+    fn viewer_can_only_read() {
+        for action in ALL_ACTIONS {
+            let allowed = role_allows(ProjectRole::Viewer, action, Some(1), true);
+            assert_eq!(allowed, action == ProjectAction::Read, "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn editor_can_read_and_add_expenses() {
+        assert!(role_allows(ProjectRole::Editor, ProjectAction::Read, None, false));
+        assert!(role_allows(ProjectRole::Editor, ProjectAction::AddExpense, None, false));
+        assert!(role_allows(ProjectRole::Editor, ProjectAction::AddSettlement, None, false));
+    }
+
+    #[test]
+    fn editor_cannot_manage_project() {
+        for action in [
+            ProjectAction::ManageParticipants,
+            ProjectAction::ManageProject,
+            ProjectAction::ChangeStatus,
+            ProjectAction::DeleteProject,
+            ProjectAction::ManageMembers,
+        ] {
+            assert!(!role_allows(ProjectRole::Editor, action, Some(1), true));
+        }
+    }
+
+    #[test]
+    fn editor_can_edit_own_expense() {
+        let action = ProjectAction::EditExpense { author_id: 7 };
+        assert!(role_allows(ProjectRole::Editor, action, Some(7), false));
+    }
+
+    #[test]
+    fn editor_cannot_edit_others_expense_when_disabled() {
+        let action = ProjectAction::EditExpense { author_id: 7 };
+        assert!(!role_allows(ProjectRole::Editor, action, Some(8), false));
+        assert!(!role_allows(ProjectRole::Editor, action, None, false));
+    }
+
+    #[test]
+    fn editor_can_edit_others_expense_when_enabled() {
+        let action = ProjectAction::EditExpense { author_id: 7 };
+        assert!(role_allows(ProjectRole::Editor, action, Some(8), true));
+        assert!(role_allows(ProjectRole::Editor, action, None, true));
+    }
+
+    #[test]
+    fn status_is_checked_after_role() {
+        let access = |role, status| ProjectAccess {
+            project: ProjectDto {
+                id: Uuid::nil(),
+                name: "Trip".to_string(),
+                created_at: chrono::NaiveDateTime::default(),
+                currency: "EUR".to_string(),
+                description: None,
+                status,
+                owner_account_id: None,
+                editors_can_edit_others_expenses: true,
+                version: 1,
+            },
+            account_id: None,
+            role,
+            user_id: None,
+        };
+        let closed = access(ProjectRole::Editor, ProjectStatus::Closed);
+        assert!(closed.require(ProjectAction::AddSettlement).is_ok());
+        assert_eq!(
+            closed.require(ProjectAction::AddExpense).unwrap_err().code,
+            ErrorCode::Conflict
+        );
+        assert_eq!(
+            closed.require(ProjectAction::ChangeStatus).unwrap_err().code,
+            ErrorCode::Forbidden
+        );
+        let archived = access(ProjectRole::Owner, ProjectStatus::Archived);
+        assert!(archived.require(ProjectAction::ChangeStatus).is_ok());
+        assert_eq!(
+            archived.require(ProjectAction::AddSettlement).unwrap_err().code,
+            ErrorCode::Conflict
+        );
+    }
+}
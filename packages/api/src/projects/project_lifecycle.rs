@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use shared::{ProjectStatus, UserAmount};
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::projects::project_access::ProjectAction;
+
+#[cfg(feature = "server")]
+use crate::db::Store;
+#[cfg(feature = "server")]
+use crate::payments::payments_repository;
+#[cfg(feature = "server")]
+use shared::ProjectSettlement;
+
+/// Lifecycle matrix.
+///
+/// Ongoing projects allow everything. Closed projects only take the settlements paying off their
+/// last balances, and can be deleted. Archived projects cannot be changed at all. Either way,
+/// the status can be changed and access managed, so that a closed project can be reopened and
+/// a member removed from an archived one.
+pub fn status_allows(status: &ProjectStatus, action: ProjectAction) -> bool {
+    let always = matches!(
+        action,
+        ProjectAction::Read | ProjectAction::ChangeStatus | ProjectAction::ManageMembers
+    );
+    match status {
+        ProjectStatus::Ongoing => true,
+        ProjectStatus::Closed => {
+            always || matches!(action, ProjectAction::AddSettlement | ProjectAction::DeleteProject)
+        }
+        ProjectStatus::Archived => always,
+    }
+}
+
+/// Why a project in `status` refuses an action.
+pub fn status_error(status: &ProjectStatus) -> ApiError {
+    match status {
+        ProjectStatus::Closed => {
+            ApiError::conflict("The project is closed: only settlements can be added")
+        }
+        _ => ApiError::conflict("The project is archived and cannot be changed"),
+    }
+}
+
+/// A project goes from ongoing to closed to archived, or back, one step at a time.
+pub fn check_transition(from: &ProjectStatus, to: &ProjectStatus) -> Result<(), ApiError> {
+    match (from, to) {
+        (ProjectStatus::Ongoing, ProjectStatus::Archived) => {
+            Err(ApiError::conflict("A project must be closed before it is archived"))
+        }
+        (ProjectStatus::Archived, ProjectStatus::Ongoing) => {
+            Err(ApiError::conflict("An archived project must be unarchived before it is reopened"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A settlement on a closed project only pays off what is still owed: no payer pays more than
+/// their debt, no recipient gets more than they are owed. `balances` are the current ones, by
+/// participant.
+pub fn check_settlement(
+    balances: &HashMap<i32, f64>,
+    payers: &[UserAmount],
+    recipients: &[UserAmount],
+) -> Result<(), ApiError> {
+    // Balances are rounded to the cent
+    const TOLERANCE: f64 = 0.005;
+
+    let mut paid: HashMap<i32, f64> = HashMap::new();
+    for payer in payers {
+        *paid.entry(payer.user_id).or_default() += payer.amount;
+    }
+    let mut received: HashMap<i32, f64> = HashMap::new();
+    for recipient in recipients {
+        *received.entry(recipient.user_id).or_default() += recipient.amount;
+    }
+
+    let balance = |user_id| balances.get(&user_id).copied().unwrap_or(0.0);
+    if paid.iter().any(|(&user_id, &amount)| amount > -balance(user_id) + TOLERANCE) {
+        return Err(ApiError::conflict(
+            "The project is closed: a settlement cannot exceed what the payer still owes",
+        ));
+    }
+    if received.iter().any(|(&user_id, &amount)| amount > balance(user_id) + TOLERANCE) {
+        return Err(ApiError::conflict(
+            "The project is closed: a settlement cannot exceed what the recipient is owed",
+        ));
+    }
+    Ok(())
+}
+
+/// Store the balances and reimbursements of a project being closed.
+#[cfg(feature = "server")]
+pub async fn settle(
+    executor: &mut dyn Store,
+    project_id: Uuid,
+) -> Result<ProjectSettlement, ApiError> {
+    let users = executor.get_users_by_project_id(project_id).await?;
+    let summary =
+        payments_repository::get_summary_by_project_id(&mut *executor, project_id).await?;
+    let settlement =
+        ProjectSettlement { project_id, closed_at: chrono::Utc::now().naive_utc(), users, summary };
+
+    executor.save_project_settlement(&settlement).await?;
+
+    Ok(settlement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    const ALL_ACTIONS: [ProjectAction; 9] = [
+        ProjectAction::Read,
+        ProjectAction::AddExpense,
+        ProjectAction::AddSettlement,
+        ProjectAction::EditExpense { author_id: 1 },
+        ProjectAction::ManageParticipants,
+        ProjectAction::ManageProject,
+        ProjectAction::ChangeStatus,
+        ProjectAction::DeleteProject,
+        ProjectAction::ManageMembers,
+    ];
+
+    #[test]
+    fn ongoing_projects_allow_everything() {
+        for action in ALL_ACTIONS {
+            assert!(status_allows(&ProjectStatus::Ongoing, action));
+        }
+    }
+
+    #[test]
+    fn closed_projects_only_take_settlements() {
+        let allowed = [
+            ProjectAction::Read,
+            ProjectAction::AddSettlement,
+            ProjectAction::ChangeStatus,
+            ProjectAction::DeleteProject,
+            ProjectAction::ManageMembers,
+        ];
+        for action in ALL_ACTIONS {
+            assert_eq!(
+                status_allows(&ProjectStatus::Closed, action),
+                allowed.contains(&action),
+                "{:?}",
+                action
+            );
+        }
+    }
+
+    #[test]
+    fn archived_projects_cannot_be_changed() {
+        let allowed =
+            [ProjectAction::Read, ProjectAction::ChangeStatus, ProjectAction::ManageMembers];
+        for action in ALL_ACTIONS {
+            assert_eq!(
+                status_allows(&ProjectStatus::Archived, action),
+                allowed.contains(&action),
+                "{:?}",
+                action
+            );
+        }
+    }
+
+    fn shares(shares: &[(i32, f64)]) -> Vec<UserAmount> {
+        shares.iter().map(|&(user_id, amount)| UserAmount { user_id, amount }).collect()
+    }
+
+    #[test]
+    fn settlements_only_pay_off_what_is_owed() {
+        // Bob owes 30, Carol 10, both to Alice
+        let balances = HashMap::from([(1, 40.0), (2, -30.0), (3, -10.0)]);
+        let settle =
+            |payers, recipients| check_settlement(&balances, &shares(payers), &shares(recipients));
+
+        assert!(settle(&[(2, 30.0)], &[(1, 30.0)]).is_ok());
+        assert!(settle(&[(2, 10.0)], &[(1, 10.0)]).is_ok());
+        assert!(settle(&[(2, 30.0), (3, 10.0)], &[(1, 40.0)]).is_ok());
+        assert!(settle(&[(3, 10.004)], &[(1, 10.004)]).is_ok());
+
+        for (payers, recipients) in [
+            (&[(2, 31.0)][..], &[(1, 31.0)][..]),
+            (&[(3, 5.0), (3, 6.0)], &[(1, 11.0)]),
+            // Alice is owed, she has nothing to pay back
+            (&[(1, 10.0)], &[(2, 10.0)]),
+            (&[(2, 10.0)], &[(3, 10.0)]),
+            (&[(4, 1.0)], &[(1, 1.0)]),
+        ] {
+            let error = settle(payers, recipients).unwrap_err();
+            assert_eq!(error.code, ErrorCode::Conflict, "{:?} -> {:?}", payers, recipients);
+        }
+    }
+
+    #[test]
+    fn transitions_go_one_step_at_a_time() {
+        use ProjectStatus::*;
+
+        for (from, to) in
+            [(Ongoing, Closed), (Closed, Ongoing), (Closed, Archived), (Archived, Closed)]
+        {
+            assert!(check_transition(&from, &to).is_ok(), "{:?} -> {:?}", from, to);
+        }
+        for (from, to) in [(Ongoing, Archived), (Archived, Ongoing)] {
+            let error = check_transition(&from, &to).unwrap_err();
+            assert_eq!(error.code, ErrorCode::Conflict, "{:?} -> {:?}", from, to);
+        }
+    }
+}
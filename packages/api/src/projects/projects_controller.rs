@@ -2,59 +2,58 @@ use chrono::Local;
 use dioxus::core::bail;
 use dioxus::fullstack::Json;
 use dioxus::prelude::*;
-use shared::{BatchProject, CreatableProject, EditableProject, ProjectDto, ProjectStatus};
+use shared::{
+    BatchProject, CreatableProject, EditableProject, ProjectDto, ProjectRole, ProjectSettlement,
+    ProjectStatus,
+};
 use uuid::Uuid;
 
+use crate::error::ApiError;
+
 #[cfg(feature = "server")]
 use crate::db::get_db;
 #[cfg(feature = "server")]
-use crate::users::users_repository::{delete_users, get_users_by_project_id};
+use crate::projects::project_access::{authorize, resolve_project_access, ProjectAction};
+#[cfg(feature = "server")]
+use crate::projects::project_lifecycle::{check_transition, settle};
+#[cfg(feature = "server")]
+use crate::sse::publish;
 #[cfg(feature = "server")]
-use crate::utils::get_current_account_id;
+use crate::utils::{ensure_account_write_allowed, get_api_token_grant, get_current_account_id};
+#[cfg(feature = "server")]
+use shared::sse::EventSSE;
 
 #[cfg(feature = "server")]
 use crate::projects::projects_repository;
 
 #[get("/api/v1/projects/{project_id}")]
-pub async fn get_project(project_id: Uuid) -> Result<ProjectDto, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    let project: ProjectDto = projects_repository::get_project(&mut *tx, project_id).await?;
+pub async fn get_project(project_id: Uuid) -> Result<ProjectDto, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    #[cfg(feature = "server")]
-    if let Some(owner_id) = project.owner_account_id {
-        let current = get_current_account_id().await;
-        let is_owner = current == Some(owner_id);
-        let is_invited = match current {
-            Some(account_id) => {
-                projects_repository::has_account_access(&mut *tx, account_id, project_id).await?
-            }
-            None => false,
-        };
-        if !is_owner && !is_invited {
-            return Err(ServerFnError::new("Forbidden"));
-        }
-    }
+    let project: ProjectDto = authorize(&mut *tx, project_id, ProjectAction::Read).await?.project;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(project)
 }
 
 #[get("/api/v1/projects")]
-pub async fn get_projects() -> Result<Vec<ProjectDto>, ServerFnError> {
+pub async fn get_projects() -> Result<Vec<ProjectDto>, ApiError> {
     #[cfg(feature = "server")]
     let account_id = get_current_account_id().await;
     #[cfg(not(feature = "server"))]
     let account_id: Option<Uuid> = None;
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    let projects: Vec<ProjectDto> = projects_repository::get_projects(&mut *tx, account_id).await?;
+    let mut projects: Vec<ProjectDto> = tx.get_projects(account_id).await?;
+    if let Some(grant) = get_api_token_grant() {
+        projects.retain(|p| grant.allows_project(p.id));
+    }
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(projects)
 }
@@ -62,34 +61,18 @@ pub async fn get_projects() -> Result<Vec<ProjectDto>, ServerFnError> {
 #[post("/api/v1/projects/batch")]
 pub async fn get_projects_by_ids(
     Json(payload): Json<BatchProject>,
-) -> Result<Vec<ProjectDto>, ServerFnError> {
-    #[cfg(feature = "server")]
-    let account_id = get_current_account_id().await;
-    #[cfg(not(feature = "server"))]
-    let account_id: Option<Uuid> = None;
+) -> Result<Vec<ProjectDto>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    let projects: Vec<ProjectDto> = projects_repository::get_projects_by_ids(&mut *tx, payload).await?;
+    let projects: Vec<ProjectDto> = tx.get_projects_by_ids(payload).await?;
 
     #[cfg(feature = "server")]
     for project in &projects {
-        if let Some(owner_id) = project.owner_account_id {
-            let is_owner = account_id == Some(owner_id);
-            let is_invited = match account_id {
-                Some(aid) => {
-                    projects_repository::has_account_access(&mut *tx, aid, project.id).await?
-                }
-                None => false,
-            };
-            if !is_owner && !is_invited {
-                return Err(ServerFnError::new("Forbidden"));
-            }
-        }
+        resolve_project_access(&mut *tx, project.id).await?.require(ProjectAction::Read)?;
     }
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(projects)
 }
@@ -97,19 +80,25 @@ pub async fn get_projects_by_ids(
 #[post("/api/v1/projects")]
 pub async fn add_project(
     Json(creatable_project): Json<CreatableProject>,
-) -> Result<ProjectDto, ServerFnError> {
+) -> Result<ProjectDto, ApiError> {
+    #[cfg(feature = "server")]
+    ensure_account_write_allowed()?;
     #[cfg(feature = "server")]
     let owner_account_id = get_current_account_id().await;
     #[cfg(not(feature = "server"))]
     let owner_account_id: Option<Uuid> = None;
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    let project_id: Uuid =
-        projects_repository::add_project(&mut *tx, creatable_project.clone(), owner_account_id).await?;
+    let project_id: Uuid = tx.add_project(creatable_project.clone(), owner_account_id).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    #[cfg(feature = "server")]
+    if let Some(owner_id) = owner_account_id {
+        tx.upsert_account_project_role(owner_id, project_id, ProjectRole::Owner).await?;
+    }
+
+    tx.commit().await?;
 
     let new_project = ProjectDto {
         id: project_id,
@@ -119,6 +108,8 @@ pub async fn add_project(
         currency: "EUR".to_string(),
         owner_account_id,
         status: ProjectStatus::Ongoing,
+        editors_can_edit_others_expenses: true,
+        version: 1,
     };
 
     Ok(new_project)
@@ -127,58 +118,124 @@ pub async fn add_project(
 #[put("/api/v1/projects")]
 pub async fn update_project_by_id(
     Json(editable_project): Json<EditableProject>,
-) -> Result<ProjectDto, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    #[cfg(feature = "server")]
-    {
-        let project = projects_repository::get_project(&mut *tx, editable_project.id).await?;
-        if let Some(owner_id) = project.owner_account_id {
-            let current = get_current_account_id().await;
-            if current != Some(owner_id) {
-                return Err(ServerFnError::new("Forbidden"));
-            }
-        }
+) -> Result<ProjectDto, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let access = resolve_project_access(&mut *tx, editable_project.id).await?;
+    let new_status = editable_project.status.clone().filter(|s| *s != access.project.status);
+    if new_status.is_none() || edits_settings(&access.project, &editable_project) {
+        access.require(ProjectAction::ManageProject)?;
+    }
+    if let Some(status) = &new_status {
+        access.require(ProjectAction::ChangeStatus)?;
+        check_transition(&access.project.status, status)?;
     }
 
     let updated_project: ProjectDto =
         projects_repository::update_project_by_id(&mut *tx, editable_project).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    // Unarchiving keeps the settlement stored when the project was closed
+    let closing = matches!(
+        (&access.project.status, &new_status),
+        (ProjectStatus::Ongoing, Some(ProjectStatus::Closed))
+    );
+    if closing {
+        settle(&mut *tx, updated_project.id).await?;
+    }
+
+    tx.commit().await?;
+    publish(updated_project.id, EventSSE::ProjectModified);
 
     Ok(updated_project)
 }
 
 #[delete("/api/v1/projects/{project_id}")]
-pub async fn delete_project_by_id(project_id: Uuid) -> Result<(), ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn delete_project_by_id(project_id: Uuid) -> Result<(), ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
     #[cfg(feature = "server")]
-    {
-        let project = projects_repository::get_project(&mut *tx, project_id).await?;
-        if let Some(owner_id) = project.owner_account_id {
-            let current = get_current_account_id().await;
-            if current != Some(owner_id) {
-                return Err(ServerFnError::new("Forbidden"));
-            }
-        }
-    }
+    authorize(&mut *tx, project_id, ProjectAction::DeleteProject).await?;
 
-    let users_bound_to_project = get_users_by_project_id(&mut *tx, project_id)
-        .await
-        .context("failed to get users bound to project")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let users_bound_to_project = tx.get_users_by_project_id(project_id).await?;
 
-    projects_repository::delete_project_by_id(&mut *tx, project_id).await?;
+    tx.delete_project_by_id(project_id).await?;
 
-    delete_users(&mut *tx, users_bound_to_project.iter().map(|user| user.id).collect())
-        .await
-        .context("failed to deleted bound users")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.delete_users(users_bound_to_project.iter().map(|user| user.id).collect()).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
+    publish(project_id, EventSSE::ProjectDeleted);
 
     Ok(())
 }
+
+#[get("/api/v1/projects/{project_id}/settlement")]
+pub async fn get_project_settlement(project_id: Uuid) -> Result<ProjectSettlement, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::Read).await?;
+
+    let settlement = tx
+        .get_project_settlement(project_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("The project was never closed"))?;
+
+    tx.commit().await?;
+
+    Ok(settlement)
+}
+
+/// Whether `edit` changes anything but the status.
+#[cfg(feature = "server")]
+fn edits_settings(project: &ProjectDto, edit: &EditableProject) -> bool {
+    edit.name.as_ref().is_some_and(|name| *name != project.name)
+        || (edit.description.is_some() && edit.description != project.description)
+        || edit.currency.as_ref().is_some_and(|currency| *currency != project.currency)
+        || edit
+            .editors_can_edit_others_expenses
+            .is_some_and(|value| value != project.editors_can_edit_others_expenses)
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_changes_alone_do_not_edit_settings() {
+        let project = ProjectDto {
+            id: Uuid::nil(),
+            name: "Trip".to_string(),
+            created_at: Local::now().naive_local(),
+            currency: "EUR".to_string(),
+            description: None,
+            status: ProjectStatus::Ongoing,
+            owner_account_id: None,
+            editors_can_edit_others_expenses: true,
+            version: 1,
+        };
+        let edit = EditableProject {
+            id: project.id,
+            name: Some("Trip".to_string()),
+            description: None,
+            currency: Some("EUR".to_string()),
+            status: Some(ProjectStatus::Closed),
+            editors_can_edit_others_expenses: Some(true),
+            version: 1,
+        };
+        assert!(!edits_settings(&project, &edit));
+        assert!(edits_settings(
+            &project,
+            &EditableProject { name: Some("Trip 2".to_string()), ..edit.clone() }
+        ));
+        assert!(edits_settings(
+            &project,
+            &EditableProject { description: Some(String::new()), ..edit.clone() }
+        ));
+        assert!(edits_settings(
+            &project,
+            &EditableProject { editors_can_edit_others_expenses: Some(false), ..edit }
+        ));
+    }
+}
@@ -1,121 +1,282 @@
 use dioxus::prelude::*;
-use shared::{BatchProject, CreatableProject, EditableProject, ProjectDto, ProjectStatus};
+use shared::{
+    BatchProject, CreatableProject, EditableProject, ProjectDto, ProjectSettlement, ProjectStatus,
+};
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
-use sqlx::PgConnection;
+use crate::db::Store;
+#[cfg(feature = "server")]
+use crate::error::{db_error, ApiError};
 
 #[cfg(feature = "server")]
-pub async fn get_project(
-    executor: &mut PgConnection,
-    project_id: Uuid,
-) -> Result<ProjectDto, ServerFnError> {
-    let project: ProjectDto = sqlx::query_as!(
-        ProjectDto,
-        r#"SELECT id, name, created_at, currency, description, status as "status: ProjectStatus", owner_account_id FROM projects WHERE id = $1"#,
-        project_id
-    )
-    .fetch_one(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to get project: {}", e)))?;
-
-    Ok(project)
-}
+use futures::future::BoxFuture;
+#[cfg(feature = "server")]
+use sqlx::PgConnection;
 
 #[cfg(feature = "server")]
-pub async fn get_projects(
-    executor: &mut PgConnection,
-    account_id: Option<Uuid>,
-) -> Result<Vec<ProjectDto>, ServerFnError> {
-    let projects: Vec<ProjectDto> = match account_id {
-        Some(id) => sqlx::query_as!(
-            ProjectDto,
-            r#"SELECT DISTINCT p.id, p.name, p.created_at, p.currency, p.description, p.owner_account_id,
-                      p.status as "status: ProjectStatus"
-               FROM projects p
-               LEFT JOIN account_projects ap ON ap.project_id = p.id AND ap.account_id = $1
-               WHERE p.owner_account_id = $1 OR ap.account_id = $1"#,
-            id
-        )
-        .fetch_all(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to get projects by account: {}", e)))?,
-
-        None => sqlx::query_as!(
-            ProjectDto,
-            r#"SELECT id, name, created_at, currency, description, owner_account_id, status as "status: ProjectStatus" FROM projects WHERE owner_account_id IS NULL"#
-        )
-        .fetch_all(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to get unowned projects: {}", e)))?,
-    };
+pub trait ProjectsRepository {
+    fn get_project(&mut self, project_id: Uuid) -> BoxFuture<'_, Result<ProjectDto, ApiError>>;
+
+    /// Projects the account owns or is a member of, or the projects without an owner for `None`.
+    fn get_projects(
+        &mut self,
+        account_id: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<Vec<ProjectDto>, ApiError>>;
+
+    fn get_projects_by_ids(
+        &mut self,
+        payload: BatchProject,
+    ) -> BoxFuture<'_, Result<Vec<ProjectDto>, ApiError>>;
+
+    fn add_project(
+        &mut self,
+        project: CreatableProject,
+        owner_account_id: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<Uuid, ApiError>>;
+
+    /// Write the editable fields of `project`. Returns `None` when the project is no longer at
+    /// `project.version`.
+    fn update_project(
+        &mut self,
+        project: ProjectDto,
+    ) -> BoxFuture<'_, Result<Option<ProjectDto>, ApiError>>;
+
+    fn delete_project_by_id(&mut self, project_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>>;
 
-    Ok(projects)
+    /// Store the settlement of a project being closed, replacing the one of an earlier closing.
+    fn save_project_settlement<'a>(
+        &'a mut self,
+        settlement: &'a ProjectSettlement,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// The settlement stored when the project was last closed, if it ever was.
+    fn get_project_settlement(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<ProjectSettlement>, ApiError>>;
 }
 
 #[cfg(feature = "server")]
-pub async fn has_account_access(
-    executor: &mut PgConnection,
-    account_id: Uuid,
-    project_id: Uuid,
-) -> Result<bool, ServerFnError> {
-    let exists: bool = sqlx::query_scalar!(
-        "SELECT EXISTS(SELECT 1 FROM account_projects WHERE account_id = $1 AND project_id = $2)",
-        account_id,
-        project_id
-    )
-    .fetch_one(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to check account access: {}", e)))?
-    .unwrap_or(false);
-
-    Ok(exists)
+impl ProjectsRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn get_project(&mut self, project_id: Uuid) -> BoxFuture<'_, Result<ProjectDto, ApiError>> {
+        Box::pin(async move {
+            let project: ProjectDto = sqlx::query_as!(
+                ProjectDto,
+                r#"SELECT id, name, created_at, currency, description, status as "status: ProjectStatus", owner_account_id, editors_can_edit_others_expenses, version FROM projects WHERE id = $1"#,
+                project_id
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to get project"))?;
+
+            Ok(project)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_projects(
+        &mut self,
+        account_id: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<Vec<ProjectDto>, ApiError>> {
+        Box::pin(async move {
+            let projects: Vec<ProjectDto> = match account_id {
+                Some(id) => sqlx::query_as!(
+                    ProjectDto,
+                    r#"SELECT DISTINCT p.id, p.name, p.created_at, p.currency, p.description, p.owner_account_id,
+                              p.status as "status: ProjectStatus", p.editors_can_edit_others_expenses, p.version
+                       FROM projects p
+                       LEFT JOIN account_projects ap ON ap.project_id = p.id AND ap.account_id = $1
+                       WHERE p.owner_account_id = $1 OR ap.account_id = $1"#,
+                    id
+                )
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to get projects by account"))?,
+
+                None => sqlx::query_as!(
+                    ProjectDto,
+                    r#"SELECT id, name, created_at, currency, description, owner_account_id, status as "status: ProjectStatus", editors_can_edit_others_expenses, version FROM projects WHERE owner_account_id IS NULL"#
+                )
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to get unowned projects"))?,
+            };
+
+            Ok(projects)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_projects_by_ids(
+        &mut self,
+        payload: BatchProject,
+    ) -> BoxFuture<'_, Result<Vec<ProjectDto>, ApiError>> {
+        Box::pin(async move {
+            let projects: Vec<ProjectDto> = sqlx::query_as!(
+                ProjectDto,
+                r#"SELECT id, name, created_at, currency, description, owner_account_id, status as "status: ProjectStatus", editors_can_edit_others_expenses, version FROM projects WHERE id = ANY($1)"#,
+                &payload.ids[..]
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get projects by ids"))?;
+
+            Ok(projects)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn add_project(
+        &mut self,
+        project: CreatableProject,
+        owner_account_id: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let project_id: Uuid = sqlx::query_scalar!(
+                "INSERT INTO projects(name, description, currency, owner_account_id) VALUES ($1, $2, $3, $4) RETURNING id",
+                project.name,
+                project.description,
+                "EUR",
+                owner_account_id
+            )
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to add project"))?;
+
+            Ok(project_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn update_project(
+        &mut self,
+        project: ProjectDto,
+    ) -> BoxFuture<'_, Result<Option<ProjectDto>, ApiError>> {
+        Box::pin(async move {
+            let update_project: Option<ProjectDto> = sqlx::query_as!(
+                ProjectDto,
+                r#"UPDATE projects SET name = $1, description = $2, currency = $3, status = $4, editors_can_edit_others_expenses = $5, version = version + 1 WHERE id = $6 AND version = $7 RETURNING id, name, created_at, currency, description, status as "status: ProjectStatus", owner_account_id, editors_can_edit_others_expenses, version"#,
+                project.name,
+                project.description,
+                project.currency,
+                project.status as ProjectStatus,
+                project.editors_can_edit_others_expenses,
+                project.id,
+                project.version
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to update project"))?;
+
+            Ok(update_project)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_project_by_id(&mut self, project_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            // TODO allow to archive projects
+
+            sqlx::query!("DELETE FROM projects WHERE id = $1", project_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete project"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn save_project_settlement<'a>(
+        &'a mut self,
+        settlement: &'a ProjectSettlement,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            let (users, summary) = settlement_columns(settlement)?;
+            sqlx::query!(
+                "INSERT INTO project_settlements (project_id, users, summary, closed_at)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (project_id)
+                 DO UPDATE SET users = EXCLUDED.users, summary = EXCLUDED.summary, closed_at = EXCLUDED.closed_at",
+                settlement.project_id,
+                users,
+                summary,
+                settlement.closed_at
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to save project settlement"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_project_settlement(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<ProjectSettlement>, ApiError>> {
+        Box::pin(async move {
+            let row = sqlx::query!(
+                "SELECT users, summary, closed_at FROM project_settlements WHERE project_id = $1",
+                project_id
+            )
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get project settlement"))?;
+
+            row.map(|row| {
+                settlement_from_columns(project_id, row.closed_at, &row.users, &row.summary)
+            })
+            .transpose()
+        })
+    }
 }
 
+/// The participants and summary of a settlement, as stored: JSON.
 #[cfg(feature = "server")]
-pub async fn get_projects_by_ids(
-    executor: &mut PgConnection,
-    payload: BatchProject,
-) -> Result<Vec<ProjectDto>, ServerFnError> {
-    let projects: Vec<ProjectDto> = sqlx::query_as!(
-        ProjectDto,
-        r#"SELECT id, name, created_at, currency, description, owner_account_id, status as "status: ProjectStatus" FROM projects WHERE id = ANY($1)"#,
-        &payload.ids[..]
-    )
-    .fetch_all(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to get projects by ids: {}", e)))?;
-
-    Ok(projects)
+pub(crate) fn settlement_columns(
+    settlement: &ProjectSettlement,
+) -> Result<(String, String), ApiError> {
+    let users = serde_json::to_string(&settlement.users);
+    let summary = serde_json::to_string(&settlement.summary);
+    match (users, summary) {
+        (Ok(users), Ok(summary)) => Ok((users, summary)),
+        (Err(e), _) | (_, Err(e)) => {
+            Err(ApiError::internal(format!("Failed to serialize project settlement: {}", e)))
+        }
+    }
 }
 
 #[cfg(feature = "server")]
-pub async fn add_project(
-    executor: &mut PgConnection,
-    project: CreatableProject,
-    owner_account_id: Option<Uuid>,
-) -> Result<Uuid, ServerFnError> {
-    let project_id: Uuid = sqlx::query_scalar!(
-        "INSERT INTO projects(name, description, currency, owner_account_id) VALUES ($1, $2, $3, $4) RETURNING id",
-        project.name,
-        project.description,
-        "EUR",
-        owner_account_id
-    )
-    .fetch_one(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to add project: {}", e)))?;
-
-    Ok(project_id)
+pub(crate) fn settlement_from_columns(
+    project_id: Uuid,
+    closed_at: chrono::NaiveDateTime,
+    users: &str,
+    summary: &str,
+) -> Result<ProjectSettlement, ApiError> {
+    let invalid = |e: serde_json::Error| {
+        ApiError::internal(format!("Failed to read project settlement: {}", e))
+    };
+    Ok(ProjectSettlement {
+        project_id,
+        closed_at,
+        users: serde_json::from_str(users).map_err(invalid)?,
+        summary: serde_json::from_str(summary).map_err(invalid)?,
+    })
 }
 
 #[cfg(feature = "server")]
+#[tracing::instrument(skip_all)]
 pub async fn update_project_by_id(
-    executor: &mut PgConnection,
+    executor: &mut dyn Store,
     editable_project: EditableProject,
-) -> Result<ProjectDto, ServerFnError> {
-    let mut new_project =
-        get_project(&mut *executor, editable_project.id).await.expect("Unable to find requested project_id");
+) -> Result<ProjectDto, ApiError> {
+    let mut new_project = executor.get_project(editable_project.id).await?;
+    let current = new_project.clone();
+    new_project.version = editable_project.version;
 
     if editable_project.name.is_some() {
         new_project.name = editable_project.name.unwrap();
@@ -133,33 +294,12 @@ pub async fn update_project_by_id(
         new_project.status = editable_project.status.unwrap();
     }
 
-    let update_project: ProjectDto = sqlx::query_as!(
-        ProjectDto,
-        r#"UPDATE projects SET name = $1, description = $2, currency = $3, status = $4 WHERE id = $5 RETURNING id, name, created_at, currency, description, status as "status: ProjectStatus", owner_account_id"#,
-        new_project.name,
-        new_project.description,
-        new_project.currency,
-        new_project.status as ProjectStatus,
-        new_project.id
-    )
-    .fetch_one(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to update project: {}", e)))?;
-
-    Ok(update_project)
-}
-
-#[cfg(feature = "server")]
-pub async fn delete_project_by_id(
-    executor: &mut PgConnection,
-    project_id: Uuid,
-) -> Result<(), ServerFnError> {
-    // TODO allow to archive projects
-
-    sqlx::query!("DELETE FROM projects WHERE id = $1", project_id)
-        .execute(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to delete project: {}", e)))?;
+    if let Some(value) = editable_project.editors_can_edit_others_expenses {
+        new_project.editors_can_edit_others_expenses = value;
+    }
 
-    Ok(())
+    executor
+        .update_project(new_project)
+        .await?
+        .ok_or_else(|| ApiError::stale("The project was edited in the meantime", &current))
 }
@@ -0,0 +1,200 @@
+use chrono::NaiveDateTime;
+use futures::future::BoxFuture;
+use shared::{BatchProject, CreatableProject, ProjectDto, ProjectSettlement};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use uuid::Uuid;
+
+use super::projects_repository::{settlement_columns, settlement_from_columns, ProjectsRepository};
+use crate::error::{db_error, ApiError};
+
+const PROJECT_COLUMNS: &str = "id, name, created_at, currency, description, status, owner_account_id, editors_can_edit_others_expenses, version";
+
+impl ProjectsRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn get_project(&mut self, project_id: Uuid) -> BoxFuture<'_, Result<ProjectDto, ApiError>> {
+        Box::pin(async move {
+            let project =
+                sqlx::query_as(&format!("SELECT {} FROM projects WHERE id = $1", PROJECT_COLUMNS))
+                    .bind(project_id)
+                    .fetch_one(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to get project"))?;
+
+            Ok(project)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_projects(
+        &mut self,
+        account_id: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<Vec<ProjectDto>, ApiError>> {
+        Box::pin(async move {
+            let projects = match account_id {
+                Some(id) => sqlx::query_as(&format!(
+                    "SELECT {} FROM projects p
+                     WHERE p.owner_account_id = $1
+                        OR EXISTS (SELECT 1 FROM account_projects ap WHERE ap.project_id = p.id AND ap.account_id = $1)",
+                    PROJECT_COLUMNS
+                ))
+                .bind(id)
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to get projects by account"))?,
+
+                None => sqlx::query_as(&format!(
+                    "SELECT {} FROM projects WHERE owner_account_id IS NULL",
+                    PROJECT_COLUMNS
+                ))
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to get unowned projects"))?,
+            };
+
+            Ok(projects)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_projects_by_ids(
+        &mut self,
+        payload: BatchProject,
+    ) -> BoxFuture<'_, Result<Vec<ProjectDto>, ApiError>> {
+        Box::pin(async move {
+            if payload.ids.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let mut query_builder: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+                "SELECT {} FROM projects WHERE id IN (",
+                PROJECT_COLUMNS
+            ));
+            let mut separated = query_builder.separated(", ");
+            for id in payload.ids {
+                separated.push_bind(id);
+            }
+            separated.push_unseparated(")");
+
+            let projects = query_builder
+                .build_query_as()
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to get projects by ids"))?;
+
+            Ok(projects)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn add_project(
+        &mut self,
+        project: CreatableProject,
+        owner_account_id: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<Uuid, ApiError>> {
+        Box::pin(async move {
+            let project_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO projects (id, name, description, currency, owner_account_id)
+                 VALUES ($1, $2, $3, $4, $5)
+                 RETURNING id",
+            )
+            .bind(Uuid::new_v4())
+            .bind(project.name)
+            .bind(project.description)
+            .bind("EUR")
+            .bind(owner_account_id)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to add project"))?;
+
+            Ok(project_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn update_project(
+        &mut self,
+        project: ProjectDto,
+    ) -> BoxFuture<'_, Result<Option<ProjectDto>, ApiError>> {
+        Box::pin(async move {
+            let update_project = sqlx::query_as(&format!(
+                "UPDATE projects
+                 SET name = $1, description = $2, currency = $3, status = $4, editors_can_edit_others_expenses = $5,
+                     version = version + 1
+                 WHERE id = $6 AND version = $7
+                 RETURNING {}",
+                PROJECT_COLUMNS
+            ))
+            .bind(project.name)
+            .bind(project.description)
+            .bind(project.currency)
+            .bind(project.status)
+            .bind(project.editors_can_edit_others_expenses)
+            .bind(project.id)
+            .bind(project.version)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to update project"))?;
+
+            Ok(update_project)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_project_by_id(&mut self, project_id: Uuid) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM projects WHERE id = $1")
+                .bind(project_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete project"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn save_project_settlement<'a>(
+        &'a mut self,
+        settlement: &'a ProjectSettlement,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            let (users, summary) = settlement_columns(settlement)?;
+            sqlx::query(
+                "INSERT INTO project_settlements (project_id, users, summary, closed_at)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (project_id)
+                 DO UPDATE SET users = excluded.users, summary = excluded.summary, closed_at = excluded.closed_at",
+            )
+            .bind(settlement.project_id)
+            .bind(users)
+            .bind(summary)
+            .bind(settlement.closed_at)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to save project settlement"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_project_settlement(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Option<ProjectSettlement>, ApiError>> {
+        Box::pin(async move {
+            let row: Option<(String, String, NaiveDateTime)> = sqlx::query_as(
+                "SELECT users, summary, closed_at FROM project_settlements WHERE project_id = $1",
+            )
+            .bind(project_id)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to get project settlement"))?;
+
+            row.map(|(users, summary, closed_at)| {
+                settlement_from_columns(project_id, closed_at, &users, &summary)
+            })
+            .transpose()
+        })
+    }
+}
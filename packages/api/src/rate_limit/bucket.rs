@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::Method;
+
+use super::RateLimitConfig;
+
+/// Buckets that would be full again are dropped this often, so memory follows active clients.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// `requests` per `period`, per client IP. Up to `requests` can be spent in a burst.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budget {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Budget {
+    pub fn new(requests: u32, period_secs: u64) -> Self {
+        Self { requests, period: Duration::from_secs(period_secs) }
+    }
+
+    /// Parse `<requests>/<seconds>`, or `off` to disable the rule.
+    pub fn parse(value: &str) -> anyhow::Result<Option<Budget>> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("off") {
+            return Ok(None);
+        }
+
+        let (requests, seconds) = value.split_once('/').ok_or_else(|| {
+            anyhow::anyhow!("expected <requests>/<seconds> or off, got \"{}\"", value)
+        })?;
+        let requests: u32 = requests.trim().parse()?;
+        let seconds: u64 = seconds.trim().parse()?;
+        anyhow::ensure!(requests > 0 && seconds > 0, "requests and seconds must be positive");
+
+        Ok(Some(Budget::new(requests, seconds)))
+    }
+
+    fn tokens_per_sec(&self) -> f64 {
+        self.requests as f64 / self.period.as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn full(budget: &Budget, now: Instant) -> Self {
+        Self { tokens: budget.requests as f64, updated_at: now }
+    }
+
+    fn refill(&mut self, budget: &Budget, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * budget.tokens_per_sec()).min(budget.requests as f64);
+        self.updated_at = now;
+    }
+
+    /// Spend one token, or return how long to wait until one is available.
+    pub fn try_take(&mut self, budget: &Budget, now: Instant) -> Result<(), Duration> {
+        self.refill(budget, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / budget.tokens_per_sec()))
+        }
+    }
+
+    pub fn is_full(&self, budget: &Budget, now: Instant) -> bool {
+        let mut bucket = self.clone();
+        bucket.refill(budget, now);
+        bucket.tokens >= budget.requests as f64
+    }
+}
+
+struct Buckets {
+    by_client: HashMap<(usize, IpAddr), TokenBucket>,
+    last_sweep: Instant,
+}
+
+/// In-memory token buckets, one per (rule, client IP). State is per process and lost on restart.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(Buckets { by_client: HashMap::new(), last_sweep: Instant::now() }),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Spend one request of the budget matching this route, if any.
+    pub fn check(
+        &self,
+        method: &Method,
+        path: &str,
+        ip: IpAddr,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let Some((index, rule)) =
+            self.config.rules.iter().enumerate().find(|(_, rule)| rule.matches(method, path))
+        else {
+            return Ok(());
+        };
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if now.saturating_duration_since(buckets.last_sweep) >= SWEEP_INTERVAL {
+            let rules = &self.config.rules;
+            buckets.by_client.retain(|(i, _), bucket| !bucket.is_full(&rules[*i].budget, now));
+            buckets.last_sweep = now;
+        }
+
+        buckets
+            .by_client
+            .entry((index, ip))
+            .or_insert_with(|| TokenBucket::full(&rule.budget, now))
+            .try_take(&rule.budget, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::RateLimitRule;
+
+    fn limiter(budget: Budget) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            rules: vec![RateLimitRule {
+                name: "login",
+                method: Method::POST,
+                paths: vec!["/api/v1/auth/login"],
+                budget,
+            }],
+            trusted_proxy_header: None,
+            trusted_proxies: vec![],
+        })
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([192, 0, 2, last])
+    }
+
+    #[test]
+    fn parse_budget() {
+        assert_eq!(Budget::parse("5/60").unwrap(), Some(Budget::new(5, 60)));
+        assert_eq!(Budget::parse(" 10 / 3600 ").unwrap(), Some(Budget::new(10, 3600)));
+        assert_eq!(Budget::parse("OFF").unwrap(), None);
+        assert!(Budget::parse("5").is_err());
+        assert!(Budget::parse("0/60").is_err());
+        assert!(Budget::parse("5/0").is_err());
+        assert!(Budget::parse("five/60").is_err());
+    }
+
+    #[test]
+    fn bucket_allows_burst_then_refills() {
+        let budget = Budget::new(3, 60);
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(&budget, start);
+
+        for _ in 0..3 {
+            assert!(bucket.try_take(&budget, start).is_ok());
+        }
+        let retry_after = bucket.try_take(&budget, start).unwrap_err();
+        assert_eq!(retry_after.as_secs(), 20);
+
+        // One token every 20 seconds
+        assert!(bucket.try_take(&budget, start + Duration::from_secs(19)).is_err());
+        assert!(bucket.try_take(&budget, start + Duration::from_secs(21)).is_ok());
+        assert!(bucket.try_take(&budget, start + Duration::from_secs(22)).is_err());
+    }
+
+    #[test]
+    fn bucket_never_exceeds_capacity() {
+        let budget = Budget::new(2, 60);
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(&budget, start);
+        assert!(bucket.is_full(&budget, start));
+
+        let later = start + Duration::from_secs(3600);
+        assert!(bucket.try_take(&budget, later).is_ok());
+        assert!(bucket.try_take(&budget, later).is_ok());
+        assert!(bucket.try_take(&budget, later).is_err());
+    }
+
+    #[test]
+    fn limiter_tracks_clients_separately() {
+        let limiter = limiter(Budget::new(1, 60));
+        let now = Instant::now();
+
+        assert!(limiter.check(&Method::POST, "/api/v1/auth/login", ip(1), now).is_ok());
+        assert!(limiter.check(&Method::POST, "/api/v1/auth/login", ip(1), now).is_err());
+        assert!(limiter.check(&Method::POST, "/api/v1/auth/login", ip(2), now).is_ok());
+    }
+
+    #[test]
+    fn limiter_ignores_other_routes() {
+        let limiter = limiter(Budget::new(1, 60));
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            assert!(limiter.check(&Method::GET, "/api/v1/auth/login", ip(1), now).is_ok());
+            assert!(limiter.check(&Method::POST, "/api/v1/auth/logout", ip(1), now).is_ok());
+        }
+    }
+
+    #[test]
+    fn sweep_drops_refilled_buckets() {
+        let limiter = limiter(Budget::new(1, 60));
+        let now = Instant::now();
+        limiter.check(&Method::POST, "/api/v1/auth/login", ip(1), now).unwrap();
+
+        let later = now + SWEEP_INTERVAL + Duration::from_secs(1);
+        limiter.check(&Method::POST, "/api/v1/auth/login", ip(2), later).unwrap();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(!buckets.by_client.contains_key(&(0, ip(1))));
+        assert!(buckets.by_client.contains_key(&(0, ip(2))));
+    }
+}
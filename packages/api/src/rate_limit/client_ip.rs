@@ -0,0 +1,128 @@
+use std::net::IpAddr;
+
+/// An IP network in CIDR notation; a bare address is a single-host network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    pub fn parse(value: &str) -> anyhow::Result<IpNet> {
+        let value = value.trim();
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>()?, Some(prefix.parse::<u8>()?)),
+            None => (value.parse::<IpAddr>()?, None),
+        };
+
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        anyhow::ensure!(prefix <= max, "prefix /{} is too long for {}", prefix, addr);
+
+        Ok(IpNet { addr, prefix })
+    }
+
+    /// Comma-separated list, empty entries ignored.
+    pub fn parse_list(value: &str) -> anyhow::Result<Vec<IpNet>> {
+        value.split(',').filter(|s| !s.trim().is_empty()).map(IpNet::parse).collect()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The address a request is attributed to.
+///
+/// The proxy header is only believed when the connection comes from a trusted proxy; anyone
+/// else could set it to dodge the limits. Its last entry is used, which for `X-Forwarded-For`
+/// is the one appended by the trusted proxy itself.
+pub fn resolve_client_ip(
+    peer: IpAddr,
+    proxy_header_value: Option<&str>,
+    trusted_proxies: &[IpNet],
+) -> IpAddr {
+    let peer = peer.to_canonical();
+    if !trusted_proxies.iter().any(|net| net.contains(peer)) {
+        return peer;
+    }
+
+    proxy_header_value
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+        .map(|ip| ip.to_canonical())
+        .unwrap_or(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_networks() {
+        assert!(IpNet::parse("10.0.0.0/8").is_ok());
+        assert!(IpNet::parse("::1").is_ok());
+        assert!(IpNet::parse("fd00::/8").is_ok());
+        assert!(IpNet::parse("10.0.0.0/33").is_err());
+        assert!(IpNet::parse("nginx").is_err());
+        assert_eq!(IpNet::parse_list("127.0.0.1, 172.16.0.0/12,").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn network_membership() {
+        let private = IpNet::parse("172.16.0.0/12").unwrap();
+        assert!(private.contains(ip("172.20.1.5")));
+        assert!(!private.contains(ip("172.32.0.1")));
+        assert!(!private.contains(ip("::1")));
+        // IPv4-mapped IPv6 peers, as seen on a dual-stack listener
+        assert!(private.contains(ip("::ffff:172.20.1.5")));
+
+        assert!(IpNet::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+        assert!(IpNet::parse("::1").unwrap().contains(ip("::1")));
+        assert!(!IpNet::parse("::1").unwrap().contains(ip("::2")));
+    }
+
+    #[test]
+    fn header_ignored_from_untrusted_peer() {
+        let trusted = IpNet::parse_list("172.16.0.0/12").unwrap();
+        assert_eq!(
+            resolve_client_ip(ip("203.0.113.9"), Some("1.2.3.4"), &trusted),
+            ip("203.0.113.9")
+        );
+        assert_eq!(resolve_client_ip(ip("203.0.113.9"), Some("1.2.3.4"), &[]), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn header_used_from_trusted_peer() {
+        let trusted = IpNet::parse_list("172.16.0.0/12").unwrap();
+        assert_eq!(
+            resolve_client_ip(ip("172.18.0.3"), Some("198.51.100.7"), &trusted),
+            ip("198.51.100.7")
+        );
+        // Forged entries prepended by the client are skipped
+        assert_eq!(
+            resolve_client_ip(ip("172.18.0.3"), Some("1.1.1.1, 198.51.100.7"), &trusted),
+            ip("198.51.100.7")
+        );
+        // Missing or garbled header: fall back to the proxy's address
+        assert_eq!(resolve_client_ip(ip("172.18.0.3"), None, &trusted), ip("172.18.0.3"));
+        assert_eq!(
+            resolve_client_ip(ip("172.18.0.3"), Some("unknown"), &trusted),
+            ip("172.18.0.3")
+        );
+    }
+}
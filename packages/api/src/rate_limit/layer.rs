@@ -0,0 +1,56 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header::RETRY_AFTER, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use super::{resolve_client_ip, RateLimiter};
+use crate::error::{ApiError, ErrorCode};
+
+/// Client address resolved by the rate limiter, stored in the request extensions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Axum middleware: resolve the client IP, then spend from the matching budget.
+///
+/// Needs the server to be started with `into_make_service_with_connect_info::<SocketAddr>()`;
+/// without a peer address requests are let through unlimited.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(peer) = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0.ip()) else {
+        return next.run(request).await;
+    };
+
+    let config = limiter.config();
+    let header_value = config
+        .trusted_proxy_header
+        .as_ref()
+        .and_then(|name| request.headers().get(name))
+        .and_then(|value| value.to_str().ok());
+    let client_ip = resolve_client_ip(peer, header_value, &config.trusted_proxies);
+    request.extensions_mut().insert(ClientIp(client_ip));
+
+    if let Err(retry_after) =
+        limiter.check(request.method(), request.uri().path(), client_ip, Instant::now())
+    {
+        return too_many_requests(retry_after);
+    }
+
+    next.run(request).await
+}
+
+/// 429 with the body of server function errors, so the UI shows the message.
+fn too_many_requests(retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let message = format!("Too many requests. Try again in {} seconds.", seconds);
+
+    let mut response = ApiError::new(ErrorCode::RateLimited, message).into_response();
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
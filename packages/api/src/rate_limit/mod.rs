@@ -0,0 +1,118 @@
+//! Per-IP rate limiting for the endpoints that are expensive or guess-able.
+//!
+//! Each rule gives every client IP a token bucket. Requests beyond the budget get a
+//! `429 Too Many Requests` with a `Retry-After` header. Budgets are overridden with
+//! `RATE_LIMIT_<RULE>=<requests>/<seconds>` (or `off`), e.g. `RATE_LIMIT_LOGIN=10/60`.
+mod bucket;
+mod client_ip;
+mod layer;
+
+pub use bucket::{Budget, RateLimiter, TokenBucket};
+pub use client_ip::{resolve_client_ip, IpNet};
+pub use layer::{rate_limit, ClientIp};
+
+use anyhow::Context;
+use axum::http::{HeaderName, Method};
+
+use crate::config::Source;
+
+/// Proxies trusted by default once `TRUSTED_PROXY_HEADER` is set: the local machine only.
+const DEFAULT_TRUSTED_PROXIES: &str = "127.0.0.0/8,::1";
+
+#[derive(Debug, Clone)]
+pub struct RateLimitRule {
+    pub name: &'static str,
+    pub method: Method,
+    pub paths: Vec<&'static str>,
+    pub budget: Budget,
+}
+
+impl RateLimitRule {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method == method && self.paths.contains(&path)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub rules: Vec<RateLimitRule>,
+    /// Header carrying the client address, e.g. `X-Real-IP`. Ignored when unset.
+    pub trusted_proxy_header: Option<HeaderName>,
+    /// Peers allowed to set `trusted_proxy_header`.
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+/// Built-in budgets, per client IP.
+pub fn default_rules() -> Vec<RateLimitRule> {
+    let rule = |name, paths, requests, period_secs| RateLimitRule {
+        name,
+        method: Method::POST,
+        paths,
+        budget: Budget::new(requests, period_secs),
+    };
+
+    vec![
+        rule(
+            "login",
+            vec![
+                "/api/v1/auth/login",
+                "/api/v1/auth/login/two-factor",
+                "/api/v1/auth/oidc/start",
+                "/api/v1/auth/oidc/callback",
+            ],
+            5,
+            60,
+        ),
+        rule("register", vec!["/api/v1/auth/register"], 10, 3600),
+        // Each request sends an email
+        rule(
+            "account_email",
+            vec!["/api/v1/auth/password-reset/request", "/api/v1/auth/verify-email/request"],
+            5,
+            3600,
+        ),
+        rule(
+            "account_token",
+            vec!["/api/v1/auth/password-reset", "/api/v1/auth/verify-email"],
+            10,
+            600,
+        ),
+        // Outbound calls to Tricount and a fresh RSA key per request
+        rule("import", vec!["/api/v1/import/tricount"], 5, 600),
+    ]
+}
+
+impl RateLimitConfig {
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let mut rules = Vec::new();
+        for mut rule in default_rules() {
+            let var = format!("RATE_LIMIT_{}", rule.name.to_uppercase());
+            let budget = match source.var(&var) {
+                Some(value) => Budget::parse(&value).with_context(|| format!("Invalid {}", var))?,
+                None => Some(rule.budget),
+            };
+            if let Some(budget) = budget {
+                rule.budget = budget;
+                rules.push(rule);
+            }
+        }
+
+        let trusted_proxy_header = source
+            .var("TRUSTED_PROXY_HEADER")
+            .map(HeaderName::try_from)
+            .transpose()
+            .context("Invalid TRUSTED_PROXY_HEADER")?;
+
+        let trusted_proxies = match &trusted_proxy_header {
+            Some(_) => IpNet::parse_list(
+                &source
+                    .var("TRUSTED_PROXIES")
+                    .unwrap_or_else(|| DEFAULT_TRUSTED_PROXIES.to_string()),
+            )
+            .context("Invalid TRUSTED_PROXIES")?,
+            None => Vec::new(),
+        };
+
+        Ok(Self { rules, trusted_proxy_header, trusted_proxies })
+    }
+}
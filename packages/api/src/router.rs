@@ -0,0 +1,31 @@
+//! The middleware around the server functions, shared by the server binary and the tests that
+//! call it over HTTP.
+use std::sync::Arc;
+
+use axum::routing::get;
+use axum::{middleware, Extension, Router};
+
+use crate::auth::api_tokens::api_token_auth;
+use crate::idempotency::idempotency;
+use crate::rate_limit::{rate_limit, RateLimiter};
+use crate::state::AppState;
+use crate::{health, metrics, telemetry};
+
+/// Wrap `router`, the application and its server functions, in the middleware of the API.
+///
+/// Probes and scrapes are added after the layers, so they skip authentication and rate limiting.
+/// Serve it with `into_make_service_with_connect_info::<SocketAddr>()` for the rate limiter.
+pub fn with_api_layers(router: Router, state: AppState) -> Router {
+    let limiter = Arc::new(RateLimiter::new(state.config.rate_limit.clone()));
+
+    router
+        .layer(middleware::from_fn_with_state(state.clone(), idempotency))
+        .layer(middleware::from_fn_with_state(state.clone(), api_token_auth))
+        .layer(middleware::from_fn_with_state(limiter, rate_limit))
+        .layer(middleware::from_fn(metrics::track_requests))
+        .layer(Extension(state.clone()))
+        .layer(middleware::from_fn(telemetry::trace_requests))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz).with_state(state.clone()))
+        .route("/metrics", get(metrics::metrics_handler).with_state(state))
+}
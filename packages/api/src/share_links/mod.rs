@@ -0,0 +1,4 @@
+pub mod share_links_controller;
+pub mod share_links_repository;
+#[cfg(feature = "server")]
+mod share_links_repository_sqlite;
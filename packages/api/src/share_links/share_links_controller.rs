@@ -0,0 +1,146 @@
+use dioxus::prelude::*;
+use shared::{CreatedShareLink, PublicProjectView, ShareLink};
+#[cfg(feature = "server")]
+use shared::{Expense, ProjectDto, PublicExpense, User, UserSummary};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+#[cfg(feature = "server")]
+use crate::db::get_db;
+#[cfg(feature = "server")]
+use crate::payments::payments_repository;
+#[cfg(feature = "server")]
+use crate::projects::project_access::{authorize, ProjectAction};
+#[cfg(feature = "server")]
+use crate::utils::{generate_token, sha256_hex};
+
+#[post("/api/v1/projects/{project_id}/share-links")]
+pub async fn create_share_link(project_id: Uuid) -> Result<CreatedShareLink, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let access = authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+
+    let token = generate_token();
+    let link = tx.create_share_link(project_id, &sha256_hex(&token), access.account_id).await?;
+
+    tx.commit().await?;
+
+    Ok(CreatedShareLink { link, token })
+}
+
+#[get("/api/v1/projects/{project_id}/share-links")]
+pub async fn get_share_links(project_id: Uuid) -> Result<Vec<ShareLink>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+
+    let links = tx.get_active_share_links(project_id).await?;
+
+    tx.commit().await?;
+
+    Ok(links)
+}
+
+#[delete("/api/v1/projects/{project_id}/share-links/{link_id}")]
+pub async fn revoke_share_link(project_id: Uuid, link_id: Uuid) -> Result<(), ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::ManageMembers).await?;
+
+    if !tx.revoke_share_link(project_id, link_id).await? {
+        return Err(ApiError::not_found("Share link not found"));
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Read-only view of a project for anyone holding a share link.
+///
+/// The token grants no membership and no session: every mutation endpoint keeps rejecting its
+/// holder like any other anonymous caller.
+#[get("/api/v1/public/{token}")]
+pub async fn get_public_project(token: String) -> Result<PublicProjectView, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let project_id = tx
+        .find_shared_project_id(&sha256_hex(&token))
+        .await?
+        .ok_or_else(|| ApiError::not_found("Invalid share link"))?;
+
+    let project = tx.get_project(project_id).await?;
+    let users = tx.get_users_by_project_id(project_id).await?;
+    let expenses = tx.get_expenses_by_project_id(project_id).await?;
+    let summary = payments_repository::get_summary_by_project_id(&mut *tx, project_id).await?;
+
+    tx.commit().await?;
+
+    Ok(public_view(project, users, expenses, summary))
+}
+
+#[cfg(feature = "server")]
+fn public_view(
+    project: ProjectDto,
+    users: Vec<User>,
+    expenses: Vec<Expense>,
+    summary: UserSummary,
+) -> PublicProjectView {
+    PublicProjectView {
+        name: project.name,
+        description: project.description,
+        currency: project.currency,
+        status: project.status,
+        users,
+        expenses: expenses.into_iter().map(PublicExpense::from).collect(),
+        summary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use shared::{ExpenseType, ProjectStatus};
+
+    #[test]
+    fn the_public_view_never_contains_the_project_id() {
+        let project_id = Uuid::new_v4();
+        let created_at = NaiveDate::from_ymd_opt(2026, 7, 1).unwrap().into();
+        let project = ProjectDto {
+            id: project_id,
+            name: "Vacances".to_string(),
+            created_at,
+            currency: "EUR".to_string(),
+            description: None,
+            status: ProjectStatus::Ongoing,
+            owner_account_id: None,
+            editors_can_edit_others_expenses: true,
+            version: 1,
+        };
+        let expense = Expense {
+            id: 1,
+            author_id: 1,
+            project_id,
+            created_at,
+            date: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            amount: 30.0,
+            description: None,
+            name: "Courses".to_string(),
+            expense_type: ExpenseType::Expense,
+            version: 1,
+        };
+        let summary =
+            UserSummary { reimbursement_suggestions: Vec::new(), summary: Default::default() };
+
+        let view = public_view(project, Vec::new(), vec![expense], summary);
+        let json = serde_json::to_string(&view).unwrap();
+        assert!(!json.contains(&project_id.to_string()), "{}", json);
+        assert!(!json.contains(&project_id.simple().to_string()), "{}", json);
+    }
+}
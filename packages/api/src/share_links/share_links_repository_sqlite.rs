@@ -0,0 +1,95 @@
+use chrono::Utc;
+use futures::future::BoxFuture;
+use shared::ShareLink;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+use super::share_links_repository::ShareLinksRepository;
+use crate::error::{db_error, ApiError};
+
+impl ShareLinksRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn create_share_link<'a>(
+        &'a mut self,
+        project_id: Uuid,
+        token_hash: &'a str,
+        created_by: Option<Uuid>,
+    ) -> BoxFuture<'a, Result<ShareLink, ApiError>> {
+        Box::pin(async move {
+            let link = sqlx::query_as(
+                "INSERT INTO project_share_links (id, project_id, token_hash, created_by)
+                 VALUES ($1, $2, $3, $4)
+                 RETURNING id, project_id, created_at",
+            )
+            .bind(Uuid::new_v4())
+            .bind(project_id)
+            .bind(token_hash)
+            .bind(created_by)
+            .fetch_one(&mut *self)
+            .await
+            .map_err(db_error("Failed to create share link"))?;
+
+            Ok(link)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_active_share_links(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<ShareLink>, ApiError>> {
+        Box::pin(async move {
+            let links = sqlx::query_as(
+                "SELECT id, project_id, created_at FROM project_share_links
+                 WHERE project_id = $1 AND revoked_at IS NULL
+                 ORDER BY created_at DESC",
+            )
+            .bind(project_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get share links"))?;
+
+            Ok(links)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_shared_project_id<'a>(
+        &'a mut self,
+        token_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Uuid>, ApiError>> {
+        Box::pin(async move {
+            let project_id = sqlx::query_scalar(
+                "SELECT project_id FROM project_share_links WHERE token_hash = $1 AND revoked_at IS NULL",
+            )
+            .bind(token_hash)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to find share link"))?;
+
+            Ok(project_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn revoke_share_link(
+        &mut self,
+        project_id: Uuid,
+        link_id: Uuid,
+    ) -> BoxFuture<'_, Result<bool, ApiError>> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "UPDATE project_share_links SET revoked_at = $1
+                 WHERE id = $2 AND project_id = $3 AND revoked_at IS NULL",
+            )
+            .bind(Utc::now().naive_utc())
+            .bind(link_id)
+            .bind(project_id)
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to revoke share link"))?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+}
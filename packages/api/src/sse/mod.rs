@@ -1,2 +1,3 @@
+pub mod project_events_controller;
 mod sse;
 pub use sse::*;
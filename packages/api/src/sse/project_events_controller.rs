@@ -0,0 +1,40 @@
+use dioxus::fullstack::ServerEvents;
+use dioxus::prelude::*;
+use shared::sse::ProjectEvent;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+#[cfg(feature = "server")]
+use crate::db::get_db;
+#[cfg(feature = "server")]
+use crate::projects::project_access::{authorize, ProjectAction};
+#[cfg(feature = "server")]
+use tokio::sync::broadcast::error::RecvError;
+
+/// Stream the changes to a project as server-sent events, one `ProjectEvent` each, until the
+/// client disconnects.
+#[get("/api/v1/projects/{project_id}/events")]
+pub async fn get_project_events(project_id: Uuid) -> Result<ServerEvents<ProjectEvent>, ApiError> {
+    let pool = get_db();
+    let mut conn = pool.acquire().await?;
+
+    authorize(&mut *conn, project_id, ProjectAction::Read).await?;
+    drop(conn);
+
+    let mut events = super::subscribe();
+
+    Ok(ServerEvents::new(move |mut tx| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) if event.project_id == project_id => {
+                    if tx.send(event).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }))
+}
@@ -2,6 +2,11 @@ use dioxus::prelude::*;
 use once_cell::sync::Lazy;
 use std::{convert::Infallible, sync::Arc};
 
+#[cfg(feature = "server")]
+use shared::sse::{EventSSE, ProjectEvent};
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
 #[cfg(feature = "server")]
 use axum::response::sse::{Event, KeepAlive, Sse};
 #[cfg(feature = "server")]
@@ -51,6 +56,13 @@ impl Broadcaster {
         // retain only connected clients
         clients.retain(|client| client.try_send(event.clone()).is_ok());
     }
+
+    /// Number of clients still connected
+    pub async fn client_count(&self) -> usize {
+        let mut clients = self.clients.lock().await;
+        clients.retain(|client| !client.is_closed());
+        clients.len()
+    }
 }
 
 /// SSE handler: subscribes a client to the broadcaster
@@ -60,3 +72,27 @@ pub async fn sse_handler() -> Sse<impl Stream<Item = Result<Event, Infallible>>>
 
     Sse::new(stream).keep_alive(KeepAlive::default())
 }
+
+#[cfg(feature = "server")]
+static PROJECT_EVENTS: Lazy<tokio::sync::broadcast::Sender<ProjectEvent>> =
+    Lazy::new(|| tokio::sync::broadcast::channel(256).0);
+
+/// Tell the clients following `project_id` that it changed. Call after the change is committed.
+#[cfg(feature = "server")]
+pub fn publish(project_id: Uuid, event: EventSSE) {
+    // No receiver just means nobody is following
+    let _ = PROJECT_EVENTS.send(ProjectEvent { project_id, event });
+}
+
+/// Changes to every project, in the order they were published. A receiver that falls more than
+/// 256 events behind skips the oldest ones.
+#[cfg(feature = "server")]
+pub fn subscribe() -> tokio::sync::broadcast::Receiver<ProjectEvent> {
+    PROJECT_EVENTS.subscribe()
+}
+
+/// Number of open project event streams.
+#[cfg(feature = "server")]
+pub fn follower_count() -> usize {
+    PROJECT_EVENTS.receiver_count()
+}
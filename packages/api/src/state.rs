@@ -0,0 +1,31 @@
+//! State shared by every request, built once at startup.
+use std::sync::Arc;
+
+use dioxus_fullstack::FullstackContext;
+
+use crate::config::Config;
+use crate::db::Db;
+use crate::mailer::{self, MailTransport};
+
+/// Added to the router as an `Extension`, so server functions find it in the request and
+/// middleware can take it with `from_fn_with_state`.
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Db,
+    pub config: Arc<Config>,
+    pub mailer: Arc<dyn MailTransport>,
+}
+
+impl AppState {
+    pub fn new(db: Db, config: Config) -> anyhow::Result<Self> {
+        let mailer = mailer::transport(&config.mail)?;
+        Ok(Self { db, config: Arc::new(config), mailer })
+    }
+
+    /// State of the server handling the current server function call.
+    pub fn current() -> Option<Self> {
+        let ctx = FullstackContext::current()?;
+        let state = ctx.parts_mut().extensions.get::<AppState>().cloned();
+        state
+    }
+}
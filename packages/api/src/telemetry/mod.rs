@@ -0,0 +1,70 @@
+//! Logs and request tracing.
+//!
+//! Every request runs in a `request` span with its ID, method and route, and repository calls
+//! open a span per query, so a log line says which request and which query it comes from.
+//! `LOG_FORMAT` chooses `pretty` (default, one line per event) or `json` output, and `RUST_LOG`
+//! filters it (default `info`; `RUST_LOG=info,sqlx=debug` adds every SQL statement).
+//!
+//! Output goes through [`redact`]: email addresses and fields named like passwords, secrets or
+//! tokens are masked whatever their origin.
+mod layer;
+mod redact;
+
+pub use layer::{trace_requests, RequestId, REQUEST_ID_HEADER};
+pub use redact::{redact, redact_links, redact_path};
+
+use anyhow::Context;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Source;
+use redact::Redacting;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => anyhow::bail!("expected pretty or json"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    /// `RUST_LOG` directives
+    pub filter: String,
+}
+
+impl LogConfig {
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let filter = source.var("RUST_LOG").unwrap_or_else(|| "info".to_string());
+        EnvFilter::try_new(&filter).context("Invalid RUST_LOG")?;
+
+        Ok(Self { format: source.parse("LOG_FORMAT", LogFormat::Pretty)?, filter })
+    }
+}
+
+/// Install the global subscriber. Call once, before anything logs.
+pub fn init(config: &LogConfig) -> anyhow::Result<()> {
+    let filter = EnvFilter::try_new(&config.filter).context("Invalid RUST_LOG")?;
+    // Colours would split field names from their values and defeat the redaction
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(false)
+        .with_writer(Redacting(std::io::stdout));
+
+    let result = match config.format {
+        LogFormat::Pretty => builder.try_init(),
+        LogFormat::Json => builder.json().with_current_span(false).with_span_list(true).try_init(),
+    };
+    result.map_err(|e| anyhow::anyhow!("Failed to install the logger: {}", e))
+}
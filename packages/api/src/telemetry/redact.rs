@@ -0,0 +1,257 @@
+use std::borrow::Cow;
+use std::io;
+
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Fields whose value is never written, matched anywhere in the field name.
+const SENSITIVE_KEYS: &[&str] = &["password", "secret", "token", "cookie", "authorization", "otp"];
+
+/// Page paths whose last segment is a secret token.
+const TOKEN_PATHS: &[&str] = &["/join/", "/share/", "/reset-password/", "/verify-email/"];
+
+const REDACTED: &str = "[redacted]";
+
+/// Hide the sensitive parts of a log line: the value of fields named like [`SENSITIVE_KEYS`]
+/// (`password="…"` or `"password":"…"`), and the local part of email addresses.
+pub fn redact(line: &str) -> Cow<'_, str> {
+    match redact_fields(line) {
+        Cow::Borrowed(line) => redact_emails(line),
+        Cow::Owned(line) => Cow::Owned(redact_emails(&line).into_owned()),
+    }
+}
+
+/// A request path safe to log: tokens in invitation, share and account links are hidden.
+pub fn redact_path(path: &str) -> Cow<'_, str> {
+    match TOKEN_PATHS.iter().find(|prefix| path.starts_with(*prefix)) {
+        Some(prefix) => Cow::Owned(format!("{}{}", prefix, REDACTED)),
+        None => Cow::Borrowed(path),
+    }
+}
+
+/// Text safe to log, such as an email body: the token of every invitation, share and account
+/// link is hidden.
+pub fn redact_links(text: &str) -> Cow<'_, str> {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some((start, prefix)) =
+        TOKEN_PATHS.iter().filter_map(|prefix| rest.find(prefix).map(|i| (i, *prefix))).min()
+    {
+        let token_start = start + prefix.len();
+        let token_end =
+            rest[token_start..].find(char::is_whitespace).map_or(rest.len(), |i| token_start + i);
+        out.push_str(&rest[..token_start]);
+        out.push_str(REDACTED);
+        rest = &rest[token_end..];
+    }
+
+    if out.is_empty() {
+        Cow::Borrowed(text)
+    } else {
+        out.push_str(rest);
+        Cow::Owned(out)
+    }
+}
+
+fn is_key_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'.'
+}
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEYS.iter().any(|sensitive| key.contains(sensitive))
+}
+
+fn redact_fields(line: &str) -> Cow<'_, str> {
+    let bytes = line.as_bytes();
+    let mut out = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !is_key_char(bytes[i]) || (i > 0 && is_key_char(bytes[i - 1])) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && is_key_char(bytes[i]) {
+            i += 1;
+        }
+        let key = &line[start..i];
+
+        // `key=value` in text logs, `"key":value` in JSON
+        let value_start = if bytes.get(i) == Some(&b'=') {
+            i + 1
+        } else if start > 0 && bytes[start - 1] == b'"' && line[i..].starts_with("\":") {
+            i + 2
+        } else {
+            continue;
+        };
+        if !is_sensitive(key) {
+            continue;
+        }
+
+        let value_end = value_end(bytes, value_start);
+        let quoted = bytes.get(value_start) == Some(&b'"');
+        out.push_str(&line[copied..value_start]);
+        if quoted {
+            out.push('"');
+            out.push_str(REDACTED);
+            out.push('"');
+        } else {
+            out.push_str(REDACTED);
+        }
+        copied = value_end;
+        i = value_end;
+    }
+
+    if copied == 0 {
+        Cow::Borrowed(line)
+    } else {
+        out.push_str(&line[copied..]);
+        Cow::Owned(out)
+    }
+}
+
+/// End of the value starting at `start`: after the closing quote of a string, or at the next
+/// separator.
+fn value_end(bytes: &[u8], start: usize) -> usize {
+    if bytes.get(start) == Some(&b'"') {
+        let mut i = start + 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return i + 1,
+                _ => i += 1,
+            }
+        }
+        return bytes.len();
+    }
+    bytes[start..]
+        .iter()
+        .position(|c| c.is_ascii_whitespace() || matches!(c, b',' | b'}'))
+        .map_or(bytes.len(), |end| start + end)
+}
+
+fn is_local_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'.' | b'_' | b'%' | b'+' | b'-')
+}
+
+fn is_domain_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'.' | b'-')
+}
+
+/// `alice@example.com` becomes `***@example.com`.
+fn redact_emails(line: &str) -> Cow<'_, str> {
+    let bytes = line.as_bytes();
+    let mut out = String::new();
+    let mut copied = 0;
+
+    for (at, _) in line.match_indices('@') {
+        let local_start = bytes[..at].iter().rposition(|c| !is_local_char(*c)).map_or(0, |i| i + 1);
+        let domain_end = bytes[at + 1..]
+            .iter()
+            .position(|c| !is_domain_char(*c))
+            .map_or(bytes.len(), |i| at + 1 + i);
+        let domain = line[at + 1..domain_end].trim_end_matches('.');
+        if local_start == at || local_start < copied || !domain.contains('.') {
+            continue;
+        }
+        out.push_str(&line[copied..local_start]);
+        out.push_str("***");
+        copied = at;
+    }
+
+    if copied == 0 {
+        Cow::Borrowed(line)
+    } else {
+        out.push_str(&line[copied..]);
+        Cow::Owned(out)
+    }
+}
+
+/// Writer for the log output that passes every line through [`redact`].
+///
+/// The formatter writes each event with a single call, so a line is never split between writes.
+pub struct RedactingWriter<W>(W);
+
+impl<W: io::Write> io::Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        self.0.write_all(redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// [`MakeWriter`] wrapping another one with [`RedactingWriter`].
+pub struct Redacting<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emails_keep_their_domain() {
+        assert_eq!(
+            redact("Failed to send email to alice.martin+trip@example.com: timeout"),
+            "Failed to send email to ***@example.com: timeout"
+        );
+        assert_eq!(
+            redact(r#"{"to":"bob@mail.example.org","cc":"eve@example.com"}"#),
+            r#"{"to":"***@mail.example.org","cc":"***@example.com"}"#
+        );
+    }
+
+    #[test]
+    fn at_signs_that_are_not_emails_stay() {
+        assert_eq!(redact("user@localhost and @mention"), "user@localhost and @mention");
+    }
+
+    #[test]
+    fn sensitive_text_fields_are_hidden() {
+        assert_eq!(
+            redact(r#"INFO sign-in password="hunter2 x" session_token=abc123 user_id=4"#),
+            r#"INFO sign-in password="[redacted]" session_token=[redacted] user_id=4"#
+        );
+    }
+
+    #[test]
+    fn sensitive_json_fields_are_hidden() {
+        assert_eq!(
+            redact(r#"{"fields":{"new_password":"a\"b","api_token":42,"route":"/login"}}"#),
+            r#"{"fields":{"new_password":"[redacted]","api_token":[redacted],"route":"/login"}}"#
+        );
+    }
+
+    #[test]
+    fn clean_lines_are_not_copied() {
+        assert!(matches!(redact("request completed status=200"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn tokens_in_page_paths_are_hidden() {
+        assert_eq!(redact_path("/reset-password/8f3a9c"), "/reset-password/[redacted]");
+        assert_eq!(redact_path("/projects/42"), "/projects/42");
+    }
+
+    #[test]
+    fn tokens_in_links_are_hidden() {
+        assert_eq!(
+            redact_links("Open https://x.example/verify-email/8f3a9c\nor https://x.example/join/b2 now"),
+            "Open https://x.example/verify-email/[redacted]\nor https://x.example/join/[redacted] now"
+        );
+        assert!(matches!(redact_links("See https://x.example/projects/42"), Cow::Borrowed(_)));
+    }
+}
@@ -1,3 +1,6 @@
-pub mod tricount_controller;
 mod tricount_client;
+pub mod tricount_controller;
 mod tricount_models;
+
+#[cfg(feature = "server")]
+pub use tricount_client::TricountConfig;
@@ -1,17 +1,35 @@
-use dioxus::prelude::*;
 use super::tricount_models::{TricountApiResponse, TricountRegistry, TricountResponseItem};
+use dioxus::prelude::*;
+
+#[cfg(feature = "server")]
+use crate::config::{get_config, Source};
+#[cfg(feature = "server")]
+use crate::error::ApiError;
 
-const TRICOUNT_API_BASE: &str = "https://api.tricount.bunq.com";
+#[cfg(feature = "server")]
+const DEFAULT_API_URL: &str = "https://api.tricount.bunq.com";
 const USER_AGENT: &str = "com.bunq.tricount.android:RELEASE:7.0.7:3174:ANDROID:13:C";
 
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TricountConfig {
+    /// Base URL of the Tricount API, without a trailing `/`
+    pub api_url: String,
+}
+
+#[cfg(feature = "server")]
+impl TricountConfig {
+    /// `TRICOUNT_API_URL`, pointed at a stub in tests and staging.
+    pub fn from_source(source: &Source) -> anyhow::Result<Self> {
+        let api_url = source.var("TRICOUNT_API_URL").unwrap_or_else(|| DEFAULT_API_URL.to_string());
+        Ok(Self { api_url: api_url.trim_end_matches('/').to_string() })
+    }
+}
+
 pub fn extract_tricount_key(input: &str) -> String {
     let trimmed = input.trim();
     if trimmed.contains('/') {
-        trimmed
-            .rsplit('/')
-            .find(|s| !s.is_empty())
-            .unwrap_or(trimmed)
-            .to_string()
+        trimmed.rsplit('/').find(|s| !s.is_empty()).unwrap_or(trimmed).to_string()
     } else {
         trimmed.to_string()
     }
@@ -24,17 +42,21 @@ struct AuthResult {
 }
 
 #[cfg(feature = "server")]
-async fn authenticate(client: &reqwest::Client, app_uuid: &str) -> Result<AuthResult, ServerFnError> {
+async fn authenticate(
+    client: &reqwest::Client,
+    api_url: &str,
+    app_uuid: &str,
+) -> Result<AuthResult, ApiError> {
     use rsa::pkcs8::EncodePublicKey;
     use rsa::RsaPrivateKey;
 
     let mut rng = rand::thread_rng();
     let private_key = RsaPrivateKey::new(&mut rng, 2048)
-        .map_err(|e| ServerFnError::new(format!("RSA key generation failed: {e}")))?;
+        .map_err(|e| ApiError::internal(format!("RSA key generation failed: {e}")))?;
     let public_key_pem = private_key
         .to_public_key()
         .to_public_key_pem(rsa::pkcs8::LineEnding::LF)
-        .map_err(|e| ServerFnError::new(format!("PEM encoding failed: {e}")))?;
+        .map_err(|e| ApiError::internal(format!("PEM encoding failed: {e}")))?;
 
     let body = serde_json::json!({
         "app_installation_uuid": app_uuid,
@@ -43,19 +65,19 @@ async fn authenticate(client: &reqwest::Client, app_uuid: &str) -> Result<AuthRe
     });
 
     let resp = client
-        .post(format!("{TRICOUNT_API_BASE}/v1/session-registry-installation"))
+        .post(format!("{api_url}/v1/session-registry-installation"))
         .header("User-Agent", USER_AGENT)
         .header("app-id", app_uuid)
         .header("X-Bunq-Client-Request-Id", uuid::Uuid::new_v4().to_string())
         .json(&body)
         .send()
         .await
-        .map_err(|e| ServerFnError::new(format!("Tricount auth request failed: {e}")))?;
+        .map_err(|e| ApiError::internal(format!("Tricount auth request failed: {e}")))?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(ServerFnError::new(format!(
+        return Err(ApiError::internal(format!(
             "Tricount authentication failed ({status}): {text}"
         )));
     }
@@ -63,20 +85,16 @@ async fn authenticate(client: &reqwest::Client, app_uuid: &str) -> Result<AuthRe
     let json: serde_json::Value = resp
         .json()
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to parse auth response: {e}")))?;
+        .map_err(|e| ApiError::internal(format!("Failed to parse auth response: {e}")))?;
 
     let response_arr = json["Response"]
         .as_array()
-        .ok_or_else(|| ServerFnError::new("Invalid auth response format".to_string()))?;
+        .ok_or_else(|| ApiError::internal("Invalid auth response format"))?;
 
     let token = response_arr
         .iter()
-        .find_map(|item| {
-            item.get("Token")
-                .and_then(|t| t.get("token"))
-                .and_then(|t| t.as_str())
-        })
-        .ok_or_else(|| ServerFnError::new("No auth token in Tricount response".to_string()))?
+        .find_map(|item| item.get("Token").and_then(|t| t.get("token")).and_then(|t| t.as_str()))
+        .ok_or_else(|| ApiError::internal("No auth token in Tricount response"))?
         .to_string();
 
     // Try multiple user types — anonymous sessions may use UserRegistryAnonymous
@@ -85,19 +103,19 @@ async fn authenticate(client: &reqwest::Client, app_uuid: &str) -> Result<AuthRe
         .find_map(|item| {
             ["UserPerson", "UserRegistryAnonymous", "UserCompany", "UserApiKey"]
                 .iter()
-                .find_map(|key| {
-                    item.get(key)
-                        .and_then(|u| u.get("id"))
-                        .and_then(|id| id.as_i64())
-                })
+                .find_map(|key| item.get(key).and_then(|u| u.get("id")).and_then(|id| id.as_i64()))
         })
         .ok_or_else(|| {
             // Dump raw response to help diagnose
             let keys: Vec<String> = response_arr
                 .iter()
-                .flat_map(|item| item.as_object().map(|o| o.keys().cloned().collect::<Vec<_>>()).unwrap_or_default())
+                .flat_map(|item| {
+                    item.as_object()
+                        .map(|o| o.keys().cloned().collect::<Vec<_>>())
+                        .unwrap_or_default()
+                })
                 .collect();
-            ServerFnError::new(format!(
+            ApiError::internal(format!(
                 "No user ID in Tricount auth response. Response item keys: {:?}",
                 keys
             ))
@@ -107,15 +125,13 @@ async fn authenticate(client: &reqwest::Client, app_uuid: &str) -> Result<AuthRe
 }
 
 #[cfg(feature = "server")]
-pub async fn fetch_tricount(tricount_key: &str) -> Result<TricountRegistry, ServerFnError> {
+pub async fn fetch_tricount(tricount_key: &str) -> Result<TricountRegistry, ApiError> {
     let client = reqwest::Client::new();
+    let api_url = get_config().tricount.api_url.clone();
     let app_uuid = uuid::Uuid::new_v4().to_string();
-    let auth = authenticate(&client, &app_uuid).await?;
+    let auth = authenticate(&client, &api_url, &app_uuid).await?;
 
-    let url = format!(
-        "{TRICOUNT_API_BASE}/v1/user/{}/registry",
-        auth.user_id
-    );
+    let url = format!("{api_url}/v1/user/{}/registry", auth.user_id);
 
     let resp = client
         .get(&url)
@@ -126,20 +142,25 @@ pub async fn fetch_tricount(tricount_key: &str) -> Result<TricountRegistry, Serv
         .header("X-Bunq-Client-Request-Id", uuid::Uuid::new_v4().to_string())
         .send()
         .await
-        .map_err(|e| ServerFnError::new(format!("Tricount API request failed: {e}")))?;
+        .map_err(|e| ApiError::internal(format!("Tricount API request failed: {e}")))?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(ServerFnError::new(format!(
-            "Tricount introuvable ou erreur API ({status}) — URL: {url}?public_identifier_token={tricount_key} — {text}"
-        )));
+        let details = format!(
+            "Tricount API error ({status}) — URL: {url}?public_identifier_token={tricount_key} — {text}"
+        );
+        if status.is_client_error() {
+            tracing::warn!("{details}");
+            return Err(ApiError::not_found("Tricount introuvable"));
+        }
+        return Err(ApiError::internal(details));
     }
 
     let api_response: TricountApiResponse = resp
         .json()
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to parse Tricount data: {e}")))?;
+        .map_err(|e| ApiError::internal(format!("Failed to parse Tricount data: {e}")))?;
 
     let registry = api_response
         .response
@@ -148,7 +169,7 @@ pub async fn fetch_tricount(tricount_key: &str) -> Result<TricountRegistry, Serv
             TricountResponseItem::Registry { registry } => Some(registry),
             _ => None,
         })
-        .ok_or_else(|| ServerFnError::new("No Registry found in Tricount response".to_string()))?;
+        .ok_or_else(|| ApiError::internal("No Registry found in Tricount response"))?;
 
     Ok(registry)
 }
@@ -3,6 +3,8 @@ use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
 use shared::{ProjectDto, User};
 
+use crate::error::ApiError;
+
 #[cfg(feature = "server")]
 use std::collections::HashMap;
 
@@ -16,21 +18,21 @@ use super::tricount_client;
 #[cfg(feature = "server")]
 use crate::db::get_db;
 #[cfg(feature = "server")]
-use crate::expenses::expenses_repository;
-#[cfg(feature = "server")]
-use crate::payments::payments_repository;
-#[cfg(feature = "server")]
-use crate::projects::projects_repository;
+use crate::metrics;
 #[cfg(feature = "server")]
 use crate::users::users_repository;
+#[cfg(feature = "server")]
+use crate::utils::ensure_account_write_allowed;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct TricountImportRequest {
     pub tricount_key: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct TricountImportResponse {
     pub project: ProjectDto,
@@ -41,26 +43,36 @@ pub struct TricountImportResponse {
 #[post("/api/v1/import/tricount")]
 pub async fn import_tricount(
     Json(payload): Json<TricountImportRequest>,
-) -> Result<TricountImportResponse, ServerFnError> {
-    let key = tricount_client::extract_tricount_key(&payload.tricount_key);
+) -> Result<TricountImportResponse, ApiError> {
+    ensure_account_write_allowed()?;
+
+    let result = import(&payload.tricount_key).await;
+    metrics::record_tricount_import(&result);
+    result
+}
+
+/// Fetch the Tricount and store it as a new project.
+#[cfg(feature = "server")]
+async fn import(tricount_key: &str) -> Result<TricountImportResponse, ApiError> {
+    let key = tricount_client::extract_tricount_key(tricount_key);
 
     // Fetch from Tricount API (outside transaction — network call)
     let registry = tricount_client::fetch_tricount(&key).await?;
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
     // Create project
-    let project_id = projects_repository::add_project(
-        &mut *tx,
-        CreatableProject {
-            name: registry.title.clone(),
-            description: Some("Imported from Tricount".to_string()),
-            currency: Some(registry.currency.clone()),
-        },
-        None, // Tricount imports are anonymous (no owner account)
-    )
-    .await?;
+    let project_id = tx
+        .add_project(
+            CreatableProject {
+                name: registry.title.clone(),
+                description: Some("Imported from Tricount".to_string()),
+                currency: Some(registry.currency.clone()),
+            },
+            None, // Tricount imports are anonymous (no owner account)
+        )
+        .await?;
 
     // Extract members and create users
     let members: Vec<_> = registry.memberships.iter().filter_map(|m| m.non_user.as_ref()).collect();
@@ -74,7 +86,8 @@ pub async fn import_tricount(
         })
         .collect();
 
-    let created_users = users_repository::add_users(&mut *tx, creatable_users).await?;
+    // Imported participants have no email address, so there is no invitation to send
+    let (created_users, _) = users_repository::add_users(&mut *tx, creatable_users).await?;
 
     // Build UUID -> user_id mapping
     let uuid_to_user_id: HashMap<String, i32> = members
@@ -151,8 +164,7 @@ pub async fn import_tricount(
         };
 
         // Create expense in DB
-        let expense_id =
-            expenses_repository::add_expense(&mut *tx, creatable_expense.clone()).await?;
+        let expense_id = tx.add_expense(creatable_expense.clone()).await?;
 
         // Create payments (mirrors expenses_controller::add_expense logic)
         let mut payments: Vec<NewPayment> = Vec::new();
@@ -178,13 +190,13 @@ pub async fn import_tricount(
             }
         }
 
-        payments_repository::add_payments(&mut *tx, payments).await?;
+        tx.add_payments(payments).await?;
         expenses_count += 1;
     }
 
-    let project = projects_repository::get_project(&mut *tx, project_id).await?;
+    let project = tx.get_project(project_id).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(TricountImportResponse { project, users: created_users, expenses_count })
 }
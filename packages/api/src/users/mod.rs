@@ -1,2 +1,4 @@
 pub mod users_controller;
 pub mod users_repository;
+#[cfg(feature = "server")]
+mod users_repository_sqlite;
@@ -1,50 +1,67 @@
 use dioxus::{fullstack::Json, prelude::*};
 use uuid::Uuid;
 
+use crate::error::ApiError;
+
+#[cfg(feature = "server")]
+use std::collections::HashSet;
+
 #[cfg(feature = "server")]
 use crate::db::get_db;
 #[cfg(feature = "server")]
-use crate::payments::payments_repository::get_payments_by_user_id;
+use crate::mailer;
+#[cfg(feature = "server")]
+use crate::projects::project_access::{authorize, ProjectAction};
+#[cfg(feature = "server")]
+use crate::sse::publish;
 use crate::users::users_repository;
+#[cfg(feature = "server")]
+use shared::sse::EventSSE;
 use shared::{CreatableUser, CreatableUserBatch, User};
 
 #[get("/api/v1/users")]
-pub async fn get_users() -> Result<Vec<User>, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn get_users() -> Result<Vec<User>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
 
-    let users = users_repository::get_users(&mut *tx).await?;
+    let users = tx.get_users().await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(users)
 }
 
 #[delete("/api/v1/users/{user_id}")]
-pub async fn delete_user(user_id: i32) -> Result<(), ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn delete_user(user_id: i32) -> Result<(), ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let project_ids = tx.get_project_ids_by_user_id(user_id).await?;
+    for project_id in &project_ids {
+        authorize(&mut *tx, *project_id, ProjectAction::ManageParticipants).await?;
+    }
 
-    let payments = get_payments_by_user_id(&mut *tx, user_id)
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let payments = tx.get_payments_by_user_id(user_id).await?;
 
     if !payments.is_empty() {
         // User still has payments → abort with a clear error
-        return Err(ServerFnError::new(
+        return Err(ApiError::conflict(
             "User has existing payments in this project and cannot be removed",
         ));
     }
 
-    users_repository::delete_user(&mut *tx, user_id).await?;
+    tx.delete_user(user_id).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
+    for project_id in project_ids {
+        publish(project_id, EventSSE::UserDeleted);
+    }
 
     Ok(())
 }
 
 #[post("/api/v1/users")]
-pub async fn add_user(Json(payload): Json<CreatableUserBatch>) -> Result<Vec<User>, ServerFnError> {
+pub async fn add_user(Json(payload): Json<CreatableUserBatch>) -> Result<Vec<User>, ApiError> {
     let users: Vec<CreatableUser> = match payload {
         CreatableUserBatch::Single(u) => vec![u],
         CreatableUserBatch::Multiple(v) => v,
@@ -52,27 +69,41 @@ pub async fn add_user(Json(payload): Json<CreatableUserBatch>) -> Result<Vec<Use
 
     const MAX_BATCH: usize = 100;
     if users.len() > MAX_BATCH {
-        return Err(ServerFnError::new(format!("Batch size exceeds {}", MAX_BATCH)));
+        return Err(ApiError::validation(format!("Batch size exceeds {}", MAX_BATCH)));
     }
 
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    let project_ids: HashSet<Uuid> = users.iter().map(|u| u.project_id).collect();
+    for project_id in &project_ids {
+        authorize(&mut *tx, *project_id, ProjectAction::ManageParticipants).await?;
+    }
 
-    let users = users_repository::add_users(&mut *tx, users.clone()).await?;
+    let (users, invitations) = users_repository::add_users(&mut *tx, users.clone()).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
+
+    for invitation in invitations {
+        mailer::send_in_background(invitation);
+    }
+    for project_id in project_ids {
+        publish(project_id, EventSSE::UserCreated);
+    }
 
     Ok(users)
 }
 
 #[get("/api/v1/projects/{project_id}/users")]
-pub async fn get_users_by_project_id(project_id: Uuid) -> Result<Vec<User>, ServerFnError> {
-    let pool = get_db().await;
-    let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+pub async fn get_users_by_project_id(project_id: Uuid) -> Result<Vec<User>, ApiError> {
+    let pool = get_db();
+    let mut tx = pool.begin().await?;
+
+    authorize(&mut *tx, project_id, ProjectAction::Read).await?;
 
-    let users = users_repository::get_users_by_project_id(&mut *tx, project_id).await?;
+    let users = tx.get_users_by_project_id(project_id).await?;
 
-    tx.commit().await.map_err(|e| ServerFnError::new(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(users)
 }
@@ -2,194 +2,387 @@ use dioxus::prelude::*;
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
-use anyhow::Context;
+use crate::db::Store;
+#[cfg(feature = "server")]
+use crate::error::{db_error, ApiError};
+
+#[cfg(feature = "server")]
+use futures::future::BoxFuture;
 use shared::UserProjects;
 use shared::{CreatableUser, User};
 #[cfg(feature = "server")]
 use sqlx::{PgConnection, Postgres, QueryBuilder};
 
 #[cfg(feature = "server")]
-use crate::account_projects::account_projects_repository;
+use crate::config::get_config;
 #[cfg(feature = "server")]
-use crate::utils::sha256_hex;
-
+use crate::mailer::{templates::invitation_email, templates::Locale, Email};
 #[cfg(feature = "server")]
-pub async fn get_users(executor: &mut PgConnection) -> Result<Vec<User>, ServerFnError> {
-    let users: Vec<User> = sqlx::query_as!(User, "SELECT id, name, balance, created_at FROM users")
-        .fetch_all(&mut *executor)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to get users: {}", e)))?;
-
-    Ok(users)
-}
+use crate::utils::{get_current_account_id, get_request_header, sha256_hex};
 
 #[cfg(feature = "server")]
-pub async fn delete_user(executor: &mut PgConnection, user_id: i32) -> Result<(), ServerFnError> {
-    sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
-        .execute(&mut *executor)
-        .await
-        .context("Failed to delete user in user table with specified id")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(())
+pub trait UsersRepository {
+    fn get_users(&mut self) -> BoxFuture<'_, Result<Vec<User>, ApiError>>;
+
+    fn delete_user(&mut self, user_id: i32) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    fn delete_users(&mut self, user_ids: Vec<i32>) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Insert the participants, with the hash of their invited address, into the project of the
+    /// first one.
+    fn insert_users(
+        &mut self,
+        creatable_users: Vec<CreatableUser>,
+    ) -> BoxFuture<'_, Result<Vec<User>, ApiError>>;
+
+    fn add_user(&mut self, user: CreatableUser) -> BoxFuture<'_, Result<i32, ApiError>>;
+
+    fn rename_user<'a>(
+        &'a mut self,
+        user_id: i32,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>>;
+
+    /// Id and display name of the account that registered and verified this lowercased address,
+    /// whatever the case it was registered with. Unverified accounts are ignored, since anyone
+    /// can register with someone else's address.
+    fn find_invited_account<'a>(
+        &'a mut self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(Uuid, String)>, ApiError>>;
+
+    /// Participants invited with the address of this hash, with their project.
+    fn find_invited_participants<'a>(
+        &'a mut self,
+        email_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UserProjects>, ApiError>>;
+
+    fn get_project_ids_by_user_id(
+        &mut self,
+        user_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Uuid>, ApiError>>;
+
+    fn get_users_by_project_id(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<User>, ApiError>>;
 }
 
 #[cfg(feature = "server")]
-pub async fn delete_users(
-    executor: &mut PgConnection,
-    user_ids: Vec<i32>,
-) -> Result<(), ServerFnError> {
-    sqlx::query!("DELETE FROM users WHERE id = ANY($1)", &user_ids[..])
-        .execute(&mut *executor)
-        .await
-        .context("Failed to delete user in user table with specified id")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    Ok(())
+impl UsersRepository for PgConnection {
+    #[tracing::instrument(skip_all)]
+    fn get_users(&mut self) -> BoxFuture<'_, Result<Vec<User>, ApiError>> {
+        Box::pin(async move {
+            let users: Vec<User> =
+                sqlx::query_as!(User, "SELECT id, name, balance, created_at FROM users")
+                    .fetch_all(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to get users"))?;
+
+            Ok(users)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_user(&mut self, user_id: i32) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete user in user table with specified id"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_users(&mut self, user_ids: Vec<i32>) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("DELETE FROM users WHERE id = ANY($1)", &user_ids[..])
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete user in user table with specified id"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn insert_users(
+        &mut self,
+        creatable_users: Vec<CreatableUser>,
+    ) -> BoxFuture<'_, Result<Vec<User>, ApiError>> {
+        Box::pin(async move {
+            let project_id = creatable_users[0].project_id;
+
+            // INSERT into users table with email_hash
+            let mut users_query_builder: QueryBuilder<Postgres> =
+                QueryBuilder::new("INSERT INTO users (name, email_hash) ");
+
+            users_query_builder
+                .push_values(creatable_users.iter(), |mut qb, user| {
+                    let hash = user.invited_email.as_ref().map(|e| sha256_hex(&e.to_lowercase()));
+                    qb.push_bind(user.name.clone()).push_bind(hash);
+                })
+                .push(" RETURNING id, name, balance, created_at");
+
+            let users_query = users_query_builder.build_query_as::<User>();
+
+            let users: Vec<User> =
+                users_query.fetch_all(&mut *self).await.map_err(db_error("Failed to add users"))?;
+
+            // INSERT into user_projects table
+            let mut user_projects_query_builder: QueryBuilder<Postgres> =
+                QueryBuilder::new("INSERT INTO user_projects(user_id, project_id) ");
+
+            user_projects_query_builder.push_values(users.clone(), |mut query_builder, user| {
+                query_builder.push_bind(user.id).push_bind(project_id);
+            });
+
+            let user_projects_query = user_projects_query_builder.build();
+
+            user_projects_query
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to associate user with project"))?;
+
+            Ok(users)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn add_user(&mut self, user: CreatableUser) -> BoxFuture<'_, Result<i32, ApiError>> {
+        Box::pin(async move {
+            let user_id: i32 =
+                sqlx::query_scalar!("INSERT INTO users(name) VALUES ($1) RETURNING id", user.name)
+                    .fetch_one(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to insert user into database"))?;
+
+            sqlx::query!(
+                "INSERT INTO user_projects(user_id, project_id) VALUES ($1, $2)",
+                user_id,
+                user.project_id
+            )
+            .execute(&mut *self)
+            .await
+            .map_err(db_error("Failed to associate user with project"))?;
+
+            Ok(user_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn rename_user<'a>(
+        &'a mut self,
+        user_id: i32,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query!("UPDATE users SET name = $1 WHERE id = $2", name, user_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to update user name"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_invited_account<'a>(
+        &'a mut self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(Uuid, String)>, ApiError>> {
+        Box::pin(async move {
+            let account: Option<(Uuid, String)> = sqlx::query_as(
+                "SELECT id, display_name FROM accounts
+                 WHERE lower(email) = $1 AND email_verified_at IS NOT NULL",
+            )
+            .bind(email)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to lookup account"))?;
+
+            Ok(account)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_invited_participants<'a>(
+        &'a mut self,
+        email_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UserProjects>, ApiError>> {
+        Box::pin(async move {
+            let participants = sqlx::query_as!(
+                UserProjects,
+                "SELECT up.project_id, u.id AS user_id FROM users u
+                 JOIN user_projects up ON up.user_id = u.id
+                 WHERE u.email_hash = $1",
+                email_hash
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to resolve pending invitations"))?;
+
+            Ok(participants)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_project_ids_by_user_id(
+        &mut self,
+        user_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Uuid>, ApiError>> {
+        Box::pin(async move {
+            let project_ids: Vec<Uuid> = sqlx::query_scalar!(
+                "SELECT project_id FROM user_projects WHERE user_id = $1",
+                user_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get project ids by user"))?;
+
+            Ok(project_ids)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_users_by_project_id(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<User>, ApiError>> {
+        Box::pin(async move {
+            let user_ids: Vec<i32> = sqlx::query_scalar!(
+                "SELECT user_id FROM user_projects WHERE project_id = $1",
+                project_id
+            )
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to get user ids by project"))?;
+
+            if user_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let mut query_builder: QueryBuilder<Postgres> =
+                QueryBuilder::new("SELECT id, name, balance, created_at FROM users WHERE id IN (");
+
+            let mut separated = query_builder.separated(", ");
+            for id in user_ids {
+                separated.push_bind(id);
+            }
+            separated.push_unseparated(")");
+
+            let query = query_builder.build_query_as::<User>();
+            let users: Vec<User> = query
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to fetch users by IDs"))?;
+
+            Ok(users)
+        })
+    }
 }
 
+/// Add participants to a project and link the ones added with an email address.
+///
+/// Returns the participants and their invitation emails, to send once the transaction commits.
 #[cfg(feature = "server")]
+#[tracing::instrument(skip_all)]
 pub async fn add_users(
-    executor: &mut PgConnection,
+    executor: &mut dyn Store,
     creatable_users: Vec<CreatableUser>,
-) -> Result<Vec<User>, ServerFnError> {
+) -> Result<(Vec<User>, Vec<Email>), ApiError> {
     if creatable_users.is_empty() {
-        return Ok(vec![]);
+        return Ok((vec![], vec![]));
     }
 
     let project_id = creatable_users[0].project_id;
 
-    // Collect email data before QueryBuilder consumes ownership
-    let email_hashes: Vec<Option<String>> = creatable_users
-        .iter()
-        .map(|u| u.invited_email.as_ref().map(|e| sha256_hex(&e.to_lowercase())))
-        .collect();
+    // Collect email data before the insert consumes ownership
     let emails_lower: Vec<Option<String>> = creatable_users
         .iter()
         .map(|u| u.invited_email.as_ref().map(|e| e.to_lowercase()))
         .collect();
 
-    // INSERT into users table with email_hash
-    let mut users_query_builder: QueryBuilder<Postgres> =
-        QueryBuilder::new("INSERT INTO users (name, email_hash) ");
-
-    users_query_builder
-        .push_values(creatable_users.iter(), |mut qb, user| {
-            let hash = user.invited_email.as_ref().map(|e| sha256_hex(&e.to_lowercase()));
-            qb.push_bind(user.name.clone()).push_bind(hash);
-        })
-        .push(" RETURNING id, name, balance, created_at");
-
-    let users_query = users_query_builder.build_query_as::<User>();
-
-    let users: Vec<User> = users_query
-        .fetch_all(&mut *executor)
-        .await
-        .context("Failed to add users")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let users = executor.insert_users(creatable_users).await?;
 
-    // INSERT into user_projects table
-    let mut user_projects_query_builder: QueryBuilder<Postgres> =
-        QueryBuilder::new("INSERT INTO user_projects(user_id, project_id) ");
+    // (email, participant name, already has an account)
+    let mut invitations: Vec<(String, String, bool)> = Vec::new();
 
-    user_projects_query_builder.push_values(users.clone(), |mut query_builder, user| {
-        query_builder.push_bind(user.id).push_bind(project_id);
-    });
+    // For each invited email: immediate account lookup + name update + account_projects upsert
+    for (user, email_opt) in users.iter().zip(emails_lower.iter()) {
+        if let Some(ref email) = email_opt {
+            let account = executor.find_invited_account(email).await?;
 
-    let user_projects_query = user_projects_query_builder.build();
+            let has_account = account.is_some();
+            let mut participant_name = user.name.clone();
 
-    user_projects_query
-        .execute(&mut *executor)
-        .await
-        .context("Failed to associate user with project")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+            if let Some((account_id, display_name)) = account {
+                executor.rename_user(user.id, &display_name).await?;
+                executor.upsert_account_project(account_id, project_id, Some(user.id)).await?;
 
-    // For each invited email: immediate account lookup + name update + account_projects upsert
-    for ((user, hash_opt), email_opt) in users.iter().zip(email_hashes.iter()).zip(emails_lower.iter()) {
-        if hash_opt.is_some() {
-            if let Some(ref email) = email_opt {
-                let account: Option<(Uuid, String)> =
-                    sqlx::query_as("SELECT id, display_name FROM accounts WHERE lower(email) = $1")
-                        .bind(email)
-                        .fetch_optional(&mut *executor)
-                        .await
-                        .map_err(|e| ServerFnError::new(format!("Failed to lookup account: {}", e)))?;
-
-                if let Some((account_id, display_name)) = account {
-                    sqlx::query!("UPDATE users SET name = $1 WHERE id = $2", display_name, user.id)
-                        .execute(&mut *executor)
-                        .await
-                        .map_err(|e| ServerFnError::new(format!("Failed to update user name: {}", e)))?;
-
-                    account_projects_repository::upsert_account_project(
-                        &mut *executor,
-                        account_id,
-                        project_id,
-                        Some(user.id),
-                    )
-                    .await?;
-                }
-                // Silent if not found — no error, no enumeration
+                participant_name = display_name;
             }
+            // Silent if not found — no error, no enumeration. An unverified account stays
+            // pending until `verify_email` resolves the invitation
+
+            invitations.push((email.clone(), participant_name, has_account));
         }
     }
 
-    Ok(users)
-}
+    let emails = if invitations.is_empty() {
+        vec![]
+    } else {
+        invitation_emails(&mut *executor, project_id, invitations).await?
+    };
 
-#[cfg(feature = "server")]
-pub async fn add_user(executor: &mut PgConnection, user: CreatableUser) -> Result<i32, ServerFnError> {
-    let user_id: i32 =
-        sqlx::query_scalar!("INSERT INTO users(name) VALUES ($1) RETURNING id", user.name)
-            .fetch_one(&mut *executor)
-            .await
-            .context("Failed to insert user into database")
-            .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    sqlx::query!(
-        "INSERT INTO user_projects(user_id, project_id) VALUES ($1, $2)",
-        user_id,
-        user.project_id
-    )
-    .execute(&mut *executor)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to associate user with project: {}", e)))?;
-
-    Ok(user_id)
+    Ok((users, emails))
 }
 
+/// Email of every invited participant, in the language of the request that added them.
+///
+/// Existing accounts get a link to the project; other addresses a link to the registration page,
+/// since the invitation resolves once they sign up with that address.
 #[cfg(feature = "server")]
-pub async fn get_users_by_project_id(
-    executor: &mut PgConnection,
+#[tracing::instrument(skip_all)]
+async fn invitation_emails(
+    executor: &mut dyn Store,
     project_id: Uuid,
-) -> Result<Vec<User>, ServerFnError> {
-    let user_ids: Vec<i32> =
-        sqlx::query_scalar!("SELECT user_id FROM user_projects WHERE project_id = $1", project_id)
-            .fetch_all(&mut *executor)
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to get user ids by project: {}", e)))?;
-
-    if user_ids.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let mut query_builder: QueryBuilder<Postgres> =
-        QueryBuilder::new("SELECT id, name, balance, created_at FROM users WHERE id IN (");
+    invitations: Vec<(String, String, bool)>,
+) -> Result<Vec<Email>, ApiError> {
+    let project_name = executor.get_project(project_id).await?.name;
 
-    let mut separated = query_builder.separated(", ");
-    for id in user_ids {
-        separated.push_bind(id);
-    }
-    separated.push_unseparated(")");
-
-    let query = query_builder.build_query_as::<User>();
-    let users: Vec<User> = query
-        .fetch_all(&mut *executor)
-        .await
-        .context("Failed to fetch users by IDs")
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let inviter_name = match get_current_account_id().await {
+        Some(account_id) => {
+            executor.get_account_by_id(account_id).await?.map(|account| account.display_name)
+        }
+        None => None,
+    };
+
+    let locale = Locale::from_accept_language(get_request_header("accept-language").as_deref());
+    let base_url = get_config().app_base_url.clone();
+
+    let emails = invitations
+        .into_iter()
+        .map(|(email, participant_name, has_account)| {
+            let link = if has_account {
+                format!("{}/projects/{}", base_url, project_id)
+            } else {
+                format!("{}/register", base_url)
+            };
+            invitation_email(
+                locale,
+                &email,
+                &participant_name,
+                &project_name,
+                inviter_name.as_deref(),
+                &link,
+            )
+        })
+        .collect();
 
-    Ok(users)
+    Ok(emails)
 }
 
 #[cfg(all(test, feature = "server"))]
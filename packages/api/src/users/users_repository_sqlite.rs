@@ -0,0 +1,211 @@
+use futures::future::BoxFuture;
+use shared::{CreatableUser, User, UserProjects};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use uuid::Uuid;
+
+use super::users_repository::UsersRepository;
+use crate::error::{db_error, ApiError};
+use crate::utils::sha256_hex;
+
+impl UsersRepository for SqliteConnection {
+    #[tracing::instrument(skip_all)]
+    fn get_users(&mut self) -> BoxFuture<'_, Result<Vec<User>, ApiError>> {
+        Box::pin(async move {
+            let users = sqlx::query_as("SELECT id, name, balance, created_at FROM users")
+                .fetch_all(&mut *self)
+                .await
+                .map_err(db_error("Failed to get users"))?;
+
+            Ok(users)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_user(&mut self, user_id: i32) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(user_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete user in user table with specified id"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn delete_users(&mut self, user_ids: Vec<i32>) -> BoxFuture<'_, Result<(), ApiError>> {
+        Box::pin(async move {
+            if user_ids.is_empty() {
+                return Ok(());
+            }
+
+            let mut query_builder: QueryBuilder<Sqlite> =
+                QueryBuilder::new("DELETE FROM users WHERE id IN (");
+            let mut separated = query_builder.separated(", ");
+            for id in user_ids {
+                separated.push_bind(id);
+            }
+            separated.push_unseparated(")");
+
+            query_builder
+                .build()
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to delete user in user table with specified id"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn insert_users(
+        &mut self,
+        creatable_users: Vec<CreatableUser>,
+    ) -> BoxFuture<'_, Result<Vec<User>, ApiError>> {
+        Box::pin(async move {
+            let project_id = creatable_users[0].project_id;
+
+            // RETURNING does not guarantee the order of the rows of a multi-row insert here
+            let mut users = Vec::with_capacity(creatable_users.len());
+            for user in creatable_users {
+                let hash = user.invited_email.as_ref().map(|e| sha256_hex(&e.to_lowercase()));
+                let created: User = sqlx::query_as(
+                    "INSERT INTO users (name, email_hash) VALUES ($1, $2)
+                     RETURNING id, name, balance, created_at",
+                )
+                .bind(user.name)
+                .bind(hash)
+                .fetch_one(&mut *self)
+                .await
+                .map_err(db_error("Failed to add users"))?;
+
+                sqlx::query("INSERT INTO user_projects (user_id, project_id) VALUES ($1, $2)")
+                    .bind(created.id)
+                    .bind(project_id)
+                    .execute(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to associate user with project"))?;
+
+                users.push(created);
+            }
+
+            Ok(users)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn add_user(&mut self, user: CreatableUser) -> BoxFuture<'_, Result<i32, ApiError>> {
+        Box::pin(async move {
+            let user_id: i32 =
+                sqlx::query_scalar("INSERT INTO users (name) VALUES ($1) RETURNING id")
+                    .bind(user.name)
+                    .fetch_one(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to insert user into database"))?;
+
+            sqlx::query("INSERT INTO user_projects (user_id, project_id) VALUES ($1, $2)")
+                .bind(user_id)
+                .bind(user.project_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to associate user with project"))?;
+
+            Ok(user_id)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn rename_user<'a>(
+        &'a mut self,
+        user_id: i32,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<(), ApiError>> {
+        Box::pin(async move {
+            sqlx::query("UPDATE users SET name = $1 WHERE id = $2")
+                .bind(name)
+                .bind(user_id)
+                .execute(&mut *self)
+                .await
+                .map_err(db_error("Failed to update user name"))?;
+
+            Ok(())
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_invited_account<'a>(
+        &'a mut self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(Uuid, String)>, ApiError>> {
+        Box::pin(async move {
+            let account = sqlx::query_as(
+                "SELECT id, display_name FROM accounts
+                 WHERE lower(email) = $1 AND email_verified_at IS NOT NULL",
+            )
+            .bind(email)
+            .fetch_optional(&mut *self)
+            .await
+            .map_err(db_error("Failed to lookup account"))?;
+
+            Ok(account)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn find_invited_participants<'a>(
+        &'a mut self,
+        email_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UserProjects>, ApiError>> {
+        Box::pin(async move {
+            let participants = sqlx::query_as(
+                "SELECT up.project_id, u.id AS user_id FROM users u
+                 JOIN user_projects up ON up.user_id = u.id
+                 WHERE u.email_hash = $1",
+            )
+            .bind(email_hash)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to resolve pending invitations"))?;
+
+            Ok(participants)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_project_ids_by_user_id(
+        &mut self,
+        user_id: i32,
+    ) -> BoxFuture<'_, Result<Vec<Uuid>, ApiError>> {
+        Box::pin(async move {
+            let project_ids =
+                sqlx::query_scalar("SELECT project_id FROM user_projects WHERE user_id = $1")
+                    .bind(user_id)
+                    .fetch_all(&mut *self)
+                    .await
+                    .map_err(db_error("Failed to get project ids by user"))?;
+
+            Ok(project_ids)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn get_users_by_project_id(
+        &mut self,
+        project_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<User>, ApiError>> {
+        Box::pin(async move {
+            let users = sqlx::query_as(
+                "SELECT u.id, u.name, u.balance, u.created_at FROM users u
+                 JOIN user_projects up ON up.user_id = u.id
+                 WHERE up.project_id = $1",
+            )
+            .bind(project_id)
+            .fetch_all(&mut *self)
+            .await
+            .map_err(db_error("Failed to fetch users by IDs"))?;
+
+            Ok(users)
+        })
+    }
+}
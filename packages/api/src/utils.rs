@@ -9,33 +9,84 @@ pub fn sha256_hex(input: &str) -> String {
     format!("{:x}", Sha256::digest(input.as_bytes()))
 }
 
-/// Extract the current account_id from the session cookie.
-/// Returns None if no valid session is found.
+/// Random URL-safe token (256 bits). Only its `sha256_hex` is meant to be stored.
 #[cfg(feature = "server")]
-pub async fn get_current_account_id() -> Option<uuid::Uuid> {
+pub fn generate_token() -> String {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Value of a header of the current request, if any.
+#[cfg(feature = "server")]
+pub fn get_request_header(name: &str) -> Option<String> {
+    use dioxus_fullstack::FullstackContext;
+
+    let ctx = FullstackContext::current()?;
+
+    // Clone the value while the guard is held, then release it
+    let parts = ctx.parts_mut();
+    let value = parts.headers.get(name).and_then(|v| v.to_str().ok()).map(|s| s.to_owned());
+    value
+}
+
+/// Address of the client, as resolved by the rate limiting layer (which honours the trusted
+/// proxy header, see `rate_limit`).
+#[cfg(feature = "server")]
+pub fn get_client_ip() -> Option<String> {
     use dioxus_fullstack::FullstackContext;
 
     let ctx = FullstackContext::current()?;
+    let parts = ctx.parts_mut();
+    let client_ip =
+        parts.extensions.get::<crate::rate_limit::ClientIp>().map(|ip| ip.0.to_string());
+    client_ip
+}
 
-    // Extract and clone the cookie string while the guard is held, then release it
-    let cookie_str: Option<String> = {
-        let parts = ctx.parts_mut();
-        parts
-            .headers
-            .get("cookie")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_owned())
-    };
-
-    let session_id_str = cookie_str?
-        .split(';')
-        .map(|s| s.trim().to_owned())
-        .find(|s| s.starts_with("session_id="))
-        .and_then(|s| s.strip_prefix("session_id=").map(|v| v.to_owned()))?;
-
-    let session_id: uuid::Uuid = session_id_str.parse().ok()?;
-
-    let pool = crate::db::get_db().await;
-    let mut conn = pool.acquire().await.ok()?;
-    crate::auth::auth_repository::get_session_account_id(&mut *conn, session_id).await.ok()?
+/// API token of the current request, as resolved by the `api_token_auth` layer.
+#[cfg(feature = "server")]
+pub fn get_api_token_grant() -> Option<crate::auth::api_tokens::ApiTokenGrant> {
+    use dioxus_fullstack::FullstackContext;
+
+    let ctx = FullstackContext::current()?;
+    let parts = ctx.parts_mut();
+    let grant = parts.extensions.get::<crate::auth::api_tokens::ApiTokenGrant>().cloned();
+    grant
+}
+
+/// Extract the current account_id from the API token or the session cookie.
+/// Returns None if neither is valid.
+///
+/// Callers acting outside of a project check `ensure_account_write_allowed` for tokens;
+/// project access applies the token scope in `resolve_project_access`.
+#[cfg(feature = "server")]
+pub async fn get_current_account_id() -> Option<uuid::Uuid> {
+    if let Some(grant) = get_api_token_grant() {
+        return Some(grant.account_id);
+    }
+    get_session_account_id().await
+}
+
+/// Extract the current account_id from the session cookie only, ignoring API tokens.
+/// Used by the endpoints managing the account itself.
+#[cfg(feature = "server")]
+pub async fn get_session_account_id() -> Option<uuid::Uuid> {
+    if get_api_token_grant().is_some() {
+        return None;
+    }
+    crate::auth::sessions::current_session().await.map(|session| session.account_id)
+}
+
+/// Refuse read-only and project-limited API tokens on writes that are not tied to a project.
+#[cfg(feature = "server")]
+pub fn ensure_account_write_allowed() -> Result<(), crate::error::ApiError> {
+    match get_api_token_grant() {
+        Some(grant) if !grant.allows_account_writes() => {
+            Err(crate::error::ApiError::forbidden("This API token does not allow this"))
+        }
+        _ => Ok(()),
+    }
 }
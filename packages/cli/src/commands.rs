@@ -0,0 +1,586 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use chrono::Local;
+use client::Client;
+use serde::Serialize;
+use shared::{
+    CreatableExpense, Expense, ExpenseOperation, ExpenseOperationResult, ExpenseType,
+    LoginResponse, Payment, ProjectDto, User, UserAmount, UserSummary,
+};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::output::{emit, money, print, Table};
+use crate::split::distribute;
+use crate::{AddExpenseArgs, Cli, Command, ExportFormat, SettleArgs};
+
+struct Context {
+    client: Client,
+    json: bool,
+}
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let mut config = Config::load()?;
+    let server = cli
+        .server
+        .clone()
+        .or_else(|| config.server.clone())
+        .context("No server: pass --server or set COUNTED_SERVER")?;
+
+    let mut client = Client::new(&server)?;
+    // Stored credentials belong to the stored server
+    let stored = config.server.as_deref() == Some(server.as_str());
+    if let Some(token) = cli.token.clone().or(config.api_token.clone().filter(|_| stored)) {
+        client = client.with_api_token(token);
+    } else if let Some(session) = config.session.as_deref().filter(|_| stored) {
+        client = client.with_session_token(session);
+    }
+    let ctx = Context { client, json: cli.json };
+
+    match cli.command {
+        Command::Login { email } => login(&ctx, &mut config, server, cli.token, email).await,
+        Command::Logout => logout(&ctx, &mut config, stored).await,
+        Command::Whoami => whoami(&ctx).await,
+        Command::Projects => projects(&ctx).await,
+        Command::Balances { project } => balances(&ctx, &project).await,
+        Command::Expenses { project } => expenses(&ctx, &project).await,
+        Command::AddExpense(args) => add_expense(&ctx, args).await,
+        Command::Settle(args) => settle(&ctx, args).await,
+        Command::Export { project, format, output } => {
+            let content = export(&ctx, &project, format).await?;
+            match output {
+                Some(path) => std::fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display())),
+                None => {
+                    emit(&content)?;
+                    Ok(())
+                }
+            }
+        }
+        Command::Follow { project } => follow(&ctx, &project).await,
+    }
+}
+
+// -------- AUTH ---------
+
+async fn login(
+    ctx: &Context,
+    config: &mut Config,
+    server: String,
+    token: Option<String>,
+    email: Option<String>,
+) -> Result<()> {
+    let account = if token.is_some() {
+        ctx.client.me().await?.context("The API token was not accepted")?
+    } else {
+        let email = match email {
+            Some(email) => email,
+            None => prompt("Email: ")?,
+        };
+        let password = match std::env::var("COUNTED_PASSWORD") {
+            Ok(password) => password,
+            Err(_) => rpassword::prompt_password("Password: ")?,
+        };
+        match ctx.client.login(&email, &password).await? {
+            LoginResponse::SignedIn(account) => account,
+            LoginResponse::TwoFactorRequired { two_factor_challenge } => {
+                let code = prompt("Authentication or recovery code: ")?;
+                ctx.client.login_two_factor(&two_factor_challenge, &code).await?
+            }
+        }
+    };
+
+    let session = match token {
+        Some(_) => None,
+        None => Some(ctx.client.session_token().context(
+            "The server did not set a session cookie; it may require HTTPS (COOKIE_SECURE)",
+        )?),
+    };
+    *config = Config { server: Some(server), session, api_token: token };
+    config.save()?;
+
+    print(ctx.json, &account, || {
+        format!("Signed in as {} <{}>", account.display_name, account.email)
+    })?;
+    Ok(())
+}
+
+async fn logout(ctx: &Context, config: &mut Config, stored: bool) -> Result<()> {
+    if stored && config.session.is_some() {
+        // The session may already be gone; forgetting it is what matters
+        let _ = ctx.client.logout().await;
+    }
+    *config = Config { server: config.server.take(), session: None, api_token: None };
+    config.save()?;
+    if !ctx.json {
+        emit("Signed out\n")?;
+    }
+    Ok(())
+}
+
+async fn whoami(ctx: &Context) -> Result<()> {
+    let account = ctx.client.me().await?.context("Not signed in, run `counted login`")?;
+    print(ctx.json, &account, || format!("{} <{}>", account.display_name, account.email))?;
+    Ok(())
+}
+
+fn prompt(label: &str) -> Result<String> {
+    eprint!("{}", label);
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+// -------- PROJECTS ---------
+
+/// Find a project by id, or by name among the projects of the caller.
+async fn resolve_project(client: &Client, project: &str) -> Result<ProjectDto> {
+    if let Ok(id) = project.parse::<Uuid>() {
+        return Ok(client.project(id).await?);
+    }
+    let mut matches: Vec<ProjectDto> = client
+        .projects()
+        .await?
+        .into_iter()
+        .filter(|p| p.name.eq_ignore_ascii_case(project))
+        .collect();
+    match matches.len() {
+        0 => bail!("No project named '{}'", project),
+        1 => Ok(matches.remove(0)),
+        _ => bail!("Several projects are named '{}', use its id", project),
+    }
+}
+
+async fn projects(ctx: &Context) -> Result<()> {
+    let projects = ctx.client.projects().await?;
+    print(ctx.json, &projects, || {
+        let mut table = Table::new(&["ID", "NAME", "CURRENCY", "STATUS"]);
+        for project in &projects {
+            table.row(vec![
+                project.id.to_string(),
+                project.name.clone(),
+                project.currency.clone(),
+                format!("{:?}", project.status).to_lowercase(),
+            ]);
+        }
+        table.render()
+    })?;
+    Ok(())
+}
+
+// -------- BALANCES ---------
+
+fn find_user<'a>(users: &'a [User], name: &str) -> Result<&'a User> {
+    users.iter().find(|u| u.name.eq_ignore_ascii_case(name)).ok_or_else(|| {
+        let names: Vec<&str> = users.iter().map(|u| u.name.as_str()).collect();
+        anyhow!("No participant named '{}' (participants: {})", name, names.join(", "))
+    })
+}
+
+fn user_name(users: &[User], user_id: i32) -> String {
+    users
+        .iter()
+        .find(|u| u.id == user_id)
+        .map(|u| u.name.clone())
+        .unwrap_or_else(|| format!("#{}", user_id))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Balance {
+    user_id: i32,
+    name: String,
+    balance: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Reimbursement {
+    from: String,
+    to: String,
+    amount: f64,
+}
+
+#[derive(Serialize)]
+struct Balances {
+    balances: Vec<Balance>,
+    suggestions: Vec<Reimbursement>,
+}
+
+fn balances_of(users: &[User], summary: &UserSummary) -> Balances {
+    let balances = users
+        .iter()
+        .map(|u| Balance {
+            user_id: u.id,
+            name: u.name.clone(),
+            balance: summary.summary.get(&u.id).copied().unwrap_or(0.0),
+        })
+        .collect();
+    let suggestions = summary
+        .reimbursement_suggestions
+        .iter()
+        .map(|s| Reimbursement {
+            from: user_name(users, s.user_id_debtor),
+            to: user_name(users, s.user_id_payer),
+            amount: s.amount,
+        })
+        .collect();
+    Balances { balances, suggestions }
+}
+
+async fn balances(ctx: &Context, project: &str) -> Result<()> {
+    let project = resolve_project(&ctx.client, project).await?;
+    let users = ctx.client.project_users(project.id).await?;
+    let summary = ctx.client.summary(project.id).await?;
+    let result = balances_of(&users, &summary);
+
+    print(ctx.json, &result, || {
+        let mut balances = Table::new(&["PARTICIPANT", "BALANCE"]).right_align(1);
+        for b in &result.balances {
+            balances.row(vec![b.name.clone(), money(b.balance, &project.currency)]);
+        }
+        let suggestions = if result.suggestions.is_empty() {
+            "Nothing to reimburse".to_string()
+        } else {
+            let mut table = Table::new(&["FROM", "TO", "AMOUNT"]).right_align(2);
+            for s in &result.suggestions {
+                table.row(vec![s.from.clone(), s.to.clone(), money(s.amount, &project.currency)]);
+            }
+            table.render()
+        };
+        format!("{}\n\n{}", balances.render(), suggestions)
+    })?;
+    Ok(())
+}
+
+// -------- EXPENSES ---------
+
+async fn expenses(ctx: &Context, project: &str) -> Result<()> {
+    let project = resolve_project(&ctx.client, project).await?;
+    let expenses = ctx.client.project_expenses(project.id).await?;
+    print(ctx.json, &expenses, || {
+        let mut table = Table::new(&["ID", "DATE", "NAME", "TYPE", "AMOUNT"]).right_align(4);
+        for e in &expenses {
+            table.row(vec![
+                e.id.to_string(),
+                e.date.to_string(),
+                e.name.clone(),
+                e.expense_type.to_string(),
+                money(e.amount, &project.currency),
+            ]);
+        }
+        table.render()
+    })?;
+    Ok(())
+}
+
+fn to_amounts(users: &[User], shares: Vec<(String, i64)>) -> Result<Vec<UserAmount>> {
+    shares
+        .into_iter()
+        .map(|(name, cents)| {
+            Ok(UserAmount { user_id: find_user(users, &name)?.id, amount: cents as f64 / 100.0 })
+        })
+        .collect()
+}
+
+async fn add_expense(ctx: &Context, args: AddExpenseArgs) -> Result<()> {
+    let total = match args.amount {
+        Some(total) => total,
+        None if args.paid.iter().all(|s| s.cents.is_some()) => {
+            args.paid.iter().filter_map(|s| s.cents).sum()
+        }
+        None => bail!("Give every --paid amount, or the total with --amount"),
+    };
+    if total <= 0 {
+        bail!("The amount must be positive");
+    }
+
+    let project = resolve_project(&ctx.client, &args.project).await?;
+    let users = ctx.client.project_users(project.id).await?;
+    let payers = to_amounts(&users, distribute(total, &args.paid).context("--paid")?)?;
+    let debtors = to_amounts(&users, distribute(total, &args.split).context("--split")?)?;
+
+    let expense = ctx
+        .client
+        .add_expense(&CreatableExpense {
+            name: args.name,
+            amount: total as f64 / 100.0,
+            expense_type: ExpenseType::Expense,
+            project_id: project.id,
+            author_id: payers[0].user_id,
+            payers,
+            debtors,
+            description: args.description,
+            date: args.date.unwrap_or_else(|| Local::now().date_naive()),
+        })
+        .await?;
+
+    print(ctx.json, &expense, || {
+        format!(
+            "Added {} ({}), id {}",
+            expense.name,
+            money(expense.amount, &project.currency),
+            expense.id
+        )
+    })?;
+    Ok(())
+}
+
+/// A transfer paying `amount` back from `from` to `to`, named as the app names them.
+fn reimbursement(project_id: Uuid, from: &User, to: &User, amount: f64) -> CreatableExpense {
+    CreatableExpense {
+        name: format!("Remboursement {} vers {}", from.name, to.name),
+        amount,
+        expense_type: ExpenseType::Transfer,
+        project_id,
+        payers: vec![UserAmount { user_id: from.id, amount }],
+        debtors: vec![UserAmount { user_id: to.id, amount }],
+        author_id: from.id,
+        description: None,
+        date: Local::now().date_naive(),
+    }
+}
+
+async fn settle(ctx: &Context, args: SettleArgs) -> Result<()> {
+    let project = resolve_project(&ctx.client, &args.project).await?;
+    let users = ctx.client.project_users(project.id).await?;
+    let summary = ctx.client.summary(project.id).await?;
+
+    let transfers: Vec<CreatableExpense> = match (&args.from, &args.to) {
+        (Some(from), Some(to)) => {
+            let from = find_user(&users, from)?;
+            let to = find_user(&users, to)?;
+            let amount = match args.amount {
+                Some(cents) => cents as f64 / 100.0,
+                None => summary
+                    .reimbursement_suggestions
+                    .iter()
+                    .find(|s| s.user_id_debtor == from.id && s.user_id_payer == to.id)
+                    .map(|s| s.amount)
+                    .with_context(|| {
+                        format!(
+                            "No reimbursement suggested from {} to {}, give --amount",
+                            from.name, to.name
+                        )
+                    })?,
+            };
+            vec![reimbursement(project.id, from, to, amount)]
+        }
+        _ => summary
+            .reimbursement_suggestions
+            .iter()
+            .map(|s| {
+                let from = users.iter().find(|u| u.id == s.user_id_debtor);
+                let to = users.iter().find(|u| u.id == s.user_id_payer);
+                match (from, to) {
+                    (Some(from), Some(to)) => Ok(reimbursement(project.id, from, to, s.amount)),
+                    _ => Err(anyhow!("A suggested reimbursement refers to an unknown participant")),
+                }
+            })
+            .collect::<Result<_>>()?,
+    };
+
+    // In one batch: either every reimbursement is recorded or none
+    let recorded: Vec<Expense> = if transfers.is_empty() {
+        Vec::new()
+    } else {
+        let operations: Vec<ExpenseOperation> =
+            transfers.into_iter().map(ExpenseOperation::Create).collect();
+        let results = ctx.client.batch_expenses(project.id, &operations).await?;
+        results
+            .into_iter()
+            .filter_map(|result| match result {
+                ExpenseOperationResult::Created(expense) => Some(expense),
+                _ => None,
+            })
+            .collect()
+    };
+
+    print(ctx.json, &recorded, || {
+        if recorded.is_empty() {
+            return "Nothing to reimburse".to_string();
+        }
+        recorded
+            .iter()
+            .map(|e| format!("Recorded {} ({})", e.name, money(e.amount, &project.currency)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    Ok(())
+}
+
+// -------- EXPORT ---------
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedExpense {
+    #[serde(flatten)]
+    expense: Expense,
+    payments: Vec<Payment>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    project: ProjectDto,
+    participants: Vec<User>,
+    expenses: Vec<ExportedExpense>,
+    balances: Balances,
+}
+
+async fn export(ctx: &Context, project: &str, format: ExportFormat) -> Result<String> {
+    let project = resolve_project(&ctx.client, project).await?;
+    let participants = ctx.client.project_users(project.id).await?;
+    let expenses = ctx.client.project_expenses(project.id).await?;
+    let summary = ctx.client.summary(project.id).await?;
+    let mut payments: HashMap<i32, Vec<Payment>> = HashMap::new();
+    for payment in ctx.client.project_payments(project.id).await? {
+        payments.entry(payment.expense_id).or_default().push(payment);
+    }
+
+    let expenses: Vec<ExportedExpense> = expenses
+        .into_iter()
+        .map(|expense| ExportedExpense {
+            payments: payments.remove(&expense.id).unwrap_or_default(),
+            expense,
+        })
+        .collect();
+
+    Ok(match format {
+        ExportFormat::Csv => to_csv(&project, &participants, &expenses),
+        ExportFormat::Json => {
+            let balances = balances_of(&participants, &summary);
+            let export = Export { project, participants, expenses, balances };
+            serde_json::to_string_pretty(&export)? + "\n"
+        }
+    })
+}
+
+/// One line per expense; who paid and who owes are `name=amount` lists.
+fn to_csv(project: &ProjectDto, users: &[User], expenses: &[ExportedExpense]) -> String {
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let shares = |payments: &[Payment], is_debt: bool| {
+        payments
+            .iter()
+            .filter(|p| p.is_debt == is_debt)
+            .map(|p| format!("{}={:.2}", user_name(users, p.user_id), p.amount))
+            .collect::<Vec<_>>()
+            .join(";")
+    };
+
+    let mut csv = String::from("id,date,name,type,amount,currency,paid_by,owed_by,description\n");
+    for ExportedExpense { expense, payments } in expenses {
+        let line = [
+            expense.id.to_string(),
+            expense.date.to_string(),
+            expense.name.clone(),
+            expense.expense_type.to_string(),
+            format!("{:.2}", expense.amount),
+            project.currency.clone(),
+            shares(payments, false),
+            shares(payments, true),
+            expense.description.clone().unwrap_or_default(),
+        ];
+        csv += &line.iter().map(|v| field(v)).collect::<Vec<_>>().join(",");
+        csv.push('\n');
+    }
+    csv
+}
+
+// -------- EVENTS ---------
+
+async fn follow(ctx: &Context, project: &str) -> Result<()> {
+    let project = resolve_project(&ctx.client, project).await?;
+    let mut events = ctx.client.follow(project.id).await?;
+    if !ctx.json {
+        eprintln!("Following {}, press Ctrl-C to stop", project.name);
+    }
+
+    while let Some(event) = events.next().await {
+        let event = event?;
+        if ctx.json {
+            // One event per line, for piping
+            emit(&format!("{}\n", serde_json::to_string(&event)?))?;
+        } else {
+            emit(&format!("{}  {}\n", Local::now().format("%H:%M:%S"), event.event))?;
+        }
+    }
+    bail!("The server closed the stream")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use shared::ProjectStatus;
+
+    fn user(id: i32, name: &str) -> User {
+        User { id, name: name.to_string(), balance: None, created_at: None }
+    }
+
+    #[test]
+    fn csv_lists_who_paid_and_who_owes() {
+        let project = ProjectDto {
+            id: Uuid::nil(),
+            name: "Trip".to_string(),
+            created_at: NaiveDateTime::default(),
+            currency: "EUR".to_string(),
+            description: None,
+            status: ProjectStatus::Ongoing,
+            owner_account_id: None,
+            editors_can_edit_others_expenses: true,
+            version: 1,
+        };
+        let users = [user(1, "alice"), user(2, "bob")];
+        let payment = |id, user_id, is_debt, amount| Payment {
+            id,
+            expense_id: 7,
+            user_id,
+            is_debt,
+            amount,
+            created_at: NaiveDateTime::default(),
+        };
+        let expenses = [ExportedExpense {
+            expense: Expense {
+                id: 7,
+                author_id: 1,
+                project_id: Uuid::nil(),
+                created_at: NaiveDateTime::default(),
+                date: NaiveDate::from_ymd_opt(2026, 7, 14).unwrap(),
+                amount: 30.0,
+                description: Some("Pizza, \"large\"".to_string()),
+                name: "Dinner".to_string(),
+                expense_type: ExpenseType::Expense,
+                version: 1,
+            },
+            payments: vec![
+                payment(1, 1, false, 30.0),
+                payment(2, 1, true, 15.0),
+                payment(3, 2, true, 15.0),
+            ],
+        }];
+
+        assert_eq!(
+            to_csv(&project, &users, &expenses),
+            "id,date,name,type,amount,currency,paid_by,owed_by,description\n\
+             7,2026-07-14,Dinner,Expense,30.00,EUR,alice=30.00,alice=15.00;bob=15.00,\"Pizza, \"\"large\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn participants_are_found_by_name() {
+        let users = [user(1, "Alice"), user(2, "bob")];
+        assert_eq!(find_user(&users, "alice").unwrap().id, 1);
+        let error = find_user(&users, "carol").unwrap_err().to_string();
+        assert_eq!(error, "No participant named 'carol' (participants: Alice, bob)");
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// What `counted login` remembers between runs.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    pub server: Option<String>,
+    /// Session cookie from a password sign-in
+    pub session: Option<String>,
+    /// Personal API token (`cnt_…`)
+    pub api_token: Option<String>,
+}
+
+impl Config {
+    /// `$COUNTED_CONFIG`, or `counted/config.json` in the user configuration directory.
+    pub fn path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os("COUNTED_CONFIG") {
+            return Ok(PathBuf::from(path));
+        }
+        let dir = dirs::config_dir().context("No configuration directory, set COUNTED_CONFIG")?;
+        Ok(dir.join("counted").join("config.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Invalid configuration in {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Write the configuration, readable by the current user only since it holds credentials.
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file =
+            options.open(&path).with_context(|| format!("Failed to write {}", path.display()))?;
+        std::io::Write::write_all(&mut file, content.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,143 @@
+//! `counted`: manage Counted projects from a terminal, against any Counted server.
+mod commands;
+mod config;
+mod output;
+mod split;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::split::{parse_cents, parse_share, Share};
+
+#[derive(Parser)]
+#[command(name = "counted", version, about = "Manage Counted projects from a terminal")]
+struct Cli {
+    /// Server URL, e.g. https://counted.example.com. Remembered by `login`
+    #[arg(long, global = true, env = "COUNTED_SERVER")]
+    server: Option<String>,
+
+    /// Personal API token (cnt_…), used instead of the stored credentials
+    #[arg(long, global = true, env = "COUNTED_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Sign in with a password (or check and store --token) and remember the server
+    Login {
+        /// Asked when missing
+        #[arg(long)]
+        email: Option<String>,
+    },
+    /// Sign out and forget the stored credentials
+    Logout,
+    /// Show the signed-in account
+    Whoami,
+    /// List your projects
+    Projects,
+    /// Show the participants of a project with their balance, and the suggested reimbursements
+    Balances {
+        /// Project id or name
+        project: String,
+    },
+    /// List the expenses of a project
+    Expenses {
+        /// Project id or name
+        project: String,
+    },
+    /// Add an expense, e.g. `add-expense Trip Dinner --paid alice=30 --split alice,bob,carol`
+    AddExpense(AddExpenseArgs),
+    /// Record reimbursements: one between two participants, or every suggested one
+    Settle(SettleArgs),
+    /// Export a project with its expenses and payments
+    Export {
+        /// Project id or name
+        project: String,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// File to write, instead of the standard output
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Print the changes to a project as they happen, until interrupted
+    Follow {
+        /// Project id or name
+        project: String,
+    },
+}
+
+#[derive(clap::Args)]
+struct AddExpenseArgs {
+    /// Project id or name
+    project: String,
+    /// Name of the expense
+    name: String,
+    /// Who paid: `name=amount`, comma-separated or repeated. A name without amount pays an even
+    /// share of the rest of --amount
+    #[arg(long, required = true, value_delimiter = ',', value_parser = parse_share)]
+    paid: Vec<Share>,
+    /// Who owes: `name` for an even share, or `name=amount`
+    #[arg(long, required = true, value_delimiter = ',', value_parser = parse_share)]
+    split: Vec<Share>,
+    /// Total amount; defaults to the sum of the --paid amounts
+    #[arg(long, value_parser = parse_cents)]
+    amount: Option<i64>,
+    /// Defaults to today
+    #[arg(long)]
+    date: Option<NaiveDate>,
+    #[arg(long)]
+    description: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct SettleArgs {
+    /// Project id or name
+    project: String,
+    /// Participant who pays back
+    #[arg(long, requires = "to", conflicts_with = "all")]
+    from: Option<String>,
+    /// Participant who is paid back
+    #[arg(long, requires = "from", conflicts_with = "all")]
+    to: Option<String>,
+    /// Defaults to the suggested reimbursement between them
+    #[arg(long, value_parser = parse_cents, requires = "from")]
+    amount: Option<i64>,
+    /// Record every suggested reimbursement
+    #[arg(long, required_unless_present = "from")]
+    all: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match commands::run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader went away, e.g. `counted export … | head`
+        Err(e)
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+/// Columns aligned on the widest cell; amounts are right-aligned.
+pub struct Table {
+    headers: Vec<&'static str>,
+    right_aligned: Vec<bool>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&'static str]) -> Self {
+        Self { headers: headers.to_vec(), right_aligned: vec![false; headers.len()], rows: vec![] }
+    }
+
+    pub fn right_align(mut self, column: usize) -> Self {
+        self.right_aligned[column] = true;
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn render(&self) -> String {
+        let widths: Vec<usize> = (0..self.headers.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([self.headers[i].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let line = |cells: Vec<&str>| {
+            let padded: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    let padding = " ".repeat(widths[i] - cell.chars().count());
+                    if self.right_aligned[i] {
+                        format!("{}{}", padding, cell)
+                    } else {
+                        format!("{}{}", cell, padding)
+                    }
+                })
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        };
+
+        let mut lines = vec![line(self.headers.clone())];
+        lines.extend(self.rows.iter().map(|row| line(row.iter().map(String::as_str).collect())));
+        lines.join("\n")
+    }
+}
+
+/// Write to the standard output. Unlike `print!`, a closed pipe is an error rather than a panic.
+pub fn emit(text: &str) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+/// Print `value` as JSON, or the human-readable text.
+pub fn print<T: Serialize>(
+    json: bool,
+    value: &T,
+    human: impl FnOnce() -> String,
+) -> std::io::Result<()> {
+    if json {
+        emit(&(serde_json::to_string_pretty(value).expect("Values are serializable") + "\n"))
+    } else {
+        emit(&(human() + "\n"))
+    }
+}
+
+pub fn money(amount: f64, currency: &str) -> String {
+    format!("{:.2} {}", amount, currency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_are_aligned() {
+        let mut table = Table::new(&["NAME", "BALANCE"]).right_align(1);
+        table.row(vec!["alice".to_string(), "15.00 EUR".to_string()]);
+        table.row(vec!["bob".to_string(), "-15.00 EUR".to_string()]);
+        assert_eq!(table.render(), "NAME      BALANCE\nalice   15.00 EUR\nbob    -15.00 EUR");
+    }
+}
@@ -0,0 +1,128 @@
+//! `--paid alice=30 --split alice,bob,carol`: who paid and who owes what.
+use anyhow::{bail, Result};
+
+/// A participant, with the amount in cents when it is given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Share {
+    pub name: String,
+    pub cents: Option<i64>,
+}
+
+/// Parse `name` or `name=amount`.
+pub fn parse_share(entry: &str) -> Result<Share, String> {
+    let (name, amount) = match entry.split_once('=') {
+        Some((name, amount)) => (name.trim(), Some(amount.trim())),
+        None => (entry.trim(), None),
+    };
+    if name.is_empty() {
+        return Err(format!("Missing participant name in '{}'", entry));
+    }
+    let cents = amount.map(parse_cents).transpose()?;
+    Ok(Share { name: name.to_string(), cents })
+}
+
+/// Parse a positive amount with at most two decimals, e.g. `30` or `12.5`.
+pub fn parse_cents(amount: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid amount '{}'", amount);
+    let (units, decimals) = amount.split_once('.').unwrap_or((amount, ""));
+    if units.is_empty() && decimals.is_empty()
+        || decimals.len() > 2
+        || !units.chars().chain(decimals.chars()).all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let units: i64 = if units.is_empty() { 0 } else { units.parse().map_err(|_| invalid())? };
+    let decimals: i64 = format!("{:0<2}", decimals).parse().map_err(|_| invalid())?;
+    units.checked_mul(100).and_then(|c| c.checked_add(decimals)).ok_or_else(invalid)
+}
+
+/// Amount of every share once `total` is spread: given amounts are kept and the rest is split
+/// evenly between the others, the first ones taking the leftover cents.
+pub fn distribute(total: i64, shares: &[Share]) -> Result<Vec<(String, i64)>> {
+    if shares.is_empty() {
+        bail!("Nobody to split between");
+    }
+    for (i, share) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|s| s.name.eq_ignore_ascii_case(&share.name)) {
+            bail!("{} is listed twice", share.name);
+        }
+    }
+
+    let fixed: i64 = shares.iter().filter_map(|s| s.cents).sum();
+    let open = shares.iter().filter(|s| s.cents.is_none()).count() as i64;
+    let rest = total - fixed;
+    if rest < 0 || (open == 0 && rest != 0) {
+        bail!("The amounts add up to {}, not {}", format_cents(fixed), format_cents(total));
+    }
+
+    let mut leftover = if open == 0 { 0 } else { rest % open };
+    Ok(shares
+        .iter()
+        .map(|share| {
+            let cents = share.cents.unwrap_or_else(|| {
+                let extra = if leftover > 0 { 1 } else { 0 };
+                leftover -= extra;
+                rest / open + extra
+            });
+            (share.name.clone(), cents)
+        })
+        .collect())
+}
+
+pub fn format_cents(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(name: &str, cents: Option<i64>) -> Share {
+        Share { name: name.to_string(), cents }
+    }
+
+    #[test]
+    fn shares_are_parsed() {
+        assert_eq!(parse_share("alice=30").unwrap(), share("alice", Some(3000)));
+        assert_eq!(parse_share(" bob ").unwrap(), share("bob", None));
+        assert_eq!(parse_share("carol=12.5").unwrap(), share("carol", Some(1250)));
+        assert_eq!(parse_share("dan=.99").unwrap(), share("dan", Some(99)));
+        assert!(parse_share("=3").is_err());
+        assert!(parse_share("alice=-3").is_err());
+        assert!(parse_share("alice=1.234").is_err());
+        assert!(parse_share("alice=abc").is_err());
+        assert!(parse_share("alice=").is_err());
+    }
+
+    #[test]
+    fn the_rest_is_split_evenly() {
+        let shares = [share("alice", None), share("bob", None), share("carol", None)];
+        assert_eq!(
+            distribute(1000, &shares).unwrap(),
+            [("alice".to_string(), 334), ("bob".to_string(), 333), ("carol".to_string(), 333)]
+        );
+
+        let shares = [share("alice", Some(500)), share("bob", None), share("carol", None)];
+        assert_eq!(
+            distribute(1000, &shares).unwrap(),
+            [("alice".to_string(), 500), ("bob".to_string(), 250), ("carol".to_string(), 250)]
+        );
+    }
+
+    #[test]
+    fn amounts_must_add_up() {
+        assert!(distribute(1000, &[share("alice", Some(600)), share("bob", Some(300))]).is_err());
+        assert!(distribute(1000, &[share("alice", Some(1200)), share("bob", None)]).is_err());
+        assert!(distribute(1000, &[share("alice", None), share("Alice", None)]).is_err());
+        assert!(distribute(1000, &[]).is_err());
+        assert!(distribute(1000, &[share("alice", Some(1000)), share("bob", None)]).is_ok());
+    }
+
+    #[test]
+    fn cents_are_formatted() {
+        assert_eq!(format_cents(3000), "30.00");
+        assert_eq!(format_cents(-1505), "-15.05");
+        assert_eq!(format_cents(7), "0.07");
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+pub use shared::ErrorCode;
+
+/// Why a call failed.
+#[derive(Debug)]
+pub enum Error {
+    /// No valid session or API token
+    Unauthorized(String),
+    /// The caller cannot access the project, or its API token does not allow the call
+    Forbidden(String),
+    NotFound(String),
+    /// Too many requests; retry after the given number of seconds
+    RateLimited {
+        retry_after: Option<u64>,
+    },
+    /// The resource already exists, or was edited since the version an edit is based on; the
+    /// server then sends it as it is now (see [`Error::current`])
+    Conflict {
+        message: String,
+        current: Option<serde_json::Value>,
+    },
+    /// Any other error reported by the server: validation, locked or internal
+    Api {
+        status: u16,
+        code: ErrorCode,
+        message: String,
+    },
+    /// The server could not be reached or the connection failed
+    Transport(reqwest::Error),
+    /// The response was not what the API describes
+    Decode(String),
+    InvalidBaseUrl(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Unauthorized(message)
+            | Error::Forbidden(message)
+            | Error::NotFound(message)
+            | Error::Conflict { message, .. } => write!(f, "{}", message),
+            Error::RateLimited { retry_after: Some(secs) } => {
+                write!(f, "Too many requests, retry in {} seconds", secs)
+            }
+            Error::RateLimited { retry_after: None } => write!(f, "Too many requests"),
+            Error::Api { status, message, .. } => write!(f, "{} (HTTP {})", message, status),
+            Error::Transport(e) => write!(f, "Request failed: {}", e),
+            Error::Decode(message) => write!(f, "Unexpected response: {}", message),
+            Error::InvalidBaseUrl(url) => write!(f, "Invalid server URL: {}", url),
+        }
+    }
+}
+
+impl Error {
+    /// The resource sent with a conflicting edit, if it has the expected type.
+    pub fn current<T: DeserializeOwned>(&self) -> Option<T> {
+        match self {
+            Error::Conflict { current: Some(current), .. } => {
+                serde_json::from_value(current.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Error::Decode(e.to_string())
+        } else {
+            Error::Transport(e)
+        }
+    }
+}
+
+/// Error body of a server function, or of a middleware answering before one runs.
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+    #[serde(default)]
+    data: Option<ErrorData>,
+}
+
+/// The API error itself. `code` stays a string so a code added later does not break decoding.
+#[derive(Deserialize)]
+struct ErrorData {
+    code: String,
+    message: String,
+    #[serde(default)]
+    current: Option<serde_json::Value>,
+}
+
+/// Code, message and current resource of an error response, without the server function framing.
+fn parse_error(status: StatusCode, body: &str) -> (ErrorCode, String, Option<serde_json::Value>) {
+    let fallback = ErrorCode::from_status(status.as_u16());
+    let Ok(body) = serde_json::from_str::<ErrorBody>(body) else {
+        let message = status.canonical_reason().unwrap_or("Request failed").to_string();
+        return (fallback, message, None);
+    };
+    match body.data {
+        Some(data) => {
+            let code =
+                serde_json::from_value(serde_json::Value::String(data.code)).unwrap_or(fallback);
+            (code, data.message, data.current)
+        }
+        None => (fallback, body.message, None),
+    }
+}
+
+/// Map an error response to a variant, by the error code the API sends or else by the status.
+pub(crate) fn from_response(status: StatusCode, retry_after: Option<u64>, body: &str) -> Error {
+    let (code, message, current) = parse_error(status, body);
+
+    match code {
+        ErrorCode::RateLimited => Error::RateLimited { retry_after },
+        ErrorCode::Unauthorized => Error::Unauthorized(message),
+        ErrorCode::Forbidden => Error::Forbidden(message),
+        ErrorCode::NotFound => Error::NotFound(message),
+        ErrorCode::Conflict => Error::Conflict { message, current },
+        _ => Error::Api { status: status.as_u16(), code, message },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_function_errors_map_by_code() {
+        let body = r#"{"message":"Forbidden","code":403,"data":{"code":"forbidden","message":"Forbidden"}}"#;
+        assert!(matches!(
+            from_response(StatusCode::FORBIDDEN, None, body),
+            Error::Forbidden(m) if m == "Forbidden"
+        ));
+
+        let body = r#"{"message":"Payers cannot be empty","code":422,"data":{"code":"validation","message":"Payers cannot be empty"}}"#;
+        assert!(matches!(
+            from_response(StatusCode::UNPROCESSABLE_ENTITY, None, body),
+            Error::Api { status: 422, code: ErrorCode::Validation, message } if message == "Payers cannot be empty"
+        ));
+    }
+
+    #[test]
+    fn unknown_codes_fall_back_to_the_status() {
+        let body = r#"{"message":"Gone","code":409,"data":{"code":"gone","message":"Gone"}}"#;
+        assert!(matches!(
+            from_response(StatusCode::CONFLICT, None, body),
+            Error::Conflict { current: None, .. }
+        ));
+    }
+
+    #[test]
+    fn conflicts_carry_the_current_resource() {
+        let body = r#"{"message":"Edited","code":409,"data":{"code":"conflict","message":"Edited","current":{"id":7,"version":3}}}"#;
+        let error = from_response(StatusCode::CONFLICT, None, body);
+        assert!(matches!(&error, Error::Conflict { message, .. } if message == "Edited"));
+        assert_eq!(error.current::<serde_json::Value>().unwrap()["version"], 3);
+        assert_eq!(error.current::<String>(), None);
+    }
+
+    #[test]
+    fn middleware_errors_map_by_status() {
+        let body = r#"{"message":"Invalid or expired API token","code":401}"#;
+        assert!(matches!(
+            from_response(StatusCode::UNAUTHORIZED, None, body),
+            Error::Unauthorized(_)
+        ));
+
+        let body = r#"{"message":"Too many requests","code":429}"#;
+        assert!(matches!(
+            from_response(StatusCode::TOO_MANY_REQUESTS, Some(30), body),
+            Error::RateLimited { retry_after: Some(30) }
+        ));
+    }
+
+    #[test]
+    fn unparsable_bodies_fall_back_to_the_status() {
+        assert!(matches!(
+            from_response(StatusCode::BAD_GATEWAY, None, "<html>"),
+            Error::Api { status: 502, code: ErrorCode::Unavailable, message } if message == "Bad Gateway"
+        ));
+        assert!(matches!(from_response(StatusCode::NOT_FOUND, None, ""), Error::NotFound(_)));
+    }
+}
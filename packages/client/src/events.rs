@@ -0,0 +1,69 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use crate::{Error, Result};
+
+/// A stream of server-sent events, each carrying a JSON value.
+pub struct Events<T> {
+    response: reqwest::Response,
+    buffer: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Events<T> {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self { response, buffer: String::new(), _marker: PhantomData }
+    }
+
+    /// The next event, or `None` once the server closes the stream.
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        loop {
+            if let Some(data) = take_event(&mut self.buffer) {
+                return Some(serde_json::from_str(&data).map_err(|e| Error::Decode(e.to_string())));
+            }
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.push_str(&String::from_utf8_lossy(&chunk)),
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+/// Remove the first complete event from `buffer` and return its data. Comments (keep-alives) and
+/// events without data are skipped.
+fn take_event(buffer: &mut String) -> Option<String> {
+    loop {
+        let normalized = buffer.replace("\r\n", "\n");
+        if normalized.len() != buffer.len() {
+            *buffer = normalized;
+        }
+        let end = buffer.find("\n\n")?;
+        let block: String = buffer.drain(..end + 2).collect();
+
+        let data: Vec<&str> = block
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|value| value.strip_prefix(' ').unwrap_or(value))
+            .collect();
+        if !data.is_empty() {
+            return Some(data.join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_split_on_blank_lines() {
+        let mut buffer =
+            ":\n\ndata: {\"a\":1}\n\ndata:{\"a\":\ndata:2}\r\n\r\ndata: {\"a\"".to_string();
+        assert_eq!(take_event(&mut buffer).as_deref(), Some("{\"a\":1}"));
+        assert_eq!(take_event(&mut buffer).as_deref(), Some("{\"a\":\n2}"));
+        assert_eq!(take_event(&mut buffer), None);
+        assert_eq!(buffer, "data: {\"a\"");
+    }
+}
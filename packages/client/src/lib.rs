@@ -0,0 +1,496 @@
+//! Typed async client for the Counted REST API (`/api/v1`).
+//!
+//! Requests and responses use the `shared` DTOs, so the client follows the server as they change.
+//! A [`Client`] signs in with [`Client::login`] and keeps the session cookie, or sends a personal
+//! API token set with [`Client::with_api_token`].
+mod error;
+mod events;
+
+use std::sync::Arc;
+
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{HeaderValue, RETRY_AFTER};
+use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use shared::sse::ProjectEvent;
+use shared::{
+    Account, CreatableExpense, CreatableProject, CreatableUser, CreatableUserBatch,
+    EditableExpense, EditableProject, Expense, ExpenseOperation, ExpenseOperationResult,
+    LoginPayload, LoginResponse, Payment, ProjectDto, ProjectSettlement, RegisterPayload,
+    TwoFactorLoginPayload, User, UserSummary,
+};
+use uuid::Uuid;
+
+pub use error::{Error, ErrorCode, Result};
+pub use events::Events;
+
+/// Name of the session cookie set by the server.
+const SESSION_COOKIE: &str = "session_id";
+
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    cookies: Arc<Jar>,
+    api_token: Option<String>,
+}
+
+impl Client {
+    /// A client for the server at `base_url`, e.g. `https://counted.example.com`.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let mut base_url =
+            Url::parse(base_url).map_err(|_| Error::InvalidBaseUrl(base_url.to_string()))?;
+        if base_url.cannot_be_a_base() {
+            return Err(Error::InvalidBaseUrl(base_url.to_string()));
+        }
+        // Joined paths must not replace the last segment of a server under a prefix
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        let cookies = Arc::new(Jar::default());
+        let http = reqwest::Client::builder()
+            .cookie_provider(cookies.clone())
+            .user_agent(concat!("counted-client/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self { http, base_url, cookies, api_token: None })
+    }
+
+    /// Authenticate with a personal API token (`cnt_…`) instead of a session.
+    pub fn with_api_token(mut self, token: impl Into<String>) -> Self {
+        self.api_token = Some(token.into());
+        self
+    }
+
+    /// Reuse a session obtained earlier, see [`Client::session_token`].
+    pub fn with_session_token(self, token: &str) -> Self {
+        self.cookies
+            .add_cookie_str(&format!("{}={}; Path=/", SESSION_COOKIE, token), &self.base_url);
+        self
+    }
+
+    /// The session cookie set by the last sign-in, to be stored and given back to
+    /// [`Client::with_session_token`].
+    pub fn session_token(&self) -> Option<String> {
+        let header = self.cookies.cookies(&self.base_url)?;
+        header
+            .to_str()
+            .ok()?
+            .split(';')
+            .find_map(|c| c.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('='))
+            .filter(|token| !token.is_empty())
+            .map(str::to_string)
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        self.base_url
+            .join(path.trim_start_matches('/'))
+            .map_err(|_| Error::InvalidBaseUrl(self.base_url.to_string()))
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<reqwest::RequestBuilder> {
+        let request = self.http.request(method, self.url(path)?);
+        Ok(match &self.api_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        })
+    }
+
+    async fn send<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> Result<T> {
+        let mut request = self.request(method, path)?;
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = error_for_status(request.send().await?).await?;
+        let bytes = response.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|e| Error::Decode(e.to_string()))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send::<(), T>(Method::GET, path, None).await
+    }
+
+    async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(Method::POST, path, Some(body)).await
+    }
+
+    async fn put<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(Method::PUT, path, Some(body)).await
+    }
+
+    /// Functions returning nothing answer `null`
+    async fn delete(&self, path: &str) -> Result<()> {
+        self.send::<(), Option<()>>(Method::DELETE, path, None).await.map(|_| ())
+    }
+
+    // -------- AUTH ---------
+
+    /// Create an account and sign in.
+    pub async fn register(&self, payload: &RegisterPayload) -> Result<Account> {
+        self.post("api/v1/auth/register", payload).await
+    }
+
+    /// Sign in with a password. Accounts with two-factor authentication get a challenge, to be
+    /// answered with [`Client::login_two_factor`].
+    pub async fn login(&self, email: &str, password: &str) -> Result<LoginResponse> {
+        let payload = LoginPayload { email: email.to_string(), password: password.to_string() };
+        self.post("api/v1/auth/login", &payload).await
+    }
+
+    /// Finish signing in with a TOTP or recovery code.
+    pub async fn login_two_factor(&self, challenge: &str, code: &str) -> Result<Account> {
+        let payload =
+            TwoFactorLoginPayload { challenge: challenge.to_string(), code: code.to_string() };
+        self.post("api/v1/auth/login/two-factor", &payload).await
+    }
+
+    /// Revoke the current session.
+    pub async fn logout(&self) -> Result<()> {
+        self.post::<_, Option<()>>("api/v1/auth/logout", &()).await.map(|_| ())
+    }
+
+    /// The signed-in account, or `None` when the session is missing or expired.
+    pub async fn me(&self) -> Result<Option<Account>> {
+        self.get("api/v1/auth/me").await
+    }
+
+    // -------- PROJECTS ---------
+
+    pub async fn projects(&self) -> Result<Vec<ProjectDto>> {
+        self.get("api/v1/projects").await
+    }
+
+    pub async fn project(&self, project_id: Uuid) -> Result<ProjectDto> {
+        self.get(&format!("api/v1/projects/{}", project_id)).await
+    }
+
+    pub async fn create_project(&self, project: &CreatableProject) -> Result<ProjectDto> {
+        self.post("api/v1/projects", project).await
+    }
+
+    pub async fn update_project(&self, project: &EditableProject) -> Result<ProjectDto> {
+        self.put("api/v1/projects", project).await
+    }
+
+    pub async fn delete_project(&self, project_id: Uuid) -> Result<()> {
+        self.delete(&format!("api/v1/projects/{}", project_id)).await
+    }
+
+    /// Balances and reimbursements of a project when it was last closed.
+    pub async fn settlement(&self, project_id: Uuid) -> Result<ProjectSettlement> {
+        self.get(&format!("api/v1/projects/{}/settlement", project_id)).await
+    }
+
+    // -------- USERS ---------
+
+    /// Participants of a project, with their balance.
+    pub async fn project_users(&self, project_id: Uuid) -> Result<Vec<User>> {
+        self.get(&format!("api/v1/projects/{}/users", project_id)).await
+    }
+
+    pub async fn add_users(&self, users: Vec<CreatableUser>) -> Result<Vec<User>> {
+        self.post("api/v1/users", &CreatableUserBatch::Multiple(users)).await
+    }
+
+    pub async fn delete_user(&self, user_id: i32) -> Result<()> {
+        self.delete(&format!("api/v1/users/{}", user_id)).await
+    }
+
+    // -------- EXPENSES ---------
+
+    pub async fn project_expenses(&self, project_id: Uuid) -> Result<Vec<Expense>> {
+        self.get(&format!("api/v1/projects/{}/expenses", project_id)).await
+    }
+
+    pub async fn expense(&self, expense_id: i32) -> Result<Expense> {
+        self.get(&format!("api/v1/expenses/{}", expense_id)).await
+    }
+
+    pub async fn add_expense(&self, expense: &CreatableExpense) -> Result<Expense> {
+        self.post("api/v1/expenses", expense).await
+    }
+
+    /// Replace an expense and its payments.
+    pub async fn edit_expense(&self, expense: &EditableExpense) -> Result<Expense> {
+        self.put("api/v1/expenses", expense).await
+    }
+
+    pub async fn delete_expense(&self, expense_id: i32) -> Result<()> {
+        self.delete(&format!("api/v1/expenses/{}", expense_id)).await
+    }
+
+    /// Create, replace and delete expenses of a project, all or none. The results are in the
+    /// order of `operations`; on failure, the error message names the failing operation.
+    pub async fn batch_expenses(
+        &self,
+        project_id: Uuid,
+        operations: &[ExpenseOperation],
+    ) -> Result<Vec<ExpenseOperationResult>> {
+        self.post(&format!("api/v1/projects/{}/expenses/batch", project_id), operations).await
+    }
+
+    // -------- PAYMENTS ---------
+
+    pub async fn expense_payments(&self, expense_id: i32) -> Result<Vec<Payment>> {
+        self.get(&format!("api/v1/expenses/{}/payments", expense_id)).await
+    }
+
+    pub async fn project_payments(&self, project_id: Uuid) -> Result<Vec<Payment>> {
+        self.get(&format!("api/v1/projects/{}/payments", project_id)).await
+    }
+
+    /// Balances and reimbursement suggestions of a project.
+    pub async fn summary(&self, project_id: Uuid) -> Result<UserSummary> {
+        self.get(&format!("api/v1/projects/{}/expenses/summary", project_id)).await
+    }
+
+    // -------- EVENTS ---------
+
+    /// Follow the changes to a project as they are made.
+    pub async fn follow(&self, project_id: Uuid) -> Result<Events<ProjectEvent>> {
+        let request =
+            self.request(Method::GET, &format!("api/v1/projects/{}/events", project_id))?;
+        let response = error_for_status(request.send().await?).await?;
+        Ok(Events::new(response))
+    }
+}
+
+async fn error_for_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|v: &HeaderValue| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+    let body = response.text().await.unwrap_or_default();
+    Err(error::from_response(status, retry_after, &body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use api::config::{Config, Source};
+    use api::router::with_api_layers;
+    use api::state::AppState;
+    use dioxus::prelude::*;
+    use dioxus::server::{axum, DioxusRouterExt, ServeConfig};
+    use shared::sse::EventSSE;
+    use shared::{ApiTokenScope, CreateApiTokenPayload, CreatedApiToken};
+
+    const PASSWORD: &str = "correct horse";
+
+    fn app() -> Element {
+        rsx! {}
+    }
+
+    /// The server functions and middleware of the server binary, on an in-memory SQLite database.
+    async fn serve() -> String {
+        // Named, so that every connection of the pool opens the same database
+        let url = format!("sqlite:file:client-{}?mode=memory&cache=shared", Uuid::new_v4());
+        let source = Source::from_pairs(
+            &[
+                ("DATABASE_URL", url.as_str()),
+                ("COOKIE_SECURE", "false"),
+                ("RATE_LIMIT_LOGIN", "1/60"),
+            ],
+            "",
+        )
+        .unwrap();
+        let config = Config::from_source(&source).unwrap();
+        let db = api::db::connect(&config.database).await.unwrap();
+        api::migrations::migrate(&db).await.unwrap();
+        // The server functions without the static assets of the web app
+        let app = axum::Router::new().serve_api_application(ServeConfig::new(), app);
+        let router = with_api_layers(app, AppState::new(db, config).unwrap());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap()
+        });
+        format!("http://{}", address)
+    }
+
+    async fn register(url: &str, email: &str) -> (Client, Account) {
+        let client = Client::new(url).unwrap();
+        let payload = RegisterPayload {
+            email: email.to_string(),
+            password: PASSWORD.to_string(),
+            display_name: "Alice".to_string(),
+        };
+        let account = client.register(&payload).await.unwrap();
+        (client, account)
+    }
+
+    async fn new_project(client: &Client) -> ProjectDto {
+        let project = CreatableProject {
+            name: "Trip".to_string(),
+            description: None,
+            currency: Some("EUR".to_string()),
+        };
+        client.create_project(&project).await.unwrap()
+    }
+
+    fn read_token() -> CreateApiTokenPayload {
+        CreateApiTokenPayload {
+            name: "Scripts".to_string(),
+            scope: ApiTokenScope::Read,
+            project_ids: None,
+            expires_in_days: 30,
+        }
+    }
+
+    #[tokio::test]
+    async fn login_keeps_the_session() {
+        let url = serve().await;
+        let (client, account) = register(&url, "alice@example.com").await;
+        assert!(client.session_token().is_some());
+        assert_eq!(client.me().await.unwrap(), Some(account.clone()));
+
+        client.logout().await.unwrap();
+        assert_eq!(client.session_token(), None);
+        assert_eq!(client.me().await.unwrap(), None);
+
+        let response = client.login("alice@example.com", PASSWORD).await.unwrap();
+        assert_eq!(response, LoginResponse::SignedIn(account.clone()));
+        assert_eq!(client.me().await.unwrap(), Some(account));
+    }
+
+    #[tokio::test]
+    async fn a_stored_session_can_be_reused() {
+        let url = serve().await;
+        let (alice, account) = register(&url, "alice@example.com").await;
+        let project = new_project(&alice).await;
+
+        let client = Client::new(&url).unwrap().with_session_token(&alice.session_token().unwrap());
+        assert_eq!(client.me().await.unwrap(), Some(account));
+        client.delete_project(project.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn api_tokens_are_sent_as_bearer() {
+        let url = serve().await;
+        let (alice, _) = register(&url, "alice@example.com").await;
+        let project = new_project(&alice).await;
+        let created: CreatedApiToken =
+            alice.post("api/v1/auth/api-tokens", &read_token()).await.unwrap();
+
+        let client = Client::new(&url).unwrap().with_api_token(created.token);
+        assert_eq!(
+            client.project(project.id).await.unwrap(),
+            alice.project(project.id).await.unwrap()
+        );
+        assert!(matches!(client.delete_project(project.id).await, Err(Error::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn errors_are_typed() {
+        let url = serve().await;
+        let (alice, _) = register(&url, "alice@example.com").await;
+        let (bob, _) = register(&url, "bob@example.com").await;
+        let project = new_project(&alice).await;
+        let anonymous = Client::new(&url).unwrap();
+
+        assert!(matches!(bob.project(project.id).await, Err(Error::Forbidden(_))));
+        assert!(matches!(anonymous.project(project.id).await, Err(Error::Forbidden(_))));
+        assert!(matches!(
+            anonymous.post::<_, CreatedApiToken>("api/v1/auth/api-tokens", &read_token()).await,
+            Err(Error::Unauthorized(m)) if m == "Not authenticated"
+        ));
+        assert!(matches!(
+            anonymous.clone().with_api_token("cnt_unknown").projects().await,
+            Err(Error::Unauthorized(m)) if m == "Invalid or expired API token"
+        ));
+        assert!(matches!(alice.expense(1).await, Err(Error::NotFound(_))));
+
+        let stale = EditableProject {
+            id: project.id,
+            name: Some("Renamed".to_string()),
+            description: None,
+            currency: None,
+            status: None,
+            editors_can_edit_others_expenses: None,
+            version: project.version - 1,
+        };
+        assert!(matches!(
+            alice.update_project(&stale).await,
+            Err(Error::Conflict { current: Some(_), .. })
+        ));
+
+        assert!(matches!(
+            anonymous.login("alice@example.com", "wrong").await,
+            Err(Error::Unauthorized(message)) if message == "Invalid email or password"
+        ));
+        assert!(matches!(
+            anonymous.login("alice@example.com", PASSWORD).await,
+            Err(Error::RateLimited { retry_after: Some(_) })
+        ));
+    }
+
+    #[tokio::test]
+    async fn project_events_are_streamed() {
+        let url = serve().await;
+        let (alice, _) = register(&url, "alice@example.com").await;
+        let project = new_project(&alice).await;
+        let mut events = alice.follow(project.id).await.unwrap();
+
+        let bob =
+            CreatableUser { name: "Bob".to_string(), project_id: project.id, invited_email: None };
+        alice.add_users(vec![bob]).await.unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next()).await.unwrap();
+        assert_eq!(
+            event.unwrap().unwrap(),
+            ProjectEvent { project_id: project.id, event: EventSSE::UserCreated }
+        );
+    }
+
+    #[tokio::test]
+    async fn unreachable_servers_are_transport_errors() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let client = Client::new(&url).unwrap();
+        assert!(matches!(client.me().await, Err(Error::Transport(_))));
+    }
+
+    #[test]
+    fn base_urls_keep_their_prefix() {
+        let client = Client::new("https://example.com/counted").unwrap();
+        assert_eq!(
+            client.url("/api/v1/projects").unwrap().as_str(),
+            "https://example.com/counted/api/v1/projects"
+        );
+        assert!(matches!(Client::new("not a url"), Err(Error::InvalidBaseUrl(_))));
+    }
+}
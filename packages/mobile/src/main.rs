@@ -2,6 +2,7 @@ use api::auth::auth_controller::me;
 use dioxus::prelude::*;
 use shared::Account;
 use ui::common::{read_from_ls, LocalStorageState};
+use ui::offline::{read_offline_state, OfflineState};
 use ui::route::Route;
 
 const FAVICON: Asset = asset!("/assets/counted.ico");
@@ -19,6 +20,7 @@ fn main() {
 fn app() -> Element {
     let auth: Signal<Option<Account>> = use_context_provider(|| Signal::new(None));
     let _ls: Signal<LocalStorageState> = use_context_provider(|| Signal::new(read_from_ls()));
+    let _offline: Signal<OfflineState> = use_context_provider(|| Signal::new(read_offline_state()));
 
     use_effect(move || {
         let mut auth = auth;
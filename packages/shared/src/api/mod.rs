@@ -1,3 +1,3 @@
 mod api_state;
-pub use api_state::ApiState;
 pub use api_state::ApiError;
+pub use api_state::ApiState;
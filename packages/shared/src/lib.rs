@@ -10,7 +10,10 @@ use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Lifecycle of a project: a closed project only takes the settlements of its last balances,
+/// an archived one is read-only.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "server", derive(sqlx::Type))]
 #[cfg_attr(feature = "server", sqlx(type_name = "project_status", rename_all = "lowercase"))]
@@ -21,6 +24,7 @@ pub enum ProjectStatus {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "server", derive(FromRow))]
 pub struct ProjectDto {
@@ -31,11 +35,15 @@ pub struct ProjectDto {
     pub description: Option<String>,
     pub status: ProjectStatus,
     pub owner_account_id: Option<Uuid>,
+    pub editors_can_edit_others_expenses: bool,
+    /// Incremented by every edit.
+    pub version: i32,
 }
 
 // -------- ACCOUNT ---------
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "server", derive(FromRow))]
 pub struct Account {
@@ -43,9 +51,12 @@ pub struct Account {
     pub email: String,
     pub display_name: String,
     pub created_at: NaiveDateTime,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub two_factor_enabled: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterPayload {
     pub email: String,
@@ -54,13 +65,192 @@ pub struct RegisterPayload {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct LoginPayload {
     pub email: String,
     pub password: String,
 }
 
+/// Outcome of the password step. Untagged, so accounts without 2FA still get a plain `Account`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(untagged)]
+pub enum LoginResponse {
+    SignedIn(Account),
+    #[serde(rename_all = "camelCase")]
+    TwoFactorRequired {
+        two_factor_challenge: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorLoginPayload {
+    pub challenge: String,
+    /// A TOTP code or a recovery code.
+    pub code: String,
+}
+
+/// Enrollment data, shown until the first code confirms it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorSetup {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub qr_code_svg: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorCodePayload {
+    pub code: String,
+}
+
+/// One-time recovery codes. Only ever returned when they are generated.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodes {
+    pub codes: Vec<String>,
+}
+
+/// What a personal API token may do. Read tokens act as viewers on every project.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(sqlx::Type))]
+#[cfg_attr(feature = "server", sqlx(type_name = "api_token_scope", rename_all = "lowercase"))]
+pub enum ApiTokenScope {
+    Read,
+    Write,
+}
+
+/// A personal API token as listed on the account page. The secret itself is never returned again.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(FromRow))]
+pub struct ApiTokenInfo {
+    pub id: Uuid,
+    pub name: String,
+    /// First characters of the token, to recognise it
+    pub token_prefix: String,
+    pub scope: ApiTokenScope,
+    /// `None` for every project of the account
+    pub project_ids: Option<Vec<Uuid>>,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenPayload {
+    pub name: String,
+    pub scope: ApiTokenScope,
+    pub project_ids: Option<Vec<Uuid>>,
+    pub expires_in_days: i64,
+}
+
+/// A new token. `token` is shown once and cannot be retrieved later.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiToken {
+    pub token: String,
+    pub info: ApiTokenInfo,
+}
+
+/// The OpenID Connect provider configured on the server.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OidcProvider {
+    pub name: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OidcStartPayload {
+    /// Link the identity to the signed-in account instead of signing in.
+    pub link: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OidcAuthorization {
+    /// Provider page the browser is sent to.
+    pub authorization_url: String,
+}
+
+/// Query parameters the provider redirects back with.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OidcCallbackPayload {
+    pub code: String,
+    pub state: String,
+}
+
+/// An external identity linked to the current account.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(FromRow))]
+pub struct LinkedIdentity {
+    pub id: Uuid,
+    pub issuer: String,
+    pub email: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// An active sign-in of the current account, as listed on the account page.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(FromRow))]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    /// The session making the request.
+    pub current: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEmailPayload {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordResetRequestPayload {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordResetPayload {
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CreatableProject {
     pub name: String,
@@ -69,41 +259,205 @@ pub struct CreatableProject {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct EditableProject {
     pub id: Uuid,
     pub name: Option<String>,
     pub description: Option<String>,
     pub currency: Option<String>,
+    /// Move the project one step from ongoing to closed to archived, or back. Owner only;
+    /// closing stores a [`ProjectSettlement`].
     pub status: Option<ProjectStatus>,
+    pub editors_can_edit_others_expenses: Option<bool>,
+    /// Version of the project the edit is based on. The edit is refused with a `conflict`
+    /// carrying the current project when it has been edited since.
+    pub version: i32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct BatchProject {
     pub ids: Vec<Uuid>,
 }
 
+/// Balances and reimbursements of a project as they stood when it was last closed. Participants
+/// are copied, as they may be renamed or removed once the project is reopened.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSettlement {
+    pub project_id: Uuid,
+    pub closed_at: NaiveDateTime,
+    pub users: Vec<User>,
+    pub summary: UserSummary,
+}
+
 // -------- ACCOUNT PROJECT ---------
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(sqlx::Type))]
+#[cfg_attr(feature = "server", sqlx(type_name = "project_role", rename_all = "lowercase"))]
+pub enum ProjectRole {
+    Owner,
+    Editor,
+    Viewer,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "server", derive(FromRow))]
 pub struct AccountProject {
     pub project_id: Uuid,
     pub user_id: Option<i32>,
+    pub role: ProjectRole,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct UpsertAccountProject {
     pub project_id: Uuid,
     pub user_id: Option<i32>,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(FromRow))]
+pub struct ProjectMember {
+    pub account_id: Uuid,
+    pub display_name: String,
+    pub email: String,
+    pub user_id: Option<i32>,
+    pub role: ProjectRole,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProjectMember {
+    pub role: ProjectRole,
+}
+
+// -------- PROJECT INVITE ---------
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(FromRow))]
+pub struct ProjectInvite {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub role: ProjectRole,
+    pub expires_at: NaiveDateTime,
+    pub max_uses: Option<i32>,
+    pub use_count: i32,
+    pub created_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreatableProjectInvite {
+    pub role: ProjectRole,
+    pub expires_in_hours: i64,
+    pub max_uses: Option<i32>,
+}
+
+/// A freshly created invite. The token is only ever returned here.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedProjectInvite {
+    pub invite: ProjectInvite,
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct InviteRedemption {
+    pub project_id: Uuid,
+    pub role: ProjectRole,
+    pub user_id: Option<i32>,
+}
+
+// -------- PUBLIC SHARE LINK ---------
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "server", derive(FromRow))]
+pub struct ShareLink {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+/// A freshly created share link. The token is only ever returned here.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedShareLink {
+    pub link: ShareLink,
+    pub token: String,
+}
+
+/// Everything the read-only public page renders. Deliberately leaves out account data.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PublicProjectView {
+    pub name: String,
+    pub description: Option<String>,
+    pub currency: String,
+    pub status: ProjectStatus,
+    pub users: Vec<User>,
+    pub expenses: Vec<PublicExpense>,
+    pub summary: UserSummary,
+}
+
+/// An expense as shown on the public page. Leaves out the project id: on a project without an
+/// owner, knowing it is enough to edit the project.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PublicExpense {
+    pub id: i32,
+    pub author_id: i32,
+    pub created_at: NaiveDateTime,
+    pub date: NaiveDate,
+    pub amount: f64,
+    pub description: Option<String>,
+    pub name: String,
+    pub expense_type: ExpenseType,
+}
+
+impl From<Expense> for PublicExpense {
+    fn from(expense: Expense) -> Self {
+        Self {
+            id: expense.id,
+            author_id: expense.author_id,
+            created_at: expense.created_at,
+            date: expense.date,
+            amount: expense.amount,
+            description: expense.description,
+            name: expense.name,
+            expense_type: expense.expense_type,
+        }
+    }
+}
+
 // -------- USER ---------
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "server", derive(FromRow))]
 pub struct User {
     pub id: i32,
@@ -113,6 +467,7 @@ pub struct User {
 }
 
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(untagged)] // Accept either a single object or an array
 pub enum CreatableUserBatch {
     Single(CreatableUser),
@@ -120,6 +475,7 @@ pub enum CreatableUserBatch {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CreatableUser {
     pub name: String,
@@ -137,6 +493,7 @@ pub struct UserProjects {
 // -------- EXPENSE ---------
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "server", derive(FromRow))]
 #[serde(rename_all = "camelCase")]
 pub struct Expense {
@@ -149,6 +506,8 @@ pub struct Expense {
     pub description: Option<String>,
     pub name: String,
     pub expense_type: ExpenseType,
+    /// Incremented by every edit.
+    pub version: i32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -164,6 +523,7 @@ pub struct NewExpense {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CreatableExpense {
     pub name: String,
@@ -178,6 +538,7 @@ pub struct CreatableExpense {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct EditableExpense {
     pub id: i32,
@@ -190,9 +551,59 @@ pub struct EditableExpense {
     pub author_id: i32,
     pub description: Option<String>,
     pub date: NaiveDate,
+    /// Version of the expense the edit is based on. The edit is refused with a `conflict`
+    /// carrying the current expense, as an `EditableExpense`, when it has been edited since.
+    pub version: i32,
 }
 
+impl EditableExpense {
+    /// The edit leaving `expense` and its `payments` as they are.
+    pub fn from_expense(expense: &Expense, payments: &[Payment]) -> Self {
+        let shares = |is_debt: bool| {
+            payments
+                .iter()
+                .filter(|p| p.expense_id == expense.id && p.is_debt == is_debt)
+                .map(|p| UserAmount { user_id: p.user_id, amount: p.amount })
+                .collect()
+        };
+        EditableExpense {
+            id: expense.id,
+            name: expense.name.clone(),
+            amount: expense.amount,
+            expense_type: expense.expense_type.clone(),
+            project_id: expense.project_id,
+            payers: shares(false),
+            debtors: shares(true),
+            author_id: expense.author_id,
+            description: expense.description.clone(),
+            date: expense.date,
+            version: expense.version,
+        }
+    }
+}
+
+/// One change in a batch on the expenses of a project, e.g. `{"delete": {"id": 12}}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum ExpenseOperation {
+    Create(CreatableExpense),
+    Update(EditableExpense),
+    Delete { id: i32 },
+}
+
+/// What an [`ExpenseOperation`] did, at the same position in the batch.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum ExpenseOperationResult {
+    Created(Expense),
+    Updated(Expense),
+    Deleted { id: i32 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct UserAmount {
     pub user_id: i32,
@@ -200,6 +611,7 @@ pub struct UserAmount {
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(
     feature = "server",
     derive(sqlx::Type),
@@ -220,6 +632,7 @@ impl fmt::Display for ExpenseType {
 // -------- PAYMENT ---------
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "server", derive(FromRow))]
 pub struct Payment {
@@ -251,6 +664,7 @@ pub struct NewPayment {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct UserSummary {
     pub reimbursement_suggestions: Vec<ReimbursementSuggestion>,
@@ -271,9 +685,81 @@ pub struct UserBalanceComputation {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ReimbursementSuggestion {
     pub amount: f64,
     pub user_id_debtor: i32,
     pub user_id_payer: i32,
 }
+
+// -------- ERROR ---------
+
+/// Machine-readable cause of a failed API call, sent as `data.code` in error responses.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Validation,
+    Conflict,
+    Locked,
+    RateLimited,
+    Internal,
+    /// The server could not be reached, e.g. while offline. Never sent by the API itself.
+    Unavailable,
+}
+
+impl ErrorCode {
+    /// HTTP status of the responses carrying this code.
+    pub fn status(self) -> u16 {
+        match self {
+            ErrorCode::Unauthorized => 401,
+            ErrorCode::Forbidden => 403,
+            ErrorCode::NotFound => 404,
+            ErrorCode::Validation => 422,
+            ErrorCode::Conflict => 409,
+            ErrorCode::Locked => 423,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::Internal => 500,
+            ErrorCode::Unavailable => 503,
+        }
+    }
+
+    /// Best guess for responses without a code, e.g. from a proxy.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            400 | 422 => ErrorCode::Validation,
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            409 => ErrorCode::Conflict,
+            423 => ErrorCode::Locked,
+            429 => ErrorCode::RateLimited,
+            502..=504 => ErrorCode::Unavailable,
+            _ => ErrorCode::Internal,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Validation => "validation",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::Locked => "locked",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::Internal => "internal",
+            ErrorCode::Unavailable => "unavailable",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,6 +1,10 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum EventSSE {
     UserCreated,
     UserDeleted,
@@ -34,3 +38,12 @@ impl fmt::Display for EventSSE {
         }
     }
 }
+
+/// A change to a project, sent to the clients following it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEvent {
+    pub project_id: Uuid,
+    pub event: EventSSE,
+}
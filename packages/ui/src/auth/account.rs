@@ -1,7 +1,12 @@
-use api::auth::auth_controller::logout;
+use api::auth::auth_controller::{logout, request_email_verification};
 use dioxus::prelude::*;
 use shared::Account as AccountData;
 
+use crate::auth::api_tokens_card::ApiTokensCard;
+use crate::auth::linked_identities_card::LinkedIdentitiesCard;
+use crate::auth::sessions_card::SessionsCard;
+use crate::auth::two_factor_card::TwoFactorCard;
+use crate::common::error_message;
 use crate::route::Route;
 
 #[component]
@@ -10,6 +15,7 @@ pub fn Account() -> Element {
     let mut auth_ctx = use_context::<Signal<Option<AccountData>>>();
     let mut loading = use_signal(|| false);
     let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut verification_sent = use_signal(|| false);
 
     let account = auth_ctx();
 
@@ -21,22 +27,28 @@ pub fn Account() -> Element {
 
     let account: AccountData = account.unwrap();
 
-    let on_logout = move |_| {
-        async move {
-            loading.set(true);
-            match logout().await {
-                Ok(_) => {
-                    auth_ctx.set(None);
-                    nav.push(Route::ProjectsList {});
-                }
-                Err(e) => {
-                    error_msg.set(Some(e.to_string()));
-                    loading.set(false);
-                }
+    let on_logout = move |_| async move {
+        loading.set(true);
+        match logout().await {
+            Ok(_) => {
+                auth_ctx.set(None);
+                nav.push(Route::ProjectsList {});
+            }
+            Err(e) => {
+                error_msg.set(Some(error_message(&e)));
+                loading.set(false);
             }
         }
     };
 
+    let on_resend_verification = move |_| async move {
+        error_msg.set(None);
+        match request_email_verification().await {
+            Ok(_) => verification_sent.set(true),
+            Err(e) => error_msg.set(Some(error_message(&e))),
+        }
+    };
+
     rsx! {
         div { class: "container p-4 max-w-md mx-auto flex flex-col gap-4",
             // Header
@@ -76,6 +88,23 @@ pub fn Account() -> Element {
                     div { class: "flex flex-col gap-1",
                         span { class: "text-xs text-base-content/60 uppercase font-semibold", "Email" }
                         span { class: "font-medium", "{account.email}" }
+                        if account.email_verified_at.is_some() {
+                            span { class: "badge badge-success badge-sm", "Vérifié" }
+                        } else {
+                            div { class: "flex items-center gap-2 flex-wrap",
+                                span { class: "badge badge-warning badge-sm", "Non vérifié" }
+                                if verification_sent() {
+                                    span { class: "text-xs text-base-content/60", "Lien envoyé, vérifiez votre boîte mail" }
+                                } else {
+                                    button {
+                                        r#type: "button",
+                                        class: "btn btn-link btn-xs px-0",
+                                        onclick: on_resend_verification,
+                                        "Renvoyer le lien de vérification"
+                                    }
+                                }
+                            }
+                        }
                     }
                     div { class: "divider my-0" }
                     div { class: "flex flex-col gap-1",
@@ -93,6 +122,14 @@ pub fn Account() -> Element {
                     }
                 }
             }
+
+            TwoFactorCard {}
+
+            LinkedIdentitiesCard {}
+
+            SessionsCard {}
+
+            ApiTokensCard {}
         }
     }
 }
@@ -0,0 +1,249 @@
+use api::auth::api_tokens_controller::{create_api_token, list_api_tokens, revoke_api_token};
+use api::projects::projects_controller::get_projects;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{ApiTokenInfo, ApiTokenScope, CreateApiTokenPayload};
+use uuid::Uuid;
+
+use crate::common::error_message;
+
+const LIFETIME_OPTIONS: [i64; 3] = [30, 90, 365];
+
+/// Personal API tokens, for scripts and integrations.
+#[component]
+pub fn ApiTokensCard() -> Element {
+    let mut tokens = use_resource(|| async move { list_api_tokens().await });
+    let projects = use_resource(|| async move { get_projects().await });
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    // Shown once, right after it is created
+    let mut new_token: Signal<Option<String>> = use_signal(|| None);
+    let mut show_form = use_signal(|| false);
+    let mut name = use_signal(String::new);
+    let mut scope = use_signal(|| ApiTokenScope::Read);
+    let mut expires_in_days = use_signal(|| LIFETIME_OPTIONS[0]);
+    let mut all_projects = use_signal(|| true);
+    let mut selected_projects: Signal<Vec<Uuid>> = use_signal(Vec::new);
+    let mut loading = use_signal(|| false);
+
+    let project_name = move |project_id: Uuid| {
+        projects()
+            .and_then(|r| r.ok())
+            .and_then(|list| list.into_iter().find(|p| p.id == project_id).map(|p| p.name))
+            .unwrap_or_else(|| "Projet supprimé".to_string())
+    };
+
+    let on_create = move |e: FormEvent| {
+        e.prevent_default();
+        let payload = CreateApiTokenPayload {
+            name: name(),
+            scope: scope(),
+            project_ids: if all_projects() { None } else { Some(selected_projects()) },
+            expires_in_days: expires_in_days(),
+        };
+        async move {
+            loading.set(true);
+            error_msg.set(None);
+            match create_api_token(Json(payload)).await {
+                Ok(created) => {
+                    new_token.set(Some(created.token));
+                    show_form.set(false);
+                    name.set(String::new());
+                    selected_projects.set(Vec::new());
+                    all_projects.set(true);
+                    tokens.restart();
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+            loading.set(false);
+        }
+    };
+
+    let on_revoke = move |token_id| {
+        spawn(async move {
+            match revoke_api_token(token_id).await {
+                Ok(_) => tokens.restart(),
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+        });
+    };
+
+    let describe = move |token: &ApiTokenInfo| {
+        let scope = match token.scope {
+            ApiTokenScope::Read => "Lecture",
+            ApiTokenScope::Write => "Lecture et écriture",
+        };
+        let projects = match &token.project_ids {
+            None => "tous les projets".to_string(),
+            Some(ids) => ids.iter().map(|id| project_name(*id)).collect::<Vec<_>>().join(", "),
+        };
+        let last_used = match token.last_used_at {
+            Some(at) => format!("utilisé le {}", at.format("%d/%m/%Y")),
+            None => "jamais utilisé".to_string(),
+        };
+        format!(
+            "{} · {} · expire le {} · {}",
+            scope,
+            projects,
+            token.expires_at.format("%d/%m/%Y"),
+            last_used
+        )
+    };
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body gap-3",
+                h2 { class: "card-title text-base", "Jetons d'API" }
+
+                if let Some(err) = error_msg() {
+                    div { class: "alert alert-error text-sm", "{err}" }
+                }
+
+                if let Some(token) = new_token() {
+                    div { class: "alert alert-warning text-sm flex flex-col items-start gap-2",
+                        span {
+                            "Copiez ce jeton maintenant, il ne sera plus affiché. Envoyez-le dans l'en-tête Authorization: Bearer."
+                        }
+                        span { class: "font-mono break-all select-all", "{token}" }
+                    }
+                }
+
+                match &*tokens.read() {
+                    Some(Ok(list)) if list.is_empty() => rsx! {
+                        p { class: "text-sm text-base-content/70",
+                            "Créez un jeton pour accéder à vos projets depuis un script ou une autre application."
+                        }
+                    },
+                    Some(Ok(list)) => rsx! {
+                        ul { class: "flex flex-col gap-2",
+                            for token in list.iter().cloned() {
+                                li {
+                                    key: "{token.id}",
+                                    class: "flex items-center justify-between gap-2",
+                                    div { class: "flex flex-col min-w-0",
+                                        span { class: "font-medium truncate", "{token.name}" }
+                                        span { class: "text-xs font-mono text-base-content/60", "{token.token_prefix}…" }
+                                        span { class: "text-xs text-base-content/60", "{describe(&token)}" }
+                                    }
+                                    button {
+                                        r#type: "button",
+                                        class: "btn btn-ghost btn-xs text-error",
+                                        onclick: move |_| on_revoke(token.id),
+                                        "Révoquer"
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "alert alert-error text-sm", {error_message(e)} }
+                    },
+                    None => rsx! {
+                        div { class: "flex justify-center py-2",
+                            span { class: "loading loading-spinner loading-sm" }
+                        }
+                    },
+                }
+
+                if show_form() {
+                    form { class: "flex flex-col gap-2", onsubmit: on_create,
+                        input {
+                            class: "input input-bordered input-sm",
+                            r#type: "text",
+                            placeholder: "Nom du jeton",
+                            maxlength: "100",
+                            required: true,
+                            value: "{name}",
+                            oninput: move |e| name.set(e.value()),
+                        }
+                        select {
+                            class: "select select-bordered select-sm",
+                            onchange: move |e| {
+                                scope.set(if e.value() == "write" { ApiTokenScope::Write } else { ApiTokenScope::Read });
+                            },
+                            option { value: "read", selected: scope() == ApiTokenScope::Read, "Lecture seule" }
+                            option { value: "write", selected: scope() == ApiTokenScope::Write, "Lecture et écriture" }
+                        }
+                        select {
+                            class: "select select-bordered select-sm",
+                            onchange: move |e| {
+                                if let Ok(days) = e.value().parse() {
+                                    expires_in_days.set(days);
+                                }
+                            },
+                            for days in LIFETIME_OPTIONS {
+                                option {
+                                    value: "{days}",
+                                    selected: expires_in_days() == days,
+                                    "Expire dans {days} jours"
+                                }
+                            }
+                        }
+                        label { class: "label cursor-pointer justify-start gap-2",
+                            input {
+                                r#type: "checkbox",
+                                class: "checkbox checkbox-sm",
+                                checked: all_projects(),
+                                onchange: move |e| all_projects.set(e.checked()),
+                            }
+                            span { class: "label-text", "Tous mes projets" }
+                        }
+                        if !all_projects() {
+                            if let Some(Ok(list)) = projects() {
+                                div { class: "flex flex-col gap-1 pl-2",
+                                    for project in list {
+                                        label {
+                                            key: "{project.id}",
+                                            class: "label cursor-pointer justify-start gap-2 py-0",
+                                            input {
+                                                r#type: "checkbox",
+                                                class: "checkbox checkbox-xs",
+                                                checked: selected_projects().contains(&project.id),
+                                                onchange: move |e| {
+                                                    if e.checked() {
+                                                        selected_projects.write().push(project.id);
+                                                    } else {
+                                                        selected_projects.write().retain(|id| *id != project.id);
+                                                    }
+                                                },
+                                            }
+                                            span { class: "label-text", "{project.name}" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        p { class: "text-xs text-base-content/60",
+                            "Un jeton limité à certains projets ne peut ni créer de projet ni rejoindre un projet."
+                        }
+                        div { class: "card-actions justify-end",
+                            button {
+                                r#type: "button",
+                                class: "btn btn-sm btn-ghost",
+                                onclick: move |_| show_form.set(false),
+                                "Annuler"
+                            }
+                            button {
+                                class: "btn btn-sm btn-primary",
+                                r#type: "submit",
+                                disabled: loading() || (!all_projects() && selected_projects().is_empty()),
+                                "Créer"
+                            }
+                        }
+                    }
+                } else {
+                    div { class: "card-actions justify-end",
+                        button {
+                            r#type: "button",
+                            class: "btn btn-sm btn-primary btn-outline",
+                            onclick: move |_| {
+                                new_token.set(None);
+                                show_form.set(true);
+                            },
+                            "Nouveau jeton"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use api::auth::auth_controller::request_password_reset;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::PasswordResetRequestPayload;
+
+use crate::common::{error_message, AppHeader};
+use crate::route::Route;
+
+#[component]
+pub fn ForgotPassword() -> Element {
+    let mut email = use_signal(String::new);
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut loading = use_signal(|| false);
+    let mut sent = use_signal(|| false);
+
+    let on_submit = move |e: FormEvent| {
+        e.prevent_default();
+        let email_val = email();
+
+        async move {
+            loading.set(true);
+            error_msg.set(None);
+
+            match request_password_reset(Json(PasswordResetRequestPayload { email: email_val }))
+                .await
+            {
+                Ok(_) => sent.set(true),
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+            loading.set(false);
+        }
+    };
+
+    rsx! {
+        div { class: "container p-4 max-w-sm mx-auto flex flex-col gap-6 mt-16",
+            AppHeader { title: "Forgot password", back_button_route: Route::Login {} }
+
+            if let Some(err) = error_msg() {
+                div { class: "alert alert-error", "{err}" }
+            }
+
+            if sent() {
+                div { class: "alert alert-success",
+                    "If an account exists for {email}, a reset link is on its way. It is valid for one hour."
+                }
+            } else {
+                form { class: "flex flex-col gap-4", onsubmit: on_submit,
+                    p { class: "text-sm text-base-content/70",
+                        "Enter your email address and we will send you a link to choose a new password."
+                    }
+                    label { class: "form-control",
+                        span { class: "label-text mb-1", "Email" }
+                        input {
+                            class: "input input-bordered",
+                            r#type: "email",
+                            required: true,
+                            value: "{email}",
+                            oninput: move |e| email.set(e.value()),
+                        }
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        r#type: "submit",
+                        disabled: loading(),
+                        if loading() {
+                            "Sending…"
+                        } else {
+                            "Send reset link"
+                        }
+                    }
+                }
+            }
+
+            p { class: "text-center text-sm",
+                a { class: "link link-primary", href: "/login", "Back to sign in" }
+            }
+        }
+    }
+}
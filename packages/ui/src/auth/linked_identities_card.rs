@@ -0,0 +1,94 @@
+use api::auth::oidc_controller::{get_oidc_provider, list_linked_identities, unlink_identity};
+use dioxus::prelude::*;
+
+use crate::auth::oidc::redirect_to_provider;
+use crate::common::error_message;
+
+/// External identities linked to the account. Hidden when no provider is configured.
+#[component]
+pub fn LinkedIdentitiesCard() -> Element {
+    let provider = use_resource(|| async move { get_oidc_provider().await });
+    let mut identities = use_resource(|| async move { list_linked_identities().await });
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+
+    let Some(Ok(Some(provider))) = provider() else {
+        return rsx! {};
+    };
+
+    let on_link = move |_| async move {
+        error_msg.set(None);
+        if let Err(e) = redirect_to_provider(true).await {
+            error_msg.set(Some(error_message(&e)));
+        }
+    };
+
+    let on_unlink = move |identity_id| {
+        spawn(async move {
+            match unlink_identity(identity_id).await {
+                Ok(_) => identities.restart(),
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body gap-3",
+                h2 { class: "card-title text-base", "Connexions externes" }
+
+                if let Some(err) = error_msg() {
+                    div { class: "alert alert-error text-sm", "{err}" }
+                }
+
+                match &*identities.read() {
+                    Some(Ok(list)) if list.is_empty() => rsx! {
+                        p { class: "text-sm text-base-content/70",
+                            "Liez votre compte {provider.name} pour vous connecter sans mot de passe."
+                        }
+                    },
+                    Some(Ok(list)) => rsx! {
+                        ul { class: "flex flex-col gap-2",
+                            for identity in list.iter().cloned() {
+                                li {
+                                    key: "{identity.id}",
+                                    class: "flex items-center justify-between gap-2",
+                                    div { class: "flex flex-col min-w-0",
+                                        span { class: "font-medium truncate",
+                                            "{identity.email.clone().unwrap_or_else(|| provider.name.clone())}"
+                                        }
+                                        span { class: "text-xs text-base-content/60 truncate",
+                                            "{identity.issuer} · lié le {identity.created_at.format(\"%d/%m/%Y\")}"
+                                        }
+                                    }
+                                    button {
+                                        r#type: "button",
+                                        class: "btn btn-ghost btn-xs text-error",
+                                        onclick: move |_| on_unlink(identity.id),
+                                        "Délier"
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "alert alert-error text-sm", {error_message(e)} }
+                    },
+                    None => rsx! {
+                        div { class: "flex justify-center py-2",
+                            span { class: "loading loading-spinner loading-sm" }
+                        }
+                    },
+                }
+
+                div { class: "card-actions justify-end",
+                    button {
+                        r#type: "button",
+                        class: "btn btn-sm btn-primary btn-outline",
+                        onclick: on_link,
+                        "Lier {provider.name}"
+                    }
+                }
+            }
+        }
+    }
+}
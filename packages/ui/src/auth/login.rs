@@ -1,9 +1,13 @@
-use api::auth::auth_controller::login;
+use api::auth::auth_controller::{login, login_two_factor};
 use dioxus::fullstack::Json;
 use dioxus::prelude::*;
-use shared::{Account, LoginPayload};
+use shared::{Account, LoginPayload, LoginResponse, TwoFactorLoginPayload};
 
-use crate::{common::AppHeader, route::Route};
+use crate::auth::oidc::OidcSignInButton;
+use crate::{
+    common::{error_message, AppHeader},
+    route::Route,
+};
 
 #[component]
 pub fn Login() -> Element {
@@ -11,6 +15,8 @@ pub fn Login() -> Element {
     let mut password = use_signal(String::new);
     let mut error_msg: Signal<Option<String>> = use_signal(|| None);
     let mut loading = use_signal(|| false);
+    // Set once the password is accepted for an account with 2FA
+    let mut challenge: Signal<Option<String>> = use_signal(|| None);
 
     let nav = use_navigator();
     let mut auth_ctx = use_context::<Signal<Option<Account>>>();
@@ -25,18 +31,35 @@ pub fn Login() -> Element {
             error_msg.set(None);
 
             match login(Json(LoginPayload { email: email_val, password: password_val })).await {
-                Ok(account) => {
+                Ok(LoginResponse::SignedIn(account)) => {
                     auth_ctx.set(Some(account));
                     nav.push("/");
                 }
+                Ok(LoginResponse::TwoFactorRequired { two_factor_challenge }) => {
+                    challenge.set(Some(two_factor_challenge));
+                    loading.set(false);
+                }
                 Err(e) => {
-                    error_msg.set(Some(e.to_string()));
+                    error_msg.set(Some(error_message(&e)));
                     loading.set(false);
                 }
             }
         }
     };
 
+    if let Some(challenge_val) = challenge() {
+        return rsx! {
+            TwoFactorStep {
+                challenge: challenge_val,
+                on_cancel: move |_| {
+                    challenge.set(None);
+                    password.set(String::new());
+                    error_msg.set(None);
+                },
+            }
+        };
+    }
+
     rsx! {
         div { class: "container p-4 max-w-sm mx-auto flex flex-col gap-6 mt-16",
             AppHeader { title: "Sign in", back_button_route: Route::ProjectsList {} }
@@ -78,6 +101,12 @@ pub fn Login() -> Element {
                 }
             }
 
+            p { class: "text-center text-sm",
+                a { class: "link link-primary", href: "/forgot-password", "Forgot password?" }
+            }
+
+            OidcSignInButton {}
+
             p { class: "text-center text-sm",
                 "No account? "
                 a {
@@ -92,3 +121,91 @@ pub fn Login() -> Element {
         }
     }
 }
+
+/// Second login step for accounts with 2FA: a TOTP or recovery code for the challenge.
+#[component]
+pub fn TwoFactorStep(challenge: String, on_cancel: EventHandler<()>) -> Element {
+    let mut code = use_signal(String::new);
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut loading = use_signal(|| false);
+
+    let nav = use_navigator();
+    let mut auth_ctx = use_context::<Signal<Option<Account>>>();
+
+    let on_submit = move |e: FormEvent| {
+        e.prevent_default();
+        let challenge_val = challenge.clone();
+        let code_val = code();
+
+        async move {
+            loading.set(true);
+            error_msg.set(None);
+
+            match login_two_factor(Json(TwoFactorLoginPayload {
+                challenge: challenge_val,
+                code: code_val,
+            }))
+            .await
+            {
+                Ok(account) => {
+                    auth_ctx.set(Some(account));
+                    nav.push("/");
+                }
+                Err(e) => {
+                    error_msg.set(Some(error_message(&e)));
+                    code.set(String::new());
+                    loading.set(false);
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "container p-4 max-w-sm mx-auto flex flex-col gap-6 mt-16",
+            AppHeader { title: "Two-factor authentication", back_button_route: Route::ProjectsList {} }
+
+            if let Some(err) = error_msg() {
+                div { class: "alert alert-error", "{err}" }
+            }
+
+            p { class: "text-sm",
+                "Enter the 6-digit code from your authenticator app, or one of your recovery codes."
+            }
+
+            form { class: "flex flex-col gap-4", onsubmit: on_submit,
+                label { class: "form-control",
+                    span { class: "label-text mb-1", "Code" }
+                    input {
+                        class: "input input-bordered",
+                        r#type: "text",
+                        autocomplete: "one-time-code",
+                        required: true,
+                        value: "{code}",
+                        oninput: move |e| code.set(e.value()),
+                    }
+                }
+                button {
+                    class: "btn btn-primary",
+                    r#type: "submit",
+                    disabled: loading(),
+                    if loading() {
+                        "Verifying…"
+                    } else {
+                        "Verify"
+                    }
+                }
+            }
+
+            p { class: "text-center text-sm",
+                a {
+                    class: "link link-primary",
+                    onclick: move |e| {
+                        e.prevent_default();
+                        on_cancel.call(());
+                    },
+                    "Use another account"
+                }
+            }
+        }
+    }
+}
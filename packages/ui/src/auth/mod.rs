@@ -1,3 +1,11 @@
 pub mod account;
+pub mod api_tokens_card;
+pub mod forgot_password;
+pub mod linked_identities_card;
 pub mod login;
+pub mod oidc;
 pub mod register;
+pub mod reset_password;
+pub mod sessions_card;
+pub mod two_factor_card;
+pub mod verify_email;
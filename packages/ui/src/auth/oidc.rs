@@ -0,0 +1,121 @@
+use api::auth::oidc_controller::{complete_oidc_login, get_oidc_provider, start_oidc_login};
+use api::error::ApiError;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{Account, LoginResponse, OidcCallbackPayload, OidcStartPayload};
+
+use crate::auth::login::TwoFactorStep;
+use crate::common::{error_message, AppHeader};
+use crate::route::Route;
+
+/// Send the browser to the identity provider's sign-in page.
+pub async fn redirect_to_provider(link: bool) -> Result<(), ApiError> {
+    let authorization = start_oidc_login(Json(OidcStartPayload { link })).await?;
+    navigator().push(NavigationTarget::<String>::External(authorization.authorization_url));
+    Ok(())
+}
+
+/// "Sign in with …" button, only shown when the server has a provider configured.
+#[component]
+pub fn OidcSignInButton() -> Element {
+    let provider = use_resource(|| async move { get_oidc_provider().await });
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut loading = use_signal(|| false);
+
+    let Some(Ok(Some(provider))) = provider() else {
+        return rsx! {};
+    };
+
+    let on_click = move |_| async move {
+        loading.set(true);
+        error_msg.set(None);
+        if let Err(e) = redirect_to_provider(false).await {
+            error_msg.set(Some(error_message(&e)));
+            loading.set(false);
+        }
+    };
+
+    rsx! {
+        div { class: "divider text-sm", "or" }
+        if let Some(err) = error_msg() {
+            div { class: "alert alert-error", "{err}" }
+        }
+        button {
+            r#type: "button",
+            class: "btn btn-outline",
+            disabled: loading(),
+            onclick: on_click,
+            "Sign in with {provider.name}"
+        }
+    }
+}
+
+/// Page the identity provider redirects back to.
+#[component]
+pub fn OidcCallback(code: String, state: String, error: String) -> Element {
+    let nav = use_navigator();
+    let mut auth_ctx = use_context::<Signal<Option<Account>>>();
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut challenge: Signal<Option<String>> = use_signal(|| None);
+
+    // Complete once on mount. A signed-in account is here to link an identity.
+    use_effect(move || {
+        if !error.is_empty() {
+            error_msg.set(Some("Sign-in was cancelled or refused by the provider.".to_string()));
+            return;
+        }
+        let payload = OidcCallbackPayload { code: code.clone(), state: state.clone() };
+        let linking = auth_ctx.peek().is_some();
+        spawn(async move {
+            match complete_oidc_login(Json(payload)).await {
+                Ok(LoginResponse::SignedIn(account)) => {
+                    auth_ctx.set(Some(account));
+                    if linking {
+                        nav.replace(Route::Account {});
+                    } else {
+                        nav.replace(Route::ProjectsList {});
+                    }
+                }
+                Ok(LoginResponse::TwoFactorRequired { two_factor_challenge }) => {
+                    challenge.set(Some(two_factor_challenge));
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+        });
+    });
+
+    if let Some(challenge_val) = challenge() {
+        return rsx! {
+            TwoFactorStep {
+                challenge: challenge_val,
+                on_cancel: move |_| { nav.replace(Route::Login {}); },
+            }
+        };
+    }
+
+    rsx! {
+        div { class: "container p-4 max-w-sm mx-auto flex flex-col gap-6 mt-16",
+            AppHeader { title: "Sign in", back_button_route: Route::ProjectsList {} }
+
+            if let Some(err) = error_msg() {
+                div { class: "alert alert-error", "{err}" }
+                button {
+                    r#type: "button",
+                    class: "btn btn-primary",
+                    onclick: move |_| {
+                        if auth_ctx().is_some() {
+                            nav.replace(Route::Account {});
+                        } else {
+                            nav.replace(Route::Login {});
+                        }
+                    },
+                    "Back"
+                }
+            } else {
+                div { class: "flex justify-center py-8",
+                    span { class: "loading loading-spinner loading-md" }
+                }
+            }
+        }
+    }
+}
@@ -3,7 +3,8 @@ use dioxus::fullstack::Json;
 use dioxus::prelude::*;
 use shared::{Account, RegisterPayload};
 
-use crate::common::AppHeader;
+use crate::auth::oidc::OidcSignInButton;
+use crate::common::{error_message, AppHeader};
 use crate::route::Route;
 
 #[component]
@@ -39,7 +40,7 @@ pub fn Register() -> Element {
                     nav.push("/");
                 }
                 Err(e) => {
-                    error_msg.set(Some(e.to_string()));
+                    error_msg.set(Some(error_message(&e)));
                     loading.set(false);
                 }
             }
@@ -101,6 +102,8 @@ pub fn Register() -> Element {
                 }
             }
 
+            OidcSignInButton {}
+
             p { class: "text-center text-sm",
                 "Already have an account? "
                 a { class: "link link-primary", href: "/login", "Sign in" }
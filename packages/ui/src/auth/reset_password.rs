@@ -0,0 +1,103 @@
+use api::auth::auth_controller::reset_password;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{Account, PasswordResetPayload};
+
+use crate::common::{error_message, AppHeader};
+use crate::route::Route;
+
+#[component]
+pub fn ResetPassword(token: String) -> Element {
+    let mut password = use_signal(String::new);
+    let mut confirmation = use_signal(String::new);
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut loading = use_signal(|| false);
+    let mut done = use_signal(|| false);
+
+    let nav = use_navigator();
+    let mut auth_ctx = use_context::<Signal<Option<Account>>>();
+
+    let on_submit = move |e: FormEvent| {
+        e.prevent_default();
+        let token = token.clone();
+        let password_val = password();
+
+        async move {
+            if password_val != confirmation() {
+                error_msg.set(Some("Passwords do not match".to_string()));
+                return;
+            }
+
+            loading.set(true);
+            error_msg.set(None);
+
+            match reset_password(Json(PasswordResetPayload { token, password: password_val })).await
+            {
+                Ok(_) => {
+                    // Every session was revoked, including this one
+                    auth_ctx.set(None);
+                    done.set(true);
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+            loading.set(false);
+        }
+    };
+
+    rsx! {
+        div { class: "container p-4 max-w-sm mx-auto flex flex-col gap-6 mt-16",
+            AppHeader { title: "Choose a new password", back_button_route: Route::Login {} }
+
+            if let Some(err) = error_msg() {
+                div { class: "alert alert-error", "{err}" }
+            }
+
+            if done() {
+                div { class: "alert alert-success",
+                    "Your password has been changed. You have been signed out of every device."
+                }
+                button {
+                    r#type: "button",
+                    class: "btn btn-primary",
+                    onclick: move |_| { nav.push(Route::Login {}); },
+                    "Sign in"
+                }
+            } else {
+                form { class: "flex flex-col gap-4", onsubmit: on_submit,
+                    label { class: "form-control",
+                        span { class: "label-text mb-1", "New password" }
+                        input {
+                            class: "input input-bordered",
+                            r#type: "password",
+                            required: true,
+                            minlength: "8",
+                            value: "{password}",
+                            oninput: move |e| password.set(e.value()),
+                        }
+                    }
+                    label { class: "form-control",
+                        span { class: "label-text mb-1", "Confirm password" }
+                        input {
+                            class: "input input-bordered",
+                            r#type: "password",
+                            required: true,
+                            minlength: "8",
+                            value: "{confirmation}",
+                            oninput: move |e| confirmation.set(e.value()),
+                        }
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        r#type: "submit",
+                        disabled: loading(),
+                        if loading() {
+                            "Saving…"
+                        } else {
+                            "Change password"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use api::auth::auth_controller::{list_sessions, logout_everywhere, revoke_session};
+use dioxus::prelude::*;
+use shared::{Account, SessionInfo};
+
+use crate::common::error_message;
+use crate::route::Route;
+
+/// Short "browser · system" label from a User-Agent string.
+fn device_label(user_agent: Option<&str>) -> String {
+    let Some(ua) = user_agent else {
+        return "Appareil inconnu".to_string();
+    };
+
+    // Order matters: Edge and Opera also announce Chrome, Chrome also announces Safari
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ]
+    .iter()
+    .find(|(marker, _)| ua.contains(marker))
+    .map(|(_, name)| *name);
+
+    let system = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .iter()
+    .find(|(marker, _)| ua.contains(marker))
+    .map(|(_, name)| *name);
+
+    match (browser, system) {
+        (Some(b), Some(s)) => format!("{} · {}", b, s),
+        (Some(name), None) | (None, Some(name)) => name.to_string(),
+        (None, None) => ua.chars().take(40).collect(),
+    }
+}
+
+#[component]
+pub fn SessionsCard() -> Element {
+    let nav = use_navigator();
+    let mut auth_ctx = use_context::<Signal<Option<Account>>>();
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+
+    let mut sessions = use_resource(|| async move { list_sessions().await });
+
+    let on_revoke = move |session_id| {
+        spawn(async move {
+            match revoke_session(session_id).await {
+                Ok(_) => sessions.restart(),
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+        });
+    };
+
+    let on_logout_everywhere = move |_| async move {
+        match logout_everywhere().await {
+            Ok(_) => {
+                auth_ctx.set(None);
+                nav.push(Route::ProjectsList {});
+            }
+            Err(e) => error_msg.set(Some(error_message(&e))),
+        }
+    };
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body gap-3",
+                h2 { class: "card-title text-base", "Sessions actives" }
+
+                if let Some(err) = error_msg() {
+                    div { class: "alert alert-error text-sm", "{err}" }
+                }
+
+                match &*sessions.read() {
+                    None => rsx! {
+                        div { class: "flex justify-center py-4",
+                            span { class: "loading loading-spinner loading-sm" }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "alert alert-error text-sm", {error_message(e)} }
+                    },
+                    Some(Ok(list)) => rsx! {
+                        ul { class: "flex flex-col divide-y divide-base-200",
+                            for session in list.iter().cloned() {
+                                SessionRow { key: "{session.id}", session, on_revoke }
+                            }
+                        }
+                    },
+                }
+
+                div { class: "card-actions justify-end",
+                    button {
+                        r#type: "button",
+                        class: "btn btn-error btn-outline btn-sm",
+                        onclick: on_logout_everywhere,
+                        "Se déconnecter partout"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SessionRow(session: SessionInfo, on_revoke: EventHandler<uuid::Uuid>) -> Element {
+    let label = device_label(session.user_agent.as_deref());
+    let last_seen = session.last_seen_at.format("%d/%m/%Y %H:%M").to_string();
+
+    rsx! {
+        li { class: "flex items-center justify-between gap-2 py-2",
+            div { class: "flex flex-col min-w-0",
+                span { class: "font-medium text-sm truncate",
+                    "{label}"
+                    if session.current {
+                        span { class: "badge badge-primary badge-sm ml-2", "Cet appareil" }
+                    }
+                }
+                span { class: "text-xs text-base-content/60",
+                    "Dernière activité le {last_seen} UTC"
+                    if let Some(ip) = &session.ip_address {
+                        " · {ip}"
+                    }
+                }
+            }
+            if !session.current {
+                button {
+                    r#type: "button",
+                    class: "btn btn-ghost btn-xs",
+                    onclick: move |_| on_revoke.call(session.id),
+                    "Révoquer"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use api::auth::two_factor_controller::{
+    confirm_two_factor_setup, disable_two_factor, regenerate_recovery_codes, start_two_factor_setup,
+};
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{Account, TwoFactorCodePayload, TwoFactorSetup};
+
+use crate::common::error_message;
+
+#[component]
+pub fn TwoFactorCard() -> Element {
+    let mut auth_ctx = use_context::<Signal<Option<Account>>>();
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut setup: Signal<Option<TwoFactorSetup>> = use_signal(|| None);
+    // Shown once, right after they are issued
+    let mut recovery_codes: Signal<Option<Vec<String>>> = use_signal(|| None);
+    let mut code = use_signal(String::new);
+    let mut loading = use_signal(|| false);
+
+    let enabled = auth_ctx().is_some_and(|account| account.two_factor_enabled);
+
+    let mut set_enabled = move |value: bool| {
+        if let Some(mut account) = auth_ctx() {
+            account.two_factor_enabled = value;
+            auth_ctx.set(Some(account));
+        }
+    };
+
+    let on_start = move |_| async move {
+        error_msg.set(None);
+        recovery_codes.set(None);
+        match start_two_factor_setup().await {
+            Ok(s) => setup.set(Some(s)),
+            Err(e) => error_msg.set(Some(error_message(&e))),
+        }
+    };
+
+    let on_confirm = move |e: FormEvent| {
+        e.prevent_default();
+        let code_val = code();
+        async move {
+            loading.set(true);
+            error_msg.set(None);
+            match confirm_two_factor_setup(Json(TwoFactorCodePayload { code: code_val })).await {
+                Ok(codes) => {
+                    setup.set(None);
+                    recovery_codes.set(Some(codes.codes));
+                    set_enabled(true);
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+            code.set(String::new());
+            loading.set(false);
+        }
+    };
+
+    let on_disable = move |_| async move {
+        loading.set(true);
+        error_msg.set(None);
+        match disable_two_factor(Json(TwoFactorCodePayload { code: code() })).await {
+            Ok(_) => {
+                recovery_codes.set(None);
+                set_enabled(false);
+            }
+            Err(e) => error_msg.set(Some(error_message(&e))),
+        }
+        code.set(String::new());
+        loading.set(false);
+    };
+
+    let on_regenerate = move |_| async move {
+        loading.set(true);
+        error_msg.set(None);
+        match regenerate_recovery_codes(Json(TwoFactorCodePayload { code: code() })).await {
+            Ok(codes) => recovery_codes.set(Some(codes.codes)),
+            Err(e) => error_msg.set(Some(error_message(&e))),
+        }
+        code.set(String::new());
+        loading.set(false);
+    };
+
+    rsx! {
+        div { class: "card bg-base-100 shadow",
+            div { class: "card-body gap-3",
+                div { class: "flex items-center justify-between",
+                    h2 { class: "card-title text-base", "Double authentification" }
+                    if enabled {
+                        span { class: "badge badge-success badge-sm", "Activée" }
+                    } else {
+                        span { class: "badge badge-ghost badge-sm", "Désactivée" }
+                    }
+                }
+
+                if let Some(err) = error_msg() {
+                    div { class: "alert alert-error text-sm", "{err}" }
+                }
+
+                if let Some(codes) = recovery_codes() {
+                    div { class: "alert alert-warning text-sm flex flex-col items-start gap-2",
+                        span {
+                            "Codes de secours : chacun ne sert qu'une fois. Conservez-les en lieu sûr, ils ne seront plus affichés."
+                        }
+                        ul { class: "font-mono grid grid-cols-2 gap-x-4",
+                            for recovery_code in codes {
+                                li { "{recovery_code}" }
+                            }
+                        }
+                    }
+                }
+
+                if enabled {
+                    p { class: "text-sm text-base-content/70",
+                        "Un code de votre application d'authentification (ou un code de secours) est demandé à chaque connexion."
+                    }
+                    input {
+                        class: "input input-bordered input-sm",
+                        r#type: "text",
+                        autocomplete: "one-time-code",
+                        placeholder: "Code actuel ou code de secours",
+                        value: "{code}",
+                        oninput: move |e| code.set(e.value()),
+                    }
+                    div { class: "card-actions justify-end",
+                        button {
+                            r#type: "button",
+                            class: "btn btn-sm btn-ghost",
+                            disabled: loading() || code().trim().is_empty(),
+                            onclick: on_regenerate,
+                            "Nouveaux codes de secours"
+                        }
+                        button {
+                            r#type: "button",
+                            class: "btn btn-sm btn-error btn-outline",
+                            disabled: loading() || code().trim().is_empty(),
+                            onclick: on_disable,
+                            "Désactiver"
+                        }
+                    }
+                } else if let Some(s) = setup() {
+                    p { class: "text-sm",
+                        "Scannez ce QR code avec votre application d'authentification, puis saisissez le code affiché."
+                    }
+                    div { class: "flex justify-center", dangerous_inner_html: "{s.qr_code_svg}" }
+                    p { class: "text-xs text-base-content/60 break-all",
+                        "Clé à saisir manuellement : "
+                        span { class: "font-mono", "{s.secret}" }
+                    }
+                    form { class: "flex gap-2", onsubmit: on_confirm,
+                        input {
+                            class: "input input-bordered input-sm flex-1",
+                            r#type: "text",
+                            inputmode: "numeric",
+                            autocomplete: "one-time-code",
+                            placeholder: "123456",
+                            required: true,
+                            value: "{code}",
+                            oninput: move |e| code.set(e.value()),
+                        }
+                        button {
+                            class: "btn btn-sm btn-primary",
+                            r#type: "submit",
+                            disabled: loading(),
+                            "Activer"
+                        }
+                    }
+                } else {
+                    p { class: "text-sm text-base-content/70",
+                        "Protégez votre compte avec un code à usage unique en plus du mot de passe."
+                    }
+                    div { class: "card-actions justify-end",
+                        button {
+                            r#type: "button",
+                            class: "btn btn-sm btn-primary btn-outline",
+                            onclick: on_start,
+                            "Configurer"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use api::auth::auth_controller::verify_email;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{Account, VerifyEmailPayload};
+
+use crate::common::{error_message, AppHeader};
+use crate::route::Route;
+
+#[component]
+pub fn VerifyEmail(token: String) -> Element {
+    let nav = use_navigator();
+    let mut auth_ctx = use_context::<Signal<Option<Account>>>();
+    let mut verified = use_signal(|| false);
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+
+    // Verify once on mount. The link may be opened in a browser without a session.
+    use_effect(move || {
+        let token = token.clone();
+        spawn(async move {
+            match verify_email(Json(VerifyEmailPayload { token })).await {
+                Ok(account) => {
+                    if auth_ctx.peek().as_ref().is_some_and(|a| a.id == account.id) {
+                        auth_ctx.set(Some(account));
+                    }
+                    verified.set(true);
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "container p-4 max-w-sm mx-auto flex flex-col gap-6 mt-16",
+            AppHeader { title: "Email verification", back_button_route: Route::ProjectsList {} }
+
+            if let Some(err) = error_msg() {
+                div { class: "alert alert-error", "{err}" }
+                if auth_ctx().is_some() {
+                    p { class: "text-sm text-center",
+                        "You can ask for a new link from your account page."
+                    }
+                }
+            } else if verified() {
+                div { class: "alert alert-success",
+                    "Your email address is verified. Projects you were invited to are now in your list."
+                }
+                button {
+                    r#type: "button",
+                    class: "btn btn-primary",
+                    onclick: move |_| { nav.push(Route::ProjectsList {}); },
+                    "Continue"
+                }
+            } else {
+                div { class: "flex justify-center py-8",
+                    span { class: "loading loading-spinner loading-md" }
+                }
+            }
+        }
+    }
+}
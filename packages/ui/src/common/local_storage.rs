@@ -1,3 +1,4 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -15,44 +16,53 @@ pub struct LocalStorageProject {
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn storage_file() -> std::path::PathBuf {
+fn storage_file(key: &str) -> std::path::PathBuf {
     let dir = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    std::path::PathBuf::from(dir).join("counted_local_storage.json")
+    std::path::PathBuf::from(dir).join(format!("{}.json", key))
 }
 
 pub fn read_from_ls() -> LocalStorageState {
+    read_json(LS_KEY)
+}
+
+pub fn write_to_ls(state: &LocalStorageState) {
+    write_json(LS_KEY, state)
+}
+
+/// Read the value stored under `key`: localStorage on the web, a JSON file in `$HOME` elsewhere.
+pub(crate) fn read_json<T: DeserializeOwned + Default>(key: &str) -> T {
     #[cfg(target_arch = "wasm32")]
     {
-        let result = (|| -> Option<LocalStorageState> {
+        let result = (|| -> Option<T> {
             let storage = web_sys::window()?.local_storage().ok()??;
-            let raw = storage.get_item(LS_KEY).ok()??;
+            let raw = storage.get_item(key).ok()??;
             serde_json::from_str(&raw).ok()
         })();
         return result.unwrap_or_default();
     }
     #[cfg(not(target_arch = "wasm32"))]
     {
-        std::fs::read_to_string(storage_file())
+        std::fs::read_to_string(storage_file(key))
             .ok()
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default()
     }
 }
 
-pub fn write_to_ls(state: &LocalStorageState) {
+pub(crate) fn write_json<T: Serialize>(key: &str, value: &T) {
     #[cfg(target_arch = "wasm32")]
     {
         let _ = (|| -> Option<()> {
             let storage = web_sys::window()?.local_storage().ok()??;
-            let json = serde_json::to_string(state).ok()?;
-            storage.set_item(LS_KEY, &json).ok()?;
+            let json = serde_json::to_string(value).ok()?;
+            storage.set_item(key, &json).ok()?;
             Some(())
         })();
     }
     #[cfg(not(target_arch = "wasm32"))]
     {
-        if let Ok(json) = serde_json::to_string(state) {
-            let _ = std::fs::write(storage_file(), json);
+        if let Ok(json) = serde_json::to_string(value) {
+            let _ = std::fs::write(storage_file(key), json);
         }
     }
 }
@@ -90,12 +100,7 @@ pub fn initials(name: &str) -> String {
             first.chars().next().unwrap_or_default().to_uppercase(),
             last.chars().next().unwrap_or_default().to_uppercase()
         ),
-        (Some(first), None) => first
-            .chars()
-            .next()
-            .unwrap_or_default()
-            .to_uppercase()
-            .to_string(),
+        (Some(first), None) => first.chars().next().unwrap_or_default().to_uppercase().to_string(),
         _ => "?".to_string(),
     }
 }
@@ -8,7 +8,7 @@ mod app_header;
 pub use app_header::AppHeader;
 
 mod toast;
-pub use toast::Toast;
+pub use toast::{error_message, Toast};
 
 mod dropdown_button;
 pub use dropdown_button::DropdownButton;
@@ -21,3 +21,6 @@ pub use local_storage::{
     initials, read_from_ls, upsert_project, user_color_class, write_to_ls, LocalStorageProject,
     LocalStorageState,
 };
+
+mod url;
+pub use url::absolute_url;
@@ -1,8 +1,28 @@
+use api::error::{ApiError, ErrorCode};
 use dioxus::prelude::*;
 
+/// Message shown for a failed API call, chosen by error code.
+///
+/// Validation and conflict errors keep the server's explanation, which says what to change.
+pub fn error_message(error: &ApiError) -> String {
+    match error.code {
+        ErrorCode::Unauthorized => {
+            "Authentification refusée. Vérifiez vos identifiants ou reconnectez-vous.".to_string()
+        }
+        ErrorCode::Forbidden => "Vous n'avez pas les droits pour faire cela.".to_string(),
+        ErrorCode::NotFound => "Élément introuvable. Il a peut-être été supprimé.".to_string(),
+        ErrorCode::Validation => format!("Saisie invalide : {}", error.message),
+        ErrorCode::Conflict => format!("Action impossible : {}", error.message),
+        ErrorCode::Locked => "Compte temporairement verrouillé. Réessayez plus tard.".to_string(),
+        ErrorCode::RateLimited => "Trop de tentatives. Réessayez dans un instant.".to_string(),
+        ErrorCode::Internal => "Une erreur est survenue. Réessayez plus tard.".to_string(),
+        ErrorCode::Unavailable => "Serveur injoignable. Vérifiez votre connexion.".to_string(),
+    }
+}
+
 #[derive(PartialEq, Props, Clone)]
 pub struct ToastProps {
-    error: String,
+    error: ApiError,
     onclick: EventHandler<MouseEvent>,
 }
 
@@ -16,7 +36,7 @@ pub fn Toast(props: ToastProps) -> Element {
                 props.onclick.call(event)
             },
             div { class: "alert alert-error",
-                span { class: "text-xs", "{props.error}" }
+                span { class: "text-xs", {error_message(&props.error)} }
             }
         }
     }
@@ -0,0 +1,8 @@
+/// Absolute URL for an app path when running in a browser, the path itself otherwise.
+pub fn absolute_url(path: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(origin) = web_sys::window().and_then(|w| w.location().origin().ok()) {
+        return format!("{}{}", origin, path);
+    }
+    path.to_string()
+}
@@ -3,6 +3,7 @@
 pub mod auth;
 pub mod common;
 pub mod icons;
+pub mod offline;
 pub mod payments;
 pub mod project_details;
 pub mod projects;
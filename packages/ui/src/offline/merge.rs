@@ -0,0 +1,189 @@
+use shared::{EditableExpense, ExpenseType, UserAmount};
+
+/// An edit merged with the changes made on the server since it started.
+#[derive(Debug, Clone)]
+pub struct MergedEdit {
+    pub expense: EditableExpense,
+    /// Parts changed on both sides, where the server's version was kept.
+    pub conflicts: Vec<&'static str>,
+}
+
+/// Type, amount and who pays or owes what: merged as one part, since they must add up.
+#[derive(Debug, Clone, PartialEq)]
+struct Split {
+    expense_type: ExpenseType,
+    amount: f64,
+    payers: Vec<(i32, f64)>,
+    debtors: Vec<(i32, f64)>,
+}
+
+impl Split {
+    fn of(expense: &EditableExpense) -> Self {
+        Split {
+            expense_type: expense.expense_type.clone(),
+            amount: expense.amount,
+            payers: sorted(&expense.payers),
+            debtors: sorted(&expense.debtors),
+        }
+    }
+}
+
+fn sorted(shares: &[UserAmount]) -> Vec<(i32, f64)> {
+    let mut shares: Vec<(i32, f64)> = shares.iter().map(|s| (s.user_id, s.amount)).collect();
+    shares.sort_by_key(|(user_id, _)| *user_id);
+    shares
+}
+
+fn user_amounts(shares: &[(i32, f64)]) -> Vec<UserAmount> {
+    shares.iter().map(|&(user_id, amount)| UserAmount { user_id, amount }).collect()
+}
+
+/// Whether the local version of a part wins: only the local side changed it.
+fn keep_local<T: PartialEq>(
+    part: &'static str,
+    base: &T,
+    server: &T,
+    local: &T,
+    conflicts: &mut Vec<&'static str>,
+) -> bool {
+    if local == base || local == server {
+        false
+    } else if server == base {
+        true
+    } else {
+        conflicts.push(part);
+        false
+    }
+}
+
+/// Three-way merge of an edit started from `base` with the `server`'s newer version: each part
+/// keeps the side that changed it since `base`. When both sides changed a part differently, the
+/// server wins and the part is reported. The result is based on the server's version.
+pub fn merge_edit(
+    base: &EditableExpense,
+    server: &EditableExpense,
+    local: &EditableExpense,
+) -> MergedEdit {
+    let mut conflicts = Vec::new();
+
+    let name = if keep_local("le nom", &base.name, &server.name, &local.name, &mut conflicts) {
+        local.name.clone()
+    } else {
+        server.name.clone()
+    };
+    let date = if keep_local("la date", &base.date, &server.date, &local.date, &mut conflicts) {
+        local.date
+    } else {
+        server.date
+    };
+    let description = if keep_local(
+        "la description",
+        &base.description,
+        &server.description,
+        &local.description,
+        &mut conflicts,
+    ) {
+        local.description.clone()
+    } else {
+        server.description.clone()
+    };
+    let local_split = Split::of(local);
+    let server_split = Split::of(server);
+    let split = if keep_local(
+        "le montant et la répartition",
+        &Split::of(base),
+        &server_split,
+        &local_split,
+        &mut conflicts,
+    ) {
+        local_split
+    } else {
+        server_split
+    };
+
+    MergedEdit {
+        expense: EditableExpense {
+            id: local.id,
+            name,
+            amount: split.amount,
+            expense_type: split.expense_type,
+            project_id: local.project_id,
+            payers: user_amounts(&split.payers),
+            debtors: user_amounts(&split.debtors),
+            author_id: local.author_id,
+            description,
+            date,
+            version: server.version,
+        },
+        conflicts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    fn expense(
+        name: &str,
+        amount: f64,
+        payers: &[(i32, f64)],
+        debtors: &[(i32, f64)],
+    ) -> EditableExpense {
+        EditableExpense {
+            id: 7,
+            name: name.to_string(),
+            amount,
+            expense_type: ExpenseType::Expense,
+            project_id: Uuid::nil(),
+            payers: user_amounts(payers),
+            debtors: user_amounts(debtors),
+            author_id: 1,
+            description: None,
+            date: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn changes_to_different_parts_are_both_kept() {
+        let base = expense("Courses", 30.0, &[(1, 30.0)], &[(1, 15.0), (2, 15.0)]);
+        let server = EditableExpense {
+            version: 2,
+            ..expense("Courses Lidl", 30.0, &[(1, 30.0)], &[(1, 15.0), (2, 15.0)])
+        };
+        let local = expense("Courses", 40.0, &[(1, 40.0)], &[(2, 20.0), (1, 20.0)]);
+
+        let merged = merge_edit(&base, &server, &local);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.expense.name, "Courses Lidl");
+        assert_eq!(merged.expense.amount, 40.0);
+        assert_eq!(merged.expense.debtors.len(), 2);
+        assert_eq!(merged.expense.version, 2);
+    }
+
+    #[test]
+    fn the_server_wins_a_part_changed_on_both_sides() {
+        let base = expense("Courses", 30.0, &[(1, 30.0)], &[(2, 30.0)]);
+        let server = expense("Courses", 50.0, &[(1, 50.0)], &[(2, 50.0)]);
+        let local = expense("Marché", 20.0, &[(1, 20.0)], &[(2, 20.0)]);
+
+        let merged = merge_edit(&base, &server, &local);
+        assert_eq!(merged.conflicts, vec!["le montant et la répartition"]);
+        assert_eq!(merged.expense.name, "Marché");
+        assert_eq!(merged.expense.amount, 50.0);
+        assert_eq!(merged.expense.payers[0].amount, 50.0);
+    }
+
+    #[test]
+    fn the_same_change_on_both_sides_is_no_conflict() {
+        let base = expense("Courses", 30.0, &[(1, 30.0)], &[(2, 30.0)]);
+        let server = expense("Marché", 30.0, &[(1, 30.0)], &[(2, 30.0)]);
+        let local = expense("Marché", 30.0, &[(1, 30.0)], &[(2, 30.0)]);
+
+        let merged = merge_edit(&base, &server, &local);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.expense.name, "Marché");
+    }
+}
@@ -0,0 +1,21 @@
+//! Offline-first access to projects.
+//!
+//! Every project page loaded is copied into a replica kept on the device, next to
+//! [`LocalStorageState`](crate::common::LocalStorageState). When the server cannot be reached,
+//! pages read the replica, and expenses added or edited are queued instead of failing. The queue
+//! is replayed, oldest first, when a project page opens or the server answers again; an edit the
+//! server refuses as based on an outdated version is merged with the current one (see
+//! [`merge_edit`]).
+mod merge;
+mod state;
+mod sync;
+
+pub use merge::{merge_edit, MergedEdit};
+pub use state::{
+    read_offline_state, update_offline_state, write_offline_state, Mutation, OfflineState,
+    ProjectReplica, QueuedMutation,
+};
+pub use sync::{
+    load_expense, load_expense_payments, load_expenses, load_payments, load_project, load_summary,
+    load_users, sync,
+};
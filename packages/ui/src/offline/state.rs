@@ -0,0 +1,440 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use shared::{CreatableExpense, EditableExpense, Expense, Payment, ProjectDto, User};
+use uuid::Uuid;
+
+use crate::common::local_storage::{read_json, write_json};
+
+const OFFLINE_KEY: &str = "counted_offline";
+
+/// What this device knows of the projects it opened, and what it still has to send.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OfflineState {
+    /// Server data of each opened project, as last loaded.
+    pub replicas: Vec<ProjectReplica>,
+    /// Mutations waiting for the server, oldest first.
+    pub queue: Vec<QueuedMutation>,
+    /// Id of the last expense created offline. They count down from -1, so they never collide
+    /// with an id given by the server.
+    pub last_local_id: i32,
+    /// What the last replays could not apply as made, until the user dismisses them.
+    pub notices: Vec<String>,
+    /// The last call to the server could not reach it.
+    #[serde(skip)]
+    pub offline: bool,
+    /// A replay is running.
+    #[serde(skip)]
+    pub syncing: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProjectReplica {
+    pub project_id: Uuid,
+    pub project: Option<ProjectDto>,
+    pub users: Vec<User>,
+    pub expenses: Vec<Expense>,
+    pub payments: Vec<Payment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedMutation {
+    /// Also the `Idempotency-Key` of an added expense: the key of the attempt that could not
+    /// reach the server, which may have created it anyway.
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub queued_at: NaiveDateTime,
+    pub mutation: Mutation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Mutation {
+    /// `local_id` stands for the expense in the replica until the server gives it an id.
+    AddExpense { local_id: i32, expense: CreatableExpense },
+    /// `base` is the expense the edit started from, to tell which side changed what when the
+    /// server has a newer version.
+    EditExpense { expense: EditableExpense, base: EditableExpense },
+}
+
+pub fn read_offline_state() -> OfflineState {
+    read_json(OFFLINE_KEY)
+}
+
+pub fn write_offline_state(state: &OfflineState) {
+    write_json(OFFLINE_KEY, state)
+}
+
+/// Change the shared state and store it. Does not subscribe the caller to the signal.
+pub fn update_offline_state<R>(
+    state: &mut Signal<OfflineState>,
+    change: impl FnOnce(&mut OfflineState) -> R,
+) -> R {
+    let mut current = state.write();
+    let result = change(&mut current);
+    write_offline_state(&current);
+    result
+}
+
+impl OfflineState {
+    pub fn replica(&self, project_id: Uuid) -> Option<&ProjectReplica> {
+        self.replicas.iter().find(|r| r.project_id == project_id)
+    }
+
+    pub fn replica_mut(&mut self, project_id: Uuid) -> &mut ProjectReplica {
+        let index = match self.replicas.iter().position(|r| r.project_id == project_id) {
+            Some(index) => index,
+            None => {
+                self.replicas.push(ProjectReplica { project_id, ..ProjectReplica::default() });
+                self.replicas.len() - 1
+            }
+        };
+        &mut self.replicas[index]
+    }
+
+    pub fn pending(&self, project_id: Uuid) -> impl Iterator<Item = &QueuedMutation> {
+        self.queue.iter().filter(move |m| m.project_id == project_id)
+    }
+
+    /// Expenses of the project with a mutation waiting for the server.
+    pub fn pending_expense_ids(&self, project_id: Uuid) -> HashSet<i32> {
+        self.pending(project_id).map(|m| m.mutation.expense_id()).collect()
+    }
+
+    /// `expenses` as they will be once the queued mutations are sent.
+    pub fn apply_to_expenses(&self, project_id: Uuid, mut expenses: Vec<Expense>) -> Vec<Expense> {
+        for queued in self.pending(project_id) {
+            match &queued.mutation {
+                Mutation::AddExpense { local_id, expense } => expenses.push(Expense {
+                    id: *local_id,
+                    author_id: expense.author_id,
+                    project_id: expense.project_id,
+                    created_at: queued.queued_at,
+                    date: expense.date,
+                    amount: expense.amount,
+                    description: expense.description.clone(),
+                    name: expense.name.clone(),
+                    expense_type: expense.expense_type.clone(),
+                    version: 1,
+                }),
+                Mutation::EditExpense { expense, .. } => {
+                    if let Some(current) = expenses.iter_mut().find(|e| e.id == expense.id) {
+                        current.author_id = expense.author_id;
+                        current.date = expense.date;
+                        current.amount = expense.amount;
+                        current.description = expense.description.clone();
+                        current.name = expense.name.clone();
+                        current.expense_type = expense.expense_type.clone();
+                    }
+                }
+            }
+        }
+        expenses
+    }
+
+    /// `payments` as they will be once the queued mutations are sent.
+    pub fn apply_to_payments(&self, project_id: Uuid, mut payments: Vec<Payment>) -> Vec<Payment> {
+        for queued in self.pending(project_id) {
+            let (expense_id, payers, debtors) = match &queued.mutation {
+                Mutation::AddExpense { local_id, expense } => {
+                    (*local_id, &expense.payers, &expense.debtors)
+                }
+                Mutation::EditExpense { expense, .. } => {
+                    payments.retain(|p| p.expense_id != expense.id);
+                    (expense.id, &expense.payers, &expense.debtors)
+                }
+            };
+            let split = payers.iter().map(|a| (a, false)).chain(debtors.iter().map(|a| (a, true)));
+            payments.extend(split.map(|(share, is_debt)| Payment {
+                id: 0,
+                expense_id,
+                user_id: share.user_id,
+                is_debt,
+                amount: share.amount,
+                created_at: queued.queued_at,
+            }));
+        }
+        payments
+    }
+
+    /// Queue an expense created offline, with the idempotency key it was first sent with.
+    /// Returns the id it has until it is sent.
+    pub fn queue_add_expense(&mut self, expense: CreatableExpense, idempotency_key: Uuid) -> i32 {
+        self.last_local_id = self.last_local_id.min(0) - 1;
+        let local_id = self.last_local_id;
+        self.push(idempotency_key, expense.project_id, Mutation::AddExpense { local_id, expense });
+        local_id
+    }
+
+    /// Queue an edit made offline. A second edit of the same expense replaces the first but keeps
+    /// its base. An expense created offline keeps the body its idempotency key was first sent
+    /// with: the edit is queued after it, against its local id.
+    pub fn queue_edit_expense(&mut self, expense: EditableExpense, base: EditableExpense) {
+        for queued in self.queue.iter_mut() {
+            match &mut queued.mutation {
+                Mutation::EditExpense { expense: edited, .. } if edited.id == expense.id => {
+                    *edited = expense;
+                    return;
+                }
+                _ => {}
+            }
+        }
+        self.push(Uuid::new_v4(), expense.project_id, Mutation::EditExpense { expense, base });
+    }
+
+    /// Take a replayed mutation off the queue. `created` is the expense the server made of an
+    /// added one: the edits queued against its local id are sent against its id from now on.
+    /// Without it, the expense was not added, and its edits are dropped with it.
+    pub fn dequeue(&mut self, id: Uuid, created: Option<&Expense>) {
+        let Some(index) = self.queue.iter().position(|m| m.id == id) else {
+            return;
+        };
+        let removed = self.queue.remove(index);
+        let Mutation::AddExpense { local_id, .. } = removed.mutation else {
+            return;
+        };
+        match created {
+            Some(created) => {
+                for queued in self.queue.iter_mut() {
+                    if let Mutation::EditExpense { expense, base } = &mut queued.mutation {
+                        if expense.id == local_id {
+                            expense.id = created.id;
+                            expense.version = created.version;
+                            base.id = created.id;
+                            base.version = created.version;
+                        }
+                    }
+                }
+            }
+            None => self.queue.retain(|m| m.mutation.expense_id() != local_id),
+        }
+    }
+
+    fn push(&mut self, id: Uuid, project_id: Uuid, mutation: Mutation) {
+        self.queue.push(QueuedMutation {
+            id,
+            project_id,
+            queued_at: chrono::Utc::now().naive_utc(),
+            mutation,
+        });
+    }
+}
+
+impl Mutation {
+    pub fn expense_id(&self) -> i32 {
+        match self {
+            Mutation::AddExpense { local_id, .. } => *local_id,
+            Mutation::EditExpense { expense, .. } => expense.id,
+        }
+    }
+
+    pub fn expense_name(&self) -> &str {
+        match self {
+            Mutation::AddExpense { expense, .. } => &expense.name,
+            Mutation::EditExpense { expense, .. } => &expense.name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use shared::{ExpenseType, UserAmount};
+
+    fn shares(shares: &[(i32, f64)]) -> Vec<UserAmount> {
+        shares.iter().map(|&(user_id, amount)| UserAmount { user_id, amount }).collect()
+    }
+
+    fn created(name: &str, amount: f64) -> CreatableExpense {
+        CreatableExpense {
+            name: name.to_string(),
+            amount,
+            expense_type: ExpenseType::Expense,
+            project_id: Uuid::nil(),
+            payers: shares(&[(1, amount)]),
+            debtors: shares(&[(1, amount / 2.0), (2, amount / 2.0)]),
+            author_id: 1,
+            description: None,
+            date: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+        }
+    }
+
+    fn edited(id: i32, name: &str, amount: f64) -> EditableExpense {
+        let expense = created(name, amount);
+        EditableExpense {
+            id,
+            name: expense.name,
+            amount: expense.amount,
+            expense_type: expense.expense_type,
+            project_id: expense.project_id,
+            payers: expense.payers,
+            debtors: expense.debtors,
+            author_id: expense.author_id,
+            description: expense.description,
+            date: expense.date,
+            version: 1,
+        }
+    }
+
+    fn on_server(id: i32, name: &str, amount: f64) -> Expense {
+        let expense = created(name, amount);
+        Expense {
+            id,
+            author_id: expense.author_id,
+            project_id: expense.project_id,
+            created_at: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap().into(),
+            date: expense.date,
+            amount: expense.amount,
+            description: expense.description,
+            name: expense.name,
+            expense_type: expense.expense_type,
+            version: 1,
+        }
+    }
+
+    fn payment(expense_id: i32, user_id: i32, is_debt: bool, amount: f64) -> Payment {
+        Payment {
+            id: 1,
+            expense_id,
+            user_id,
+            is_debt,
+            amount,
+            created_at: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap().into(),
+        }
+    }
+
+    #[test]
+    fn expenses_added_offline_get_ids_below_zero() {
+        let mut state = OfflineState::default();
+        let first_key = Uuid::new_v4();
+
+        assert_eq!(state.queue_add_expense(created("Courses", 30.0), first_key), -1);
+        assert_eq!(state.queue_add_expense(created("Cinéma", 20.0), Uuid::new_v4()), -2);
+        assert_eq!(state.queue[0].id, first_key);
+        assert_eq!(state.pending_expense_ids(Uuid::nil()), HashSet::from([-1, -2]));
+    }
+
+    #[test]
+    fn a_second_edit_replaces_the_first_but_keeps_its_base() {
+        let mut state = OfflineState::default();
+        state.queue_edit_expense(edited(5, "Marché", 30.0), edited(5, "Courses", 30.0));
+        state.queue_edit_expense(edited(5, "Marché", 45.0), edited(5, "Marché", 30.0));
+
+        assert_eq!(state.queue.len(), 1);
+        let Mutation::EditExpense { expense, base } = &state.queue[0].mutation else {
+            panic!("an edit is queued");
+        };
+        assert_eq!((expense.name.as_str(), expense.amount), ("Marché", 45.0));
+        assert_eq!((base.name.as_str(), base.amount), ("Courses", 30.0));
+    }
+
+    #[test]
+    fn editing_an_expense_created_offline_twice_queues_one_edit() {
+        let mut state = OfflineState::default();
+        let local_id = state.queue_add_expense(created("Courses", 30.0), Uuid::new_v4());
+        state.queue_edit_expense(
+            edited(local_id, "Marché", 30.0),
+            edited(local_id, "Courses", 30.0),
+        );
+        state
+            .queue_edit_expense(edited(local_id, "Marché", 45.0), edited(local_id, "Marché", 30.0));
+
+        assert_eq!(state.queue.len(), 2);
+        let Mutation::EditExpense { expense, base } = &state.queue[1].mutation else {
+            panic!("the edits are queued after the creation");
+        };
+        assert_eq!((expense.name.as_str(), expense.amount), ("Marché", 45.0));
+        assert_eq!((base.name.as_str(), base.amount), ("Courses", 30.0));
+        let expenses = state.apply_to_expenses(Uuid::nil(), Vec::new());
+        assert_eq!((expenses[0].id, expenses[0].amount), (local_id, 45.0));
+    }
+
+    #[test]
+    fn queued_mutations_are_applied_to_the_expenses() {
+        let mut state = OfflineState::default();
+        state.queue_edit_expense(edited(5, "Marché", 45.0), edited(5, "Courses", 30.0));
+        let local_id = state.queue_add_expense(created("Cinéma", 20.0), Uuid::new_v4());
+        state.queue_edit_expense(edited(local_id, "Ciné", 24.0), edited(local_id, "Cinéma", 20.0));
+        let other_project = CreatableExpense { project_id: Uuid::new_v4(), ..created("Bar", 9.0) };
+        state.queue_add_expense(other_project, Uuid::new_v4());
+
+        let expenses = state.apply_to_expenses(
+            Uuid::nil(),
+            vec![on_server(5, "Courses", 30.0), on_server(6, "Train", 80.0)],
+        );
+        let shown: Vec<_> = expenses.iter().map(|e| (e.id, e.name.as_str(), e.amount)).collect();
+        assert_eq!(shown, vec![(5, "Marché", 45.0), (6, "Train", 80.0), (local_id, "Ciné", 24.0)]);
+    }
+
+    #[test]
+    fn queued_mutations_replace_the_payments_of_their_expense() {
+        let mut state = OfflineState::default();
+        state.queue_edit_expense(edited(5, "Courses", 40.0), edited(5, "Courses", 30.0));
+        let local_id = state.queue_add_expense(created("Cinéma", 20.0), Uuid::new_v4());
+        state
+            .queue_edit_expense(edited(local_id, "Cinéma", 24.0), edited(local_id, "Cinéma", 20.0));
+
+        let server = vec![
+            payment(5, 1, false, 30.0),
+            payment(5, 1, true, 15.0),
+            payment(5, 2, true, 15.0),
+            payment(6, 2, false, 80.0),
+        ];
+        let payments = state.apply_to_payments(Uuid::nil(), server);
+        let split = |expense_id| -> Vec<_> {
+            let mut split: Vec<_> = payments
+                .iter()
+                .filter(|p| p.expense_id == expense_id)
+                .map(|p| (p.user_id, p.is_debt, p.amount))
+                .collect();
+            split.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            split
+        };
+        assert_eq!(split(5), vec![(1, false, 40.0), (1, true, 20.0), (2, true, 20.0)]);
+        assert_eq!(split(6), vec![(2, false, 80.0)]);
+        assert_eq!(split(local_id), vec![(1, false, 24.0), (1, true, 12.0), (2, true, 12.0)]);
+    }
+
+    #[test]
+    fn editing_an_expense_created_offline_keeps_the_keyed_creation() {
+        let mut state = OfflineState::default();
+        let key = Uuid::new_v4();
+        let local_id = state.queue_add_expense(created("Courses", 30.0), key);
+        state.queue_edit_expense(
+            edited(local_id, "Marché", 40.0),
+            edited(local_id, "Courses", 30.0),
+        );
+
+        assert_eq!(state.queue.len(), 2);
+        assert_eq!(state.queue[0].id, key);
+        let Mutation::AddExpense { expense, .. } = &state.queue[0].mutation else {
+            panic!("the creation comes first");
+        };
+        assert_eq!((expense.name.as_str(), expense.amount), ("Courses", 30.0));
+
+        state.dequeue(key, Some(&on_server(12, "Courses", 30.0)));
+        let Mutation::EditExpense { expense, base } = &state.queue[0].mutation else {
+            panic!("the edit is still queued");
+        };
+        assert_eq!((expense.id, base.id), (12, 12));
+        assert_eq!((expense.name.as_str(), expense.amount), ("Marché", 40.0));
+    }
+
+    #[test]
+    fn edits_of_an_expense_the_server_refused_are_dropped() {
+        let mut state = OfflineState::default();
+        let key = Uuid::new_v4();
+        let local_id = state.queue_add_expense(created("Courses", 30.0), key);
+        state.queue_edit_expense(
+            edited(local_id, "Marché", 40.0),
+            edited(local_id, "Courses", 30.0),
+        );
+        state.queue_edit_expense(edited(5, "Cinéma", 20.0), edited(5, "Ciné", 20.0));
+
+        state.dequeue(key, None);
+        assert_eq!(state.queue.len(), 1);
+        assert_eq!(state.queue[0].mutation.expense_id(), 5);
+    }
+}
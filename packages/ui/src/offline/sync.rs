@@ -0,0 +1,263 @@
+use api::error::{ApiError, ErrorCode};
+use api::expenses::expenses_controller::{
+    add_expense, edit_expense, get_expense_by_id, get_expenses_by_project_id,
+};
+use api::idempotency::with_idempotency_key;
+use api::payments::balances::get_reimbursement_suggestions;
+use api::payments::payments_controller::{
+    get_payments_by_expense_id, get_payments_by_project_id, get_summary_by_project_id,
+};
+use api::payments::payments_repository::calculate_balances;
+use api::projects::projects_controller::get_project;
+use api::users::users_controller::get_users_by_project_id;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{EditableExpense, Expense, Payment, ProjectDto, User, UserBalance, UserSummary};
+use uuid::Uuid;
+
+use crate::common::error_message;
+use crate::offline::{
+    merge_edit, update_offline_state, Mutation, OfflineState, ProjectReplica, QueuedMutation,
+};
+
+/// Keep the replica up to date with what the server sent, or fall back to it when the server
+/// cannot be reached.
+fn cached<T: Clone>(
+    mut state: Signal<OfflineState>,
+    project_id: Uuid,
+    loaded: Result<T, ApiError>,
+    part: fn(&mut ProjectReplica) -> &mut T,
+) -> Result<T, ApiError> {
+    match loaded {
+        Ok(value) => {
+            update_offline_state(&mut state, |s| {
+                s.offline = false;
+                *part(s.replica_mut(project_id)) = value.clone();
+            });
+            Ok(value)
+        }
+        Err(error) if error.code == ErrorCode::Unavailable => {
+            state.write().offline = true;
+            let mut replica = state.peek().replica(project_id).cloned().ok_or(error)?;
+            Ok(part(&mut replica).clone())
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Whether the server could not be reached; records it so the UI can tell.
+fn unreachable(mut state: Signal<OfflineState>, error: &ApiError) -> bool {
+    let unreachable = error.code == ErrorCode::Unavailable;
+    if unreachable {
+        state.write().offline = true;
+    }
+    unreachable
+}
+
+pub async fn load_project(
+    state: Signal<OfflineState>,
+    project_id: Uuid,
+) -> Result<ProjectDto, ApiError> {
+    let loaded = get_project(project_id).await;
+    let unavailable = loaded.as_ref().err().cloned();
+    cached(state, project_id, loaded.map(Some), |r| &mut r.project)?
+        .ok_or_else(|| unavailable.unwrap_or_else(|| ApiError::not_found("Project not found")))
+}
+
+pub async fn load_users(
+    state: Signal<OfflineState>,
+    project_id: Uuid,
+) -> Result<Vec<User>, ApiError> {
+    cached(state, project_id, get_users_by_project_id(project_id).await, |r| &mut r.users)
+}
+
+/// Expenses of the project, including those waiting to be sent.
+pub async fn load_expenses(
+    state: Signal<OfflineState>,
+    project_id: Uuid,
+) -> Result<Vec<Expense>, ApiError> {
+    let loaded = get_expenses_by_project_id(project_id).await;
+    let expenses = cached(state, project_id, loaded, |r| &mut r.expenses)?;
+    Ok(state.peek().apply_to_expenses(project_id, expenses))
+}
+
+/// Payments of the project, including those of the expenses waiting to be sent.
+pub async fn load_payments(
+    state: Signal<OfflineState>,
+    project_id: Uuid,
+) -> Result<Vec<Payment>, ApiError> {
+    let loaded = get_payments_by_project_id(project_id).await;
+    let payments = cached(state, project_id, loaded, |r| &mut r.payments)?;
+    Ok(state.peek().apply_to_payments(project_id, payments))
+}
+
+/// Balances from the server, or computed from the replica when it cannot be reached or does not
+/// know the queued mutations yet.
+pub async fn load_summary(
+    state: Signal<OfflineState>,
+    project_id: Uuid,
+) -> Result<UserSummary, ApiError> {
+    let has_pending = state.peek().pending(project_id).next().is_some();
+    let mut unavailable = None;
+    if !has_pending {
+        match get_summary_by_project_id(project_id).await {
+            Err(error) if unreachable(state, &error) => unavailable = Some(error),
+            loaded => return loaded,
+        }
+    }
+
+    let state = state.peek();
+    let Some(replica) = state.replica(project_id) else {
+        return Err(unavailable.unwrap_or_else(|| ApiError::unavailable("Not loaded yet")));
+    };
+    let expenses = state.apply_to_expenses(project_id, replica.expenses.clone());
+    let payments = state.apply_to_payments(project_id, replica.payments.clone());
+    Ok(local_summary(&expenses, &payments))
+}
+
+/// An expense of the project, as it will be once the queued mutations are sent.
+pub async fn load_expense(
+    state: Signal<OfflineState>,
+    project_id: Uuid,
+    expense_id: i32,
+) -> Result<Expense, ApiError> {
+    // Ids below zero are expenses created offline, unknown to the server
+    let server = if expense_id > 0 {
+        match get_expense_by_id(expense_id).await {
+            Ok(expense) => vec![expense],
+            Err(error) if unreachable(state, &error) => known_expenses(state, project_id),
+            Err(error) => return Err(error),
+        }
+    } else {
+        known_expenses(state, project_id)
+    };
+
+    state
+        .peek()
+        .apply_to_expenses(project_id, server)
+        .into_iter()
+        .find(|e| e.id == expense_id)
+        .ok_or_else(|| ApiError::not_found("Expense not found"))
+}
+
+/// Payments of an expense, as they will be once the queued mutations are sent.
+pub async fn load_expense_payments(
+    state: Signal<OfflineState>,
+    project_id: Uuid,
+    expense_id: i32,
+) -> Result<Vec<Payment>, ApiError> {
+    let server = if expense_id > 0 {
+        match get_payments_by_expense_id(expense_id).await {
+            Ok(payments) => payments,
+            Err(error) if unreachable(state, &error) => known_payments(state, project_id),
+            Err(error) => return Err(error),
+        }
+    } else {
+        known_payments(state, project_id)
+    };
+
+    let mut payments = state.peek().apply_to_payments(project_id, server);
+    payments.retain(|p| p.expense_id == expense_id);
+    Ok(payments)
+}
+
+fn known_expenses(state: Signal<OfflineState>, project_id: Uuid) -> Vec<Expense> {
+    state.peek().replica(project_id).map(|r| r.expenses.clone()).unwrap_or_default()
+}
+
+fn known_payments(state: Signal<OfflineState>, project_id: Uuid) -> Vec<Payment> {
+    state.peek().replica(project_id).map(|r| r.payments.clone()).unwrap_or_default()
+}
+
+/// What the server computes in `get_summary_by_project_id`.
+fn local_summary(expenses: &[Expense], payments: &[Payment]) -> UserSummary {
+    // Loaded at different times, payments may refer to an expense the replica does not have
+    let payments: Vec<Payment> = payments
+        .iter()
+        .filter(|p| expenses.iter().any(|e| e.id == p.expense_id))
+        .cloned()
+        .collect();
+    let summary = calculate_balances(expenses, &payments);
+    let balances =
+        summary.iter().map(|(user_id, amount)| UserBalance { amount: *amount, user_id: *user_id });
+    UserSummary {
+        reimbursement_suggestions: get_reimbursement_suggestions(balances.collect()),
+        summary,
+    }
+}
+
+/// Send the queued mutations, oldest first, until one cannot reach the server. A mutation the
+/// server rejects is dropped with a notice. Returns how many left the queue.
+pub async fn sync(mut state: Signal<OfflineState>) -> usize {
+    let busy = state.peek().syncing;
+    if busy {
+        return 0;
+    }
+    state.write().syncing = true;
+
+    let mut sent = 0;
+    loop {
+        let next = state.peek().queue.first().cloned();
+        let Some(queued) = next else {
+            break;
+        };
+        let (created, notice) = match replay(&queued).await {
+            Ok(replayed) => replayed,
+            Err(error) if unreachable(state, &error) => break,
+            Err(error) => (
+                None,
+                Some(format!(
+                    "« {} » n'a pas pu être envoyée : {}",
+                    queued.mutation.expense_name(),
+                    error_message(&error)
+                )),
+            ),
+        };
+        update_offline_state(&mut state, |s| {
+            s.offline = false;
+            s.dequeue(queued.id, created.as_ref());
+            s.notices.extend(notice);
+        });
+        sent += 1;
+    }
+
+    state.write().syncing = false;
+    sent
+}
+
+/// Send one mutation. Returns the expense it created, if any, and what the user should know
+/// about how it was applied.
+async fn replay(queued: &QueuedMutation) -> Result<(Option<Expense>, Option<String>), ApiError> {
+    match &queued.mutation {
+        Mutation::AddExpense { expense, .. } => {
+            let key = queued.id.to_string();
+            let created = with_idempotency_key(&key, add_expense(Json(expense.clone()))).await?;
+            Ok((Some(created), None))
+        }
+        Mutation::EditExpense { expense, base } => {
+            let server = match edit_expense(Json(expense.clone())).await {
+                Ok(_) => return Ok((None, None)),
+                Err(error) if error.code == ErrorCode::NotFound => {
+                    return Ok((None, Some(format!(
+                        "« {} » a été supprimée entre-temps : votre modification n'a pas été appliquée.",
+                        expense.name
+                    ))));
+                }
+                Err(error) => error.current::<EditableExpense>().ok_or(error)?,
+            };
+
+            // Edited on the server since: send the merge of both, based on its version
+            let merged = merge_edit(base, &server, expense);
+            edit_expense(Json(merged.expense)).await?;
+
+            let notice = (!merged.conflicts.is_empty()).then(|| {
+                format!(
+                    "« {} » a aussi été modifiée ailleurs : la version du serveur a été gardée pour {}.",
+                    expense.name,
+                    merged.conflicts.join(", ")
+                )
+            });
+            Ok((None, notice))
+        }
+    }
+}
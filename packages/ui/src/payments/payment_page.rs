@@ -1,27 +1,25 @@
-use api::expenses::expenses_controller::get_expense_by_id;
-use api::payments::payments_controller::get_payments_by_expense_id;
-use api::users::users_controller::get_users_by_project_id;
 use dioxus::prelude::*;
-use shared::ExpenseType;
+use shared::{ExpenseType, ProjectStatus};
 use uuid::Uuid;
 
-use crate::common::{initials, read_from_ls, user_color_class, Avatar};
+use crate::common::{error_message, initials, user_color_class, Avatar};
+use crate::offline::{load_expense, load_expense_payments, load_project, load_users, OfflineState};
 use crate::project_details::EditExpenseModal;
 use crate::route::Route;
 
 fn payers_title(t: &ExpenseType) -> &'static str {
     match t {
-        ExpenseType::Gain     => "Contributeurs",
+        ExpenseType::Gain => "Contributeurs",
         ExpenseType::Transfer => "Émetteur",
-        _                     => "Payé par",
+        _ => "Payé par",
     }
 }
 
 fn debtors_title(t: &ExpenseType) -> &'static str {
     match t {
-        ExpenseType::Gain     => "Bénéficiaires",
+        ExpenseType::Gain => "Bénéficiaires",
         ExpenseType::Transfer => "Destinataires",
-        _                     => "Débiteurs",
+        _ => "Débiteurs",
     }
 }
 
@@ -30,12 +28,26 @@ pub fn PaymentPage(project_id: Uuid, expense_id: i32) -> Element {
     let nav = use_navigator();
     let mut show_edit = use_signal(|| false);
 
-    let ls = read_from_ls();
-    let stored_user_id = ls.projects.iter().find(|p| p.project_id == project_id).and_then(|p| p.user_id);
+    let offline_ctx = use_context::<Signal<OfflineState>>();
+    let is_pending = offline_ctx.read().pending_expense_ids(project_id).contains(&expense_id);
 
-    let mut expense  = use_resource(move || async move { get_expense_by_id(expense_id).await });
-    let mut payments = use_resource(move || async move { get_payments_by_expense_id(expense_id).await });
-    let users        = use_resource(move || async move { get_users_by_project_id(project_id).await });
+    let mut expense =
+        use_resource(
+            move || async move { load_expense(offline_ctx, project_id, expense_id).await },
+        );
+    let mut payments = use_resource(move || async move {
+        load_expense_payments(offline_ctx, project_id, expense_id).await
+    });
+    let users = use_resource(move || async move { load_users(offline_ctx, project_id).await });
+    let project = use_resource(move || async move { load_project(offline_ctx, project_id).await });
+    // Only ongoing projects have editable expenses
+    let is_ongoing = move || {
+        project
+            .read()
+            .as_ref()
+            .and_then(|r| r.as_ref().ok())
+            .is_none_or(|p| p.status == ProjectStatus::Ongoing)
+    };
 
     rsx! {
         div { class: "container overflow-auto p-4 max-w-md w-full mx-auto flex flex-col gap-4",
@@ -88,13 +100,21 @@ pub fn PaymentPage(project_id: Uuid, expense_id: i32) -> Element {
                                         }
                                     }
                                     ul { class: "menu dropdown-content bg-base-100 rounded-box w-40 shadow z-10 p-2",
-                                        li { a { onclick: move |_| show_edit.set(true), "Modifier" } }
+                                        if is_ongoing() {
+                                            li { a { onclick: move |_| show_edit.set(true), "Modifier" } }
+                                        }
                                         li { a { class: "opacity-50", "Supprimer" } }
                                     }
                                 }
                             }
                         }
 
+                        if is_pending {
+                            div { class: "alert alert-info text-sm",
+                                span { "Modification enregistrée sur cet appareil, en attente de synchronisation." }
+                            }
+                        }
+
                         // Payers section
                         div { class: "card bg-base-100 shadow-sm",
                             div { class: "card-body p-4 gap-3",
@@ -157,13 +177,12 @@ pub fn PaymentPage(project_id: Uuid, expense_id: i32) -> Element {
                                 payments: pmts_clone,
                                 users: users_clone,
                                 project_id,
-                                stored_user_id,
                             }
                         }
                     }
                 }
                 (Some(Err(e)), _, _) | (_, Some(Err(e)), _) | (_, _, Some(Err(e))) => rsx! {
-                    div { class: "alert alert-error", "{e}" }
+                    div { class: "alert alert-error", {error_message(e)} }
                 },
                 _ => rsx! {
                     div { class: "flex justify-center py-8",
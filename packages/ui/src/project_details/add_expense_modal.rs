@@ -1,10 +1,15 @@
+use api::error::ErrorCode;
 use api::expenses::expenses_controller::add_expense;
+use api::idempotency::with_idempotency_key;
 use chrono::NaiveDate;
 use dioxus::fullstack::Json;
 use dioxus::prelude::*;
 use shared::{CreatableExpense, ExpenseType, User, UserAmount};
 use uuid::Uuid;
 
+use crate::common::error_message;
+use crate::offline::{update_offline_state, OfflineState};
+
 #[derive(Clone, PartialEq)]
 struct UserEntry {
     user: User,
@@ -14,12 +19,8 @@ struct UserEntry {
 }
 
 fn distribute(total: f64, entries: &mut Vec<UserEntry>) {
-    let checked_indices: Vec<usize> = entries
-        .iter()
-        .enumerate()
-        .filter(|(_, e)| e.checked)
-        .map(|(i, _)| i)
-        .collect();
+    let checked_indices: Vec<usize> =
+        entries.iter().enumerate().filter(|(_, e)| e.checked).map(|(i, _)| i).collect();
     let n = checked_indices.len();
     if n == 0 {
         return;
@@ -39,11 +40,8 @@ fn distribute(total: f64, entries: &mut Vec<UserEntry>) {
 }
 
 fn distribute_by_shares(total: f64, entries: &mut Vec<UserEntry>) {
-    let total_shares: u32 = entries
-        .iter()
-        .filter(|e| e.checked && e.shares > 0)
-        .map(|e| e.shares)
-        .sum();
+    let total_shares: u32 =
+        entries.iter().filter(|e| e.checked && e.shares > 0).map(|e| e.shares).sum();
     if total_shares == 0 {
         return;
     }
@@ -111,11 +109,7 @@ pub fn AddExpenseModal(props: AddExpenseModalProps) -> Element {
     let initial_debtor_id = props.initial_debtor_id;
 
     let mut expense_name = use_signal(move || init_name);
-    let today = chrono::Utc::now()
-        .naive_utc()
-        .date()
-        .format("%Y-%m-%d")
-        .to_string();
+    let today = chrono::Utc::now().naive_utc().date().format("%Y-%m-%d").to_string();
     let mut date_str = use_signal(move || today);
     let mut total_amount = use_signal(move || init_amount);
     let mut expense_type = use_signal(move || init_type);
@@ -158,6 +152,7 @@ pub fn AddExpenseModal(props: AddExpenseModalProps) -> Element {
     });
     let mut error_msg: Signal<Option<String>> = use_signal(|| None);
     let mut loading = use_signal(|| false);
+    let mut offline_ctx = use_context::<Signal<OfflineState>>();
 
     let project_id = props.project_id;
     let stored_user_id = props.stored_user_id;
@@ -203,35 +198,46 @@ pub fn AddExpenseModal(props: AddExpenseModalProps) -> Element {
                 return;
             }
         };
-        let author_id = stored_user_id
-            .unwrap_or_else(|| users_for_author.first().map(|u| u.id).unwrap_or(0));
+        let author_id =
+            stored_user_id.unwrap_or_else(|| users_for_author.first().map(|u| u.id).unwrap_or(0));
         let etype = expense_type();
 
         loading.set(true);
         error_msg.set(None);
 
+        let expense = CreatableExpense {
+            name: name_val,
+            amount: total,
+            expense_type: etype,
+            project_id,
+            payers: active_payers,
+            debtors: active_debtors,
+            author_id,
+            description: None,
+            date,
+        };
+        // The same key for the request and, if it cannot be sent, its replay
+        let idempotency_key = Uuid::new_v4();
         let on_created = on_created.clone();
         let on_close_submit = on_close_submit.clone();
         spawn(async move {
-            match add_expense(Json(CreatableExpense {
-                name: name_val,
-                amount: total,
-                expense_type: etype,
-                project_id,
-                payers: active_payers,
-                debtors: active_debtors,
-                author_id,
-                description: None,
-                date,
-            }))
-            .await
-            {
+            let key = idempotency_key.to_string();
+            match with_idempotency_key(&key, add_expense(Json(expense.clone()))).await {
                 Ok(_) => {
                     on_created.call(());
                     on_close_submit.call(());
                 }
+                // Kept on the device and sent once the server answers again
+                Err(e) if e.code == ErrorCode::Unavailable => {
+                    update_offline_state(&mut offline_ctx, |s| {
+                        s.offline = true;
+                        s.queue_add_expense(expense, idempotency_key);
+                    });
+                    on_created.call(());
+                    on_close_submit.call(());
+                }
                 Err(e) => {
-                    error_msg.set(Some(e.to_string()));
+                    error_msg.set(Some(error_message(&e)));
                     loading.set(false);
                 }
             }
@@ -24,10 +24,7 @@ pub fn BalanceTab(props: BalanceTabProps) -> Element {
     // Sort ascending (most negative first)
     entries.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
-    let max_abs = entries
-        .iter()
-        .map(|(_, b)| b.abs())
-        .fold(1.0_f64, f64::max);
+    let max_abs = entries.iter().map(|(_, b)| b.abs()).fold(1.0_f64, f64::max);
 
     let currency = props.currency.clone();
 
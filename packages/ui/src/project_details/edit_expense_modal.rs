@@ -1,3 +1,4 @@
+use api::error::{ApiError, ErrorCode};
 use api::expenses::expenses_controller::edit_expense;
 use chrono::NaiveDate;
 use dioxus::fullstack::Json;
@@ -5,6 +6,9 @@ use dioxus::prelude::*;
 use shared::{EditableExpense, Expense, ExpenseType, Payment, User, UserAmount};
 use uuid::Uuid;
 
+use crate::common::error_message;
+use crate::offline::{merge_edit, update_offline_state, OfflineState};
+
 #[derive(Clone, PartialEq)]
 struct UserEntry {
     user: User,
@@ -13,13 +17,24 @@ struct UserEntry {
     shares: u32,
 }
 
-fn distribute(total: f64, entries: &mut Vec<UserEntry>) {
-    let checked_indices: Vec<usize> = entries
+fn entries(users: &[User], shares: &[UserAmount]) -> Vec<UserEntry> {
+    users
         .iter()
-        .enumerate()
-        .filter(|(_, e)| e.checked)
-        .map(|(i, _)| i)
-        .collect();
+        .map(|u| {
+            let share = shares.iter().find(|s| s.user_id == u.id);
+            UserEntry {
+                user: u.clone(),
+                checked: share.is_some(),
+                amount: share.map(|s| s.amount).unwrap_or(0.0),
+                shares: if share.is_some() { 1 } else { 0 },
+            }
+        })
+        .collect()
+}
+
+fn distribute(total: f64, entries: &mut Vec<UserEntry>) {
+    let checked_indices: Vec<usize> =
+        entries.iter().enumerate().filter(|(_, e)| e.checked).map(|(i, _)| i).collect();
     let n = checked_indices.len();
     if n == 0 {
         return;
@@ -39,11 +54,8 @@ fn distribute(total: f64, entries: &mut Vec<UserEntry>) {
 }
 
 fn distribute_by_shares(total: f64, entries: &mut Vec<UserEntry>) {
-    let total_shares: u32 = entries
-        .iter()
-        .filter(|e| e.checked && e.shares > 0)
-        .map(|e| e.shares)
-        .sum();
+    let total_shares: u32 =
+        entries.iter().filter(|e| e.checked && e.shares > 0).map(|e| e.shares).sum();
     if total_shares == 0 {
         return;
     }
@@ -95,7 +107,6 @@ pub struct EditExpenseModalProps {
     pub payments: Vec<Payment>,
     pub users: Vec<User>,
     pub project_id: Uuid,
-    pub stored_user_id: Option<i32>,
 }
 
 #[component]
@@ -103,6 +114,13 @@ pub fn EditExpenseModal(props: EditExpenseModalProps) -> Element {
     let expense_id = props.expense.id;
     let expense_author_id = props.expense.author_id;
 
+    // The version of the expense the form is based on, sent with the edit
+    let init_base = EditableExpense::from_expense(&props.expense, &props.payments);
+    let mut base = use_signal(move || init_base);
+    // The server's version, when saving found the expense edited since `base`
+    let mut conflict: Signal<Option<EditableExpense>> = use_signal(|| None);
+    let mut merge_notice: Signal<Option<String>> = use_signal(|| None);
+
     let init_name = props.expense.name.clone();
     let mut expense_name = use_signal(move || init_name);
 
@@ -118,60 +136,41 @@ pub fn EditExpenseModal(props: EditExpenseModalProps) -> Element {
     let mut payers_share_mode = use_signal(|| false);
     let mut debtors_share_mode = use_signal(|| false);
 
-    let init_payments_p = props.payments.clone();
     let init_users_p = props.users.clone();
-    let mut payers: Signal<Vec<UserEntry>> = use_signal(move || {
-        init_users_p
-            .iter()
-            .map(|u| {
-                let payment = init_payments_p.iter().find(|p| !p.is_debt && p.user_id == u.id);
-                UserEntry {
-                    user: u.clone(),
-                    checked: payment.is_some(),
-                    amount: payment.map(|p| p.amount).unwrap_or(0.0),
-                    shares: if payment.is_some() { 1 } else { 0 },
-                }
-            })
-            .collect()
-    });
+    let mut payers: Signal<Vec<UserEntry>> =
+        use_signal(move || entries(&init_users_p, &base.peek().payers));
 
-    let init_payments_d = props.payments.clone();
     let init_users_d = props.users.clone();
-    let mut debtors: Signal<Vec<UserEntry>> = use_signal(move || {
-        init_users_d
-            .iter()
-            .map(|u| {
-                let payment = init_payments_d.iter().find(|p| p.is_debt && p.user_id == u.id);
-                UserEntry {
-                    user: u.clone(),
-                    checked: payment.is_some(),
-                    amount: payment.map(|p| p.amount).unwrap_or(0.0),
-                    shares: if payment.is_some() { 1 } else { 0 },
-                }
-            })
-            .collect()
-    });
+    let mut debtors: Signal<Vec<UserEntry>> =
+        use_signal(move || entries(&init_users_d, &base.peek().debtors));
 
     let mut error_msg: Signal<Option<String>> = use_signal(|| None);
     let mut loading = use_signal(|| false);
+    let mut offline_ctx = use_context::<Signal<OfflineState>>();
 
     let project_id = props.project_id;
-    let stored_user_id = props.stored_user_id;
     let on_edited = props.on_edited.clone();
     let on_close_submit = props.on_close.clone();
 
-    let on_submit = move |e: FormEvent| {
-        e.prevent_default();
+    let form_users = props.users.clone();
+    let fill_form = use_callback(move |expense: EditableExpense| {
+        expense_name.set(expense.name);
+        date_str.set(expense.date.format("%Y-%m-%d").to_string());
+        total_amount.set(expense.amount);
+        expense_type.set(expense.expense_type);
+        payers.set(entries(&form_users, &expense.payers));
+        debtors.set(entries(&form_users, &expense.debtors));
+    });
 
+    // The edit in the form, or why it cannot be sent
+    let form_expense = move || -> Result<EditableExpense, String> {
         let name_val = expense_name().trim().to_string();
         if name_val.is_empty() {
-            error_msg.set(Some("Le nom est requis.".into()));
-            return;
+            return Err("Le nom est requis.".into());
         }
         let total = total_amount();
         if total <= 0.0 {
-            error_msg.set(Some("Le montant doit être supérieur à 0.".into()));
-            return;
+            return Err("Le montant doit être supérieur à 0.".into());
         }
         let active_payers: Vec<UserAmount> = payers()
             .iter()
@@ -184,49 +183,109 @@ pub fn EditExpenseModal(props: EditExpenseModalProps) -> Element {
             .map(|e| UserAmount { user_id: e.user.id, amount: e.amount })
             .collect();
         if active_payers.is_empty() {
-            error_msg.set(Some("Sélectionnez au moins un payeur.".into()));
-            return;
+            return Err("Sélectionnez au moins un payeur.".into());
         }
         if active_debtors.is_empty() {
-            error_msg.set(Some("Sélectionnez au moins un débiteur.".into()));
-            return;
+            return Err("Sélectionnez au moins un débiteur.".into());
         }
-        let date = match NaiveDate::parse_from_str(&date_str(), "%Y-%m-%d") {
-            Ok(d) => d,
-            Err(_) => {
-                error_msg.set(Some("Date invalide.".into()));
+        let date = NaiveDate::parse_from_str(&date_str(), "%Y-%m-%d")
+            .map_err(|_| "Date invalide.".to_string())?;
+        let base = base.peek();
+
+        Ok(EditableExpense {
+            id: expense_id,
+            name: name_val,
+            amount: total,
+            expense_type: expense_type(),
+            project_id,
+            payers: active_payers,
+            debtors: active_debtors,
+            // Only owners may give an expense to another participant
+            author_id: expense_author_id,
+            // Not editable here
+            description: base.description.clone(),
+            date,
+            version: base.version,
+        })
+    };
+
+    let on_reload = move |_| {
+        if let Some(current) = conflict.take() {
+            fill_form.call(current.clone());
+            base.set(current);
+            merge_notice.set(None);
+        }
+    };
+
+    let on_merge = move |_| {
+        let Some(current) = conflict() else {
+            return;
+        };
+        let local = match form_expense() {
+            Ok(local) => local,
+            Err(message) => {
+                error_msg.set(Some(message));
+                return;
+            }
+        };
+        let merged = merge_edit(&base.peek(), &current, &local);
+        fill_form.call(merged.expense);
+        base.set(current);
+        conflict.set(None);
+        error_msg.set(None);
+        merge_notice.set(Some(if merged.conflicts.is_empty() {
+            "Vos modifications ont été reportées sur la dernière version. Vérifiez puis enregistrez."
+                .to_string()
+        } else {
+            format!(
+                "Modifié des deux côtés : la dernière version a été gardée pour {}. Vérifiez puis enregistrez.",
+                merged.conflicts.join(", ")
+            )
+        }));
+    };
+
+    let on_submit = move |e: FormEvent| {
+        e.prevent_default();
+
+        let expense = match form_expense() {
+            Ok(expense) => expense,
+            Err(message) => {
+                error_msg.set(Some(message));
                 return;
             }
         };
-        let author_id = stored_user_id.unwrap_or(expense_author_id);
-        let etype = expense_type();
 
         loading.set(true);
         error_msg.set(None);
 
+        // What the edit started from, compared with the server's version when it is replayed
+        let base = base();
         let on_edited = on_edited.clone();
         let on_close_submit = on_close_submit.clone();
         spawn(async move {
-            match edit_expense(Json(EditableExpense {
-                id: expense_id,
-                name: name_val,
-                amount: total,
-                expense_type: etype,
-                project_id,
-                payers: active_payers,
-                debtors: active_debtors,
-                author_id,
-                description: None,
-                date,
-            }))
-            .await
-            {
+            // An expense created offline is unknown to the server until its creation is sent
+            let sent = if expense_id > 0 {
+                edit_expense(Json(expense.clone())).await.map(|_| ())
+            } else {
+                Err(ApiError::unavailable("Not sent yet"))
+            };
+            match sent {
                 Ok(_) => {
                     on_edited.call(());
                     on_close_submit.call(());
                 }
+                Err(e) if e.code == ErrorCode::Unavailable => {
+                    update_offline_state(&mut offline_ctx, |s| s.queue_edit_expense(expense, base));
+                    on_edited.call(());
+                    on_close_submit.call(());
+                }
+                Err(e) if e.current::<EditableExpense>().is_some() => {
+                    conflict.set(e.current());
+                    merge_notice.set(None);
+                    loading.set(false);
+                }
                 Err(e) => {
-                    error_msg.set(Some(e.to_string()));
+                    error_msg.set(Some(error_message(&e)));
                     loading.set(false);
                 }
             }
@@ -253,6 +312,32 @@ pub fn EditExpenseModal(props: EditExpenseModalProps) -> Element {
                     div { class: "alert alert-error text-sm mb-3", "{err}" }
                 }
 
+                if conflict().is_some() {
+                    div { class: "alert alert-warning text-sm mb-3 flex flex-col items-start",
+                        span {
+                            "Cette dépense a été modifiée pendant que vous l'éditiez. Rechargez la dernière version, ou fusionnez-y vos modifications."
+                        }
+                        div { class: "flex gap-2 self-end",
+                            button {
+                                r#type: "button",
+                                class: "btn btn-xs",
+                                onclick: on_reload,
+                                "Recharger"
+                            }
+                            button {
+                                r#type: "button",
+                                class: "btn btn-xs btn-primary",
+                                onclick: on_merge,
+                                "Fusionner"
+                            }
+                        }
+                    }
+                }
+
+                if let Some(notice) = merge_notice() {
+                    div { class: "alert alert-info text-sm mb-3", "{notice}" }
+                }
+
                 form {
                     class: "flex flex-col gap-4",
                     onsubmit: on_submit,
@@ -3,14 +3,15 @@ use shared::{Expense, ExpenseType, Payment, User};
 use std::collections::{BTreeMap, HashSet};
 use uuid::Uuid;
 
+use crate::offline::OfflineState;
 use crate::project_details::AddExpenseModal;
 use crate::route::Route;
 
-fn expense_type_label(t: &ExpenseType) -> &'static str {
+pub(crate) fn expense_type_label(t: &ExpenseType) -> &'static str {
     match t {
-        ExpenseType::Expense  => "Dépense",
+        ExpenseType::Expense => "Dépense",
         ExpenseType::Transfer => "Transfert",
-        ExpenseType::Gain     => "Gain",
+        ExpenseType::Gain => "Gain",
     }
 }
 
@@ -22,6 +23,8 @@ pub struct ExpensesTabProps {
     pub project_id: Uuid,
     pub currency: String,
     pub users: Vec<User>,
+    /// Hides the add button, e.g. once the project is closed
+    pub can_add: bool,
     pub on_expense_created: EventHandler<()>,
 }
 
@@ -31,6 +34,7 @@ pub fn ExpensesTab(props: ExpensesTabProps) -> Element {
     let mut show_my_payments = use_signal(|| false);
     let mut show_my_debts = use_signal(|| false);
     let mut show_add_expense = use_signal(|| false);
+    let offline_ctx = use_context::<Signal<OfflineState>>();
 
     let filtered_expenses = move || -> Vec<Expense> {
         let uid = match props.stored_user_id {
@@ -38,7 +42,9 @@ pub fn ExpensesTab(props: ExpensesTabProps) -> Element {
             _ => return props.expenses.clone(),
         };
 
-        let expense_ids: HashSet<i32> = props.payments.iter()
+        let expense_ids: HashSet<i32> = props
+            .payments
+            .iter()
             .filter(|p| {
                 p.user_id == uid
                     && ((show_my_payments() && !p.is_debt) || (show_my_debts() && p.is_debt))
@@ -46,10 +52,7 @@ pub fn ExpensesTab(props: ExpensesTabProps) -> Element {
             .map(|p| p.expense_id)
             .collect();
 
-        props.expenses.iter()
-            .filter(|e| expense_ids.contains(&e.id))
-            .cloned()
-            .collect()
+        props.expenses.iter().filter(|e| expense_ids.contains(&e.id)).cloned().collect()
     };
 
     // Group by date, sorted descending
@@ -67,6 +70,7 @@ pub fn ExpensesTab(props: ExpensesTabProps) -> Element {
     let is_empty = groups.is_empty();
     let project_id = props.project_id;
     let currency = props.currency.clone();
+    let pending_ids = offline_ctx.read().pending_expense_ids(project_id);
 
     rsx! {
         div { class: "flex flex-col gap-2",
@@ -138,6 +142,7 @@ pub fn ExpensesTab(props: ExpensesTabProps) -> Element {
                                     let name = expense.name.clone();
                                     let etype = expense.expense_type.clone();
                                     let curr = currency.clone();
+                                    let is_pending = pending_ids.contains(&expense_id);
                                     rsx! {
                                         li {
                                             class: "flex items-center gap-3 p-3 bg-base-100 rounded-lg shadow-sm cursor-pointer hover:bg-base-200 transition-colors",
@@ -148,6 +153,13 @@ pub fn ExpensesTab(props: ExpensesTabProps) -> Element {
                                             }
                                             div { class: "text-right shrink-0",
                                                 p { class: "text-sm font-semibold", "{amount:.2} {curr}" }
+                                                if is_pending {
+                                                    span {
+                                                        class: "badge badge-warning badge-xs",
+                                                        title: "En attente de synchronisation",
+                                                        "Non synchronisée"
+                                                    }
+                                                }
                                             }
                                         }
                                     }
@@ -159,19 +171,21 @@ pub fn ExpensesTab(props: ExpensesTabProps) -> Element {
             }
 
             // FAB
-            div { class: "fixed bottom-6 right-6",
-                button {
-                    r#type: "button",
-                    class: "btn btn-circle btn-lg btn-primary shadow-lg",
-                    "aria-label": "Ajouter une dépense",
-                    onclick: move |_| { show_add_expense.set(true); },
-                    svg {
-                        class: "w-6 h-6",
-                        fill: "none",
-                        stroke: "currentColor",
-                        "stroke-width": "2",
-                        view_box: "0 0 24 24",
-                        path { d: "M12 5v14M5 12h14" }
+            if props.can_add {
+                div { class: "fixed bottom-6 right-6",
+                    button {
+                        r#type: "button",
+                        class: "btn btn-circle btn-lg btn-primary shadow-lg",
+                        "aria-label": "Ajouter une dépense",
+                        onclick: move |_| { show_add_expense.set(true); },
+                        svg {
+                            class: "w-6 h-6",
+                            fill: "none",
+                            stroke: "currentColor",
+                            "stroke-width": "2",
+                            view_box: "0 0 24 24",
+                            path { d: "M12 5v14M5 12h14" }
+                        }
                     }
                 }
             }
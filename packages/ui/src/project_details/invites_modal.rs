@@ -0,0 +1,206 @@
+use api::invites::invites_controller::{
+    create_project_invite, get_project_invites, revoke_project_invite,
+};
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{CreatableProjectInvite, ProjectRole};
+use uuid::Uuid;
+
+use crate::common::{absolute_url, error_message};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct InvitesModalProps {
+    pub project_id: Uuid,
+    pub on_close: EventHandler<()>,
+}
+
+fn role_label(role: ProjectRole) -> &'static str {
+    match role {
+        ProjectRole::Owner => "Propriétaire",
+        ProjectRole::Editor => "Éditeur",
+        ProjectRole::Viewer => "Lecteur",
+    }
+}
+
+#[component]
+pub fn InvitesModal(props: InvitesModalProps) -> Element {
+    let project_id = props.project_id;
+    let mut role = use_signal(|| ProjectRole::Editor);
+    let mut expires_in_hours = use_signal(|| 24 * 7_i64);
+    let mut max_uses = use_signal(String::new);
+    let mut created_link: Signal<Option<String>> = use_signal(|| None);
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut loading = use_signal(|| false);
+
+    let mut invites = use_resource(move || async move { get_project_invites(project_id).await });
+
+    let on_submit = move |e: FormEvent| {
+        e.prevent_default();
+
+        let max_uses_val = match max_uses().trim() {
+            "" => None,
+            raw => match raw.parse::<i32>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => {
+                    error_msg.set(Some("Le nombre d'utilisations doit être positif.".into()));
+                    return;
+                }
+            },
+        };
+
+        loading.set(true);
+        error_msg.set(None);
+
+        spawn(async move {
+            match create_project_invite(
+                project_id,
+                Json(CreatableProjectInvite {
+                    role: role(),
+                    expires_in_hours: expires_in_hours(),
+                    max_uses: max_uses_val,
+                }),
+            )
+            .await
+            {
+                Ok(created) => {
+                    created_link.set(Some(absolute_url(&format!("/join/{}", created.token))));
+                    invites.restart();
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+            loading.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "modal modal-open", role: "dialog",
+            div { class: "modal-box max-w-sm relative",
+                button {
+                    r#type: "button",
+                    class: "btn btn-ghost btn-sm btn-circle absolute right-2 top-2",
+                    onclick: move |_| props.on_close.call(()),
+                    "✕"
+                }
+
+                h3 { class: "font-bold text-lg mb-4", "Liens d'invitation" }
+
+                if let Some(err) = error_msg() {
+                    div { class: "alert alert-error text-sm mb-3", "{err}" }
+                }
+
+                if let Some(link) = created_link() {
+                    div { class: "flex flex-col gap-1 mb-4",
+                        span { class: "text-sm", "Copiez ce lien, il ne sera plus affiché :" }
+                        input {
+                            class: "input input-bordered input-sm font-mono",
+                            r#type: "text",
+                            readonly: true,
+                            value: "{link}",
+                        }
+                    }
+                }
+
+                form { class: "flex flex-col gap-3", onsubmit: on_submit,
+                    label { class: "form-control",
+                        span { class: "label-text mb-1", "Rôle" }
+                        select {
+                            class: "select select-bordered select-sm",
+                            onchange: move |e| {
+                                role.set(if e.value() == "viewer" { ProjectRole::Viewer } else { ProjectRole::Editor });
+                            },
+                            option { value: "editor", selected: role() == ProjectRole::Editor, "Éditeur" }
+                            option { value: "viewer", selected: role() == ProjectRole::Viewer, "Lecteur" }
+                        }
+                    }
+                    label { class: "form-control",
+                        span { class: "label-text mb-1", "Expire dans" }
+                        select {
+                            class: "select select-bordered select-sm",
+                            onchange: move |e| {
+                                if let Ok(hours) = e.value().parse() {
+                                    expires_in_hours.set(hours);
+                                }
+                            },
+                            option { value: "24", selected: expires_in_hours() == 24, "1 jour" }
+                            option { value: "168", selected: expires_in_hours() == 168, "7 jours" }
+                            option { value: "720", selected: expires_in_hours() == 720, "30 jours" }
+                        }
+                    }
+                    label { class: "form-control",
+                        span { class: "label-text mb-1", "Nombre d'utilisations" }
+                        input {
+                            class: "input input-bordered input-sm",
+                            r#type: "number",
+                            min: "1",
+                            placeholder: "Illimité",
+                            value: "{max_uses}",
+                            oninput: move |e| max_uses.set(e.value()),
+                        }
+                    }
+                    button {
+                        r#type: "submit",
+                        class: "btn btn-primary btn-sm",
+                        disabled: loading(),
+                        if loading() { "Création…" } else { "Créer un lien" }
+                    }
+                }
+
+                div { class: "divider", "Liens actifs" }
+
+                match &*invites.read() {
+                    None => rsx! {
+                        div { class: "flex justify-center py-4",
+                            span { class: "loading loading-spinner loading-sm" }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "alert alert-error text-sm", {error_message(e)} }
+                    },
+                    Some(Ok(list)) if list.is_empty() => rsx! {
+                        p { class: "text-sm text-base-content/60 text-center", "Aucun lien actif" }
+                    },
+                    Some(Ok(list)) => rsx! {
+                        ul { class: "flex flex-col gap-2",
+                            for invite in list.clone() {
+                                {
+                                    let expires = invite.expires_at.format("%d/%m/%Y %H:%M").to_string();
+                                    let uses = match invite.max_uses {
+                                        Some(max) => format!("{}/{} utilisations", invite.use_count, max),
+                                        None => format!("{} utilisation(s)", invite.use_count),
+                                    };
+                                    rsx! {
+                                        li { key: "{invite.id}", class: "flex items-center justify-between gap-2 text-sm",
+                                            div { class: "flex flex-col",
+                                                span { class: "font-medium", "{role_label(invite.role)}" }
+                                                span { class: "text-xs text-base-content/60", "Expire le {expires} · {uses}" }
+                                            }
+                                            button {
+                                                r#type: "button",
+                                                class: "btn btn-xs btn-error btn-outline",
+                                                onclick: move |_| {
+                                                    let invite_id = invite.id;
+                                                    spawn(async move {
+                                                        match revoke_project_invite(project_id, invite_id).await {
+                                                            Ok(_) => invites.restart(),
+                                                            Err(e) => error_msg.set(Some(error_message(&e))),
+                                                        }
+                                                    });
+                                                },
+                                                "Révoquer"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+
+            div {
+                class: "modal-backdrop",
+                onclick: move |_| props.on_close.call(()),
+            }
+        }
+    }
+}
@@ -2,12 +2,24 @@ mod add_expense_modal;
 mod balance_tab;
 mod edit_expense_modal;
 mod expenses_tab;
+mod invites_modal;
 mod project_details;
+mod public_project;
 mod reimbursements_tab;
+mod settlement_modal;
+mod share_links_modal;
+mod status_menu;
+mod sync_banner;
 
 pub use add_expense_modal::AddExpenseModal;
 pub use balance_tab::BalanceTab;
 pub use edit_expense_modal::EditExpenseModal;
 pub use expenses_tab::ExpensesTab;
+pub use invites_modal::InvitesModal;
 pub use project_details::ProjectDetails;
+pub use public_project::PublicProject;
 pub use reimbursements_tab::ReimbursementsTab;
+pub use settlement_modal::SettlementModal;
+pub use share_links_modal::ShareLinksModal;
+pub use status_menu::StatusMenu;
+pub use sync_banner::SyncBanner;
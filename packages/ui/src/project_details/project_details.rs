@@ -1,13 +1,17 @@
-use api::expenses::expenses_controller::get_expenses_by_project_id;
-use api::payments::payments_controller::{get_payments_by_project_id, get_summary_by_project_id};
-use api::projects::projects_controller::get_project;
-use api::users::users_controller::get_users_by_project_id;
 use dioxus::prelude::*;
-use shared::{ExpenseType, ProjectStatus, ReimbursementSuggestion, User};
+use shared::{Account, ExpenseType, ProjectStatus, ReimbursementSuggestion, User};
 use uuid::Uuid;
 
-use crate::common::{initials, user_color_class, AppHeader, Avatar, LocalStorageState};
-use crate::project_details::{AddExpenseModal, BalanceTab, ExpensesTab, ReimbursementsTab};
+use crate::common::{
+    error_message, initials, user_color_class, AppHeader, Avatar, LocalStorageState,
+};
+use crate::offline::{
+    load_expenses, load_payments, load_project, load_summary, load_users, OfflineState,
+};
+use crate::project_details::{
+    AddExpenseModal, BalanceTab, ExpensesTab, InvitesModal, ReimbursementsTab, SettlementModal,
+    ShareLinksModal, StatusMenu, SyncBanner,
+};
 use crate::route::Route;
 
 #[derive(PartialEq, Clone)]
@@ -21,8 +25,13 @@ enum Tab {
 pub fn ProjectDetails(project_id: Uuid) -> Element {
     let nav = use_navigator();
     let ls_ctx = use_context::<Signal<LocalStorageState>>();
+    let auth_ctx = use_context::<Signal<Option<Account>>>();
+    let offline_ctx = use_context::<Signal<OfflineState>>();
     let mut active_tab = use_signal(|| Tab::Expenses);
     let mut show_transfer_modal = use_signal(|| false);
+    let mut show_invites_modal = use_signal(|| false);
+    let mut show_share_links_modal = use_signal(|| false);
+    let mut show_settlement_modal = use_signal(|| false);
     // (name, amount, payer_user_id [=debtor in suggestion], debtor_user_id [=payer in suggestion])
     let mut transfer_preset: Signal<Option<(String, f64, i32, i32)>> = use_signal(|| None);
 
@@ -30,14 +39,16 @@ pub fn ProjectDetails(project_id: Uuid) -> Element {
         ls_ctx().projects.iter().find(|p| p.project_id == project_id).and_then(|p| p.user_id)
     };
 
-    let project = use_resource(move || async move { get_project(project_id).await });
-    let users = use_resource(move || async move { get_users_by_project_id(project_id).await });
+    // Read from the replica when the server cannot be reached
+    let mut project =
+        use_resource(move || async move { load_project(offline_ctx, project_id).await });
+    let users = use_resource(move || async move { load_users(offline_ctx, project_id).await });
     let mut expenses =
-        use_resource(move || async move { get_expenses_by_project_id(project_id).await });
+        use_resource(move || async move { load_expenses(offline_ctx, project_id).await });
     let mut payments =
-        use_resource(move || async move { get_payments_by_project_id(project_id).await });
+        use_resource(move || async move { load_payments(offline_ctx, project_id).await });
     let mut summary =
-        use_resource(move || async move { get_summary_by_project_id(project_id).await });
+        use_resource(move || async move { load_summary(offline_ctx, project_id).await });
 
     rsx! {
         div { class: "container overflow-auto p-4 max-w-md w-full mx-auto flex flex-col gap-4 pb-24",
@@ -50,17 +61,79 @@ pub fn ProjectDetails(project_id: Uuid) -> Element {
                 }
             },
                 Some(Err(e)) => rsx! {
-                div { class: "alert alert-error", "{e}" }
+                div { class: "alert alert-error", {error_message(e)} }
             },
                 Some(Ok(p)) => {
                     let project_name = p.name.clone();
-                    let is_archived = p.status == ProjectStatus::Archived;
+                    let status = p.status.clone();
+                    let currency = p.currency.clone();
+                    // Only owners manage invites and share links; projects without an owner are shared by URL
+                    let is_owner = p.owner_account_id.is_none()
+                        || p.owner_account_id == auth_ctx().map(|a| a.id);
                     rsx! {
                 AppHeader { back_button_route: Route::ProjectsList {}, title: {project_name.clone()} }
+                if is_owner {
+                    div { class: "flex justify-end gap-2",
+                        button {
+                            r#type: "button",
+                            class: "btn btn-sm btn-outline",
+                            onclick: move |_| show_share_links_modal.set(true),
+                            "Lien public"
+                        }
+                        button {
+                            r#type: "button",
+                            class: "btn btn-sm btn-outline",
+                            onclick: move |_| show_invites_modal.set(true),
+                            "Inviter"
+                        }
+                    }
+                }
+                if show_invites_modal() {
+                    InvitesModal { project_id, on_close: move |_| show_invites_modal.set(false) }
+                }
+                if show_share_links_modal() {
+                    ShareLinksModal { project_id, on_close: move |_| show_share_links_modal.set(false) }
+                }
+                if is_owner {
+                    StatusMenu { project: p.clone(), on_changed: move |_| project.restart() }
+                }
+                if status != ProjectStatus::Ongoing {
+                    div { class: "alert alert-warning text-sm flex justify-between",
+                        span {
+                            if status == ProjectStatus::Closed {
+                                "Projet clôturé : seuls les remboursements peuvent être enregistrés."
+                            } else {
+                                "Projet archivé : il ne peut plus être modifié."
+                            }
+                        }
+                        button {
+                            r#type: "button",
+                            class: "btn btn-xs",
+                            onclick: move |_| show_settlement_modal.set(true),
+                            "Bilan de clôture"
+                        }
+                    }
+                }
+                if show_settlement_modal() {
+                    SettlementModal {
+                        project_id,
+                        currency,
+                        on_close: move |_| show_settlement_modal.set(false),
+                    }
+                }
             }
                 }
             }
 
+            SyncBanner {
+                project_id,
+                on_synced: move |_| {
+                    expenses.restart();
+                    payments.restart();
+                    summary.restart();
+                },
+            }
+
             // Users + summary — waits for users + expenses + payments
             match (&*users.read(), &*expenses.read(), &*payments.read()) {
                 (Some(Ok(user_list)), Some(Ok(expense_list)), Some(Ok(payment_list))) => {
@@ -70,6 +143,12 @@ pub fn ProjectDetails(project_id: Uuid) -> Element {
                         .and_then(|r| r.as_ref().ok())
                         .map(|p| p.currency.clone())
                         .unwrap_or_else(|| "EUR".to_string());
+                    let status = project
+                        .read()
+                        .as_ref()
+                        .and_then(|r| r.as_ref().ok())
+                        .map(|p| p.status.clone())
+                        .unwrap_or(ProjectStatus::Ongoing);
                     let global_total: f64 = expense_list
                         .iter()
                         .filter(|e| e.expense_type != ExpenseType::Transfer)
@@ -150,6 +229,7 @@ pub fn ProjectDetails(project_id: Uuid) -> Element {
                         project_id,
                         currency: currency.clone(),
                         users: user_list_c.clone(),
+                        can_add: status == ProjectStatus::Ongoing,
                         on_expense_created: move |_| {
                             expenses.restart();
                             payments.restart();
@@ -165,7 +245,7 @@ pub fn ProjectDetails(project_id: Uuid) -> Element {
                     }
                 },
                             Some(Err(e)) => rsx! {
-                    div { class: "alert alert-error", "{e}" }
+                    div { class: "alert alert-error", {error_message(e)} }
                 },
                             Some(Ok(s)) => rsx! {
                     BalanceTab {
@@ -184,24 +264,28 @@ pub fn ProjectDetails(project_id: Uuid) -> Element {
                     }
                 },
                             Some(Err(e)) => rsx! {
-                    div { class: "alert alert-error", "{e}" }
+                    div { class: "alert alert-error", {error_message(e)} }
                 },
                             Some(Ok(s)) => {
                                 let users_for_cb = user_list_c.clone();
+                                // Archived projects take no settlement either
+                                let on_reimburse = (status != ProjectStatus::Archived).then(|| {
+                                    EventHandler::new(move |s: ReimbursementSuggestion| {
+                                        let debtor = users_for_cb.iter().find(|u| u.id == s.user_id_debtor);
+                                        let payer  = users_for_cb.iter().find(|u| u.id == s.user_id_payer);
+                                        if let (Some(d), Some(p)) = (debtor, payer) {
+                                            let name = format!("Remboursement {} vers {}", d.name, p.name);
+                                            transfer_preset.set(Some((name, s.amount, s.user_id_debtor, s.user_id_payer)));
+                                            show_transfer_modal.set(true);
+                                        }
+                                    })
+                                });
                                 rsx! {
                     ReimbursementsTab {
                         suggestions: s.reimbursement_suggestions.clone(),
                         users: user_list_c.clone(),
                         currency: currency.clone(),
-                        on_reimburse: move |s: ReimbursementSuggestion| {
-                            let debtor = users_for_cb.iter().find(|u| u.id == s.user_id_debtor);
-                            let payer  = users_for_cb.iter().find(|u| u.id == s.user_id_payer);
-                            if let (Some(d), Some(p)) = (debtor, payer) {
-                                let name = format!("Remboursement {} vers {}", d.name, p.name);
-                                transfer_preset.set(Some((name, s.amount, s.user_id_debtor, s.user_id_payer)));
-                                show_transfer_modal.set(true);
-                            }
-                        },
+                        on_reimburse,
                     }
                 }},
                         }
@@ -235,7 +319,7 @@ pub fn ProjectDetails(project_id: Uuid) -> Element {
                 }
                 (Some(Err(e)), _, _) | (_, Some(Err(e)), _) | (_, _, Some(Err(e))) => {
                     rsx! {
-                div { class: "alert alert-error", "{e}" }
+                div { class: "alert alert-error", {error_message(e)} }
             }
                 }
                 _ => rsx! {
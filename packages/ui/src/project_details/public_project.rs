@@ -0,0 +1,158 @@
+use api::share_links::share_links_controller::get_public_project;
+use dioxus::prelude::*;
+use shared::{ExpenseType, PublicExpense, PublicProjectView};
+use std::collections::BTreeMap;
+
+use crate::common::error_message;
+use crate::project_details::expenses_tab::expense_type_label;
+use crate::project_details::{BalanceTab, ReimbursementsTab};
+
+#[derive(PartialEq, Clone)]
+enum Tab {
+    Expenses,
+    Balance,
+    Reimbursements,
+}
+
+/// Read-only project page reached through a public share link.
+#[component]
+pub fn PublicProject(token: String) -> Element {
+    let project = use_resource(move || {
+        let token = token.clone();
+        async move { get_public_project(token).await }
+    });
+
+    rsx! {
+        div { class: "container overflow-auto p-4 max-w-md w-full mx-auto flex flex-col gap-4 pb-24",
+            match &*project.read() {
+                None => rsx! {
+                    div { class: "flex justify-center py-8",
+                        span { class: "loading loading-spinner loading-md" }
+                    }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "alert alert-error", {error_message(e)} }
+                },
+                Some(Ok(view)) => rsx! {
+                    PublicProjectContent { view: view.clone() }
+                },
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Props, Clone)]
+struct PublicProjectContentProps {
+    view: PublicProjectView,
+}
+
+#[component]
+fn PublicProjectContent(props: PublicProjectContentProps) -> Element {
+    let mut active_tab = use_signal(|| Tab::Expenses);
+    let view = props.view;
+
+    let global_total: f64 = view.expenses.iter().fold(0.0, |acc, e| match e.expense_type {
+        ExpenseType::Expense => acc + e.amount,
+        ExpenseType::Gain => acc - e.amount,
+        ExpenseType::Transfer => acc,
+    });
+
+    // Group by date, sorted descending
+    let mut groups: Vec<(chrono::NaiveDate, Vec<PublicExpense>)> = view
+        .expenses
+        .iter()
+        .fold(BTreeMap::<chrono::NaiveDate, Vec<PublicExpense>>::new(), |mut map, e| {
+            map.entry(e.date).or_default().push(e.clone());
+            map
+        })
+        .into_iter()
+        .collect();
+    groups.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    rsx! {
+        div { class: "navbar px-0",
+            div { class: "navbar-center mx-auto flex flex-col",
+                h1 { class: "text-xl font-bold", "{view.name}" }
+                if let Some(desc) = &view.description {
+                    span { class: "text-xs text-base-content/60", "{desc}" }
+                }
+            }
+        }
+
+        div { class: "badge badge-neutral self-center", "Lecture seule" }
+
+        div { class: "card bg-base-100 shadow-sm",
+            div { class: "card-body p-4",
+                div { class: "flex justify-between items-center",
+                    span { class: "text-sm text-base-content/60", "Total des dépenses" }
+                    span { class: "font-bold", "{global_total:.2} {view.currency}" }
+                }
+            }
+        }
+
+        div { role: "tablist", class: "tabs tabs-box bg-base-300",
+            button {
+                role: "tab",
+                class: if *active_tab.read() == Tab::Expenses { "tab tab-active text-xs" } else { "tab text-xs" },
+                onclick: move |_| active_tab.set(Tab::Expenses),
+                "Dépenses"
+            }
+            button {
+                role: "tab",
+                class: if *active_tab.read() == Tab::Balance { "tab tab-active text-xs" } else { "tab text-xs" },
+                onclick: move |_| active_tab.set(Tab::Balance),
+                "Équilibre"
+            }
+            button {
+                role: "tab",
+                class: if *active_tab.read() == Tab::Reimbursements { "tab tab-active text-xs" } else { "tab text-xs" },
+                onclick: move |_| active_tab.set(Tab::Reimbursements),
+                "Remboursements"
+            }
+        }
+
+        match *active_tab.read() {
+            Tab::Expenses => rsx! {
+                if groups.is_empty() {
+                    div { class: "flex flex-col items-center gap-2 py-12 text-base-content/60",
+                        span { class: "font-semibold", "Aucune dépense" }
+                    }
+                }
+                for (date, group) in groups.clone() {
+                    div { class: "flex flex-col",
+                        div { class: "divider divider-start text-xs text-base-content/60 font-medium my-1",
+                            "{date.format(\"%d/%m/%Y\")}"
+                        }
+                        ul { class: "flex flex-col gap-1",
+                            for expense in group {
+                                li { class: "flex items-center gap-3 p-3 bg-base-100 rounded-lg shadow-sm",
+                                    div { class: "flex-1 min-w-0",
+                                        p { class: "font-medium truncate", "{expense.name}" }
+                                        p { class: "text-xs text-base-content/60", "{expense_type_label(&expense.expense_type)}" }
+                                    }
+                                    div { class: "text-right shrink-0",
+                                        p { class: "text-sm font-semibold", "{expense.amount:.2} {view.currency}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            Tab::Balance => rsx! {
+                BalanceTab {
+                    summary: view.summary.clone(),
+                    users: view.users.clone(),
+                    currency: view.currency.clone(),
+                }
+            },
+            Tab::Reimbursements => rsx! {
+                ReimbursementsTab {
+                    suggestions: view.summary.reimbursement_suggestions.clone(),
+                    users: view.users.clone(),
+                    currency: view.currency.clone(),
+                }
+            },
+        }
+    }
+}
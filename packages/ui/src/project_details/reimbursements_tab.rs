@@ -8,7 +8,8 @@ pub struct ReimbursementsTabProps {
     pub suggestions: Vec<ReimbursementSuggestion>,
     pub users: Vec<User>,
     pub currency: String,
-    pub on_reimburse: EventHandler<ReimbursementSuggestion>,
+    /// Hides the record button when absent (read-only pages)
+    pub on_reimburse: Option<EventHandler<ReimbursementSuggestion>>,
 }
 
 #[component]
@@ -72,18 +73,20 @@ pub fn ReimbursementsTab(props: ReimbursementsTabProps) -> Element {
                                 }
                             }
                             // Record button
-                            button {
-                                r#type: "button",
-                                class: "btn btn-circle btn-sm btn-ghost",
-                                title: "Enregistrer le remboursement",
-                                onclick: move |_| props.on_reimburse.call(suggestion_clone.clone()),
-                                svg {
-                                    class: "w-4 h-4",
-                                    fill: "none",
-                                    stroke: "currentColor",
-                                    "stroke-width": "2",
-                                    view_box: "0 0 24 24",
-                                    path { d: "M12 2v20M17 5H9.5a3.5 3.5 0 0 0 0 7h5a3.5 3.5 0 0 1 0 7H6" }
+                            if let Some(on_reimburse) = props.on_reimburse {
+                                button {
+                                    r#type: "button",
+                                    class: "btn btn-circle btn-sm btn-ghost",
+                                    title: "Enregistrer le remboursement",
+                                    onclick: move |_| on_reimburse.call(suggestion_clone.clone()),
+                                    svg {
+                                        class: "w-4 h-4",
+                                        fill: "none",
+                                        stroke: "currentColor",
+                                        "stroke-width": "2",
+                                        view_box: "0 0 24 24",
+                                        path { d: "M12 2v20M17 5H9.5a3.5 3.5 0 0 0 0 7h5a3.5 3.5 0 0 1 0 7H6" }
+                                    }
                                 }
                             }
                         }
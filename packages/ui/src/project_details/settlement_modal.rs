@@ -0,0 +1,67 @@
+use api::projects::projects_controller::get_project_settlement;
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+use crate::common::error_message;
+use crate::project_details::{BalanceTab, ReimbursementsTab};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SettlementModalProps {
+    pub project_id: Uuid,
+    pub currency: String,
+    pub on_close: EventHandler<()>,
+}
+
+/// Balances and reimbursements stored when the project was last closed.
+#[component]
+pub fn SettlementModal(props: SettlementModalProps) -> Element {
+    let project_id = props.project_id;
+    let settlement = use_resource(move || async move { get_project_settlement(project_id).await });
+
+    rsx! {
+        div { class: "modal modal-open", role: "dialog",
+            div { class: "modal-box max-w-md relative",
+                button {
+                    r#type: "button",
+                    class: "btn btn-ghost btn-sm btn-circle absolute right-2 top-2",
+                    onclick: move |_| props.on_close.call(()),
+                    "✕"
+                }
+
+                h3 { class: "font-bold text-lg mb-2", "Bilan de clôture" }
+
+                match &*settlement.read() {
+                    None => rsx! {
+                        div { class: "flex justify-center py-4",
+                            span { class: "loading loading-spinner loading-md" }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "alert alert-error text-sm", {error_message(e)} }
+                    },
+                    Some(Ok(s)) => {
+                        let closed_at = s.closed_at.format("%d/%m/%Y à %H:%M").to_string();
+                        rsx! {
+                            p { class: "text-sm text-base-content/60 mb-4",
+                                "Comptes arrêtés le {closed_at} (UTC)."
+                            }
+                            div { class: "divider", "Équilibre" }
+                            BalanceTab {
+                                summary: s.summary.clone(),
+                                users: s.users.clone(),
+                                currency: props.currency.clone(),
+                            }
+                            div { class: "divider", "Remboursements" }
+                            ReimbursementsTab {
+                                suggestions: s.summary.reimbursement_suggestions.clone(),
+                                users: s.users.clone(),
+                                currency: props.currency.clone(),
+                                on_reimburse: None,
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use api::share_links::share_links_controller::{
+    create_share_link, get_share_links, revoke_share_link,
+};
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+use crate::common::{absolute_url, error_message};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ShareLinksModalProps {
+    pub project_id: Uuid,
+    pub on_close: EventHandler<()>,
+}
+
+#[component]
+pub fn ShareLinksModal(props: ShareLinksModalProps) -> Element {
+    let project_id = props.project_id;
+    let mut created_link: Signal<Option<String>> = use_signal(|| None);
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut loading = use_signal(|| false);
+
+    let mut links = use_resource(move || async move { get_share_links(project_id).await });
+
+    let on_create = move |_| {
+        loading.set(true);
+        error_msg.set(None);
+        spawn(async move {
+            match create_share_link(project_id).await {
+                Ok(created) => {
+                    created_link.set(Some(absolute_url(&format!("/share/{}", created.token))));
+                    links.restart();
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+            loading.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "modal modal-open", role: "dialog",
+            div { class: "modal-box max-w-sm relative",
+                button {
+                    r#type: "button",
+                    class: "btn btn-ghost btn-sm btn-circle absolute right-2 top-2",
+                    onclick: move |_| props.on_close.call(()),
+                    "✕"
+                }
+
+                h3 { class: "font-bold text-lg mb-2", "Liens publics" }
+                p { class: "text-sm text-base-content/60 mb-4",
+                    "Toute personne disposant du lien peut consulter le projet, sans pouvoir le modifier."
+                }
+
+                if let Some(err) = error_msg() {
+                    div { class: "alert alert-error text-sm mb-3", "{err}" }
+                }
+
+                if let Some(link) = created_link() {
+                    div { class: "flex flex-col gap-1 mb-4",
+                        span { class: "text-sm", "Copiez ce lien, il ne sera plus affiché :" }
+                        input {
+                            class: "input input-bordered input-sm font-mono",
+                            r#type: "text",
+                            readonly: true,
+                            value: "{link}",
+                        }
+                    }
+                }
+
+                button {
+                    r#type: "button",
+                    class: "btn btn-primary btn-sm w-full",
+                    disabled: loading(),
+                    onclick: on_create,
+                    if loading() { "Création…" } else { "Créer un lien public" }
+                }
+
+                div { class: "divider", "Liens actifs" }
+
+                match &*links.read() {
+                    None => rsx! {
+                        div { class: "flex justify-center py-4",
+                            span { class: "loading loading-spinner loading-sm" }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "alert alert-error text-sm", {error_message(e)} }
+                    },
+                    Some(Ok(list)) if list.is_empty() => rsx! {
+                        p { class: "text-sm text-base-content/60 text-center", "Aucun lien actif" }
+                    },
+                    Some(Ok(list)) => rsx! {
+                        ul { class: "flex flex-col gap-2",
+                            for link in list.clone() {
+                                li { key: "{link.id}", class: "flex items-center justify-between gap-2 text-sm",
+                                    span { "Créé le {link.created_at.format(\"%d/%m/%Y\")}" }
+                                    button {
+                                        r#type: "button",
+                                        class: "btn btn-xs btn-error btn-outline",
+                                        onclick: move |_| {
+                                            let link_id = link.id;
+                                            spawn(async move {
+                                                match revoke_share_link(project_id, link_id).await {
+                                                    Ok(_) => links.restart(),
+                                                    Err(e) => error_msg.set(Some(error_message(&e))),
+                                                }
+                                            });
+                                        },
+                                        "Révoquer"
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+
+            div {
+                class: "modal-backdrop",
+                onclick: move |_| props.on_close.call(()),
+            }
+        }
+    }
+}
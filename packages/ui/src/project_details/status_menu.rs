@@ -0,0 +1,75 @@
+use api::projects::projects_controller::update_project_by_id;
+use dioxus::fullstack::Json;
+use dioxus::prelude::*;
+use shared::{EditableProject, ProjectDto, ProjectStatus};
+
+use crate::common::error_message;
+
+/// Statuses a project can move to, one step at a time, with the label of the move.
+fn transitions(status: &ProjectStatus) -> Vec<(ProjectStatus, &'static str)> {
+    match status {
+        ProjectStatus::Ongoing => vec![(ProjectStatus::Closed, "Clôturer le projet")],
+        ProjectStatus::Closed => vec![
+            (ProjectStatus::Ongoing, "Rouvrir le projet"),
+            (ProjectStatus::Archived, "Archiver le projet"),
+        ],
+        ProjectStatus::Archived => vec![(ProjectStatus::Closed, "Désarchiver le projet")],
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub struct StatusMenuProps {
+    pub project: ProjectDto,
+    pub on_changed: EventHandler<ProjectDto>,
+}
+
+/// Owner actions moving a project through its lifecycle.
+#[component]
+pub fn StatusMenu(props: StatusMenuProps) -> Element {
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+    let mut loading = use_signal(|| false);
+    let project = props.project.clone();
+
+    rsx! {
+        div { class: "flex flex-col items-end gap-1",
+            div { class: "flex gap-2",
+                for (status, label) in transitions(&project.status) {
+                    {
+                        let project = project.clone();
+                        rsx! {
+                            button {
+                                r#type: "button",
+                                class: "btn btn-sm btn-outline",
+                                disabled: loading(),
+                                onclick: move |_| {
+                                    let edit = EditableProject {
+                                        id: project.id,
+                                        name: None,
+                                        description: None,
+                                        currency: None,
+                                        status: Some(status.clone()),
+                                        editors_can_edit_others_expenses: None,
+                                        version: project.version,
+                                    };
+                                    loading.set(true);
+                                    error_msg.set(None);
+                                    spawn(async move {
+                                        match update_project_by_id(Json(edit)).await {
+                                            Ok(updated) => props.on_changed.call(updated),
+                                            Err(e) => error_msg.set(Some(error_message(&e))),
+                                        }
+                                        loading.set(false);
+                                    });
+                                },
+                                "{label}"
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(err) = error_msg() {
+                div { class: "alert alert-error text-sm", "{err}" }
+            }
+        }
+    }
+}
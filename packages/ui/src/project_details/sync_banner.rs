@@ -0,0 +1,81 @@
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+use crate::offline::{sync, update_offline_state, OfflineState};
+
+#[derive(PartialEq, Props, Clone)]
+pub struct SyncBannerProps {
+    pub project_id: Uuid,
+    /// Called when queued mutations reached the server, to reload the project.
+    pub on_synced: EventHandler<()>,
+}
+
+/// Offline mode, mutations waiting for the server and what their replay could not apply.
+///
+/// Replays the queue when shown, then each time the server answers again.
+#[component]
+pub fn SyncBanner(props: SyncBannerProps) -> Element {
+    let mut offline_ctx = use_context::<Signal<OfflineState>>();
+    let on_synced = props.on_synced;
+
+    let replay = move || {
+        spawn(async move {
+            if sync(offline_ctx).await > 0 {
+                on_synced.call(());
+            }
+        });
+    };
+
+    use_effect(move || {
+        let state = offline_ctx.read();
+        let should_replay = !state.offline && !state.syncing && !state.queue.is_empty();
+        drop(state);
+        if should_replay {
+            replay();
+        }
+    });
+
+    let state = offline_ctx.read();
+    let pending = state.pending(props.project_id).count();
+    let syncing = state.syncing;
+    let offline = state.offline;
+    let notices = state.notices.clone();
+    drop(state);
+
+    rsx! {
+        if offline {
+            div { class: "alert alert-warning text-sm",
+                span { "Hors ligne : les données affichées sont celles enregistrées sur cet appareil." }
+            }
+        }
+        if pending > 0 {
+            div { class: "alert alert-info text-sm",
+                span { class: "flex-1", "{pending} modification(s) en attente de synchronisation" }
+                button {
+                    r#type: "button",
+                    class: "btn btn-xs",
+                    disabled: syncing,
+                    onclick: move |_| replay(),
+                    if syncing {
+                        span { class: "loading loading-spinner loading-xs" }
+                    } else {
+                        "Synchroniser"
+                    }
+                }
+            }
+        }
+        if !notices.is_empty() {
+            div { class: "alert alert-warning text-sm flex flex-col items-start",
+                for notice in notices {
+                    span { "{notice}" }
+                }
+                button {
+                    r#type: "button",
+                    class: "btn btn-xs self-end",
+                    onclick: move |_| update_offline_state(&mut offline_ctx, |s| s.notices.clear()),
+                    "OK"
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use api::account_projects::account_projects_controller::upsert_account_project;
+use api::idempotency::with_idempotency_key;
 use api::projects::projects_controller::add_project;
 use api::users::users_controller::add_user;
 use dioxus::fullstack::Json;
@@ -7,8 +8,9 @@ use shared::{
     Account as AccountData, CreatableProject, CreatableUser, CreatableUserBatch,
     UpsertAccountProject,
 };
+use uuid::Uuid;
 
-use crate::common::{upsert_project, write_to_ls, LocalStorageState};
+use crate::common::{error_message, upsert_project, write_to_ls, LocalStorageState};
 use crate::route::Route;
 
 #[derive(Props, Clone, PartialEq)]
@@ -20,8 +22,7 @@ pub struct AddProjectModalProps {
 pub fn AddProjectModal(props: AddProjectModalProps) -> Element {
     let mut project_name = use_signal(String::new);
     let mut description = use_signal(String::new);
-    let mut user_names: Signal<Vec<String>> =
-        use_signal(|| vec![String::new(), String::new()]);
+    let mut user_names: Signal<Vec<String>> = use_signal(|| vec![String::new(), String::new()]);
     let mut selected_idx: Signal<Option<usize>> = use_signal(|| None);
     let mut error_msg: Signal<Option<String>> = use_signal(|| None);
     let mut loading = use_signal(|| false);
@@ -62,16 +63,20 @@ pub fn AddProjectModal(props: AddProjectModalProps) -> Element {
 
         spawn(async move {
             // Step A — create project
-            let project = match add_project(Json(CreatableProject {
-                name: name_val,
-                description: if desc_val.is_empty() { None } else { Some(desc_val) },
-                currency: None,
-            }))
+            let project_key = Uuid::new_v4().to_string();
+            let project = match with_idempotency_key(
+                &project_key,
+                add_project(Json(CreatableProject {
+                    name: name_val,
+                    description: if desc_val.is_empty() { None } else { Some(desc_val) },
+                    currency: None,
+                })),
+            )
             .await
             {
                 Ok(p) => p,
                 Err(e) => {
-                    error_msg.set(Some(e.to_string()));
+                    error_msg.set(Some(error_message(&e)));
                     loading.set(false);
                     return;
                 }
@@ -91,20 +96,23 @@ pub fn AddProjectModal(props: AddProjectModalProps) -> Element {
                 })
                 .collect();
 
-            let created_users = match add_user(Json(CreatableUserBatch::Multiple(creatables))).await {
+            let users_key = Uuid::new_v4().to_string();
+            let created_users = match with_idempotency_key(
+                &users_key,
+                add_user(Json(CreatableUserBatch::Multiple(creatables))),
+            )
+            .await
+            {
                 Ok(u) => u,
                 Err(e) => {
-                    error_msg.set(Some(e.to_string()));
+                    error_msg.set(Some(error_message(&e)));
                     loading.set(false);
                     return;
                 }
             };
 
             // Step C — resolve which user the person selected
-            let sel_name = sel_idx
-                .and_then(|i| filled_names.get(i))
-                .cloned()
-                .unwrap_or_default();
+            let sel_name = sel_idx.and_then(|i| filled_names.get(i)).cloned().unwrap_or_default();
             let user_id = created_users
                 .iter()
                 .find(|u| u.name.to_lowercase() == sel_name.to_lowercase())
@@ -0,0 +1,56 @@
+use api::invites::invites_controller::redeem_invite;
+use dioxus::prelude::*;
+use shared::Account;
+
+use crate::common::{error_message, upsert_project, write_to_ls, AppHeader, LocalStorageState};
+use crate::route::Route;
+
+#[component]
+pub fn JoinProject(token: String) -> Element {
+    let nav = use_navigator();
+    let auth_ctx = use_context::<Signal<Option<Account>>>();
+    let mut ls_ctx = use_context::<Signal<LocalStorageState>>();
+    let mut error_msg: Signal<Option<String>> = use_signal(|| None);
+
+    // Redeem once on mount, then jump to the project
+    use_effect(move || {
+        let token = token.clone();
+        spawn(async move {
+            match redeem_invite(token).await {
+                Ok(redemption) => {
+                    let mut state = ls_ctx();
+                    upsert_project(&mut state, redemption.project_id, redemption.user_id);
+                    write_to_ls(&state);
+                    ls_ctx.set(state);
+                    nav.replace(Route::ProjectDetails { project_id: redemption.project_id });
+                }
+                Err(e) => error_msg.set(Some(error_message(&e))),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "container p-4 max-w-md mx-auto flex flex-col gap-4",
+            AppHeader { title: "Rejoindre un projet", back_button_route: Route::ProjectsList {} }
+
+            match error_msg() {
+                None => rsx! {
+                    div { class: "flex justify-center py-8",
+                        span { class: "loading loading-spinner loading-md" }
+                    }
+                },
+                Some(err) => rsx! {
+                    div { class: "alert alert-error text-sm", "{err}" }
+                    if auth_ctx().is_none() {
+                        button {
+                            r#type: "button",
+                            class: "btn btn-primary",
+                            onclick: move |_| { nav.push(Route::Login {}); },
+                            "Se connecter"
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
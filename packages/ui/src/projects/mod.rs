@@ -1,4 +1,6 @@
-mod projects_list;
 mod add_project_modal;
-pub use projects_list::ProjectsList;
+mod join_project;
+mod projects_list;
 pub use add_project_modal::AddProjectModal;
+pub use join_project::JoinProject;
+pub use projects_list::ProjectsList;
@@ -7,7 +7,8 @@ use shared::{Account, BatchProject, ProjectDto, ProjectStatus, User};
 use uuid::Uuid;
 
 use crate::common::{
-    initials, upsert_project, user_color_class, write_to_ls, Avatar, LocalStorageState,
+    error_message, initials, upsert_project, user_color_class, write_to_ls, Avatar,
+    LocalStorageState,
 };
 use crate::icons::{SettingsIcon, UserIcon};
 use crate::projects::AddProjectModal;
@@ -114,7 +115,7 @@ pub fn ProjectsList() -> Element {
                 }
             },
                 Some(Err(e)) => rsx! {
-                div { class: "alert alert-error", "{e}" }
+                div { class: "alert alert-error", {error_message(e)} }
             },
                 Some(Ok(list)) => {
                     let filtered: Vec<ProjectDto> = list
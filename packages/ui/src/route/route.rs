@@ -1,7 +1,10 @@
-use crate::auth::{account::Account, login::Login, register::Register};
+use crate::auth::{
+    account::Account, forgot_password::ForgotPassword, login::Login, oidc::OidcCallback,
+    register::Register, reset_password::ResetPassword, verify_email::VerifyEmail,
+};
 use crate::payments::PaymentPage;
-use crate::project_details::ProjectDetails;
-use crate::projects::ProjectsList;
+use crate::project_details::{ProjectDetails, PublicProject};
+use crate::projects::{JoinProject, ProjectsList};
 use dioxus::prelude::*;
 use uuid::Uuid;
 
@@ -14,10 +17,22 @@ pub enum Route {
     ProjectDetails { project_id: Uuid },
     #[route("/projects/:project_id/expenses/:expense_id")]
     PaymentPage { project_id: Uuid, expense_id: i32 },
+    #[route("/join/:token")]
+    JoinProject { token: String },
+    #[route("/share/:token")]
+    PublicProject { token: String },
     #[route("/login")]
     Login {},
     #[route("/register")]
     Register {},
+    #[route("/forgot-password")]
+    ForgotPassword {},
+    #[route("/reset-password/:token")]
+    ResetPassword { token: String },
+    #[route("/verify-email/:token")]
+    VerifyEmail { token: String },
+    #[route("/auth/oidc/callback?:code&:state&:error")]
+    OidcCallback { code: String, state: String, error: String },
     #[route("/account")]
     Account {},
 }
@@ -2,6 +2,7 @@ use api::auth::auth_controller::me;
 use dioxus::prelude::*;
 use shared::Account;
 use ui::common::{read_from_ls, LocalStorageState};
+use ui::offline::{read_offline_state, OfflineState};
 use ui::route::Route;
 
 use shared::sse::EventSSE;
@@ -23,8 +24,67 @@ const MAIN_CSS: Asset = asset!("/assets/main.css");
 const TAILWIND_CSS: Asset = asset!("/assets/tailwind.css");
 
 fn main() {
-    dioxus::logger::initialize_default();
-    dioxus::launch(app);
+    #[cfg(feature = "server")]
+    serve();
+
+    #[cfg(not(feature = "server"))]
+    {
+        dioxus::logger::initialize_default();
+        dioxus::launch(app);
+    }
+}
+
+/// Serve the app with the peer address of each connection available to the rate limiter,
+/// which `dioxus::serve` does not provide.
+///
+/// Pending migrations are applied first. `--migrate-only` stops there; `--check-migrations`
+/// only reports the state of the schema, and exits with `1` unless it is up to date.
+#[cfg(feature = "server")]
+fn serve() {
+    use dioxus::server::axum;
+    use std::net::SocketAddr;
+
+    let mode = std::env::args().nth(1);
+    if !matches!(mode.as_deref(), None | Some("--migrate-only") | Some("--check-migrations")) {
+        eprintln!("Usage: web [--migrate-only | --check-migrations]");
+        std::process::exit(2);
+    }
+
+    let config = api::config::Config::load().unwrap_or_else(|e| panic!("{:#}", e));
+    api::telemetry::init(&config.log).unwrap_or_else(|e| panic!("{:#}", e));
+
+    tokio::runtime::Runtime::new().expect("Failed to start the async runtime").block_on(async {
+        let pool = api::db::connect(&config.database).await.unwrap_or_else(|e| panic!("{:#}", e));
+
+        if mode.as_deref() == Some("--check-migrations") {
+            let mut conn = pool.acquire().await.unwrap_or_else(|e| panic!("{:#}", e));
+            let status =
+                api::migrations::check(&mut conn).await.unwrap_or_else(|e| panic!("{:#}", e));
+            println!("{}", status);
+            std::process::exit(if status.is_up_to_date() { 0 } else { 1 });
+        }
+
+        let applied = api::migrations::migrate(&pool).await.unwrap_or_else(|e| panic!("{:#}", e));
+        tracing::info!("{} migration(s) applied", applied);
+        if mode.is_some() {
+            return;
+        }
+        let state =
+            api::state::AppState::new(pool.clone(), config).unwrap_or_else(|e| panic!("{:#}", e));
+
+        api::auth::sessions::spawn_purge_job(pool);
+
+        let router = api::router::with_api_layers(dioxus::server::router(app), state);
+
+        let addr = dioxus::cli_config::fullstack_address_or_localhost();
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap_or_else(|e| panic!("Failed to bind to {}: {}", addr, e));
+
+        axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .expect("Server error");
+    });
 }
 
 #[component]
@@ -33,6 +93,8 @@ fn app() -> Element {
     let auth: Signal<Option<Account>> = use_context_provider(|| Signal::new(None));
     // Persistent project/user associations from localStorage
     let _ls: Signal<LocalStorageState> = use_context_provider(|| Signal::new(read_from_ls()));
+    // Replicas of the opened projects and the mutations made offline
+    let _offline: Signal<OfflineState> = use_context_provider(|| Signal::new(read_offline_state()));
 
     use_effect(move || {
         let mut auth = auth;